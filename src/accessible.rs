@@ -0,0 +1,219 @@
+use crate::glossary::full_names;
+use crate::text::{capitalize, stitches, times};
+use crate::{Directive, Instruction, Round};
+use std::fmt::Write;
+
+/// The abbreviations that are spelled out in full wherever they're used, so aren't listed
+const SPELLED_OUT: [&str; 3] = ["skip", "in next st", "cross"];
+
+/// Writes rounds out for reading with a screen reader.
+///
+/// Every abbreviation the rounds use is spelled out once at the top, and after that stitches are
+/// written as a count followed by the abbreviation, so `sc 3` is `3 sc`. Nothing is said with
+/// punctuation alone: repeats are written `repeat 6 times: ..., end of repeat` instead of with
+/// brackets, ranges of rounds with `to` instead of a dash, and notes start with `note:` instead of
+/// being set off with `%`. Each round is one line with the same three parts: the round's number,
+/// its instructions, and its stitch count.
+///
+/// ```
+/// # use crochet::{accessible_format, parse_rounds};
+/// let rounds = parse_rounds("sc 6 in mr\nhook 4mm\n[inc, sc] 6\nx2: sc 18").unwrap();
+///
+/// assert_eq!(
+///     accessible_format(&rounds),
+///     "Abbreviations:
+/// mr means magic ring.
+/// sc means single crochet.
+/// inc means increase.
+///
+/// Round 1. 6 sc into the mr. Stitch count: 6.
+/// Switch to a 4mm hook.
+/// Round 2. Repeat 6 times: 1 inc, then 1 sc, end of repeat. Stitch count: 18.
+/// Rounds 3 to 4. 18 sc. Stitch count: 18."
+/// );
+/// ```
+pub fn accessible_format(rounds: &[Round]) -> String {
+    let mut ret = String::new();
+
+    let abbrs: Vec<_> = full_names(rounds)
+        .into_iter()
+        .filter(|(abbr, _)| !SPELLED_OUT.contains(abbr))
+        .collect();
+    if !abbrs.is_empty() {
+        ret.push_str("Abbreviations:\n");
+        for (abbr, name) in abbrs {
+            writeln!(ret, "{abbr} means {name}.").expect("writing to a string shouldn't fail");
+        }
+        ret.push('\n');
+    }
+
+    let mut number = 1;
+    for round in rounds {
+        for d in &round.directives {
+            match d {
+                Directive::Hook(size) => writeln!(ret, "Switch to a {size} hook."),
+                Directive::Yarn(yarn) => writeln!(ret, "Switch to the yarn named {yarn}."),
+            }
+            .expect("writing to a string shouldn't fail");
+        }
+
+        let last = round.last_number(number);
+        let numbering = if round.times == 1 {
+            format!("Round {number}")
+        } else {
+            format!("Rounds {number} to {last}")
+        };
+        let label = match round.label {
+            Some(l) => format!(", labeled {l}"),
+            None => String::new(),
+        };
+
+        writeln!(
+            ret,
+            "{numbering}{label}. {}. Stitch count: {}.",
+            capitalize(&list(&round.instructions)),
+            round.output_count()
+        )
+        .expect("writing to a string shouldn't fail");
+
+        number = last.saturating_add(1);
+    }
+
+    // remove trailing newline
+    ret.pop();
+
+    ret
+}
+
+/// Describes a list of instructions worked one after another
+fn list(insts: &[Instruction]) -> String {
+    let described: Vec<_> = insts.iter().map(describe).collect();
+    described.join(", then ")
+}
+
+fn describe(inst: &Instruction) -> String {
+    use Instruction::*;
+
+    match inst {
+        Repeat(i, n) => match &**i {
+            Group(insts) => format!(
+                "repeat {}: {}, end of repeat",
+                times(*n, |n| n.to_string()),
+                list(insts)
+            ),
+            i if stitch(i).is_some() => stitch_run(i, *n),
+            i => format!(
+                "repeat {}: {}, end of repeat",
+                times(*n, |n| n.to_string()),
+                describe(i)
+            ),
+        },
+        Group(insts) => list(insts),
+        IntoMagicRing(i) => format!("{} into the mr", describe(i)),
+        IntoOne(i) => format!("{}, all into the next stitch", describe(i)),
+        Crossed(a, b) => format!(
+            "cross: skip 1 stitch, {} into the next stitch, then {} into the skipped stitch, \
+                end of cross",
+            describe(a),
+            describe(b)
+        ),
+        ChSpace { chains, skipped } => format!("{chains} ch, then skip {}", stitches(*skipped)),
+        Skip(n) => format!("skip {}", stitches(*n)),
+        SurfaceSlSt(n) => format!("{n} surface sl st"),
+        Comment(c) => format!("note: {}", c.trim()),
+        Color(c) => format!("change to color {c}"),
+        _ => stitch_run(inst, 1),
+    }
+}
+
+/// What a single stitch is called in a count like `3 sc`
+fn stitch(inst: &Instruction) -> Option<String> {
+    use Instruction::*;
+
+    Some(match inst {
+        Ch => "ch".to_string(),
+        Tch => "tch".to_string(),
+        Custom(stitch) => stitch.name().to_string(),
+        Sc | Fpsc | Bpsc | Blsc | Inc | Flinc | Blinc | Dec => inst.to_string(),
+        _ => return None,
+    })
+}
+
+fn stitch_run(inst: &Instruction, n: u32) -> String {
+    match stitch(inst) {
+        Some(name) => format!("{n} {name}"),
+        None => unreachable!("{inst} isn't a single stitch"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn accessible(src: &str) -> String {
+        accessible_format(&parse_rounds(src).unwrap())
+    }
+
+    #[test]
+    fn test_no_brackets() {
+        let text = accessible(
+            "sc 6 in mr\n[[inc, sc] 2, (sc, ch 2, sc) in next st] 3\n\
+            x2: [fpsc, skip 1, cross(blsc, dec)] 4, % stuff it %\nears: sc 12",
+        );
+
+        assert!(!text.contains(['[', ']', '(', ')', '%', '-']), "{text}");
+        assert_eq!(
+            text.lines().nth(10),
+            Some(
+                "Round 2. Repeat 3 times: repeat twice: 1 inc, then 1 sc, end of repeat, then \
+                1 sc, then 2 ch, then 1 sc, all into the next stitch, end of repeat. \
+                Stitch count: 30."
+            )
+        );
+        assert_eq!(
+            text.lines().nth(11),
+            Some(
+                "Rounds 3 to 4. Repeat 4 times: 1 fpsc, then skip 1 stitch, then \
+                cross: skip 1 stitch, 1 blsc into the next stitch, then 1 dec into the skipped \
+                stitch, end of cross, end of repeat, then note: stuff it. Stitch count: 12."
+            )
+        );
+        assert_eq!(
+            text.lines().nth(12),
+            Some("Round 5, labeled ears. 12 sc. Stitch count: 12.")
+        );
+    }
+
+    #[test]
+    fn test_abbreviations_once() {
+        let text =
+            accessible("colors: A = red, B = blue\nsc 6 in mr\nch 3, skip 2, sc 4\n@B, blinc 3");
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(
+            lines[..6],
+            [
+                "Abbreviations:",
+                "ch means chain.",
+                "mr means magic ring.",
+                "sc means single crochet.",
+                "blinc means back loop increase.",
+                ""
+            ]
+        );
+        assert_eq!(
+            lines[7],
+            "Round 2. 3 ch, then skip 2 stitches, then 4 sc. Stitch count: 7."
+        );
+        assert_eq!(
+            lines[8],
+            "Round 3. Change to color B, then 3 blinc. Stitch count: 6."
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(accessible_format(&[]), "");
+    }
+}
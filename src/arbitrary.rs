@@ -0,0 +1,249 @@
+use crate::{Directive, Instruction, Part, Round};
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+/// How deeply instructions are nested at most, so that generated trees stay small
+const MAX_DEPTH: u32 = 3;
+
+const LABELS: [&str; 4] = ["top", "ears", "brim", "widest"];
+const HOOKS: [&str; 3] = ["2.5mm", "3.5mm", "4mm"];
+const YARNS: [&str; 3] = ["DK blue", "cream", "worsted"];
+const PARTS: [&str; 3] = ["Head", "Body", "Left Arm"];
+
+/// Generates instructions that are written out and parsed back the same.
+///
+/// That rules out a few trees the type allows but the language can't express, like a count on an
+/// instruction that can't have one (`skip 2 3`), or a group that isn't repeated, worked into the
+/// magic ring or worked into one stitch. Color changes and custom stitches aren't generated either,
+/// since parsing them needs a palette or a [`crate::Dialect`], and neither are stitches worked into
+/// the magic ring, which only belong at the start of a piece (see [`Part`]'s impl).
+impl<'a> Arbitrary<'a> for Instruction<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        instruction(u, 0, false)
+    }
+}
+
+/// Generates unnumbered rounds without spans, like [`Round::new`], with some of the labels,
+/// counts and directives rounds can have. Their instructions are generated like
+/// [`Instruction`]'s, so writing a round out and parsing it back gives the same instructions.
+impl<'a> Arbitrary<'a> for Round<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        round(u, false)
+    }
+}
+
+/// Generates numbered parts whose rounds are generated like [`Round`]'s, except that the first
+/// round can also work stitches into the magic ring
+impl<'a> Arbitrary<'a> for Part<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let name = match u.arbitrary()? {
+            true => Some(*u.choose(&PARTS)?),
+            false => None,
+        };
+
+        let mut rounds = vec![round(u, true)?];
+        for _ in 0..u.int_in_range(0..=6)? {
+            rounds.push(round(u, false)?);
+        }
+
+        let mut part = Part { name, rounds };
+        part.renumber();
+        Ok(part)
+    }
+}
+
+/// A round whose top-level instructions can be worked into the magic ring if `magic_ring` is set
+fn round<'a>(u: &mut Unstructured<'a>, magic_ring: bool) -> Result<Round<'a>> {
+    let mut round = Round::new(list(u, 0, magic_ring)?);
+
+    // a round can't have both, and working into the magic ring again would be after the first
+    // round
+    match u.int_in_range(0..=3)? {
+        0 => round.label = Some(*u.choose(&LABELS)?),
+        1 if !magic_ring => round.times = u.int_in_range(2..=10)?,
+        _ => {}
+    }
+
+    for _ in 0..u.int_in_range(0..=2)? {
+        round.directives.push(match u.arbitrary()? {
+            true => Directive::Hook(u.choose(&HOOKS)?),
+            false => Directive::Yarn(u.choose(&YARNS)?),
+        });
+    }
+
+    Ok(round)
+}
+
+/// A small count, since huge ones aren't any more interesting to test with
+fn count(u: &mut Unstructured) -> Result<u32> {
+    u.int_in_range(1..=24)
+}
+
+/// An instruction that can be an element of a list, e.g. of a round or a group, and can be worked
+/// into the magic ring if `magic_ring` is set
+fn instruction<'a>(
+    u: &mut Unstructured<'a>,
+    depth: u32,
+    magic_ring: bool,
+) -> Result<Instruction<'a>> {
+    use Instruction::*;
+
+    let nested = depth < MAX_DEPTH;
+
+    Ok(match u.int_in_range(0..=9)? {
+        0 => Tch,
+        1 => Comment(comment(u)?),
+        2 => Skip(count(u)?),
+        3 => SurfaceSlSt(count(u)?),
+        4 => ChSpace {
+            chains: count(u)?,
+            skipped: count(u)?,
+        },
+        5 if nested => IntoOne(Group(list(u, depth + 1, false)?).into()),
+        6 => Repeat(countable(u, depth)?.into(), count(u)?),
+        7 if magic_ring => {
+            let inst = match u.arbitrary()? {
+                true => Repeat(countable(u, depth)?.into(), count(u)?),
+                false => countable(u, depth)?,
+            };
+            IntoMagicRing(inst.into())
+        }
+        // stitches are the most common, and where the nesting bottoms out
+        _ => match countable(u, depth)? {
+            Group(_) => Sc,
+            inst => inst,
+        },
+    })
+}
+
+/// An instruction that can be written with a count or `in mr` after it
+fn countable<'a>(u: &mut Unstructured<'a>, depth: u32) -> Result<Instruction<'a>> {
+    use Instruction::*;
+
+    let nested = depth < MAX_DEPTH;
+
+    Ok(match u.int_in_range(0..=11)? {
+        0 => Ch,
+        1 => Sc,
+        2 => Fpsc,
+        3 => Bpsc,
+        4 => Blsc,
+        5 => Inc,
+        6 => Flinc,
+        7 => Blinc,
+        8 => Dec,
+        9 if nested => Group(list(u, depth + 1, false)?),
+        10 if nested => Crossed(crossed(u, depth + 1)?.into(), crossed(u, depth + 1)?.into()),
+        _ => Sc,
+    })
+}
+
+/// One of the instructions of a `cross(a, b)`, which can't be a `ch N, skip M` since that's
+/// written as two
+fn crossed<'a>(u: &mut Unstructured<'a>, depth: u32) -> Result<Instruction<'a>> {
+    Ok(match instruction(u, depth, false)? {
+        Instruction::ChSpace { .. } => Instruction::Sc,
+        inst => inst,
+    })
+}
+
+/// A non-empty list of instructions, which can be worked into the magic ring if `magic_ring` is
+/// set
+fn list<'a>(
+    u: &mut Unstructured<'a>,
+    depth: u32,
+    magic_ring: bool,
+) -> Result<Vec<Instruction<'a>>> {
+    let mut insts = vec![instruction(u, depth, magic_ring)?];
+
+    while insts.len() < 8 && u.arbitrary()? {
+        let inst = instruction(u, depth, magic_ring)?;
+
+        // `ch N` right before `skip M` is parsed as a chain space
+        let after_chains = matches!(insts.last(), Some(Instruction::Ch))
+            || matches!(insts.last(), Some(Instruction::Repeat(i, _)) if **i == Instruction::Ch);
+        if after_chains && matches!(inst, Instruction::Skip(_)) {
+            continue;
+        }
+
+        insts.push(inst);
+    }
+
+    Ok(insts)
+}
+
+/// Comment text that's borrowed from the input, made of letters, digits and spaces so it can't
+/// end the comment early
+fn comment<'a>(u: &mut Unstructured<'a>) -> Result<&'a str> {
+    let s: &str = u.arbitrary()?;
+    let len = s
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != ' ')
+        .unwrap_or(s.len());
+
+    Ok(match s[..len].trim() {
+        "" => "note",
+        s => s,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    /// Pseudo-random bytes to generate from
+    fn bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for seed in 0..500 {
+            let data = bytes(seed, 1024);
+            let mut u = Unstructured::new(&data);
+            let part: Part = u.arbitrary().unwrap();
+            let rounds = part.rounds;
+
+            // the magic ring is only ever worked into at the start
+            let lints = crate::lint_rounds(&rounds);
+            assert!(
+                lints
+                    .iter()
+                    .all(|l| l.name() != "magic_ring_after_first_round"),
+                "{lints:?}"
+            );
+
+            let src = rounds
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let parsed = parse_rounds(&src).unwrap_or_else(|e| panic!("{e}: {src}"));
+
+            assert_eq!(parsed.len(), rounds.len(), "{src}");
+            for (parsed, round) in parsed.iter().zip(&rounds) {
+                assert_eq!(parsed.instructions, round.instructions, "{src}");
+                assert_eq!(
+                    (parsed.index, parsed.label, parsed.times, &parsed.directives),
+                    (round.index, round.label, round.times, &round.directives)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_instruction() {
+        let data = bytes(1, 256);
+        let inst = Instruction::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+        let src = format!("sc 0, {inst}");
+        assert_eq!(parse_rounds(&src).unwrap()[0].instructions[1], inst);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::lex::{tokenize, TokenKind};
+use crate::parse::{parse_list, parse_round_start, Build, Context};
+use crate::{Instruction, ParseError, Span};
+
+/// Identifies a node in an [`InstructionArena`]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+pub struct NodeId(u32);
+
+/// Nodes stored next to each other in an [`InstructionArena`], like the elements of a group,
+/// which can be looked up with [`InstructionArena::list`]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct NodeList {
+    start: u32,
+    len: u32,
+}
+
+/// An [`Instruction`] stored in an [`InstructionArena`], with the instructions nested in it
+/// referred to by their [`NodeId`] instead of being boxed.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Node<'a> {
+    /// An instruction with no other instructions nested in it, like `sc` or `skip 2`
+    Leaf(Instruction<'a>),
+    IntoMagicRing(NodeId),
+    Group(NodeList),
+    Repeat(NodeId, u32),
+    Crossed(NodeId, NodeId),
+    IntoOne(NodeId),
+}
+
+/// A round parsed into an [`InstructionArena`] by [`InstructionArena::parse_rounds`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ArenaRound<'a> {
+    /// One-based number of this round (the first one, for a block of identical rounds)
+    pub index: u32,
+    pub label: Option<&'a str>,
+    /// How many times in a row this round is worked
+    pub times: u32,
+    pub instructions: NodeList,
+}
+
+/// Flat storage for the instructions of very large patterns.
+///
+/// The parser builds nodes straight into the arena, so a whole pattern takes a couple of growing
+/// `Vec`s instead of an allocation for every nested instruction, and none for the spans that
+/// [`crate::Round`]s keep. This matters for generated patterns with hundreds of thousands of
+/// instructions; for ordinary patterns, the owned [`Instruction`] tree is simpler to work with.
+///
+/// ```
+/// # use crochet::{InstructionArena, Node};
+/// let mut arena = InstructionArena::new();
+/// let rounds = arena.parse_rounds("sc 6 in mr\n[inc, sc] 6").unwrap();
+///
+/// let [repeat] = arena.list(rounds[1].instructions) else { unreachable!() };
+/// let Node::Repeat(group, 6) = arena[*repeat] else { unreachable!() };
+/// let Node::Group(elements) = arena[group] else { unreachable!() };
+/// assert_eq!(arena.list(elements).len(), 2);
+///
+/// assert_eq!(arena.to_instruction(*repeat).output_count(), 18);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct InstructionArena<'a> {
+    nodes: Vec<Node<'a>>,
+    /// The elements of every [`NodeList`], with each list's elements next to each other
+    lists: Vec<NodeId>,
+}
+
+impl<'a> InstructionArena<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many nodes are in the arena
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Parses rounds into the arena, like [`crate::parse_rounds`] but without spans. Only lines
+    /// of rounds are supported: there's no header, so no colors either, and no includes, hook
+    /// or yarn changes, or variables.
+    ///
+    /// If there's an error, the nodes of the rounds before it are left in the arena.
+    pub fn parse_rounds(&mut self, source: &'a str) -> Result<Vec<ArenaRound<'a>>, ParseError> {
+        let mut ts = tokenize(source);
+        let mut cx = Context::default();
+        let mut rounds = Vec::new();
+        let mut number: u32 = 1;
+
+        loop {
+            while let Some(TokenKind::Newline) = ts.peek_kind() {
+                ts.next();
+            }
+            if ts.is_empty() {
+                return Ok(rounds);
+            }
+
+            let start = ts.current_loc();
+            let (label, times) = parse_round_start(&mut ts, number)?;
+            let insts = parse_list(&mut ts, &mut cx, self)?;
+
+            if !matches!(ts.peek_kind(), Some(TokenKind::Newline)) && !ts.is_empty() {
+                return Err(ParseError::expected(
+                    ts.current_loc(),
+                    &[TokenKind::Comma.describe(), TokenKind::Newline.describe()],
+                ));
+            }
+
+            rounds.push(ArenaRound {
+                index: number,
+                label,
+                times,
+                instructions: self.alloc_list(&insts),
+            });
+            number = number.checked_add(times).ok_or(start)?;
+        }
+    }
+
+    /// The nodes in `list`
+    pub fn list(&self, list: NodeList) -> &[NodeId] {
+        &self.lists[list.start as usize..][..list.len as usize]
+    }
+
+    /// Copies a node, along with everything nested in it, out of the arena as an ordinary
+    /// [`Instruction`]
+    pub fn to_instruction(&self, id: NodeId) -> Instruction<'a> {
+        match &self[id] {
+            Node::Leaf(inst) => inst.clone(),
+            Node::IntoMagicRing(i) => Instruction::IntoMagicRing(self.to_instruction(*i).into()),
+            Node::Group(list) => Instruction::Group(
+                self.list(*list)
+                    .iter()
+                    .map(|&i| self.to_instruction(i))
+                    .collect(),
+            ),
+            Node::Repeat(i, times) => Instruction::Repeat(self.to_instruction(*i).into(), *times),
+            Node::Crossed(a, b) => Instruction::Crossed(
+                self.to_instruction(*a).into(),
+                self.to_instruction(*b).into(),
+            ),
+            Node::IntoOne(i) => Instruction::IntoOne(self.to_instruction(*i).into()),
+        }
+    }
+
+    fn push(&mut self, node: Node<'a>) -> NodeId {
+        let id = NodeId(
+            self.nodes
+                .len()
+                .try_into()
+                .expect("too many nodes for an arena"),
+        );
+        self.nodes.push(node);
+        id
+    }
+
+    fn alloc_list(&mut self, ids: &[NodeId]) -> NodeList {
+        let start = self.lists.len();
+        self.lists.extend_from_slice(ids);
+
+        NodeList {
+            start: start.try_into().expect("too many nodes for an arena"),
+            len: ids.len().try_into().expect("too many nodes for an arena"),
+        }
+    }
+}
+
+impl<'a> std::ops::Index<NodeId> for InstructionArena<'a> {
+    type Output = Node<'a>;
+
+    fn index(&self, id: NodeId) -> &Node<'a> {
+        &self.nodes[id.0 as usize]
+    }
+}
+
+impl<'a> Build<'a> for InstructionArena<'a> {
+    type Inst = NodeId;
+
+    fn leaf(&mut self, inst: Instruction<'a>, _: Span) -> NodeId {
+        self.push(Node::Leaf(inst))
+    }
+
+    fn repeat(&mut self, inst: NodeId, times: u32, _: Span) -> NodeId {
+        self.push(Node::Repeat(inst, times))
+    }
+
+    fn magic_ring(&mut self, inst: NodeId, _: Span) -> NodeId {
+        self.push(Node::IntoMagicRing(inst))
+    }
+
+    fn one_stitch(&mut self, group: NodeId, _: Span) -> NodeId {
+        self.push(Node::IntoOne(group))
+    }
+
+    fn crossed(&mut self, first: NodeId, second: NodeId, _: Span) -> NodeId {
+        self.push(Node::Crossed(first, second))
+    }
+
+    fn group(&mut self, insts: Vec<NodeId>, _: Span) -> NodeId {
+        let list = self.alloc_list(&insts);
+        self.push(Node::Group(list))
+    }
+
+    fn chains(&self, inst: &NodeId) -> Option<u32> {
+        match self[*inst] {
+            Node::Leaf(Instruction::Ch) => Some(1),
+            Node::Repeat(i, n) if self[i] == Node::Leaf(Instruction::Ch) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn skipped(&self, inst: &NodeId) -> Option<u32> {
+        match self[*inst] {
+            Node::Leaf(Instruction::Skip(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn ch_space(&mut self, ch: NodeId, skip: NodeId) -> NodeId {
+        let inst = Instruction::ChSpace {
+            chains: self.chains(&ch).unwrap(),
+            skipped: self.skipped(&skip).unwrap(),
+        };
+
+        // the skip was the last node parsed, so it can go, and the chains' node is reused
+        if skip.0 as usize == self.nodes.len() - 1 {
+            self.nodes.pop();
+        }
+        self.nodes[ch.0 as usize] = Node::Leaf(inst);
+        ch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    #[test]
+    fn test_same_as_parse_rounds() {
+        let sources = [
+            "sc 6 in mr\n[inc, sc] 6\n\nx2: sc 18",
+            "mr 6\nch 2, skip 2, [sc, ch, skip 1] 2, skip 1",
+            "altmr 6\nedge: (sc, ch 2, sc) in next st, cross(fpsc, blsc) 3, % note %",
+            "sc 6 in mr\nrounds 2-4: [[sc 2, dec] 2, inc] 3",
+        ];
+
+        for src in sources {
+            let mut arena = InstructionArena::new();
+            let arena_rounds = arena.parse_rounds(src).unwrap();
+            let rounds = parse_rounds(src).unwrap();
+
+            assert_eq!(arena_rounds.len(), rounds.len());
+            for (arena_round, round) in arena_rounds.iter().zip(&rounds) {
+                let instructions: Vec<_> = arena
+                    .list(arena_round.instructions)
+                    .iter()
+                    .map(|&id| arena.to_instruction(id))
+                    .collect();
+
+                assert_eq!(instructions, round.instructions, "{src}");
+                assert_eq!(arena_round.index, round.index);
+                assert_eq!(arena_round.label, round.label);
+                assert_eq!(arena_round.times, round.times);
+            }
+        }
+    }
+
+    #[test]
+    fn test_chain_spaces_reuse_nodes() {
+        let mut arena = InstructionArena::new();
+        arena.parse_rounds("ch, skip 2").unwrap();
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        for src in [
+            "sc 6 in mr\n[inc, sc 6",
+            "sc 6 in mr\nsc 6 sc",
+            "sc 6 in mr\nrounds 3-4: sc 6",
+            "sc 6 in mr\nx4294967295: sc 6\nsc 6",
+        ] {
+            let mut arena = InstructionArena::new();
+            assert_eq!(
+                arena.parse_rounds(src).map(|_| ()),
+                parse_rounds(src).map(|_| ()),
+                "{src}"
+            );
+        }
+    }
+}
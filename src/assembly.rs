@@ -0,0 +1,118 @@
+use crate::Span;
+
+/// One line of a pattern's `== Assembly ==` section, e.g. `sew Head to Body between rounds 18-20`.
+///
+/// ```
+/// # use crochet::parse_pattern;
+/// let src = "
+///     == Head ==
+///     sc 6 in mr
+///     == Body ==
+///     sc 6 in mr
+///     == Assembly ==
+///     sew Head to Body between rounds 18-20
+///     stuff Body firmly
+/// ";
+///
+/// let pattern = parse_pattern(src).unwrap();
+/// let step = &pattern.assembly[0];
+/// assert_eq!(step.text, "sew Head to Body between rounds 18-20");
+/// assert_eq!(step.parts, vec!["Head", "Body"]);
+/// assert_eq!(step.rounds, Some((18, 20)));
+/// assert_eq!(pattern.assembly[1].rounds, None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssemblyStep<'a> {
+    /// The step as written
+    pub text: &'a str,
+    /// Names of the pattern's parts mentioned in the step, in the order they're mentioned
+    pub parts: Vec<&'a str>,
+    /// The first and last round the step refers to, from e.g. `round 5` or `rounds 18-20`
+    pub rounds: Option<(u32, u32)>,
+    /// Where the step is in the source
+    pub span: Span,
+}
+
+impl<'a> AssemblyStep<'a> {
+    /// Extracts the parts and rounds referenced by `text`, given the names of the pattern's parts.
+    pub(crate) fn new(text: &'a str, span: Span, part_names: &[&'a str]) -> Self {
+        let mut parts: Vec<_> = part_names
+            .iter()
+            .filter_map(|&name| find_word(text, name).map(|idx| (idx, name)))
+            .collect();
+        parts.sort_by_key(|&(idx, _)| idx);
+
+        Self {
+            text,
+            parts: parts.into_iter().map(|(_, name)| name).collect(),
+            rounds: find_rounds(text),
+            span,
+        }
+    }
+}
+
+impl std::fmt::Display for AssemblyStep<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Finds where `word` first appears in `text` on its own, rather than as part of a longer word
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    text.match_indices(word).map(|(idx, _)| idx).find(|&idx| {
+        let before = text[..idx].chars().next_back();
+        let after = text[idx + word.len()..].chars().next();
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    })
+}
+
+/// Finds the first `round N` or `rounds A-B` in `text`
+fn find_rounds(text: &str) -> Option<(u32, u32)> {
+    let words: Vec<_> = text.split_whitespace().collect();
+
+    words.windows(2).find_map(|w| {
+        let word = w[0].trim_start_matches(|c: char| c.is_ascii_punctuation());
+        if !["round", "rounds", "rnd", "rnds"].contains(&word.to_lowercase().as_str()) {
+            return None;
+        }
+
+        let range = w[1].trim_end_matches(|c: char| c.is_ascii_punctuation());
+        match range.split_once(['-', '–']) {
+            Some((a, b)) => Some((a.parse().ok()?, b.parse().ok()?)),
+            None => range.parse().ok().map(|n| (n, n)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references() {
+        let parts = ["Head", "Ear", "Body"];
+
+        let step = AssemblyStep::new(
+            "sew Ears to Head at round 5, then to Body",
+            Span::default(),
+            &parts,
+        );
+        assert_eq!(step.parts, vec!["Head", "Body"]);
+        assert_eq!(step.rounds, Some((5, 5)));
+
+        let step = AssemblyStep::new("Sew Ear to Head between Rnds 3–6.", Span::default(), &parts);
+        assert_eq!(step.parts, vec!["Ear", "Head"]);
+        assert_eq!(step.rounds, Some((3, 6)));
+
+        let step = AssemblyStep::new(
+            "weave in all the ends around the rounds",
+            Span::default(),
+            &parts,
+        );
+        assert_eq!(step.parts, Vec::<&str>::new());
+        assert_eq!(step.rounds, None);
+    }
+}
@@ -1,10 +1,38 @@
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
-    let args: Vec<_> = std::env::args().collect();
+    let mut args: Vec<_> = std::env::args().collect();
+
+    // print the lints as JSON or SARIF instead of the formatted pattern, for other programs to read
+    let format = args
+        .iter()
+        .position(|a| a == "--json" || a == "--sarif")
+        .map(|i| args.remove(i));
+    // print the pattern as HTML or LaTeX instead of plain text, for publishing, in plain English
+    // or one stitch at a time for beginners, or without abbreviated notation for screen readers
+    // and braille embossers
+    let output = args
+        .iter()
+        .position(|a| {
+            [
+                "--html",
+                "--latex",
+                "--pdf",
+                "--prose",
+                "--steps",
+                "--accessible",
+                "--brf",
+            ]
+            .contains(&a.as_str())
+        })
+        .map(|i| args.remove(i));
 
     if args.len() != 2 {
-        eprintln!("Usage: {} path/to/pattern.crochet", args[0]);
+        eprintln!(
+            "Usage: {} [--json | --sarif | --html | --latex | --pdf | --prose | --steps | \
+                --accessible | --brf] path/to/pattern.crochet",
+            args[0]
+        );
         return ExitCode::FAILURE;
     }
 
@@ -16,44 +44,222 @@ fn main() -> ExitCode {
         }
     };
 
-    let rounds = match crochet::parse_rounds(&source) {
+    // included files are looked up relative to the pattern file
+    let dir = std::path::Path::new(&args[1])
+        .parent()
+        .unwrap_or(std::path::Path::new(""));
+    // the paths and sources of the included files, in the order they're loaded, to show the
+    // lints in them
+    let mut included: Vec<(String, &str)> = Vec::new();
+    let loader = |path: &str| match std::fs::read_to_string(dir.join(path)) {
+        // included sources have to outlive the parsed pattern; just leak them,
+        // since they're needed until the program exits anyways
+        Ok(s) => {
+            let s = &*Box::leak(s.into_boxed_str());
+            included.push((path.to_owned(), s));
+            Some(s)
+        }
+        Err(e) => {
+            eprintln!("Can't read included file `{path}`: {e}");
+            None
+        }
+    };
+
+    let pattern = match crochet::parse_pattern_with_loader(&source, loader) {
         Ok(r) => r,
-        Err((lineno, col)) => {
-            eprintln!("Parse error at {lineno}:{col}");
+        Err(e) => {
+            eprintln!("Parse error at {e}");
+            let (line, col) = e.loc();
+            print_snippet(&source, line, col, col + 1);
 
-            let line = source.split("\n").nth(lineno - 1).unwrap();
-            let prefix = format!("{lineno} ");
+            return ExitCode::FAILURE;
+        }
+    };
 
-            let mut lpad = String::with_capacity(prefix.len() + 1);
-            for _ in 0..prefix.len() {
-                lpad.push(' ');
-            }
-            lpad.push('|');
+    if let Some(format) = format {
+        return print_machine_readable(&pattern, &format, &args[1]);
+    }
+
+    let lints = lint(&pattern);
+    // lints between parts aren't about any one part
+    let part_names = pattern.parts.iter().map(|p| p.name).chain([None]);
 
-            eprintln!("{lpad}");
-            eprintln!("{prefix}| {line}");
+    for (name, part_lints) in part_names.zip(lints.iter()) {
+        for (l, severity) in part_lints {
+            let span = l.span();
+            let loc = location(span, &included);
 
-            eprint!("{lpad} ");
-            for _ in 1..col {
-                eprint!(" ");
+            let code = l.code();
+            match name {
+                Some(name) => eprintln!("Lint {code} ({severity}) at {loc}: {name}: {l}"),
+                None => eprintln!("Lint {code} ({severity}) at {loc}: {l}"),
             }
-            eprintln!("^");
 
-            return ExitCode::FAILURE;
+            print_span(&source, &included, span);
+
+            for note in l.notes() {
+                let loc = location(note.span, &included);
+                eprintln!("  note at {loc}: {}", note.message);
+                print_span(&source, &included, note.span);
+            }
         }
-    };
+    }
 
-    let lints = crochet::lint_rounds(&rounds);
+    match output.as_deref() {
+        Some("--html") => println!("{}", crochet::to_html(&pattern)),
+        Some("--pdf") => {
+            if !write_pdf(&pattern) {
+                return ExitCode::FAILURE;
+            }
+        }
+        Some(flag @ ("--prose" | "--steps" | "--accessible")) => {
+            let format = match flag {
+                "--prose" => crochet::prose_format,
+                "--steps" => crochet::step_by_step_format,
+                _ => crochet::accessible_format,
+            };
+            let parts = pattern.parts.iter().map(|part| match part.name {
+                Some(name) => format!("{name}\n{}", format(&part.rounds)),
+                None => format(&part.rounds),
+            });
+            let sections: Vec<_> = pattern
+                .meta
+                .name
+                .map(str::to_string)
+                .into_iter()
+                .chain(parts)
+                .chain(pattern.meta.provenance_footer())
+                .collect();
+            println!("{}", sections.join("\n\n"));
+        }
+        Some("--brf") => print!("{}", crochet::to_brf(&pattern, &Default::default())),
+        Some("--latex") => println!("{}", crochet::to_latex(&pattern)),
+        // `output` is always one of the flags looked for above
+        Some(_) => unreachable!(),
+        None => println!("{}", crochet::pretty_format_pattern(&pattern)),
+    }
 
-    for l in lints.iter() {
-        eprintln!("Lint: {l}");
+    // only errors make the pattern unusable, so only they fail
+    if lints
+        .iter()
+        .flatten()
+        .all(|(_, s)| *s != crochet::Severity::Error)
+    {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
     }
+}
+
+/// The lints of each part of the pattern, followed by the lints between its parts, each with how
+/// seriously it's reported
+fn lint(pattern: &crochet::Pattern) -> Vec<Vec<(crochet::Lint, crochet::Severity)>> {
+    let config = crochet::LintConfig::new().mode(pattern.mode());
+    let cross_part = crochet::lint_parts(pattern)
+        .into_iter()
+        .filter_map(|l| Some((config.severity_of(&l)?, l)))
+        .map(|(severity, l)| (l, severity))
+        .collect();
+
+    pattern
+        .parts
+        .iter()
+        .map(|p| crochet::lint_rounds_with_config(&p.rounds, &config).into_lints())
+        .chain([cross_part])
+        .collect()
+}
 
-    println!("{}", crochet::pretty_format(&rounds));
+#[cfg(feature = "json")]
+fn print_machine_readable(pattern: &crochet::Pattern, format: &str, path: &str) -> ExitCode {
+    let lints: Vec<_> = lint(pattern).into_iter().flatten().collect();
+    match format {
+        "--sarif" => println!("{}", crochet::lints_to_sarif(&lints, path)),
+        _ => println!("{}", crochet::lints_to_json(&lints)),
+    }
 
-    if lints.is_empty() {
+    if lints.iter().all(|(_, s)| *s != crochet::Severity::Error) {
         ExitCode::SUCCESS
     } else {
         ExitCode::FAILURE
     }
 }
+
+#[cfg(not(feature = "json"))]
+fn print_machine_readable(_: &crochet::Pattern, format: &str, _: &str) -> ExitCode {
+    eprintln!("{format} needs crochetcli to be built with the `json` feature");
+    ExitCode::FAILURE
+}
+
+/// Writes the pattern to stdout as a PDF, returning whether it could
+#[cfg(feature = "pdf")]
+fn write_pdf(pattern: &crochet::Pattern) -> bool {
+    use std::io::Write;
+
+    match std::io::stdout().write_all(&crochet::render_pdf(pattern)) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Can't write the PDF: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "pdf"))]
+fn write_pdf(_: &crochet::Pattern) -> bool {
+    eprintln!("--pdf needs crochetcli to be built with the `pdf` feature");
+    false
+}
+
+/// Where `span` starts, as `line:col`, with the path of the file it's in if it's in an included
+/// file
+fn location(span: crochet::Span, included: &[(String, &str)]) -> String {
+    let (line, col) = span.start;
+    match span.file.checked_sub(1).and_then(|i| included.get(i)) {
+        Some((path, _)) => format!("{path}:{line}:{col}"),
+        None => format!("{line}:{col}"),
+    }
+}
+
+/// Prints the first line of `span`, with the span underlined
+fn print_span(source: &str, included: &[(String, &str)], span: crochet::Span) {
+    let (line, col) = span.start;
+    let source = match span.file.checked_sub(1) {
+        Some(i) => included.get(i).map_or("", |(_, s)| s),
+        None => source,
+    };
+
+    // rounds that weren't parsed from the source don't have a span to show
+    if line != 0 {
+        // only the first line of spans over several lines is underlined
+        let end = if span.end.0 == line {
+            span.end.1
+        } else {
+            col + 1
+        };
+        print_snippet(source, line, col, end);
+    }
+}
+
+/// Prints line `lineno` of `source`, with the columns `start..end` underlined
+fn print_snippet(source: &str, lineno: usize, start: usize, end: usize) {
+    let line = source.split("\n").nth(lineno - 1).unwrap_or("");
+    let prefix = format!("{lineno} ");
+
+    let mut lpad = String::with_capacity(prefix.len() + 1);
+    for _ in 0..prefix.len() {
+        lpad.push(' ');
+    }
+    lpad.push('|');
+
+    eprintln!("{lpad}");
+    eprintln!("{prefix}| {line}");
+
+    eprint!("{lpad} ");
+    for _ in 1..start {
+        eprint!(" ");
+    }
+    for _ in start..end.max(start + 1) {
+        eprint!("^");
+    }
+    eprintln!();
+}
@@ -0,0 +1,496 @@
+use crate::meta::MetaField;
+use crate::{
+    AssemblyStep, Directive, Instruction, Part, Pattern, PatternMeta, Round, Span, SpanTree,
+    TextBlock,
+};
+
+/// The bytes every encoded pattern starts with
+const MAGIC: &[u8; 4] = b"CRCH";
+
+/// Version of the encoding itself (not of the pattern language). Bumped whenever the layout
+/// changes, so that stale caches are rejected instead of being misread.
+const FORMAT_VERSION: u8 = 2;
+
+/// How deeply instructions can be nested in an encoded pattern. Decoding is recursive, so deeper
+/// input is rejected rather than overflowing the stack.
+const MAX_DEPTH: u32 = 256;
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, n: u8) {
+        self.bytes.push(n);
+    }
+
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.u8(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.u8(n as u8);
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.varint(n.into());
+    }
+
+    fn usize(&mut self, n: usize) {
+        self.varint(n as u64);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.usize(s.len());
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: Option<T>, f: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(v) => {
+                self.u8(1);
+                f(self, v);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn seq<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        self.usize(items.len());
+        for item in items {
+            f(self, item);
+        }
+    }
+
+    fn span(&mut self, span: Span) {
+        self.usize(span.start.0);
+        self.usize(span.start.1);
+        self.usize(span.end.0);
+        self.usize(span.end.1);
+    }
+
+    fn span_tree(&mut self, tree: &SpanTree) {
+        self.span(tree.span);
+        self.seq(&tree.children, Self::span_tree);
+    }
+
+    fn instruction(&mut self, inst: &Instruction) {
+        use Instruction::*;
+
+        match inst {
+            Ch => self.u8(0),
+            Tch => self.u8(1),
+            Sc => self.u8(2),
+            Fpsc => self.u8(3),
+            Bpsc => self.u8(4),
+            Blsc => self.u8(5),
+            Inc => self.u8(6),
+            Flinc => self.u8(7),
+            Blinc => self.u8(8),
+            Dec => self.u8(9),
+            IntoMagicRing(i) => {
+                self.u8(10);
+                self.instruction(i);
+            }
+            Group(insts) => {
+                self.u8(11);
+                self.seq(insts, Self::instruction);
+            }
+            Repeat(i, times) => {
+                self.u8(12);
+                self.instruction(i);
+                self.u32(*times);
+            }
+            Comment(s) => {
+                self.u8(13);
+                self.str(s);
+            }
+            Skip(n) => {
+                self.u8(14);
+                self.u32(*n);
+            }
+            SurfaceSlSt(n) => {
+                self.u8(15);
+                self.u32(*n);
+            }
+            Crossed(a, b) => {
+                self.u8(16);
+                self.instruction(a);
+                self.instruction(b);
+            }
+            ChSpace { chains, skipped } => {
+                self.u8(17);
+                self.u32(*chains);
+                self.u32(*skipped);
+            }
+            IntoOne(i) => {
+                self.u8(18);
+                self.instruction(i);
+            }
+            Color(c) => {
+                self.u8(19);
+                self.str(c);
+            }
+            Custom(stitch) => {
+                self.u8(20);
+                self.str(stitch.name());
+            }
+        }
+    }
+
+    fn directive(&mut self, directive: &Directive) {
+        match directive {
+            Directive::Hook(size) => {
+                self.u8(0);
+                self.str(size);
+            }
+            Directive::Yarn(yarn) => {
+                self.u8(1);
+                self.str(yarn);
+            }
+        }
+    }
+
+    fn round(&mut self, round: &Round) {
+        self.u32(round.index);
+        self.option(round.label, Self::str);
+        self.seq(&round.instructions, Self::instruction);
+        self.u32(round.times);
+        self.seq(&round.directives, Self::directive);
+        self.span(round.span);
+        self.seq(&round.instruction_spans, Self::span_tree);
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        for field in MetaField::ALL {
+            self.option(pattern.meta.get(field), Self::str);
+        }
+
+        self.seq(&pattern.parts, |w, part| {
+            w.option(part.name, Self::str);
+            w.seq(&part.rounds, Self::round);
+        });
+        self.seq(&pattern.assembly, |w, step| {
+            w.str(step.text);
+            w.seq(&step.parts, |w, name| w.str(name));
+            w.option(step.rounds, |w, (first, last)| {
+                w.u32(first);
+                w.u32(last);
+            });
+            w.span(step.span);
+        });
+        self.u32(pattern.version);
+        self.seq(&pattern.notes, |w, block| {
+            w.str(block.name);
+            w.str(block.text);
+        });
+    }
+}
+
+/// Reads an encoded pattern back. Every method returns `None` if the input is malformed or ends
+/// early.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    /// How many instructions or span trees are being read around the current one
+    depth: u32,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if n > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut n = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            // the last byte only has room for the top bit
+            if shift == 63 && byte > 1 {
+                return None;
+            }
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(n);
+            }
+        }
+
+        None
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.varint()?.try_into().ok()
+    }
+
+    fn usize(&mut self) -> Option<usize> {
+        self.varint()?.try_into().ok()
+    }
+
+    fn str(&mut self) -> Option<&'a str> {
+        let len = self.usize()?;
+        std::str::from_utf8(self.take(len)?).ok()
+    }
+
+    fn option<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<Option<T>> {
+        match self.u8()? {
+            0 => Some(None),
+            1 => f(self).map(Some),
+            _ => None,
+        }
+    }
+
+    fn seq<T>(&mut self, mut f: impl FnMut(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let len = self.usize()?;
+        // every element takes at least one byte, so a bogus length can't allocate much
+        let mut items = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            items.push(f(self)?);
+        }
+        Some(items)
+    }
+
+    fn span(&mut self) -> Option<Span> {
+        Some(Span::new(
+            (self.usize()?, self.usize()?),
+            (self.usize()?, self.usize()?),
+        ))
+    }
+
+    /// Reads a value that can contain further values of its own kind, failing if they're nested
+    /// more than [`MAX_DEPTH`] deep
+    fn nested<T>(&mut self, read: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+
+        self.depth += 1;
+        let ret = read(self);
+        self.depth -= 1;
+        ret
+    }
+
+    fn span_tree(&mut self) -> Option<SpanTree> {
+        self.nested(|r| {
+            Some(SpanTree {
+                span: r.span()?,
+                children: r.seq(Self::span_tree)?,
+            })
+        })
+    }
+
+    fn instruction(&mut self) -> Option<Instruction<'a>> {
+        self.nested(Self::unnested_instruction)
+    }
+
+    fn unnested_instruction(&mut self) -> Option<Instruction<'a>> {
+        use Instruction::*;
+
+        Some(match self.u8()? {
+            0 => Ch,
+            1 => Tch,
+            2 => Sc,
+            3 => Fpsc,
+            4 => Bpsc,
+            5 => Blsc,
+            6 => Inc,
+            7 => Flinc,
+            8 => Blinc,
+            9 => Dec,
+            10 => IntoMagicRing(self.instruction()?.into()),
+            11 => Group(self.seq(Self::instruction)?),
+            12 => Repeat(self.instruction()?.into(), self.u32()?),
+            13 => Comment(self.str()?),
+            14 => Skip(self.u32()?),
+            15 => SurfaceSlSt(self.u32()?),
+            16 => Crossed(self.instruction()?.into(), self.instruction()?.into()),
+            17 => ChSpace {
+                chains: self.u32()?,
+                skipped: self.u32()?,
+            },
+            18 => IntoOne(self.instruction()?.into()),
+            19 => Color(self.str()?),
+            // only the name of a custom stitch is encoded, which isn't enough to get it back
+            _ => return None,
+        })
+    }
+
+    fn directive(&mut self) -> Option<Directive<'a>> {
+        match self.u8()? {
+            0 => Some(Directive::Hook(self.str()?)),
+            1 => Some(Directive::Yarn(self.str()?)),
+            _ => None,
+        }
+    }
+
+    fn round(&mut self) -> Option<Round<'a>> {
+        Some(Round {
+            index: self.u32()?,
+            label: self.option(Self::str)?,
+            instructions: self.seq(Self::instruction)?,
+            times: self.u32()?,
+            directives: self.seq(Self::directive)?,
+            span: self.span()?,
+            instruction_spans: self.seq(Self::span_tree)?,
+        })
+    }
+
+    fn pattern(&mut self) -> Option<Pattern<'a>> {
+        let mut meta = PatternMeta::default();
+        for field in MetaField::ALL {
+            *meta.field_mut(field) = self.option(Self::str)?;
+        }
+
+        Some(Pattern {
+            meta,
+            parts: self.seq(|r| {
+                Some(Part {
+                    name: r.option(Self::str)?,
+                    rounds: r.seq(Self::round)?,
+                })
+            })?,
+            assembly: self.seq(|r| {
+                Some(AssemblyStep {
+                    text: r.str()?,
+                    parts: r.seq(Self::str)?,
+                    rounds: r.option(|r| Some((r.u32()?, r.u32()?)))?,
+                    span: r.span()?,
+                })
+            })?,
+            version: self.u32()?,
+            notes: self.seq(|r| {
+                Some(TextBlock {
+                    name: r.str()?,
+                    text: r.str()?,
+                })
+            })?,
+        })
+    }
+}
+
+/// Encodes `pattern` for [`Pattern::to_bytes`].
+///
+/// The encoding is [`MAGIC`] and [`FORMAT_VERSION`], followed by the pattern's fields in the order
+/// they're declared. Integers are LEB128 varints, strings and sequences are their length followed
+/// by their bytes or elements, and enum variants are a tag byte followed by their fields.
+pub(crate) fn encode(pattern: &Pattern) -> Vec<u8> {
+    let mut w = Writer {
+        bytes: MAGIC.to_vec(),
+    };
+    w.u8(FORMAT_VERSION);
+    w.pattern(pattern);
+    w.bytes
+}
+
+/// Decodes a pattern encoded by [`encode`], borrowing its strings from `bytes`
+pub(crate) fn decode(bytes: &[u8]) -> Option<Pattern<'_>> {
+    let mut r = Reader { bytes, depth: 0 };
+
+    if r.take(MAGIC.len())? != MAGIC || r.u8()? != FORMAT_VERSION {
+        return None;
+    }
+
+    let pattern = r.pattern()?;
+    r.bytes.is_empty().then_some(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let src = "%crochet 1%
+name: Bear
+mode: joined
+colors: A = cream, B = brown
+```materials
+4mm hook
+```
+== Head ==
+mr 6, @B
+hook 4mm
+yarn \"DK brown\"
+x2: [inc, sc] 3, % note %
+rounds 4-5: cross(fpsc, sc) 3, (sc, ch 2, sc) in next st, ch 2, skip 2, surface sl st 2
+== Ear ==
+ears: sc 6 in mr, tch, blsc, flinc, blinc, bpsc, dec
+== Assembly ==
+sew Ear to Head between rounds 3-4";
+        let pattern = crate::parse_pattern(src).unwrap();
+
+        let bytes = encode(&pattern);
+        assert_eq!(decode(&bytes), Some(pattern));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let pattern = crate::parse_pattern("name: Ball\nsc 6 in mr\ninc 6").unwrap();
+        let bytes = encode(&pattern);
+
+        for len in 0..bytes.len() {
+            assert_eq!(decode(&bytes[..len]), None);
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(decode(&trailing), None);
+
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert_eq!(decode(&newer), None);
+    }
+
+    #[test]
+    fn test_too_deep() {
+        // `IntoMagicRing` around itself over and over
+        let mut bytes = vec![10; 100_000];
+        bytes.push(2);
+        let mut r = Reader {
+            bytes: &bytes,
+            depth: 0,
+        };
+        assert_eq!(r.instruction(), None);
+
+        let mut bytes = vec![10; MAX_DEPTH as usize - 1];
+        bytes.push(2);
+        let mut r = Reader {
+            bytes: &bytes,
+            depth: 0,
+        };
+        assert!(r.instruction().is_some());
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut w = Writer { bytes: Vec::new() };
+        for n in [0, 127, 128, 300, u32::MAX.into(), u64::MAX] {
+            w.varint(n);
+        }
+        assert_eq!(&w.bytes[..4], [0, 127, 0x80, 1]);
+
+        let mut r = Reader {
+            bytes: &w.bytes,
+            depth: 0,
+        };
+        for n in [0, 127, 128, 300, u32::MAX.into(), u64::MAX] {
+            assert_eq!(r.varint(), Some(n));
+        }
+        assert!(r.bytes.is_empty());
+
+        // more than 64 bits
+        let mut r = Reader {
+            bytes: &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f],
+            depth: 0,
+        };
+        assert_eq!(r.varint(), None);
+    }
+}
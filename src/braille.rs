@@ -0,0 +1,425 @@
+use crate::{accessible_format, Pattern};
+
+/// Words that are written as a single sign when they stand alone in contracted braille
+const WORDSIGNS: [(&str, &str); 33] = [
+    ("and", "&"),
+    ("for", "="),
+    ("of", "("),
+    ("the", "!"),
+    ("with", ")"),
+    ("child", "*"),
+    ("shall", "%"),
+    ("this", "?"),
+    ("which", ":"),
+    ("out", "\\"),
+    ("still", "/"),
+    ("but", "B"),
+    ("can", "C"),
+    ("do", "D"),
+    ("every", "E"),
+    ("from", "F"),
+    ("go", "G"),
+    ("have", "H"),
+    ("just", "J"),
+    ("knowledge", "K"),
+    ("like", "L"),
+    ("more", "M"),
+    ("not", "N"),
+    ("people", "P"),
+    ("quite", "Q"),
+    ("rather", "R"),
+    ("so", "S"),
+    ("that", "T"),
+    ("us", "U"),
+    ("very", "V"),
+    ("will", "W"),
+    ("it", "X"),
+    ("you", "Y"),
+];
+
+/// Groups of letters that are written as a single sign within words in contracted braille,
+/// longest first
+const GROUPSIGNS: [(&str, &str); 17] = [
+    ("with", ")"),
+    ("and", "&"),
+    ("for", "="),
+    ("the", "!"),
+    ("ing", "+"),
+    ("of", "("),
+    ("ch", "*"),
+    ("gh", "<"),
+    ("sh", "%"),
+    ("th", "?"),
+    ("wh", ":"),
+    ("ed", "$"),
+    ("er", "]"),
+    ("ou", "\\"),
+    ("ow", "["),
+    ("st", "/"),
+    ("ar", ">"),
+];
+
+/// Options for [`to_brf`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BrailleOptions {
+    /// How many braille cells fit on a line. Lines are broken between words to fit.
+    pub cells_per_line: usize,
+    /// How many lines fit on a page, with a form feed between pages, or `None` to not break the
+    /// text into pages
+    pub lines_per_page: Option<usize>,
+    /// Use the common contractions of Unified English Braille, e.g. `!` for `the`, or write every
+    /// letter out in uncontracted braille
+    pub contracted: bool,
+}
+
+impl Default for BrailleOptions {
+    /// Lines and pages the size of a standard 11 by 11.5 inch braille page, in contracted braille
+    fn default() -> Self {
+        Self {
+            cells_per_line: 40,
+            lines_per_page: Some(25),
+            contracted: true,
+        }
+    }
+}
+
+/// Renders a pattern as a braille-ready file (BRF) in North American ASCII braille, which braille
+/// embossers and notetakers print or show directly.
+///
+/// The pattern is written out as by [`accessible_format`], so abbreviations are spelled out and
+/// nothing depends on brackets, with its name, free-text blocks and part names as headings.
+/// Capital letters and numbers are marked with the Unified English Braille indicators. Contracted
+/// braille uses the whole-word and strong contractions, like `&` for `and`, `*` for `ch` and `+`
+/// for `ing`, but not the rarer contractions that depend on how a word is pronounced.
+///
+/// Characters that have no braille equivalent, like `é`, are written as [`BRF_PLACEHOLDER`]. Lines
+/// end with `\r\n`, with no trailing spaces, and are wrapped to
+/// [`BrailleOptions::cells_per_line`].
+///
+/// ```
+/// # use crochet::{parse_pattern, to_brf, BrailleOptions};
+/// let pattern = parse_pattern("sc 6 in mr").unwrap();
+/// let uncontracted = BrailleOptions {
+///     contracted: false,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     to_brf(&pattern, &uncontracted),
+///     ",ABBREVIATIONS3\r\n\
+///     MR MEANS MAGIC RING4\r\n\
+///     SC MEANS SINGLE CROCHET4\r\n\
+///     \r\n\
+///     ,ROUND #A4 #F SC INTO THE MR4 ,STITCH\r\n\
+///     COUNT3 #F4\r\n"
+/// );
+/// assert_eq!(
+///     to_brf(&pattern, &BrailleOptions::default()),
+///     ",ABBREVIATIONS3\r\n\
+///     MR MEANS MAGIC R+4\r\n\
+///     SC MEANS S+LE CRO*ET4\r\n\
+///     \r\n\
+///     ,R\\ND #A4 #F SC INTO ! MR4 ,/IT* C\\NT3\r\n\
+///     #F4\r\n"
+/// );
+/// ```
+pub fn to_brf(pattern: &Pattern, opts: &BrailleOptions) -> String {
+    let mut sections = Vec::new();
+
+    if let Some(name) = pattern.meta.name {
+        sections.push(name.to_string());
+    }
+    for block in &pattern.notes {
+        sections.push(match block.name {
+            "" => block.text.trim().to_string(),
+            name => format!("{name}\n{}", block.text.trim()),
+        });
+    }
+    for part in &pattern.parts {
+        let rounds = accessible_format(&part.rounds);
+        sections.push(match part.name {
+            Some(name) => format!("{name}\n{rounds}"),
+            None => rounds,
+        });
+    }
+
+    let text = sections.join("\n\n");
+    let lines: Vec<String> = text
+        .lines()
+        .flat_map(|line| wrap(&translate(line, opts.contracted), opts.cells_per_line))
+        .collect();
+
+    let mut ret = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if opts
+            .lines_per_page
+            .is_some_and(|n| n > 0 && i > 0 && i % n == 0)
+        {
+            ret.push('\x0c');
+        }
+        ret.push_str(line.trim_end());
+        ret.push_str("\r\n");
+    }
+
+    ret
+}
+
+/// Translates a line of text into ASCII braille
+fn translate(line: &str, contracted: bool) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut ret = String::new();
+    // whether the last thing written was a number, so letters `a` to `j` would be read as digits
+    let mut after_number = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_ascii_digit() {
+            ret.push('#');
+            while let Some(&c) = chars.get(i) {
+                let in_number = chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+                match c {
+                    '1'..='9' => ret.push(char::from(b'A' + (c as u8 - b'1'))),
+                    '0' => ret.push('J'),
+                    '.' if in_number => ret.push('4'),
+                    ',' if in_number => ret.push('1'),
+                    _ => break,
+                }
+                i += 1;
+            }
+            after_number = true;
+        } else if c.is_ascii_alphabetic() {
+            let start = i;
+            while chars.get(i).is_some_and(char::is_ascii_alphabetic) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let standalone = [start.checked_sub(1), Some(i)]
+                .into_iter()
+                .all(|j| j.and_then(|j| chars.get(j)).is_none_or(|&c| !joins_word(c)));
+
+            let braille = translate_word(&word, contracted && standalone, contracted);
+            if after_number && braille.starts_with(|c| matches!(c, 'A'..='J')) {
+                // the grade 1 indicator ends the number
+                ret.push(';');
+            }
+            ret.push_str(&braille);
+            after_number = false;
+        } else {
+            ret.push_str(punctuation(c));
+            after_number = false;
+            i += 1;
+        }
+    }
+
+    ret
+}
+
+/// Whether `c` is part of the same word as letters next to it, so they don't stand alone
+fn joins_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '\''
+}
+
+/// Translates a word made of only letters. `standalone` is whether it's a whole word that could be
+/// a wordsign, and `contracted` whether to use groupsigns within it.
+fn translate_word(word: &str, standalone: bool, contracted: bool) -> String {
+    let lower = word.to_ascii_lowercase();
+    // contractions only stand for lowercase or capitalized words
+    let capitalized = word.chars().skip(1).all(|c| c.is_ascii_lowercase());
+    let capital = if word.starts_with(|c: char| c.is_ascii_uppercase()) {
+        ","
+    } else {
+        ""
+    };
+
+    if standalone && capitalized {
+        if let Some((_, sign)) = WORDSIGNS.iter().find(|(w, _)| *w == lower) {
+            return format!("{capital}{sign}");
+        }
+        if word.len() == 1 && !matches!(lower.as_str(), "a" | "i" | "o") {
+            // a letter on its own would be read as the word it stands for
+            return format!(";{capital}{}", word.to_ascii_uppercase());
+        }
+    }
+
+    let mut ret = String::new();
+    let mut i = 0;
+    while i < word.len() {
+        let group = GROUPSIGNS.iter().find(|(letters, _)| {
+            contracted
+                && capitalized
+                && lower[i..].starts_with(letters)
+                // a word that's nothing but a groupsign would be read as a wordsign
+                && letters.len() < word.len()
+                && !(*letters == "ing" && i == 0)
+        });
+
+        let c = &word[i..=i];
+        if c.starts_with(|c: char| c.is_ascii_uppercase()) {
+            ret.push(',');
+        }
+        match group {
+            Some((letters, sign)) => {
+                ret.push_str(sign);
+                i += letters.len();
+            }
+            None => {
+                ret.push_str(&c.to_ascii_uppercase());
+                i += 1;
+            }
+        }
+    }
+
+    ret
+}
+
+/// What [`to_brf`] writes for characters that have no braille equivalent, so they aren't silently
+/// left out
+pub const BRF_PLACEHOLDER: &str = "@?";
+
+/// The ASCII braille for a character that isn't a letter or digit, or [`BRF_PLACEHOLDER`] if it
+/// doesn't have a braille equivalent
+fn punctuation(c: char) -> &'static str {
+    match c {
+        ' ' => " ",
+        '.' => "4",
+        ',' => "1",
+        ':' => "3",
+        ';' => "2",
+        '?' => "8",
+        '!' => "6",
+        '\'' => "'",
+        '-' => "-",
+        '"' => "_8",
+        '(' => "\"<",
+        ')' => "\">",
+        '[' => ".<",
+        ']' => ".>",
+        '/' => "_/",
+        '%' => ".0",
+        '&' => "@&",
+        '@' => "@A",
+        '*' => "\"9",
+        '+' => "\"6",
+        '=' => "\"7",
+        '#' => "_?",
+        '<' => "@<",
+        '>' => "@>",
+        '$' => "@S",
+        '^' => "@5",
+        '~' => "@9",
+        '_' => ".-",
+        '\\' => "_*",
+        '|' => "_|",
+        '{' => "_<",
+        '}' => "_>",
+        '\t' => " ",
+        _ => BRF_PLACEHOLDER,
+    }
+}
+
+/// Breaks a line of braille between words so each line fits in `width` cells, splitting words
+/// that don't fit on a line of their own
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut ret = Vec::new();
+    let mut current = String::new();
+
+    for word in line.split(' ') {
+        let mut word = word;
+        if !current.is_empty() && current.len() + 1 + word.len() > width {
+            ret.push(std::mem::take(&mut current));
+        }
+        while word.len() > width {
+            let (head, tail) = word.split_at(width);
+            if !current.is_empty() {
+                ret.push(std::mem::take(&mut current));
+            }
+            ret.push(head.to_string());
+            word = tail;
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    ret.push(current);
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pattern;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(translate("18 stitches", false), "#AH STITCHES");
+        assert_eq!(translate("3.5mm hook", false), "#C4EMM HOOK");
+        assert_eq!(translate("2b or 2, 3", false), "#B;B OR #B1 #C");
+        assert_eq!(translate("Round 10.", false), ",ROUND #AJ4");
+    }
+
+    #[test]
+    fn test_contractions() {
+        assert_eq!(translate("the thing and", true), "! ?+ &");
+        assert_eq!(translate("The Chain", true), ",! ,*AIN");
+        // groupsigns that are the whole word, or "ing" at the start, aren't contracted
+        assert_eq!(translate("ch st ingot", true), "CH ST INGOT");
+        assert_eq!(translate("color B", true), "COLOR ;,B");
+        assert_eq!(translate("a I", true), "A ,I");
+        // nor are words with capitals after their first letter
+        assert_eq!(translate("THE", true), ",T,H,E");
+        // the letters of a word with an apostrophe in it don't stand alone
+        assert_eq!(translate("don't", true), "DON'T");
+        assert_eq!(translate("the", false), "THE");
+    }
+
+    #[test]
+    fn test_punctuation() {
+        assert_eq!(
+            translate("note: stuff (a lot)!", false),
+            "NOTE3 STUFF \"<A LOT\">6"
+        );
+        assert_eq!(translate("50% é", false), "#EJ.0 @?");
+        assert_eq!(translate("Part <2>", false), ",PART @<#B@>");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("AB CD EF", 5), ["AB CD", "EF"]);
+        assert_eq!(wrap("ABCDEFGH IJ", 3), ["ABC", "DEF", "GH", "IJ"]);
+        assert_eq!(wrap("", 5), [""]);
+    }
+
+    #[test]
+    fn test_trailing_spaces() {
+        let pattern = parse_pattern("```notes\nhi \nthere\n```\nsc 6 in mr").unwrap();
+        let brf = to_brf(&pattern, &BrailleOptions::default());
+        assert!(brf.starts_with("NOTES\r\nHI\r\n!RE\r\n"));
+        assert!(!brf.contains(" \r\n"));
+    }
+
+    #[test]
+    fn test_pages() {
+        let src = "name: Ball\n== Head ==\nsc 6 in mr\ninc 6\n== Arm ==\nsc 4 in mr\nsc 4";
+        let pattern = parse_pattern(src).unwrap();
+        let opts = BrailleOptions {
+            cells_per_line: 30,
+            lines_per_page: Some(4),
+            contracted: false,
+        };
+        let brf = to_brf(&pattern, &opts);
+        let lines: Vec<_> = brf.split("\r\n").collect();
+
+        assert!(lines
+            .iter()
+            .all(|l| l.trim_start_matches('\x0c').len() <= 30));
+        assert_eq!(lines[..3], [",BALL", "", ",HEAD"]);
+        assert_eq!(lines[4], "\x0cMR MEANS MAGIC RING4");
+        assert_eq!(brf.matches('\x0c').count(), (lines.len() - 2) / 4);
+        assert!(brf.ends_with("COUNT3 #D4\r\n"));
+    }
+}
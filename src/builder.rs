@@ -0,0 +1,208 @@
+use crate::{Directive, Instruction, Round};
+
+/// Builds a [`Round`] one instruction at a time, for constructing patterns in code.
+///
+/// Stitch methods take how many of the stitch to work, and only wrap it in a `Repeat` when that
+/// isn't 1. The result displays the same as the equivalent source would.
+///
+/// ```
+/// # use crochet::RoundBuilder;
+/// let round = RoundBuilder::new().sc(6).in_mr().build();
+/// assert_eq!(round.to_string(), "sc 6 in mr");
+///
+/// let round = RoundBuilder::new()
+///     .label("widest")
+///     .ch(1)
+///     .repeat(6, |b| b.inc(1).sc(2))
+///     .build();
+/// assert_eq!(round.to_string(), "widest: ch, [inc, sc 2] 6");
+/// assert_eq!(round.output_count(), 25);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RoundBuilder<'a> {
+    instructions: Vec<Instruction<'a>>,
+    label: Option<&'a str>,
+    times: u32,
+    directives: Vec<Directive<'a>>,
+}
+
+impl Default for RoundBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> RoundBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+            label: None,
+            times: 1,
+            directives: Vec::new(),
+        }
+    }
+
+    /// Adds an instruction as is
+    pub fn push(mut self, inst: Instruction<'a>) -> Self {
+        self.instructions.push(inst);
+        self
+    }
+
+    /// Adds `n` of a stitch
+    fn stitch(self, inst: Instruction<'a>, n: u32) -> Self {
+        match n {
+            1 => self.push(inst),
+            _ => self.push(Instruction::Repeat(inst.into(), n)),
+        }
+    }
+
+    pub fn ch(self, n: u32) -> Self {
+        self.stitch(Instruction::Ch, n)
+    }
+
+    pub fn tch(self, n: u32) -> Self {
+        self.stitch(Instruction::Tch, n)
+    }
+
+    pub fn sc(self, n: u32) -> Self {
+        self.stitch(Instruction::Sc, n)
+    }
+
+    pub fn fpsc(self, n: u32) -> Self {
+        self.stitch(Instruction::Fpsc, n)
+    }
+
+    pub fn bpsc(self, n: u32) -> Self {
+        self.stitch(Instruction::Bpsc, n)
+    }
+
+    pub fn blsc(self, n: u32) -> Self {
+        self.stitch(Instruction::Blsc, n)
+    }
+
+    pub fn inc(self, n: u32) -> Self {
+        self.stitch(Instruction::Inc, n)
+    }
+
+    pub fn flinc(self, n: u32) -> Self {
+        self.stitch(Instruction::Flinc, n)
+    }
+
+    pub fn blinc(self, n: u32) -> Self {
+        self.stitch(Instruction::Blinc, n)
+    }
+
+    pub fn dec(self, n: u32) -> Self {
+        self.stitch(Instruction::Dec, n)
+    }
+
+    pub fn skip(self, n: u32) -> Self {
+        self.push(Instruction::Skip(n))
+    }
+
+    pub fn surface_sl_st(self, n: u32) -> Self {
+        self.push(Instruction::SurfaceSlSt(n))
+    }
+
+    /// Adds a `ch N, skip M` chain space
+    pub fn ch_space(self, chains: u32, skipped: u32) -> Self {
+        self.push(Instruction::ChSpace { chains, skipped })
+    }
+
+    pub fn comment(self, text: &'a str) -> Self {
+        self.push(Instruction::Comment(text))
+    }
+
+    /// Switches to the color with the given palette key
+    pub fn color(self, key: &'a str) -> Self {
+        self.push(Instruction::Color(key))
+    }
+
+    /// Works the last instruction added into a magic ring.
+    ///
+    /// # Panics
+    ///
+    /// If no instructions have been added yet.
+    pub fn in_mr(mut self) -> Self {
+        let last = self
+            .instructions
+            .pop()
+            .expect("no instruction to work in mr");
+        self.push(Instruction::IntoMagicRing(last.into()))
+    }
+
+    /// Adds a group built by `f`, worked `times` times, e.g. `[inc, sc 2] 6`
+    pub fn repeat(self, times: u32, f: impl FnOnce(Self) -> Self) -> Self {
+        let group = Instruction::Group(f(Self::new()).instructions);
+        self.push(Instruction::Repeat(group.into(), times))
+    }
+
+    /// Adds a group built by `f`, worked into a single stitch, e.g. `(sc, ch 2, sc) in next st`
+    pub fn in_next_st(self, f: impl FnOnce(Self) -> Self) -> Self {
+        let group = Instruction::Group(f(Self::new()).instructions);
+        self.push(Instruction::IntoOne(group.into()))
+    }
+
+    /// Adds `cross(a, b)`
+    pub fn cross(self, a: Instruction<'a>, b: Instruction<'a>) -> Self {
+        self.push(Instruction::Crossed(a.into(), b.into()))
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Sets how many times in a row the round is worked, like `xN:`
+    pub fn times(mut self, times: u32) -> Self {
+        self.times = times;
+        self
+    }
+
+    /// Switches to the given hook size before the round
+    pub fn hook(mut self, size: &'a str) -> Self {
+        self.directives.push(Directive::Hook(size));
+        self
+    }
+
+    /// Switches to the given yarn before the round
+    pub fn yarn(mut self, yarn: &'a str) -> Self {
+        self.directives.push(Directive::Yarn(yarn));
+        self
+    }
+
+    pub fn build(self) -> Round<'a> {
+        Round {
+            label: self.label,
+            times: self.times,
+            directives: self.directives,
+            ..Round::new(self.instructions)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    #[test]
+    fn test_matches_parsed() {
+        let src = "hook 4mm\nx3: blsc, [sc 2, @A] 4, (sc, ch 2, sc) in next st, cross(fpsc, sc), ch 2, skip 2, % hi %";
+        let built = RoundBuilder::new()
+            .hook("4mm")
+            .times(3)
+            .blsc(1)
+            .repeat(4, |b| b.sc(2).color("A"))
+            .in_next_st(|b| b.sc(1).ch(2).sc(1))
+            .cross(Instruction::Fpsc, Instruction::Sc)
+            .ch_space(2, 2)
+            .comment("hi")
+            .build();
+
+        let with_palette = format!("colors: A = red\n{src}");
+        let parsed = &parse_rounds(&with_palette).unwrap()[0];
+        assert_eq!(built.instructions, parsed.instructions);
+        assert_eq!(built.to_string(), src);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::Instruction;
+
+/// Rewrites a list of instructions into the shortest equivalent form, for tidying up imported or
+/// generated patterns.
+///
+/// - runs of the same stitch are counted: `sc, sc 2` becomes `sc 3`
+/// - groups with a single instruction, and groups or repeats worked once, are unwrapped
+/// - repeats of repeats are multiplied out: `[sc 2] 3` becomes `sc 6`
+/// - repeating sequences are grouped: `inc, sc, inc, sc` becomes `[inc, sc] 2`
+/// - neighbouring instructions worked into the magic ring are combined
+///
+/// ```
+/// # use crochet::{canonicalize, parse_rounds, Instruction};
+/// let round = &parse_rounds("sc, sc, [inc, sc, inc, sc] 1, sc 2, sc").unwrap()[0];
+/// let canonical = Instruction::Group(canonicalize(&round.instructions));
+/// assert_eq!(canonical.to_string(), "sc 2, [inc, sc] 2, sc 3");
+/// ```
+pub fn canonicalize<'a>(insts: &[Instruction<'a>]) -> Vec<Instruction<'a>> {
+    let mut flat = Vec::new();
+    for inst in insts {
+        match canonicalize_one(inst.clone()) {
+            Instruction::Group(g) => flat.extend(g),
+            inst => flat.push(inst),
+        }
+    }
+
+    merge_runs(fold_periods(merge_runs(merge_rings(flat))))
+}
+
+/// Tidies up an instruction by undoing the artifacts that code generating patterns tends to
+/// leave, without otherwise changing how it's written like [`canonicalize`] does:
+///
+/// - groups directly inside groups are flattened: `[sc, [inc, sc]] 2` becomes `[sc, inc, sc] 2`
+/// - repeats worked once are unwrapped: `[inc] 1` becomes `inc`
+/// - repeats are merged with the same instructions next to them: `[inc, sc] 2, inc, sc` becomes
+///   `[inc, sc] 3`, and `sc, sc 2` becomes `sc 3`
+///
+/// ```
+/// # use crochet::{parse_rounds, simplify, Instruction};
+/// let round = &parse_rounds("[sc 2] 1, sc, [inc, [sc, inc]], [sc, inc] 2").unwrap()[0];
+/// let simplified = simplify(&Instruction::Group(round.instructions.clone()));
+/// assert_eq!(simplified.to_string(), "sc 3, inc, [sc, inc] 3");
+/// ```
+pub fn simplify<'a>(inst: &Instruction<'a>) -> Instruction<'a> {
+    simplify_one(inst.clone())
+}
+
+fn simplify_one(inst: Instruction) -> Instruction {
+    use Instruction::*;
+
+    match inst {
+        Group(g) => {
+            let mut flat = Vec::new();
+            for inst in g {
+                match simplify_one(inst) {
+                    Group(g) => flat.extend(g),
+                    inst => flat.push(inst),
+                }
+            }
+            Group(merge_repeats(flat))
+        }
+        // the brackets were only there for the count
+        Repeat(i, 1) => match simplify_one(*i) {
+            Group(mut g) if g.len() == 1 => g.remove(0),
+            i => i,
+        },
+        Repeat(i, times) => Repeat(simplify_one(*i).into(), times),
+        IntoMagicRing(i) => IntoMagicRing(simplify_one(*i).into()),
+        IntoOne(i) => IntoOne(simplify_one(*i).into()),
+        Crossed(a, b) => Crossed(simplify_one(*a).into(), simplify_one(*b).into()),
+        _ => inst,
+    }
+}
+
+/// The instructions worked by each repetition of `inst`, e.g. `inc, sc` for `[inc, sc]`
+fn repeated<'r, 'a>(inst: &'r Instruction<'a>) -> &'r [Instruction<'a>] {
+    match inst {
+        Instruction::Group(g) => g,
+        inst => std::slice::from_ref(inst),
+    }
+}
+
+/// Merges each repeat with repeats of the same thing, or those instructions written out, next to
+/// it
+fn merge_repeats(insts: Vec<Instruction>) -> Vec<Instruction> {
+    use Instruction::*;
+
+    let mut ret: Vec<Instruction> = Vec::new();
+    let mut i = 0;
+
+    while i < insts.len() {
+        if let Some(Repeat(prev, times)) = ret.last_mut() {
+            match &insts[i] {
+                Repeat(next, more) if next == prev && times.checked_add(*more).is_some() => {
+                    *times += more;
+                    i += 1;
+                    continue;
+                }
+                _ if !repeated(prev).is_empty()
+                    && insts[i..].starts_with(repeated(prev))
+                    && *times < u32::MAX =>
+                {
+                    *times += 1;
+                    i += repeated(prev).len();
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        match &insts[i] {
+            // the instructions were written out once before being repeated
+            Repeat(next, times)
+                if !repeated(next).is_empty()
+                    && ret.ends_with(repeated(next))
+                    && *times < u32::MAX =>
+            {
+                ret.truncate(ret.len() - repeated(next).len());
+                ret.push(Repeat(next.clone(), times + 1));
+            }
+            inst => ret.push(inst.clone()),
+        }
+        i += 1;
+    }
+
+    ret
+}
+
+fn canonicalize_one(inst: Instruction) -> Instruction {
+    use Instruction::*;
+
+    match inst {
+        Group(g) => {
+            let mut g = canonicalize(&g);
+            if g.len() == 1 {
+                g.remove(0)
+            } else {
+                Group(g)
+            }
+        }
+        Repeat(i, 1) => canonicalize_one(*i),
+        Repeat(i, times) => match canonicalize_one(*i) {
+            // counts too big to multiply out are left nested
+            Repeat(i, inner) => match inner.checked_mul(times) {
+                Some(product) => Repeat(i, product),
+                None => repeat(Repeat(i, inner), times),
+            },
+            i => repeat(i, times),
+        },
+        IntoMagicRing(i) => IntoMagicRing(canonicalize_one(*i).into()),
+        // the group is kept so that it's still written in parentheses
+        IntoOne(i) => {
+            let insts = match *i {
+                Group(g) => canonicalize(&g),
+                i => canonicalize(&[i]),
+            };
+            IntoOne(Group(insts).into())
+        }
+        Crossed(a, b) => Crossed(canonicalize_one(*a).into(), canonicalize_one(*b).into()),
+        _ => inst,
+    }
+}
+
+/// Whether `inst` can be written with a count after it, like `sc 3` or `[inc, sc] 6`
+fn takes_count(inst: &Instruction) -> bool {
+    use Instruction::*;
+
+    matches!(
+        inst,
+        Ch | Sc | Fpsc | Bpsc | Blsc | Inc | Flinc | Blinc | Dec | Group(_) | Crossed(..)
+    )
+}
+
+/// Repeats `inst`, wrapping it in a group if it can't be written with a count otherwise
+fn repeat(inst: Instruction, times: u32) -> Instruction {
+    if takes_count(&inst) {
+        Instruction::Repeat(inst.into(), times)
+    } else {
+        Instruction::Repeat(Instruction::Group(vec![inst]).into(), times)
+    }
+}
+
+/// Combines neighbouring instructions worked into the magic ring, e.g. `sc in mr, inc in mr`
+/// becomes `[sc, inc] in mr`
+fn merge_rings(insts: Vec<Instruction>) -> Vec<Instruction> {
+    use Instruction::*;
+
+    let mut ret: Vec<Instruction> = Vec::new();
+    for inst in insts {
+        match (ret.last_mut(), inst) {
+            (Some(IntoMagicRing(prev)), IntoMagicRing(i)) => {
+                let prev_inst = std::mem::replace(&mut **prev, Group(Vec::new()));
+                **prev = canonicalize_one(Group(vec![prev_inst, *i]));
+            }
+            (_, inst) => ret.push(inst),
+        }
+    }
+
+    ret
+}
+
+/// Counts runs of the same instruction, e.g. `sc 2, sc` becomes `sc 3`
+fn merge_runs(insts: Vec<Instruction>) -> Vec<Instruction> {
+    let mut runs: Vec<(Instruction, u32)> = Vec::new();
+
+    for inst in insts {
+        let (base, times) = match inst {
+            Instruction::Repeat(i, times) => (*i, times),
+            inst => (inst, 1),
+        };
+
+        match runs.last_mut() {
+            Some((prev, n))
+                if *prev == base && takes_count(&base) && n.checked_add(times).is_some() =>
+            {
+                *n += times
+            }
+            _ => runs.push((base, times)),
+        }
+    }
+
+    runs.into_iter()
+        .map(|(inst, times)| match times {
+            1 => inst,
+            _ => repeat(inst, times),
+        })
+        .collect()
+}
+
+/// The most instructions a sequence found by [`fold_periods`] can have. Longer sequences would be
+/// hard to follow as a repeat anyways, and looking for them makes folding quadratic in the length
+/// of the round.
+const MAX_PERIOD: usize = 16;
+
+/// Groups repeating sequences of two to [`MAX_PERIOD`] instructions, e.g. `inc, sc, inc, sc`
+/// becomes `[inc, sc] 2`. At each point, the repetition covering the most instructions is picked.
+fn fold_periods(insts: Vec<Instruction>) -> Vec<Instruction> {
+    let mut ret = Vec::new();
+    let mut i = 0;
+
+    while i < insts.len() {
+        // continue a repeat that was already written as one, e.g. the `inc, sc` after `[inc, sc] 2`
+        if let Some(Instruction::Repeat(g, times)) = ret.last_mut() {
+            if let Instruction::Group(seq) = &**g {
+                if insts[i..].starts_with(seq) && *times < u32::MAX {
+                    i += seq.len();
+                    *times += 1;
+                    continue;
+                }
+            }
+        }
+
+        // (length of the sequence, how many times it's repeated)
+        let mut best = (1, 1);
+
+        for len in 2..=((insts.len() - i) / 2).min(MAX_PERIOD) {
+            let seq = &insts[i..i + len];
+            let times = insts[i..]
+                .chunks_exact(len)
+                .take_while(|chunk| *chunk == seq)
+                .count();
+
+            if times >= 2 && len * times > best.0 * best.1 {
+                best = (len, times);
+            }
+        }
+
+        match best {
+            (len, times) if times >= 2 => {
+                let group = Instruction::Group(insts[i..i + len].to_vec());
+                ret.push(Instruction::Repeat(group.into(), times as u32));
+                i += len * times;
+            }
+            _ => {
+                ret.push(insts[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn canonical(src: &str) -> String {
+        let round = &parse_rounds(src).unwrap()[0];
+        Instruction::Group(canonicalize(&round.instructions)).to_string()
+    }
+
+    #[test]
+    fn test_runs() {
+        assert_eq!(canonical("sc, sc, sc"), "sc 3");
+        assert_eq!(canonical("sc 2, sc"), "sc 3");
+        assert_eq!(canonical("sc, [sc] 1, [[sc] 2] 1"), "sc 4");
+        assert_eq!(
+            canonical("ch, ch, sc, skip 1, skip 1"),
+            "ch 2, sc, skip 1, skip 1"
+        );
+        assert_eq!(canonical("sc, % hi %, sc"), "sc, % hi %, sc");
+    }
+
+    #[test]
+    fn test_nested_repeats() {
+        assert_eq!(canonical("[sc 2] 3"), "sc 6");
+        assert_eq!(canonical("[[inc, sc] 2] 3"), "[inc, sc] 6");
+        assert_eq!(canonical("[skip 1] 2"), "[skip 1] 2");
+        // too many stitches to count in a u32
+        assert_eq!(canonical("[sc 100000] 100000"), "[sc 100000] 100000");
+        assert_eq!(canonical("sc 4294967295, sc 2"), "sc 4294967295, sc 2");
+    }
+
+    #[test]
+    fn test_periods() {
+        assert_eq!(canonical("inc, sc, inc, sc"), "[inc, sc] 2");
+        assert_eq!(
+            canonical("sc, inc, sc, inc, sc, inc, sc 2"),
+            "[sc, inc] 3, sc 2"
+        );
+        assert_eq!(canonical("sc, sc, inc, sc 2, inc"), "[sc 2, inc] 2");
+        assert_eq!(canonical("[inc, sc] 2, inc, sc"), "[inc, sc] 3");
+        assert_eq!(canonical("inc, sc, dec, sc"), "inc, sc, dec, sc");
+    }
+
+    #[test]
+    fn test_wrappers() {
+        assert_eq!(
+            canonical("sc in mr, sc 2 in mr, inc in mr"),
+            "[sc 3, inc] in mr"
+        );
+        assert_eq!(canonical("[sc, sc, sc] 2 in mr"), "sc 6 in mr");
+        assert_eq!(canonical("(sc, sc) in next st"), "(sc 2) in next st");
+        assert_eq!(canonical("cross([fpsc] 1, sc)"), "cross(fpsc, sc)");
+    }
+
+    fn simplified(src: &str) -> String {
+        let round = &parse_rounds(src).unwrap()[0];
+        simplify(&Instruction::Group(round.instructions.clone())).to_string()
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(simplified("[sc, [inc, [sc]]] 2"), "[sc, inc, sc] 2");
+        assert_eq!(simplified("[[inc] 1] 1, [sc 2] 1 in mr"), "inc, sc 2 in mr");
+        assert_eq!(simplified("sc 2, sc 3, sc"), "sc 6");
+        assert_eq!(simplified("inc, sc, [inc, sc] 2, inc, sc"), "[inc, sc] 4");
+        assert_eq!(
+            simplified("cross([fpsc] 1, sc), (sc, [ch] 1) in next st"),
+            "cross(fpsc, sc), (sc, ch) in next st"
+        );
+        // unlike canonicalize, runs without any repeats are left alone
+        assert_eq!(
+            simplified("sc, sc, inc, sc, inc, sc"),
+            "sc, sc, inc, sc, inc, sc"
+        );
+        assert_eq!(
+            simplified("[skip 1] 2, skip 1, % hi %"),
+            "[skip 1] 3, % hi %"
+        );
+        assert_eq!(
+            simplified("[sc] 4294967295, sc, [sc] 2"),
+            "[sc] 4294967295, [sc] 3"
+        );
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let src = "sc, sc, inc, sc, inc, sc, [skip 1] 2, [[dec] 2] 3, ch, ch";
+        let expected = &parse_rounds(src).unwrap()[0].instructions;
+        let canonical = canonical(src);
+        let reparsed = &parse_rounds(&canonical).unwrap()[0].instructions;
+
+        let reparsed = Instruction::Group(reparsed.clone());
+        assert!(reparsed.semantically_eq(&Instruction::Group(expected.clone())));
+    }
+}
@@ -0,0 +1,659 @@
+use crate::round::expand_rounds;
+use crate::text::escape;
+use crate::{Instruction, Round, StitchGraph};
+use std::f64::consts::TAU;
+use std::fmt::Write;
+
+/// The radius of the innermost round, and the least space between rounds
+const FIRST_RADIUS: f64 = 30.0;
+const ROUND_SPACING: f64 = 30.0;
+/// The least distance between neighbouring stitches along their round
+const STITCH_SPACING: f64 = 16.0;
+/// The space around the outermost round
+const MARGIN: f64 = 20.0;
+/// The distance between rows of a row chart
+const ROW_HEIGHT: f64 = 24.0;
+
+/// A symbol drawn on the chart
+#[derive(Debug, PartialEq)]
+struct Symbol {
+    /// Which round it's on, from 0
+    round: usize,
+    /// Clockwise from the top, in radians
+    angle: f64,
+    /// The SVG elements of the symbol, drawn upright around the origin, with up pointing away from
+    /// the center of the chart
+    shape: String,
+    /// What the symbol stands for, shown as its tooltip
+    title: String,
+}
+
+/// Renders rounds worked in the round as a stitch chart in SVG, with each round on its own circle
+/// around the magic ring and the standard Craft Yarn Council symbols: an oval for a chain, an `X`
+/// for a single crochet, a `V` for an increase and a `Λ` for a decrease. Back and front loop
+/// stitches are marked with an arc under them, and post stitches with a hook. Custom stitches are
+/// drawn with their [`crate::CustomStitch::symbol`].
+///
+/// Each stitch is drawn above the stitches of the previous round that it's worked into, as worked
+/// out by [`StitchGraph`], so an increase sits over its stitch and a decrease over the gap between
+/// its two. Stitches that aren't worked into the previous round, like chains, are spread out
+/// evenly between the ones that are. Each symbol has its stitch as a tooltip.
+///
+/// ```
+/// # use crochet::{parse_rounds, render_chart};
+/// let svg = render_chart(&parse_rounds("sc 6 in mr\ninc 6\n[inc, sc] 6").unwrap());
+///
+/// assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+/// assert_eq!(svg.matches("<title>sc</title>").count(), 12);
+/// assert_eq!(svg.matches("<title>inc</title>").count(), 12);
+/// ```
+pub fn render_chart(rounds: &[Round]) -> String {
+    let (symbols, radii) = layout(rounds);
+    let size = radii.last().copied().unwrap_or(FIRST_RADIUS) + MARGIN;
+
+    let mut ret = String::new();
+    writeln!(
+        ret,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" \
+            height=\"{}\">",
+        -size,
+        -size,
+        2.0 * size,
+        2.0 * size,
+        2.0 * size,
+        2.0 * size
+    )
+    .expect("writing to a string shouldn't fail");
+
+    // light guide circles for the rounds, under the symbols
+    ret.push_str("<g fill=\"none\" stroke=\"#ccc\" stroke-width=\"0.5\">\n");
+    for radius in &radii {
+        writeln!(ret, "<circle r=\"{radius:.1}\"/>").expect("writing to a string shouldn't fail");
+    }
+    ret.push_str("</g>\n");
+
+    let magic_ring = rounds.first().is_some_and(|r| {
+        r.instructions
+            .iter()
+            .any(|i| matches!(i, Instruction::IntoMagicRing(_)))
+    });
+    ret.push_str("<g fill=\"none\" stroke=\"black\" stroke-width=\"1.2\">\n");
+    if magic_ring {
+        ret.push_str("<circle r=\"8\"><title>magic ring</title></circle>\n");
+    }
+    for symbol in &symbols {
+        let radius = radii[symbol.round];
+        let (x, y) = (radius * symbol.angle.sin(), -radius * symbol.angle.cos());
+        let rotation = symbol.angle.to_degrees();
+        write_symbol(&mut ret, (x, y), rotation, &symbol.title, &symbol.shape);
+    }
+    ret.push_str("</g>\n</svg>");
+
+    ret
+}
+
+/// Writes a symbol, centered at `at` and turned clockwise by `rotation` degrees
+fn write_symbol(ret: &mut String, at: (f64, f64), rotation: f64, title: &str, shape: &str) {
+    let (x, y) = at;
+    writeln!(
+        ret,
+        "<g transform=\"translate({x:.1} {y:.1}) rotate({rotation:.1})\"><title>{}</title>{shape}</g>",
+        escape(title)
+    )
+    .expect("writing to a string shouldn't fail");
+}
+
+/// Works out where each stitch's symbol goes, and the radius of each round
+fn layout(rounds: &[Round]) -> (Vec<Symbol>, Vec<f64>) {
+    let graph = StitchGraph::new(rounds);
+    let mut symbols = Vec::new();
+    let mut radii: Vec<f64> = Vec::new();
+    // the angle of each stitch of the previous round
+    let mut previous: Vec<f64> = Vec::new();
+
+    for (r, round) in expand_rounds(rounds).enumerate() {
+        let count = graph.stitch_count(r).unwrap_or(0);
+
+        // stitches worked into the previous round go above their parents
+        let parents: Vec<&[usize]> = (0..count)
+            .map(|s| graph.parents(r, s).unwrap_or(&[]))
+            .collect();
+        let mut angles: Vec<Option<f64>> = parents
+            .iter()
+            .map(|ps| mean_angle(ps.iter().filter_map(|&p| previous.get(p).copied())))
+            .collect();
+        spread_siblings(&mut angles, &parents, TAU / previous.len().max(1) as f64);
+        spread_out(&mut angles);
+        let angles: Vec<f64> = angles
+            .into_iter()
+            .map(|a| a.unwrap_or(0.0).rem_euclid(TAU))
+            .collect();
+
+        let min_radius = count as f64 * STITCH_SPACING / TAU;
+        let radius = match radii.last() {
+            Some(last) => min_radius.max(last + ROUND_SPACING),
+            None => min_radius.max(FIRST_RADIUS),
+        };
+        radii.push(radius);
+
+        let mut next = 0;
+        for (_, inst) in round.iter_stitches() {
+            let made = inst.output_count() as usize;
+            let stitches = next..(next + made).min(count);
+            next += made;
+
+            if let Instruction::ChSpace { .. } = inst {
+                // each chain is a symbol of its own
+                for s in stitches {
+                    symbols.push(Symbol {
+                        round: r,
+                        angle: angles[s],
+                        shape: shape(&Instruction::Ch),
+                        title: Instruction::Ch.to_string(),
+                    });
+                }
+            } else if let Some(angle) = mean_angle(stitches.map(|s| angles[s])) {
+                symbols.push(Symbol {
+                    round: r,
+                    angle,
+                    shape: shape(inst),
+                    title: inst.to_string(),
+                });
+            }
+        }
+
+        previous = angles;
+    }
+
+    (symbols, radii)
+}
+
+/// The average direction of some angles, or `None` if there aren't any
+fn mean_angle(angles: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut n = 0;
+    let (mut x, mut y) = (0.0, 0.0);
+    for a in angles {
+        n += 1;
+        x += a.sin();
+        y += a.cos();
+    }
+
+    (n > 0).then(|| x.atan2(y).rem_euclid(TAU))
+}
+
+/// Spreads out each run of stitches worked into the same stitches of the previous round, which
+/// would otherwise all be drawn in the same place, evenly across `width` around that place. A
+/// negative `width` spreads them the other way.
+fn spread_siblings(positions: &mut [Option<f64>], parents: &[&[usize]], width: f64) {
+    let mut start = 0;
+    while start < positions.len() {
+        let len = parents[start..]
+            .iter()
+            .take_while(|ps| **ps == parents[start])
+            .count();
+
+        if !parents[start].is_empty() {
+            for (i, pos) in positions[start..start + len].iter_mut().enumerate() {
+                if let Some(pos) = pos {
+                    *pos += width * ((i as f64 + 0.5) / len as f64 - 0.5);
+                }
+            }
+        }
+        start += len;
+    }
+}
+
+/// Fills in the angles of stitches that aren't worked into anything by spreading them out evenly
+/// between the stitches around them, or around the whole circle if none of the stitches are
+fn spread_out(angles: &mut [Option<f64>]) {
+    let n = angles.len();
+    let placed: Vec<usize> = (0..n).filter(|&i| angles[i].is_some()).collect();
+
+    let Some(&first) = placed.first() else {
+        for (i, a) in angles.iter_mut().enumerate() {
+            *a = Some(TAU * (i as f64 + 0.5) / n as f64);
+        }
+        return;
+    };
+
+    // each run of unplaced stitches goes between the placed stitch before it and the one after
+    // it, wrapping around the end of the round
+    let mut start = first;
+    for &end in placed.iter().skip(1).chain([&(first + n)]) {
+        let gap = end - start;
+        let from = angles[start].expect("placed stitches have angles");
+        let to = angles[end % n].expect("placed stitches have angles");
+        let mut span = (to - from).rem_euclid(TAU);
+        if span == 0.0 && gap == n {
+            span = TAU;
+        }
+
+        for k in 1..gap {
+            let angle = from + span * k as f64 / gap as f64;
+            angles[(start + k) % n] = Some(angle.rem_euclid(TAU));
+        }
+        start = end;
+    }
+}
+
+/// A symbol drawn on a row chart
+#[derive(Debug, PartialEq)]
+struct RowSymbol {
+    /// Which row it's on, from 0
+    row: usize,
+    x: f64,
+    /// Whether it's a turning chain, which stands on end at the edge of the work
+    turning: bool,
+    shape: String,
+    title: String,
+}
+
+/// Renders the rows of flat work as a stitch chart in SVG, with the same symbols as
+/// [`render_chart`]. The foundation is at the bottom, and each row goes back over the one below it
+/// in the other direction, as the work is turned, with an arrow beside it showing which way it's
+/// worked. Turning chains stand on end at the edge each row starts from, including the ones that
+/// [`crate::pretty_format_with_options`] adds in [`crate::Mode::Rows`].
+///
+/// Like in [`crate::Mode::Rows`], the last chain of a foundation chain and the chains a row starts
+/// with are turning chains, which aren't worked into.
+///
+/// ```
+/// # use crochet::{parse_rounds, render_row_chart};
+/// let svg = render_row_chart(&parse_rounds("ch 7\nx2: sc 6").unwrap());
+///
+/// assert_eq!(svg.matches("<title>sc</title>").count(), 12);
+/// // the foundation's turning chain and the one added before the last row
+/// assert_eq!(svg.matches("<title>tch</title>").count(), 2);
+/// assert_eq!(svg.matches("class=\"direction\"").count(), 3);
+/// ```
+pub fn render_row_chart(rounds: &[Round]) -> String {
+    let symbols = row_layout(rounds);
+    let rows = expand_rounds(rounds).count();
+
+    let (min_x, max_x) = symbols
+        .iter()
+        .fold((0.0_f64, 0.0_f64), |(lo, hi), s| (lo.min(s.x), hi.max(s.x)));
+    // the arrows and row numbers go to the right of the rows
+    let arrows_x = max_x + 2.0 * STITCH_SPACING;
+    let left = min_x - MARGIN;
+    let top = -(rows.max(1) as f64 - 1.0) * ROW_HEIGHT - MARGIN;
+    let (width, height) = (arrows_x + 30.0 + MARGIN - left, -top + MARGIN);
+
+    let mut ret = String::new();
+    writeln!(
+        ret,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{left:.1} {top:.1} {width:.1} \
+            {height:.1}\" width=\"{width:.1}\" height=\"{height:.1}\">"
+    )
+    .expect("writing to a string shouldn't fail");
+
+    ret.push_str("<g fill=\"none\" stroke=\"black\" stroke-width=\"1.2\">\n");
+    for symbol in &symbols {
+        let y = -(symbol.row as f64) * ROW_HEIGHT;
+        let rotation = if symbol.turning { 90.0 } else { 0.0 };
+        write_symbol(
+            &mut ret,
+            (symbol.x, y),
+            rotation,
+            &symbol.title,
+            &symbol.shape,
+        );
+    }
+    ret.push_str("</g>\n");
+
+    ret.push_str("<g fill=\"none\" stroke=\"#888\" stroke-width=\"1\">\n");
+    for row in 0..rows {
+        let y = -(row as f64) * ROW_HEIGHT;
+        let rotation = if row % 2 == 0 { 0 } else { 180 };
+        writeln!(
+            ret,
+            "<path class=\"direction\" d=\"M-8 0H8M4 -4L8 0L4 4\" \
+                transform=\"translate({arrows_x:.1} {y:.1}) rotate({rotation})\"/>\n\
+                <text x=\"{:.1}\" y=\"{y:.1}\" fill=\"#888\" stroke=\"none\" font-size=\"10\" \
+                dominant-baseline=\"central\">{}</text>",
+            arrows_x + 14.0,
+            row + 1
+        )
+        .expect("writing to a string shouldn't fail");
+    }
+    ret.push_str("</g>\n</svg>");
+
+    ret
+}
+
+/// Works out where each stitch's symbol goes on a row chart
+fn row_layout(rounds: &[Round]) -> Vec<RowSymbol> {
+    let graph = StitchGraph::new(rounds);
+    let mut symbols = Vec::new();
+    // the x of each stitch of the previous row that can be worked into, in the order they're
+    // worked into after turning
+    let mut workable: Vec<f64> = Vec::new();
+    let mut previous: Option<&Round> = None;
+
+    for (r, row) in expand_rounds(rounds).enumerate() {
+        let count = graph.stitch_count(r).unwrap_or(0);
+        // which way the row is worked, foundation first from left to right
+        let dir = if r % 2 == 0 { 1.0 } else { -1.0 };
+
+        // the stitches at the start and end of the row that are turning chains
+        let leading = if r > 0 {
+            row.leading_chains() as usize
+        } else {
+            0
+        };
+        let trailing = usize::from(r == 0 && row.trailing_chains() > 0);
+        let is_turning = |s: usize| s < leading || s + trailing >= count;
+
+        let parents: Vec<&[usize]> = (0..count)
+            .map(|s| graph.parents(r, s).unwrap_or(&[]))
+            .collect();
+        let mut xs: Vec<Option<f64>> = parents
+            .iter()
+            .map(|ps| {
+                let xs: Vec<_> = ps
+                    .iter()
+                    .filter_map(|&p| workable.get(p).copied())
+                    .collect();
+                (!xs.is_empty()).then(|| xs.iter().sum::<f64>() / xs.len() as f64)
+            })
+            .collect();
+        spread_siblings(&mut xs, &parents, dir * STITCH_SPACING);
+        let start = workable.first().copied().unwrap_or(0.0);
+        spread_along(&mut xs, start, dir);
+        let xs: Vec<f64> = xs.into_iter().map(|x| x.unwrap_or(start)).collect();
+
+        // where the row starts and ends, for turning chains that don't make a stitch
+        let first_x = xs.first().copied().unwrap_or(start);
+        let last_x = xs.last().copied().unwrap_or(start);
+        let turning_chain = |x: f64, title: &str| RowSymbol {
+            row: r,
+            x,
+            turning: true,
+            shape: shape(&Instruction::Ch),
+            title: title.to_string(),
+        };
+
+        // the turning chain the pretty printer adds when neither row has one
+        let explicit = matches!(row.iter_stitches().next(), Some((_, Instruction::Tch)));
+        if r > 0 && leading == 0 && !explicit && previous.is_some_and(|p| p.trailing_chains() == 0)
+        {
+            symbols.push(turning_chain(first_x - dir * STITCH_SPACING, "tch"));
+        }
+
+        let mut next = 0;
+        for (_, inst) in row.iter_stitches() {
+            let made = inst.output_count() as usize;
+            let stitches = next..(next + made).min(count);
+            next += made;
+
+            match inst {
+                Instruction::Tch if stitches.start == 0 => {
+                    symbols.push(turning_chain(first_x - dir * STITCH_SPACING, "tch"));
+                }
+                Instruction::Tch => {
+                    symbols.push(turning_chain(last_x + dir * STITCH_SPACING, "tch"));
+                }
+                // chains are drawn one by one, and some of them may be turning chains
+                Instruction::Ch | Instruction::ChSpace { .. } => {
+                    for s in stitches {
+                        symbols.push(RowSymbol {
+                            row: r,
+                            x: xs[s],
+                            turning: is_turning(s),
+                            shape: shape(&Instruction::Ch),
+                            title: if is_turning(s) { "tch" } else { "ch" }.to_string(),
+                        });
+                    }
+                }
+                _ if stitches.is_empty() => {}
+                _ => symbols.push(RowSymbol {
+                    row: r,
+                    x: stitches.clone().map(|s| xs[s]).sum::<f64>() / stitches.len() as f64,
+                    turning: false,
+                    shape: shape(inst),
+                    title: inst.to_string(),
+                }),
+            }
+        }
+
+        // the next row is worked back along this one, skipping its turning chains
+        workable = (0..count)
+            .filter(|&s| !is_turning(s))
+            .map(|s| xs[s])
+            .rev()
+            .collect();
+        previous = Some(row);
+    }
+
+    symbols
+}
+
+/// Fills in the positions of stitches in a row that aren't worked into anything: between the
+/// stitches around them, or going on from the first or last stitch that is, one stitch apart in
+/// direction `dir`. If none of them are, they're laid out from `start`.
+fn spread_along(xs: &mut [Option<f64>], start: f64, dir: f64) {
+    let placed: Vec<usize> = (0..xs.len()).filter(|&i| xs[i].is_some()).collect();
+
+    let (Some(&first), Some(&last)) = (placed.first(), placed.last()) else {
+        for (i, x) in xs.iter_mut().enumerate() {
+            *x = Some(start + dir * STITCH_SPACING * i as f64);
+        }
+        return;
+    };
+
+    let at = |xs: &[Option<f64>], i: usize| xs[i].expect("placed stitches have positions");
+    let (first_x, last_x) = (at(xs, first), at(xs, last));
+    for (i, x) in xs[..first].iter_mut().enumerate() {
+        *x = Some(first_x - dir * STITCH_SPACING * (first - i) as f64);
+    }
+    for (i, x) in xs[last + 1..].iter_mut().enumerate() {
+        *x = Some(last_x + dir * STITCH_SPACING * (i + 1) as f64);
+    }
+    for pair in placed.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let (from, to) = (at(xs, a), at(xs, b));
+        for (k, x) in xs[a + 1..b].iter_mut().enumerate() {
+            *x = Some(from + (to - from) * (k + 1) as f64 / (b - a) as f64);
+        }
+    }
+}
+
+/// The symbol for a stitch, drawn upright around the origin
+fn shape(inst: &Instruction) -> String {
+    use Instruction::*;
+
+    const X: &str = "<path d=\"M-5 -5L5 5M5 -5L-5 5\"/>";
+    const V: &str = "<path d=\"M-6 -6L0 6L6 -6\"/>";
+    // arcs under a stitch for working into the back or front loop only
+    const BACK_LOOP: &str = "<path d=\"M-5 9Q0 14 5 9\"/>";
+    const FRONT_LOOP: &str = "<path d=\"M-5 13Q0 8 5 13\"/>";
+    // hooks under a stitch for working around the post from the front or back
+    const FRONT_POST: &str = "<path d=\"M0 5V9Q0 12 -3 12\"/>";
+    const BACK_POST: &str = "<path d=\"M0 5V9Q0 12 3 12\"/>";
+
+    match inst {
+        Ch => "<ellipse rx=\"6\" ry=\"3\"/>".to_string(),
+        Sc => X.to_string(),
+        Fpsc => format!("{X}{FRONT_POST}"),
+        Bpsc => format!("{X}{BACK_POST}"),
+        Blsc => format!("{X}{BACK_LOOP}"),
+        Inc => V.to_string(),
+        Flinc => format!("{V}{FRONT_LOOP}"),
+        Blinc => format!("{V}{BACK_LOOP}"),
+        Dec => "<path d=\"M-6 6L0 -6L6 6\"/>".to_string(),
+        Custom(stitch) => format!(
+            "<text fill=\"black\" stroke=\"none\" font-size=\"10\" text-anchor=\"middle\" \
+                dominant-baseline=\"central\">{}</text>",
+            escape(stitch.symbol())
+        ),
+        // the rest don't make any stitches, so they never get a symbol
+        _ => "<circle r=\"2\" fill=\"black\"/>".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn symbols(src: &str) -> Vec<(usize, String, f64)> {
+        layout(&parse_rounds(src).unwrap())
+            .0
+            .into_iter()
+            .map(|s| {
+                (
+                    s.round,
+                    s.title,
+                    (s.angle.to_degrees() * 10.0).round() / 10.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_layout() {
+        let s = symbols("sc 4 in mr\ninc, sc, dec");
+        assert_eq!(
+            s,
+            [
+                (0, "sc".to_string(), 45.0),
+                (0, "sc".to_string(), 135.0),
+                (0, "sc".to_string(), 225.0),
+                (0, "sc".to_string(), 315.0),
+                // over the stitches they're worked into
+                (1, "inc".to_string(), 45.0),
+                (1, "sc".to_string(), 135.0),
+                (1, "dec".to_string(), 270.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_siblings_are_spread_out() {
+        let s = symbols("sc 6 in mr\ninc 6\n[inc, sc] 6");
+        let mut angles: Vec<_> = s.iter().map(|(r, _, a)| (*r, (*a * 10.0) as i64)).collect();
+        angles.sort();
+        angles.dedup();
+        assert_eq!(angles.len(), 24);
+
+        // the two stitches of each increase are worked into on either side of it
+        let round: Vec<_> = s[12..16].iter().map(|(_, t, a)| (t.as_str(), *a)).collect();
+        assert_eq!(
+            round,
+            [("inc", 15.0), ("sc", 45.0), ("inc", 75.0), ("sc", 105.0)]
+        );
+    }
+
+    #[test]
+    fn test_chains_are_spread_out() {
+        let s = symbols("sc 4 in mr\nsc, ch 2, skip 2, sc");
+        let round: Vec<_> = s[4..].iter().map(|(_, t, a)| (t.as_str(), *a)).collect();
+        assert_eq!(
+            round,
+            [("sc", 45.0), ("ch", 135.0), ("ch", 225.0), ("sc", 315.0)]
+        );
+
+        // with nothing to go between, chains go around the whole circle
+        let s = symbols("ch 4");
+        let angles: Vec<_> = s.iter().map(|(_, _, a)| *a).collect();
+        assert_eq!(angles, [45.0, 135.0, 225.0, 315.0]);
+    }
+
+    #[test]
+    fn test_spread_out_around_one_stitch() {
+        let mut angles = [None, Some(0.0), None, None];
+        spread_out(&mut angles);
+        let degrees: Vec<_> = angles
+            .iter()
+            .map(|a| a.unwrap().to_degrees().round())
+            .collect();
+        assert_eq!(degrees, [270.0, 0.0, 90.0, 180.0]);
+    }
+
+    fn rows(src: &str) -> Vec<(usize, String, f64, bool)> {
+        row_layout(&parse_rounds(src).unwrap())
+            .into_iter()
+            .map(|s| (s.row, s.title, s.x, s.turning))
+            .collect()
+    }
+
+    #[test]
+    fn test_row_layout() {
+        let s = rows("ch 4\ninc, dec\nch 1, sc 3");
+        let row = |n: usize| -> Vec<_> {
+            s.iter()
+                .filter(|(r, ..)| *r == n)
+                .map(|(_, t, x, turning)| (t.as_str(), *x, *turning))
+                .collect()
+        };
+
+        // the last chain of the foundation is its turning chain
+        assert_eq!(
+            row(0),
+            [
+                ("ch", 0.0, false),
+                ("ch", 16.0, false),
+                ("ch", 32.0, false),
+                ("tch", 48.0, true)
+            ]
+        );
+        // worked back from the right, over the chains
+        assert_eq!(row(1), [("inc", 32.0, false), ("dec", 8.0, false)]);
+        // the row's own chain is its turning chain, at the left edge where it starts
+        assert_eq!(
+            row(2),
+            [
+                ("tch", -8.0, true),
+                ("sc", 8.0, false),
+                // over the two stitches of the increase, which are side by side
+                ("sc", 28.0, false),
+                ("sc", 36.0, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_added_turning_chains() {
+        let s = rows("ch 3\nsc 2\nsc 2\ntch, sc 2");
+        let turning: Vec<_> = s
+            .iter()
+            .filter(|(.., turning)| *turning)
+            .map(|(r, _, x, _)| (*r, *x))
+            .collect();
+        // the foundation's own, one added before row 3, and an explicit one
+        assert_eq!(turning, [(0, 32.0), (2, -16.0), (3, 32.0)]);
+    }
+
+    #[test]
+    fn test_spread_along() {
+        let mut xs = [None, Some(10.0), None, Some(40.0), None];
+        spread_along(&mut xs, 0.0, -1.0);
+        assert_eq!(xs.map(Option::unwrap), [26.0, 10.0, 25.0, 40.0, 24.0]);
+    }
+
+    #[test]
+    fn test_render_row_chart() {
+        let svg = render_row_chart(&parse_rounds("ch 5\nx3: [blsc, sc] 2").unwrap());
+        assert_eq!(svg.matches("<title>blsc</title>").count(), 6);
+        assert_eq!(svg.matches("class=\"direction\"").count(), 4);
+        assert_eq!(svg.matches("rotate(180)").count(), 2);
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_render_chart() {
+        let svg = render_chart(&parse_rounds("sc 6 in mr\n[blinc, fpsc] 3, ch 1").unwrap());
+
+        assert_eq!(svg.matches("<circle r=").count(), 3);
+        assert!(svg.contains("<title>magic ring</title>"));
+        assert_eq!(svg.matches("<title>blinc</title>").count(), 3);
+        assert_eq!(svg.matches("<title>fpsc</title>").count(), 3);
+        assert_eq!(svg.matches("<title>ch</title>").count(), 1);
+        assert!(svg.ends_with("</svg>"));
+
+        // wider rounds get bigger circles, so their stitches don't overlap
+        let (_, radii) = layout(&parse_rounds("sc 6 in mr\nsc 100").unwrap());
+        assert_eq!(radii[0], FIRST_RADIUS);
+        assert!(radii[1] * TAU / 100.0 >= STITCH_SPACING - 1e-9);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::Instruction;
+use std::collections::BTreeMap;
+
+/// How many stitches of each color a round makes, keyed by the colors' palette keys (e.g. `A`).
+pub type ColorCounts<'a> = BTreeMap<&'a str, u32>;
+
+/// Parses a `colors:` header value like `A = cream, B = brown` into `(key, color)` pairs.
+/// Returns `None` if it's malformed.
+pub(crate) fn parse_palette(value: &str) -> Option<Vec<(&str, &str)>> {
+    value
+        .split(',')
+        .map(|entry| {
+            let (key, color) = entry.split_once('=')?;
+            let (key, color) = (key.trim(), color.trim());
+
+            let valid_key = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_key || color.is_empty() {
+                return None;
+            }
+
+            Some((key, color))
+        })
+        .collect()
+}
+
+/// Adds the stitches made by `inst` to `counts`, under whichever color is active when they're
+/// made. `active` is updated by any color changes in `inst`.
+pub(crate) fn count_colors<'a>(
+    inst: &Instruction<'a>,
+    active: &mut Option<&'a str>,
+    counts: &mut ColorCounts<'a>,
+) {
+    use Instruction::*;
+
+    match inst {
+        Color(c) => *active = Some(c),
+        Group(insts) => {
+            for i in insts {
+                count_colors(i, active, counts);
+            }
+        }
+        // no need to step through every repetition if the color doesn't change partway through
+        Repeat(i, times) if !changes_color(i) => {
+            if let Some(c) = active {
+                *counts.entry(c).or_default() += i.output_count() * times;
+            }
+        }
+        Repeat(i, times) => {
+            for _ in 0..*times {
+                count_colors(i, active, counts);
+            }
+        }
+        IntoMagicRing(i) | IntoOne(i) => count_colors(i, active, counts),
+        Crossed(a, b) => {
+            count_colors(a, active, counts);
+            count_colors(b, active, counts);
+        }
+        _ => {
+            if let Some(c) = active {
+                *counts.entry(c).or_default() += inst.output_count();
+            }
+        }
+    }
+}
+
+fn changes_color(inst: &Instruction) -> bool {
+    use Instruction::*;
+
+    match inst {
+        Color(_) => true,
+        Group(insts) => insts.iter().any(changes_color),
+        Repeat(i, _) | IntoMagicRing(i) | IntoOne(i) => changes_color(i),
+        Crossed(a, b) => changes_color(a) || changes_color(b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    #[test]
+    fn test_parse_palette() {
+        assert_eq!(
+            parse_palette("A = cream, B=dark brown"),
+            Some(vec![("A", "cream"), ("B", "dark brown")])
+        );
+        assert_eq!(parse_palette("A = cream, B"), None);
+        assert_eq!(parse_palette("A B = cream"), None);
+        assert_eq!(parse_palette("A ="), None);
+    }
+
+    #[test]
+    fn test_count_colors() {
+        let rounds =
+            parse_rounds("colors: A = red, B = blue\nsc 6 in mr\n[@B, inc, @A, sc] 3\n[inc, sc] 2")
+                .unwrap();
+        let mut active = Some("A");
+
+        let counts: Vec<_> = rounds
+            .iter()
+            .map(|r| {
+                let mut counts = ColorCounts::new();
+                for i in &r.instructions {
+                    count_colors(i, &mut active, &mut counts);
+                }
+                counts
+            })
+            .collect();
+
+        assert_eq!(counts[0], [("A", 6)].into());
+        assert_eq!(counts[1], [("A", 3), ("B", 6)].into());
+        assert_eq!(counts[2], [("A", 6)].into());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::round::expand_rounds;
+use crate::{canonicalize, Instruction, Round};
+use std::fmt::Write;
+
+/// Summarizes rounds as CSV, with one row for each round worked: its number, its instructions in
+/// canonical form (see [`canonicalize`]), how many stitches it's worked into and makes, and the
+/// running total of stitches made so far. Each round of a block of repeated rounds gets its own
+/// row.
+///
+/// The first row is a header, and rows end with `\r\n` as in RFC 4180.
+///
+/// ```
+/// # use crochet::{parse_rounds, to_csv};
+/// let rounds = parse_rounds("sc 6 in mr\ninc, inc, inc 4\nx2: [inc, sc] 6").unwrap();
+///
+/// assert_eq!(
+///     to_csv(&rounds),
+///     "round,instructions,consumed,produced,total\r\n\
+///     1,sc 6 in mr,0,6,6\r\n\
+///     2,inc 6,6,12,18\r\n\
+///     3,\"[inc, sc] 6\",12,18,36\r\n\
+///     4,\"[inc, sc] 6\",12,18,54\r\n"
+/// );
+/// ```
+pub fn to_csv(rounds: &[Round]) -> String {
+    let mut ret = String::from("round,instructions,consumed,produced,total\r\n");
+    // a u64 so the total can't overflow however many stitches the rounds make
+    let mut total = 0u64;
+
+    for (i, round) in expand_rounds(rounds).enumerate() {
+        let instructions = Instruction::Group(canonicalize(&round.instructions)).to_string();
+        let produced = round.output_count();
+        total += u64::from(produced);
+
+        write!(
+            ret,
+            "{},{},{},{produced},{total}\r\n",
+            i + 1,
+            field(&instructions),
+            round.input_count()
+        )
+        .expect("writing to a string shouldn't fail");
+    }
+
+    ret
+}
+
+/// Quotes a field if it contains anything that would break up the row
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    #[test]
+    fn test_field() {
+        assert_eq!(field("sc 6"), "sc 6");
+        assert_eq!(field("sc, inc"), "\"sc, inc\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let rounds =
+            parse_rounds("hook 4mm\nsc 6 in mr\nsc, sc, % a \"note\" %, sc 4\nx2: dec 3").unwrap();
+
+        assert_eq!(
+            to_csv(&rounds),
+            "round,instructions,consumed,produced,total\r\n\
+            1,sc 6 in mr,0,6,6\r\n\
+            2,\"sc 2, % a \"\"note\"\" %, sc 4\",6,6,12\r\n\
+            3,dec 3,6,3,15\r\n\
+            4,dec 3,6,3,18\r\n"
+        );
+        assert_eq!(
+            to_csv(&[]),
+            "round,instructions,consumed,produced,total\r\n"
+        );
+    }
+
+    #[test]
+    fn test_large_counts() {
+        let rounds = parse_rounds("sc 4000000000 in mr\nsc 4000000000").unwrap();
+
+        assert_eq!(
+            to_csv(&rounds),
+            "round,instructions,consumed,produced,total\r\n\
+            1,sc 4000000000 in mr,0,4000000000,4000000000\r\n\
+            2,sc 4000000000,4000000000,4000000000,8000000000\r\n"
+        );
+    }
+}
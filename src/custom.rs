@@ -0,0 +1,179 @@
+use std::hash::{Hash, Hasher};
+
+/// A stitch that isn't built into the pattern language, e.g. a bobble or a puff stitch, so that
+/// applications can extend the stitch set.
+///
+/// Custom stitches are registered on a [`crate::Dialect`], and are written in patterns by their
+/// name, with the same suffixes as the built-in stitches. They show up in the parsed rounds as
+/// [`crate::Instruction::Custom`].
+///
+/// A custom stitch is identified by its name alone: two stitches with the same name compare equal
+/// even if their counts differ, and exporting a pattern (with [`crate::Pattern::to_bytes`] or as
+/// JSON) only keeps the name. Patterns containing custom stitches therefore can't be read back from
+/// either format.
+///
+/// ```
+/// # use crochet::{parse_rounds_with_dialect, CustomStitch, Dialect, Instruction};
+/// #[derive(Debug)]
+/// struct Bobble;
+///
+/// impl CustomStitch for Bobble {
+///     fn name(&self) -> &str {
+///         "bobble"
+///     }
+///
+///     fn input_count(&self) -> u32 {
+///         1
+///     }
+///
+///     fn output_count(&self) -> u32 {
+///         1
+///     }
+///
+///     fn symbol(&self) -> &str {
+///         "⬤"
+///     }
+/// }
+///
+/// let dialect = Dialect::new().stitch(Bobble);
+/// let rounds = parse_rounds_with_dialect("sc 6 in mr\n[bobble, inc] 3", &dialect).unwrap();
+///
+/// assert_eq!(rounds[1].output_count(), 9);
+/// assert_eq!(rounds[1].to_string(), "[bobble, inc] 3");
+/// ```
+pub trait CustomStitch: std::fmt::Debug + Send + Sync {
+    /// What the stitch is called in patterns. This has to be a single word that isn't one of the
+    /// language's keywords, e.g. `bobble`.
+    fn name(&self) -> &str;
+
+    /// How many stitches of the previous round this stitch consumes, like
+    /// [`crate::Instruction::input_count`]
+    fn input_count(&self) -> u32;
+
+    /// How many stitches this stitch creates, like [`crate::Instruction::output_count`]
+    fn output_count(&self) -> u32;
+
+    /// The chart symbol for the stitch
+    fn symbol(&self) -> &str;
+}
+
+/// Custom stitches are identified by their name, since that's all a pattern can refer to them by
+impl PartialEq for dyn CustomStitch {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for dyn CustomStitch {}
+
+impl Hash for dyn CustomStitch {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name().hash(state);
+    }
+}
+
+/// Whether `name` is lexed as a single identifier, which custom stitches' names have to be
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.bytes();
+
+    matches!(chars.next(), Some(b'a'..=b'z' | b'A'..=b'Z' | b'_'))
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == b'_')
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn serialize<S: serde::Serializer>(
+    stitch: &std::sync::Arc<dyn CustomStitch>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(stitch.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_rounds_with_dialect, Dialect, Instruction};
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Stitch(&'static str, u32, u32);
+
+    impl CustomStitch for Stitch {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn input_count(&self) -> u32 {
+            self.1
+        }
+
+        fn output_count(&self) -> u32 {
+            self.2
+        }
+
+        fn symbol(&self) -> &str {
+            "?"
+        }
+    }
+
+    #[test]
+    fn test_custom_stitches() {
+        let dialect = Dialect::new()
+            .stitch(Stitch("bobble", 1, 1))
+            .stitch(Stitch("cluster", 3, 1));
+        let parse = |src| parse_rounds_with_dialect(src, &dialect);
+
+        let rounds = parse("ch 12\ncluster 4\nbobble 4 in mr, cross(bobble, sc)").unwrap();
+        assert_eq!(rounds[1].input_count(), 12);
+        assert_eq!(rounds[1].output_count(), 4);
+        assert_eq!(rounds[2].output_count(), 6);
+        assert_eq!(rounds[2].to_string(), "bobble 4 in mr, cross(bobble, sc)");
+
+        let Instruction::Repeat(inst, 4) = &rounds[1].instructions[0] else {
+            panic!("expected a repeat");
+        };
+        assert_eq!(
+            **inst,
+            Instruction::Custom(Arc::new(Stitch("cluster", 0, 0)))
+        );
+
+        // unregistered names are still errors
+        assert_eq!(parse("puff 6").unwrap_err().loc(), (1, 1));
+        assert!(crate::parse_rounds("bobble 6").is_err());
+    }
+
+    #[test]
+    fn test_names_with_keyword_prefix() {
+        let dialect = Dialect::new().stitch(Stitch("sc3tog", 3, 1));
+        let rounds = parse_rounds_with_dialect("sc 6 in mr\nsc3tog 2", &dialect).unwrap();
+        assert_eq!(rounds[1].input_count(), 6);
+        assert_eq!(rounds[1].output_count(), 2);
+        assert_eq!(rounds[1].to_string(), "sc3tog 2");
+
+        // without the custom stitch, the keyword is still lexed on its own
+        assert!(crate::parse_rounds("sc 6 in mr\nsc3tog 2").is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let dialect = Dialect::new().stitch(Stitch("bobble", 1, 1));
+        let src = "name: Ball\nsc 6 in mr\n[bobble, sc] 3";
+        let pattern = crate::parse_pattern_with_dialect(src, &dialect).unwrap();
+        assert_eq!(crate::Pattern::from_bytes(&pattern.to_bytes()), None);
+
+        #[cfg(feature = "json")]
+        {
+            let json = crate::to_json(&pattern.parts[0].rounds);
+            assert!(json.contains(r#"{"Group":[{"Custom":"bobble"},"Sc"]}"#));
+            assert!(crate::from_json(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn test_valid_names() {
+        assert!(is_valid_name("bobble"));
+        assert!(is_valid_name("sc3tog"));
+        assert!(!is_valid_name("3tog"));
+        assert!(!is_valid_name("puff st"));
+        assert!(!is_valid_name(""));
+    }
+}
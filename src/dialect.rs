@@ -0,0 +1,109 @@
+use crate::custom::{self, CustomStitch};
+use crate::lex::{TokenKind, KEYWORDS};
+use std::sync::Arc;
+
+/// Alternative spellings for the pattern language's keywords, for reading patterns written with
+/// other abbreviations than the ones this crate uses.
+///
+/// An alias is lexed exactly like the keyword it stands for, so it can be used anywhere the
+/// keyword can, and is written back out with the keyword's usual spelling.
+///
+/// ```
+/// # use crochet::{parse_rounds_with_dialect, Dialect, UnknownKeyword};
+/// let dialect = Dialect::new()
+///     .alias("single", "sc")?
+///     .alias("increase", "inc")?
+///     .alias("in mc", "in mr")?;
+///
+/// let rounds = parse_rounds_with_dialect("single 6 in mc\nincrease 6", &dialect).unwrap();
+/// assert_eq!(rounds[0].to_string(), "sc 6 in mr");
+/// assert_eq!(rounds[1].output_count(), 12);
+/// # Ok::<(), UnknownKeyword>(())
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Dialect {
+    /// Each alias and the keyword it stands for, longest alias first
+    aliases: Vec<(String, TokenKind<'static>)>,
+    /// Stitches that are parsed as [`crate::Instruction::Custom`]
+    stitches: Vec<Arc<dyn CustomStitch>>,
+}
+
+impl Dialect {
+    /// A dialect without any aliases, which only understands the usual keywords
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `spelling` as another way of writing `keyword`, e.g. `alias("single", "sc")`.
+    /// Aliases are checked before the usual keywords, so they can also change what one of those
+    /// means.
+    ///
+    /// Fails if `keyword` isn't one of the language's keywords (`sc`, `in mr`, `surface sl st`,
+    /// ...), and panics if `spelling` is empty.
+    ///
+    /// ```
+    /// # use crochet::{Dialect, UnknownKeyword};
+    /// let err = Dialect::new().alias("treble", "tr").unwrap_err();
+    /// assert_eq!(err, UnknownKeyword("tr".to_string()));
+    /// assert_eq!(err.to_string(), "`tr` isn't a keyword");
+    /// ```
+    pub fn alias(mut self, spelling: &str, keyword: &str) -> Result<Self, UnknownKeyword> {
+        assert!(!spelling.is_empty(), "an alias can't be empty");
+
+        let kind = KEYWORDS
+            .iter()
+            .find(|(k, _)| *k == keyword)
+            .map(|&(_, kind)| kind)
+            .ok_or_else(|| UnknownKeyword(keyword.to_string()))?;
+
+        self.aliases.retain(|(s, _)| s != spelling);
+        self.aliases.push((spelling.to_owned(), kind));
+        self.aliases
+            .sort_by_key(|(s, _)| std::cmp::Reverse(s.len()));
+
+        Ok(self)
+    }
+
+    /// Adds a [`CustomStitch`], which patterns can then use by its name. Registering a stitch with
+    /// the same name as an earlier one replaces it.
+    ///
+    /// Panics if the stitch's name isn't a single word, or is one of the language's keywords.
+    pub fn stitch(mut self, stitch: impl CustomStitch + 'static) -> Self {
+        let name = stitch.name();
+        assert!(
+            custom::is_valid_name(name),
+            "`{name}` isn't a valid stitch name"
+        );
+        assert!(
+            KEYWORDS.iter().all(|(k, _)| *k != name),
+            "`{name}` is already a keyword"
+        );
+
+        self.stitches.retain(|s| s.name() != name);
+        self.stitches.push(Arc::new(stitch));
+
+        self
+    }
+
+    /// The custom stitch called `name`, if there is one
+    pub(crate) fn custom_stitch(&self, name: &str) -> Option<&Arc<dyn CustomStitch>> {
+        self.stitches.iter().find(|s| s.name() == name)
+    }
+
+    /// Each alias and the keyword it stands for, longest alias first
+    pub(crate) fn aliases(&self) -> impl Iterator<Item = (&[u8], TokenKind<'static>)> {
+        self.aliases.iter().map(|(s, kind)| (s.as_bytes(), *kind))
+    }
+}
+
+/// A keyword given to [`Dialect::alias`] that isn't one of the language's keywords
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnknownKeyword(pub String);
+
+impl std::fmt::Display for UnknownKeyword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` isn't a keyword", self.0)
+    }
+}
+
+impl std::error::Error for UnknownKeyword {}
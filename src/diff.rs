@@ -0,0 +1,204 @@
+use crate::{canonicalize, Instruction, Round};
+
+/// How one version of a list of rounds differs from another, as reported by [`diff`].
+///
+/// Indices are zero-based, into the old rounds for removed rounds and the new rounds for added
+/// ones.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RoundDiff<'a> {
+    Added(usize),
+    Removed(usize),
+    /// The round at `old` in the old rounds was changed into the one at `new` in the new rounds
+    Changed {
+        old: usize,
+        new: usize,
+        /// The round's instructions, in canonical form (see [`canonicalize`])
+        instructions: Vec<InstructionDiff<'a>>,
+    },
+}
+
+/// One instruction of a changed round.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InstructionDiff<'a> {
+    Same(Instruction<'a>),
+    Added(Instruction<'a>),
+    Removed(Instruction<'a>),
+}
+
+impl std::fmt::Display for InstructionDiff<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Same(i) => write!(f, "{i}"),
+            Self::Added(i) => write!(f, "+{i}"),
+            Self::Removed(i) => write!(f, "-{i}"),
+        }
+    }
+}
+
+/// Compares two versions of a list of rounds, e.g. of the same part of a pattern.
+///
+/// Rounds are compared by their canonical form (see [`canonicalize`]), so rewriting a round
+/// without changing how it's worked doesn't count as a change. Rounds that are the same in both
+/// versions aren't reported.
+///
+/// ```
+/// # use crochet::{diff, parse_rounds, RoundDiff};
+/// let old = parse_rounds("sc 6 in mr\ninc 6\nsc 12\nsc 12").unwrap();
+/// let new = parse_rounds("sc 6 in mr\ninc, inc 5\n[inc, sc] 6\nsc 18\nsc 18").unwrap();
+///
+/// let changes = diff(&old, &new);
+/// assert_eq!(changes.len(), 3);
+/// assert!(matches!(changes[0], RoundDiff::Changed { old: 2, new: 2, .. }));
+/// assert!(matches!(changes[1], RoundDiff::Changed { old: 3, new: 3, .. }));
+/// assert_eq!(changes[2], RoundDiff::Added(4));
+///
+/// let RoundDiff::Changed { instructions, .. } = &changes[0] else { unreachable!() };
+/// let instructions: Vec<_> = instructions.iter().map(ToString::to_string).collect();
+/// assert_eq!(instructions, ["-sc 12", "+[inc, sc] 6"]);
+/// ```
+pub fn diff<'a>(old: &[Round<'a>], new: &[Round<'a>]) -> Vec<RoundDiff<'a>> {
+    let canonical = |r: &Round<'a>| (r.label, r.times, canonicalize(&r.instructions));
+    let old_canonical: Vec<_> = old.iter().map(canonical).collect();
+    let new_canonical: Vec<_> = new.iter().map(canonical).collect();
+
+    let mut ret = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    // rounds removed and added between two unchanged rounds are paired up as changed rounds
+    let flush = |removed: &mut Vec<usize>, added: &mut Vec<usize>, ret: &mut Vec<_>| {
+        for (&o, &n) in removed.iter().zip(added.iter()) {
+            ret.push(RoundDiff::Changed {
+                old: o,
+                new: n,
+                instructions: diff_instructions(&old_canonical[o].2, &new_canonical[n].2),
+            });
+        }
+        let paired = removed.len().min(added.len());
+        ret.extend(removed.drain(..).skip(paired).map(RoundDiff::Removed));
+        ret.extend(added.drain(..).skip(paired).map(RoundDiff::Added));
+    };
+
+    for op in align(&old_canonical, &new_canonical) {
+        match op {
+            Op::Same(..) => flush(&mut removed, &mut added, &mut ret),
+            Op::Removed(o) => removed.push(o),
+            Op::Added(n) => added.push(n),
+        }
+    }
+    flush(&mut removed, &mut added, &mut ret);
+
+    ret
+}
+
+fn diff_instructions<'a>(
+    old: &[Instruction<'a>],
+    new: &[Instruction<'a>],
+) -> Vec<InstructionDiff<'a>> {
+    align(old, new)
+        .into_iter()
+        .map(|op| match op {
+            Op::Same(o) => InstructionDiff::Same(old[o].clone()),
+            Op::Removed(o) => InstructionDiff::Removed(old[o].clone()),
+            Op::Added(n) => InstructionDiff::Added(new[n].clone()),
+        })
+        .collect()
+}
+
+/// A step in turning one list into another
+enum Op {
+    /// The element at the index in the old list is kept
+    Same(usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Lines up two lists along their longest common subsequence. Removals come before additions at
+/// each point the lists differ.
+fn align<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Op> {
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push(Op::Same(i));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(Op::Removed(i));
+            i += 1;
+        } else {
+            ops.push(Op::Added(j));
+            j += 1;
+        }
+    }
+
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn diffed(old: &str, new: &str) -> Vec<RoundDiff<'static>> {
+        let old = parse_rounds(old.to_owned().leak()).unwrap();
+        let new = parse_rounds(new.to_owned().leak()).unwrap();
+        diff(&old, &new)
+    }
+
+    #[test]
+    fn test_rewrites_arent_changes() {
+        assert_eq!(
+            diffed("sc 6 in mr\ninc, sc, inc, sc", "[sc] 6 in mr\n[inc, sc] 2"),
+            []
+        );
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        assert_eq!(
+            diffed(
+                "sc 6 in mr\ninc 6\nsc 12",
+                "sc 6 in mr\n[inc] 6\nsc 12, % hi %"
+            ),
+            [RoundDiff::Changed {
+                old: 2,
+                new: 2,
+                instructions: vec![
+                    InstructionDiff::Same(Instruction::Repeat(Instruction::Sc.into(), 12)),
+                    InstructionDiff::Added(Instruction::Comment("hi")),
+                ],
+            },]
+        );
+
+        assert_eq!(
+            diffed("sc 6 in mr\ninc 6\nsc 12", "sc 6 in mr"),
+            [RoundDiff::Removed(1), RoundDiff::Removed(2)]
+        );
+        assert_eq!(
+            diffed("sc 6 in mr", "ch 1\nsc 6 in mr"),
+            [RoundDiff::Added(0)]
+        );
+    }
+
+    #[test]
+    fn test_repeated_rounds() {
+        let changes = diffed("x3: sc 12", "x4: sc 12");
+        let RoundDiff::Changed { instructions, .. } = &changes[0] else {
+            panic!("{changes:?}")
+        };
+        assert!(matches!(instructions[..], [InstructionDiff::Same(_)]));
+    }
+}
@@ -0,0 +1,18 @@
+/// A change of materials between rounds, like `hook 3.5mm` or `yarn "DK blue"`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Directive<'a> {
+    /// Switch to the given hook size, e.g. `3.5mm`
+    Hook(&'a str),
+    /// Switch to the given yarn, e.g. `DK blue`
+    Yarn(&'a str),
+}
+
+impl std::fmt::Display for Directive<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hook(size) => write!(f, "hook {size}"),
+            Self::Yarn(yarn) => write!(f, "yarn \"{yarn}\""),
+        }
+    }
+}
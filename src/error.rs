@@ -0,0 +1,55 @@
+/// Why a pattern couldn't be parsed.
+///
+/// ```
+/// # use crochet::parse_rounds;
+/// let err = parse_rounds("sc 6 in mr\n[inc, sc 6").unwrap_err();
+/// assert_eq!(err.loc(), (2, 11));
+/// assert_eq!(err.expected, ["']'", "','"]);
+/// assert_eq!(err.to_string(), "2:11: expected ']' or ','");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    /// One-based line of the problem
+    pub line: usize,
+    /// One-based column of the problem
+    pub col: usize,
+    /// Descriptions of what would have been valid at that point, e.g. `']'` or `a number`. This is
+    /// empty when there's nothing more specific to say than that the input is wrong there.
+    pub expected: Vec<&'static str>,
+}
+
+impl ParseError {
+    /// An error at `loc` where one of `expected` would have been valid instead
+    pub fn expected(loc: (usize, usize), expected: &[&'static str]) -> Self {
+        Self {
+            line: loc.0,
+            col: loc.1,
+            expected: expected.to_vec(),
+        }
+    }
+
+    /// The `(line, col)` of the problem
+    pub fn loc(&self) -> (usize, usize) {
+        (self.line, self.col)
+    }
+}
+
+impl From<(usize, usize)> for ParseError {
+    fn from(loc: (usize, usize)) -> Self {
+        Self::expected(loc, &[])
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.col)?;
+
+        match &self.expected[..] {
+            [] => write!(f, "unexpected input"),
+            [only] => write!(f, "expected {only}"),
+            [rest @ .., last] => write!(f, "expected {} or {last}", rest.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
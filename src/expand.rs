@@ -0,0 +1,119 @@
+use crate::{Instruction, Round};
+
+/// Writes an instruction out long-hand, unrolling every repeat and working each stitch into the
+/// magic ring on its own. Nested groups are flattened, so the result is a single instruction or a
+/// flat group of them.
+///
+/// ```
+/// # use crochet::{expand, parse_rounds, Instruction};
+/// let round = &parse_rounds("[sc, inc] 2 in mr, (sc 2) in next st").unwrap()[0];
+/// let expanded = expand(&Instruction::Group(round.instructions.clone()));
+/// assert_eq!(
+///     expanded.to_string(),
+///     "sc in mr, inc in mr, sc in mr, inc in mr, (sc, sc) in next st"
+/// );
+/// ```
+pub fn expand<'a>(inst: &Instruction<'a>) -> Instruction<'a> {
+    let mut insts = Vec::new();
+    expand_into(inst, false, &mut insts);
+
+    match insts.len() {
+        1 => insts.remove(0),
+        _ => Instruction::Group(insts),
+    }
+}
+
+/// Like [`expand`], for every instruction of a round. The round's label, count and directives are
+/// kept, but it no longer has instruction spans since they don't match the expanded instructions.
+///
+/// ```
+/// # use crochet::{expand_round, parse_rounds};
+/// let round = &parse_rounds("x2: sc, [inc] 3").unwrap()[0];
+/// assert_eq!(expand_round(round).to_string(), "x2: sc, inc, inc, inc");
+/// ```
+pub fn expand_round<'a>(round: &Round<'a>) -> Round<'a> {
+    let mut instructions = Vec::new();
+    for inst in &round.instructions {
+        expand_into(inst, false, &mut instructions);
+    }
+
+    Round {
+        label: round.label,
+        times: round.times,
+        directives: round.directives.clone(),
+        index: round.index,
+        span: round.span,
+        ..Round::new(instructions)
+    }
+}
+
+/// Pushes the expanded form of `inst` to `out`, working each stitch into the magic ring if
+/// `in_mr` is set
+fn expand_into<'a>(inst: &Instruction<'a>, in_mr: bool, out: &mut Vec<Instruction<'a>>) {
+    use Instruction::*;
+
+    match inst {
+        Group(insts) => {
+            for i in insts {
+                expand_into(i, in_mr, out);
+            }
+        }
+        Repeat(i, times) => {
+            for _ in 0..*times {
+                expand_into(i, in_mr, out);
+            }
+        }
+        IntoMagicRing(i) => expand_into(i, true, out),
+        IntoOne(i) => {
+            let mut insts = Vec::new();
+            expand_into(i, false, &mut insts);
+            push(IntoOne(Group(insts).into()), in_mr, out);
+        }
+        Crossed(a, b) => push(Crossed(expand(a).into(), expand(b).into()), in_mr, out),
+        Comment(_) | Color(_) => out.push(inst.clone()),
+        _ => push(inst.clone(), in_mr, out),
+    }
+}
+
+fn push<'a>(inst: Instruction<'a>, in_mr: bool, out: &mut Vec<Instruction<'a>>) {
+    out.push(match in_mr {
+        true => Instruction::IntoMagicRing(inst.into()),
+        false => inst,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn expanded(src: &str) -> String {
+        expand_round(&parse_rounds(src).unwrap()[0]).to_string()
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expanded("sc 3 in mr"), "sc in mr, sc in mr, sc in mr");
+        assert_eq!(
+            expanded("[inc, [sc] 2, % hi %] 2"),
+            "inc, sc, sc, % hi %, inc, sc, sc, % hi %"
+        );
+        assert_eq!(
+            expanded("cross(fpsc, sc) 2"),
+            "cross(fpsc, sc), cross(fpsc, sc)"
+        );
+        assert_eq!(expanded("[dec] 0, ch 2, skip 2"), "ch 2, skip 2");
+        assert_eq!(expand(&Instruction::Sc), Instruction::Sc);
+    }
+
+    #[test]
+    fn test_counts_unchanged() {
+        let src = "sc 6 in mr\nhook 4mm\n[inc, sc 2] 6 in mr, (sc, ch 2, sc) in next st";
+        for round in parse_rounds(src).unwrap() {
+            let expanded = expand_round(&round);
+            assert_eq!(expanded.output_count(), round.output_count());
+            assert_eq!(expanded.input_count(), round.input_count());
+            assert_eq!(expanded.directives, round.directives);
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{Instruction, Locale, Mode, Round, Terminology};
+use std::fmt::Write;
+
+/// Controls how [`format_instruction`] and [`format_round`] render instructions.
+///
+/// The default options produce the same output as the `Display` impls.
+///
+/// ```
+/// # use crochet::{format_round, parse_rounds, FormatOptions};
+/// let round = &parse_rounds("sc 6 in mr, [inc, sc] 3").unwrap()[0];
+/// let opts = FormatOptions {
+///     bracket_spacing: true,
+///     repeat_x: true,
+///     uppercase_keywords: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(format_round(round, &opts), "SC x6 IN MR, [ INC, SC ] x3");
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct FormatOptions {
+    /// Write out the first iteration of each repeated group in a round, followed by how many more
+    /// times to work it, e.g. `*inc, sc; repeat from * 5 more times` instead of `[inc, sc] 6`.
+    pub progressive_repeats: bool,
+    /// How the rounds are connected. In [`Mode::Joined`] and [`Mode::Rows`],
+    /// [`crate::pretty_format_with_options`] ends each round with a join or turn and doesn't count
+    /// joining and turning chains as stitches.
+    pub mode: Mode,
+    /// Put spaces just inside brackets and parentheses, e.g. `[ inc, sc ] 6`
+    pub bracket_spacing: bool,
+    /// Write repeat counts with an `x`, e.g. `sc x6` instead of `sc 6`
+    pub repeat_x: bool,
+    /// Write stitch names and other keywords in uppercase, e.g. `SC 6 IN MR`. Comments and
+    /// color keys are written as-is.
+    pub uppercase_keywords: bool,
+    /// Separate instructions with just `,` instead of `, `
+    pub tight_commas: bool,
+    /// What [`crate::pretty_format_with_options`] calls each round, e.g. `Rnd 1:`
+    pub round_word: RoundWord,
+    /// The number [`crate::pretty_format_with_options`] gives the first round, or 1 if `None`.
+    /// For example, `Some(0)` numbers a foundation chain as row 0.
+    pub first_number: Option<u32>,
+    /// Leave out the stitch count at the end of each round in
+    /// [`crate::pretty_format_with_options`]
+    pub hide_counts: bool,
+    /// How [`crate::pretty_format_with_options`] writes the stitch count at the end of each round
+    pub count_style: CountStyle,
+    /// Which names to write the stitches with, e.g. `dc` instead of `sc` in UK terms
+    pub terminology: Terminology,
+    /// The language to write the stitches and rounds in. Other languages than English don't
+    /// have US and UK terms, so they ignore `terminology`, and they name rounds their own way
+    /// instead of with `round_word`.
+    pub locale: Locale,
+}
+
+/// What each round is called when pretty printing, see [`FormatOptions::round_word`]. Rows are
+/// called `Row` instead of `Round` or `Rnd`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum RoundWord {
+    /// `Round 1:`, and `Rounds 2-4:` for a block of repeated rounds
+    #[default]
+    Round,
+    /// `Rnd 1:` and `Rnds 2-4:`
+    Rnd,
+    /// `R1:` and `R2-4:`, for rounds and rows alike
+    R,
+}
+
+impl RoundWord {
+    /// The word for one round or several, including the space before the number if it has one
+    pub(crate) fn word(self, mode: Mode, plural: bool) -> &'static str {
+        match (self, mode == Mode::Rows, plural) {
+            (Self::R, ..) => "R",
+            (_, true, false) => "Row ",
+            (_, true, true) => "Rows ",
+            (Self::Round, false, false) => "Round ",
+            (Self::Round, false, true) => "Rounds ",
+            (Self::Rnd, false, false) => "Rnd ",
+            (Self::Rnd, false, true) => "Rnds ",
+        }
+    }
+}
+
+/// How the stitch count of a round is written when pretty printing, see
+/// [`FormatOptions::count_style`]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum CountStyle {
+    /// `(6)`
+    #[default]
+    Parenthesized,
+    /// `= 6 sts`
+    Equals,
+}
+
+impl FormatOptions {
+    fn keyword(&self, ret: &mut String, keyword: &str) {
+        let keyword = match self.locale.keyword(keyword) {
+            Some(k) => k,
+            None => self.terminology.keyword(keyword),
+        };
+        if self.uppercase_keywords {
+            ret.push_str(&keyword.to_uppercase());
+        } else {
+            ret.push_str(keyword);
+        }
+    }
+
+    fn comma(&self, ret: &mut String) {
+        ret.push_str(if self.tight_commas { "," } else { ", " });
+    }
+
+    fn open(&self, ret: &mut String, bracket: char) {
+        ret.push(bracket);
+        if self.bracket_spacing {
+            ret.push(' ');
+        }
+    }
+
+    fn close(&self, ret: &mut String, bracket: char) {
+        if self.bracket_spacing {
+            ret.push(' ');
+        }
+        ret.push(bracket);
+    }
+}
+
+/// Formats a single instruction like its `Display` impl, but in the style given by `opts`.
+/// Repeated groups are always written with brackets, even with `opts.progressive_repeats`.
+fn format_inst(ret: &mut String, inst: &Instruction, opts: &FormatOptions) {
+    use Instruction::*;
+
+    // instructions with a suffix need brackets around a group
+    let bracketed = |ret: &mut String, inst: &Instruction| {
+        if let Group(_) = inst {
+            opts.open(ret, '[');
+            format_inst(ret, inst, opts);
+            opts.close(ret, ']');
+        } else {
+            format_inst(ret, inst, opts);
+        }
+    };
+
+    match inst {
+        Ch => opts.keyword(ret, "ch"),
+        Tch => opts.keyword(ret, "tch"),
+        Sc => opts.keyword(ret, "sc"),
+        Fpsc => opts.keyword(ret, "fpsc"),
+        Bpsc => opts.keyword(ret, "bpsc"),
+        Blsc => opts.keyword(ret, "blsc"),
+        Inc => opts.keyword(ret, "inc"),
+        Flinc => opts.keyword(ret, "flinc"),
+        Blinc => opts.keyword(ret, "blinc"),
+        Dec => opts.keyword(ret, "dec"),
+        IntoMagicRing(i) => {
+            bracketed(ret, i);
+            ret.push(' ');
+            opts.keyword(ret, "in mr");
+        }
+        Repeat(i, times) => {
+            bracketed(ret, i);
+            ret.push_str(if opts.repeat_x { " x" } else { " " });
+            write!(ret, "{times}").expect("writing to a string shouldn't fail");
+        }
+        Group(insts) => {
+            for (idx, i) in insts.iter().enumerate() {
+                if idx != 0 {
+                    opts.comma(ret);
+                }
+                format_inst(ret, i, opts);
+            }
+        }
+        Comment(s) => write!(ret, "% {s} %").expect("writing to a string shouldn't fail"),
+        Skip(n) => {
+            opts.keyword(ret, "skip");
+            write!(ret, " {n}").expect("writing to a string shouldn't fail");
+        }
+        SurfaceSlSt(n) => {
+            opts.keyword(ret, "surface sl st");
+            write!(ret, " {n}").expect("writing to a string shouldn't fail");
+        }
+        Crossed(a, b) => {
+            opts.keyword(ret, "cross");
+            opts.open(ret, '(');
+            format_inst(ret, a, opts);
+            opts.comma(ret);
+            format_inst(ret, b, opts);
+            opts.close(ret, ')');
+        }
+        ChSpace { chains, skipped } => {
+            opts.keyword(ret, "ch");
+            write!(ret, " {chains}").expect("writing to a string shouldn't fail");
+            opts.comma(ret);
+            opts.keyword(ret, "skip");
+            write!(ret, " {skipped}").expect("writing to a string shouldn't fail");
+        }
+        IntoOne(g) => {
+            opts.open(ret, '(');
+            format_inst(ret, g, opts);
+            opts.close(ret, ')');
+            ret.push(' ');
+            opts.keyword(ret, "in next st");
+        }
+        Color(c) => write!(ret, "@{c}").expect("writing to a string shouldn't fail"),
+        // not a keyword, so it's written exactly as it was registered
+        Custom(stitch) => ret.push_str(stitch.name()),
+    }
+}
+
+/// Formats a list of comma-separated instructions, e.g. the contents of a round or group.
+fn format_list(ret: &mut String, insts: &[Instruction], opts: &FormatOptions) {
+    let mut stars = String::new();
+
+    for (i, inst) in insts.iter().enumerate() {
+        if i != 0 {
+            opts.comma(ret);
+        }
+
+        match inst {
+            Instruction::Repeat(g, times)
+                if opts.progressive_repeats
+                    && *times > 1
+                    && matches!(**g, Instruction::Group(_)) =>
+            {
+                stars.push('*');
+                let more = times - 1;
+                let plural = if more == 1 { "time" } else { "times" };
+
+                ret.push_str(&stars);
+                format_inst(ret, g, opts);
+                write!(ret, "; repeat from {stars} {more} more {plural}")
+                    .expect("writing to a string shouldn't fail");
+            }
+            _ => format_inst(ret, inst, opts),
+        }
+    }
+}
+
+/// Formats an instruction according to `opts`.
+///
+/// ```
+/// # use crochet::{format_instruction, FormatOptions, Instruction};
+/// let inst = Instruction::Repeat(Instruction::Inc.into(), 6);
+/// assert_eq!(format_instruction(&inst, &FormatOptions::default()), "inc 6");
+/// ```
+pub fn format_instruction(inst: &Instruction, opts: &FormatOptions) -> String {
+    let mut ret = String::new();
+
+    match inst {
+        Instruction::Group(insts) => format_list(&mut ret, insts, opts),
+        _ => format_inst(&mut ret, inst, opts),
+    }
+
+    ret
+}
+
+/// Formats a whole round according to `opts`.
+///
+/// ```
+/// # use crochet::{format_round, parse_rounds, FormatOptions};
+/// let rounds = parse_rounds("sc, [inc, sc 2] 6").unwrap();
+/// let opts = FormatOptions {
+///     progressive_repeats: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     format_round(&rounds[0], &opts),
+///     "sc, *inc, sc 2; repeat from * 5 more times"
+/// );
+/// ```
+pub fn format_round(round: &Round, opts: &FormatOptions) -> String {
+    let mut ret = String::new();
+
+    if round.times != 1 {
+        write!(ret, "x{}: ", round.times).expect("writing to a string shouldn't fail");
+    }
+
+    if let Some(label) = round.label {
+        write!(ret, "{label}: ").expect("writing to a string shouldn't fail");
+    }
+
+    format_list(&mut ret, &round.instructions, opts);
+
+    ret
+}
+
+/// Formats the instructions of a round according to `opts`, without its label.
+pub(crate) fn format_round_instructions(round: &Round, opts: &FormatOptions) -> String {
+    let mut ret = String::new();
+    format_list(&mut ret, &round.instructions, opts);
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn progressive(src: &str) -> String {
+        let opts = FormatOptions {
+            progressive_repeats: true,
+            ..Default::default()
+        };
+        format_round(&parse_rounds(src).unwrap()[0], &opts)
+    }
+
+    #[test]
+    fn test_default_options_match_display() {
+        let src = "sc 4 in mr, inc, [sc, % hi im a comment %, inc] 2";
+        let round = &parse_rounds(src).unwrap()[0];
+        assert_eq!(format_round(round, &FormatOptions::default()), src);
+    }
+
+    #[test]
+    fn test_default_options_match_display_for_every_instruction() {
+        let src = "tch, ch 2, sc, fpsc, bpsc, blsc, inc, flinc, blinc, dec, [sc 2] in mr, skip 2, \
+            surface sl st 3, cross(fpsc, [sc] 2), ch 3, skip 2, (sc, ch 2, sc) in next st";
+        let round = &parse_rounds(src).unwrap()[0];
+        assert_eq!(
+            format_round(round, &FormatOptions::default()),
+            round.to_string()
+        );
+    }
+
+    #[test]
+    fn test_house_styles() {
+        let round = &parse_rounds(
+            "[inc, sc] 2 in mr, cross(fpsc, sc), ch 3, skip 2, (sc, ch 2) in next st, % Hi %",
+        )
+        .unwrap()[0];
+
+        let opts = FormatOptions {
+            uppercase_keywords: true,
+            tight_commas: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_round(round, &opts),
+            "[INC,SC] 2 IN MR,CROSS(FPSC,SC),CH 3,SKIP 2,(SC,CH 2) IN NEXT ST,% Hi %"
+        );
+
+        let opts = FormatOptions {
+            bracket_spacing: true,
+            repeat_x: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_round(round, &opts),
+            "[ inc, sc ] x2 in mr, cross( fpsc, sc ), ch 3, skip 2, ( sc, ch x2 ) in next st, % Hi %"
+        );
+
+        let opts = FormatOptions {
+            progressive_repeats: true,
+            bracket_spacing: true,
+            repeat_x: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_round(&parse_rounds("[[inc, sc] 2, dec] 3").unwrap()[0], &opts),
+            "*[ inc, sc ] x2, dec; repeat from * 2 more times"
+        );
+    }
+
+    #[test]
+    fn test_progressive_repeats() {
+        assert_eq!(
+            progressive("[inc, sc] 6"),
+            "*inc, sc; repeat from * 5 more times"
+        );
+        assert_eq!(
+            progressive("[inc, sc] 2, sc 3, [dec, sc] 2"),
+            "*inc, sc; repeat from * 1 more time, sc 3, **dec, sc; repeat from ** 1 more time"
+        );
+        // single stitches and single iterations aren't expanded
+        assert_eq!(progressive("sc 6, [inc, sc] 1"), "sc 6, [inc, sc] 1");
+        assert_eq!(progressive("[sc, inc] 3 in mr"), "[sc, inc] 3 in mr");
+    }
+}
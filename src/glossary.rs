@@ -0,0 +1,294 @@
+use crate::text::stitches;
+use crate::{walk_instruction, Instruction, InstructionVisitor, Mode, Pattern, Round};
+
+/// Each abbreviation and other keyword with its full name and how it's worked, in the order
+/// they're listed in a glossary
+pub(crate) const ENTRIES: [(&str, &str, &str); 17] = [
+    (
+        "ch",
+        "chain",
+        "Yarn over and pull through the loop on the hook.",
+    ),
+    (
+        "tch",
+        "turning chain",
+        "A chain at the end of a row that brings the yarn up to the height of the next row. It \
+            doesn't count as a stitch.",
+    ),
+    (
+        "mr",
+        "magic ring",
+        "An adjustable loop that the first round is worked into, then pulled tight to close the \
+            center.",
+    ),
+    (
+        "altmr",
+        "alternative magic ring",
+        "Any other way of starting a ring that the first round is worked into.",
+    ),
+    (
+        "in mr",
+        "into the magic ring",
+        "Work the stitches into the magic ring.",
+    ),
+    (
+        "sc",
+        "single crochet",
+        "Insert the hook into the stitch, yarn over and pull up a loop, then yarn over and pull \
+            through both loops on the hook.",
+    ),
+    (
+        "fpsc",
+        "front post single crochet",
+        "A single crochet worked around the post of the stitch from the front, instead of into \
+            its top.",
+    ),
+    (
+        "bpsc",
+        "back post single crochet",
+        "A single crochet worked around the post of the stitch from the back, instead of into \
+            its top.",
+    ),
+    (
+        "blsc",
+        "back loop single crochet",
+        "A single crochet worked into only the back loop of the stitch.",
+    ),
+    ("inc", "increase", "Two single crochet in the same stitch."),
+    (
+        "flinc",
+        "front loop increase",
+        "Two single crochet in the front loop of the same stitch.",
+    ),
+    (
+        "blinc",
+        "back loop increase",
+        "Two single crochet in the back loop of the same stitch.",
+    ),
+    (
+        "dec",
+        "decrease",
+        "Single crochet two stitches together: pull up a loop in each of the next two stitches, \
+            then yarn over and pull through all three loops on the hook.",
+    ),
+    (
+        "skip",
+        "skip",
+        "Leave the stitches unworked and go on to the one after them.",
+    ),
+    (
+        "in next st",
+        "into the next stitch",
+        "Work everything in the parentheses into the same stitch.",
+    ),
+    (
+        "cross",
+        "crossed stitches",
+        "Skip a stitch and work the first stitch into the next one, then work the second stitch \
+            into the skipped one, crossing in front of the first.",
+    ),
+    (
+        "surface sl st",
+        "surface slip stitch",
+        "A slip stitch worked on top of the fabric, with the yarn held behind it, to embroider a \
+            line.",
+    ),
+];
+
+/// Collects the abbreviations and custom stitches used by instructions
+#[derive(Default)]
+struct Used {
+    abbrs: Vec<&'static str>,
+    /// The name of each custom stitch, how many stitches it's worked into and how many it makes
+    custom: Vec<(String, u32, u32)>,
+}
+
+impl Used {
+    fn add(&mut self, abbr: &'static str) {
+        if !self.abbrs.contains(&abbr) {
+            self.abbrs.push(abbr);
+        }
+    }
+}
+
+impl InstructionVisitor<'_> for Used {
+    fn visit_instruction(&mut self, inst: &Instruction) {
+        use Instruction::*;
+
+        match inst {
+            Ch => self.add("ch"),
+            Tch => self.add("tch"),
+            Sc => self.add("sc"),
+            Fpsc => self.add("fpsc"),
+            Bpsc => self.add("bpsc"),
+            Blsc => self.add("blsc"),
+            Inc => self.add("inc"),
+            Flinc => self.add("flinc"),
+            Blinc => self.add("blinc"),
+            Dec => self.add("dec"),
+            IntoMagicRing(_) => self.add("mr"),
+            IntoOne(_) => self.add("in next st"),
+            Skip(_) => self.add("skip"),
+            SurfaceSlSt(_) => self.add("surface sl st"),
+            Crossed(..) => self.add("cross"),
+            ChSpace { .. } => {
+                self.add("ch");
+                self.add("skip");
+            }
+            Custom(stitch) => {
+                if self.custom.iter().all(|(name, ..)| name != stitch.name()) {
+                    self.custom.push((
+                        stitch.name().to_string(),
+                        stitch.input_count(),
+                        stitch.output_count(),
+                    ));
+                }
+            }
+            Repeat(..) | Group(_) | Comment(_) | Color(_) => {}
+        }
+
+        walk_instruction(self, inst);
+    }
+}
+
+/// Lists the abbreviations used in a pattern, with their full names and a description of how
+/// they're worked, for the abbreviations section at the top of a published pattern.
+///
+/// Only the stitches and other keywords that actually appear in the pattern's rounds are listed,
+/// in the order glossaries usually list them, followed by any custom stitches in the order
+/// they're first used. Joined rounds and rows add a chain to each round when pretty printed, so
+/// `ch` is always listed for them.
+///
+/// ```
+/// # use crochet::{abbreviation_glossary, parse_pattern};
+/// let pattern = parse_pattern("sc 6 in mr\n[inc, sc] 6\ndec 9").unwrap();
+/// let abbrs: Vec<_> = abbreviation_glossary(&pattern)
+///     .into_iter()
+///     .map(|(abbr, full_name, _)| format!("{abbr}: {full_name}"))
+///     .collect();
+///
+/// assert_eq!(
+///     abbrs,
+///     ["mr: magic ring", "sc: single crochet", "inc: increase", "dec: decrease"]
+/// );
+/// ```
+pub fn abbreviation_glossary(pattern: &Pattern) -> Vec<(String, String, String)> {
+    let mut used = Used::default();
+    if pattern.mode() != Mode::Spiral {
+        used.add("ch");
+    }
+    for round in pattern.parts.iter().flat_map(|p| &p.rounds) {
+        used.visit_round(round);
+    }
+
+    let mut ret: Vec<_> = ENTRIES
+        .iter()
+        .filter(|(abbr, ..)| used.abbrs.contains(abbr))
+        .map(|&(abbr, name, desc)| (abbr.to_string(), name.to_string(), desc.to_string()))
+        .collect();
+
+    for (name, input, output) in used.custom {
+        let description = format!(
+            "A custom stitch worked into {} that makes {}.",
+            stitches(input),
+            stitches(output)
+        );
+        ret.push((name.clone(), name, description));
+    }
+
+    ret
+}
+
+/// The abbreviations used by `rounds` with their full names, in the order glossaries list them
+pub(crate) fn full_names<'r, 'a: 'r>(
+    rounds: impl IntoIterator<Item = &'r Round<'a>>,
+) -> Vec<(&'static str, &'static str)> {
+    let mut used = Used::default();
+    for round in rounds {
+        used.visit_round(round);
+    }
+
+    ENTRIES
+        .iter()
+        .filter(|(abbr, ..)| used.abbrs.contains(abbr))
+        .map(|&(abbr, name, _)| (abbr, name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_pattern, parse_pattern_with_dialect, CustomStitch, Dialect};
+
+    #[derive(Debug)]
+    struct Bobble;
+
+    impl CustomStitch for Bobble {
+        fn name(&self) -> &str {
+            "bobble"
+        }
+
+        fn input_count(&self) -> u32 {
+            1
+        }
+
+        fn output_count(&self) -> u32 {
+            1
+        }
+
+        fn symbol(&self) -> &str {
+            "B"
+        }
+    }
+
+    fn abbrs(pattern: &Pattern) -> Vec<String> {
+        abbreviation_glossary(pattern)
+            .into_iter()
+            .map(|(abbr, ..)| abbr)
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_and_across_parts() {
+        let src = "== Head ==\nsc 6 in mr\n[cross(fpsc, blsc), (sc, ch 2) in next st] 2\n\
+            == Arm ==\nsc 4 in mr\nch 3, skip 2, surface sl st 2";
+        assert_eq!(
+            abbrs(&parse_pattern(src).unwrap()),
+            [
+                "ch",
+                "mr",
+                "sc",
+                "fpsc",
+                "blsc",
+                "skip",
+                "in next st",
+                "cross",
+                "surface sl st"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rows_always_have_chains() {
+        let pattern = parse_pattern("mode: rows\nsc 10 in mr\nsc 10").unwrap();
+        assert_eq!(abbrs(&pattern), ["ch", "mr", "sc"]);
+    }
+
+    #[test]
+    fn test_custom_stitches() {
+        let dialect = Dialect::new().stitch(Bobble);
+        let pattern =
+            parse_pattern_with_dialect("sc 6 in mr\n[bobble, sc] 3, bobble 3", &dialect).unwrap();
+        let glossary = abbreviation_glossary(&pattern);
+
+        assert_eq!(glossary.len(), 3);
+        assert_eq!(
+            glossary[2],
+            (
+                "bobble".to_string(),
+                "bobble".to_string(),
+                "A custom stitch worked into 1 stitch that makes 1 stitch.".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use crate::mesh::{cross, dot};
+use crate::{Mesh, Pattern};
+use std::fmt::Write;
+
+/// The colors of yarn that are recognized in a palette, e.g. `blue` in `A = DK blue`
+const COLOR_NAMES: [(&str, [f32; 3]); 26] = [
+    ("white", [0.95, 0.95, 0.95]),
+    ("cream", [0.96, 0.92, 0.8]),
+    ("ivory", [0.96, 0.94, 0.85]),
+    ("beige", [0.86, 0.8, 0.66]),
+    ("tan", [0.82, 0.7, 0.55]),
+    ("brown", [0.5, 0.32, 0.2]),
+    ("black", [0.08, 0.08, 0.08]),
+    ("gray", [0.55, 0.55, 0.55]),
+    ("grey", [0.55, 0.55, 0.55]),
+    ("silver", [0.75, 0.75, 0.75]),
+    ("red", [0.8, 0.15, 0.15]),
+    ("pink", [0.95, 0.6, 0.7]),
+    ("coral", [0.95, 0.5, 0.4]),
+    ("orange", [0.95, 0.55, 0.15]),
+    ("yellow", [0.95, 0.85, 0.25]),
+    ("gold", [0.85, 0.68, 0.2]),
+    ("green", [0.25, 0.6, 0.3]),
+    ("mint", [0.65, 0.9, 0.75]),
+    ("olive", [0.5, 0.5, 0.2]),
+    ("teal", [0.15, 0.55, 0.55]),
+    ("blue", [0.2, 0.4, 0.8]),
+    ("navy", [0.1, 0.15, 0.4]),
+    ("purple", [0.5, 0.25, 0.6]),
+    ("lavender", [0.75, 0.7, 0.9]),
+    ("lilac", [0.8, 0.65, 0.85]),
+    ("turquoise", [0.25, 0.8, 0.8]),
+];
+
+/// The color used for rounds without a known color
+const DEFAULT_COLOR: [f32; 3] = [0.8, 0.8, 0.8];
+
+impl Mesh {
+    /// Writes the mesh as a self-contained glTF 2.0 file, for showing in web pages with e.g.
+    /// `<model-viewer>`. Each round is its own primitive, in a color of its own so the rounds can
+    /// be told apart.
+    ///
+    /// The mesh is turned so that the rounds stack up along glTF's Y axis.
+    ///
+    /// ```
+    /// # use crochet::{parse_rounds, simulate_mesh};
+    /// let mesh = simulate_mesh(&parse_rounds("sc 6 in mr\ninc 6\nsc 12").unwrap());
+    /// let gltf = mesh.to_gltf();
+    ///
+    /// assert!(gltf.starts_with(r#"{"asset":{"version":"2.0","generator":"crochet"}"#));
+    /// assert_eq!(gltf.matches(r#""material":"#).count(), 3);
+    /// ```
+    pub fn to_gltf(&self) -> String {
+        let rounds = self.triangle_rounds.iter().max().map_or(0, |r| r + 1);
+        let colors: Vec<_> = (0..rounds).map(round_color).collect();
+        self.to_gltf_with_colors(&colors)
+    }
+
+    /// Like [`Self::to_gltf`], but with round `r` in `colors[r]`, given as red, green and blue
+    /// from 0 to 1, e.g. from [`yarn_colors`]. Rounds of the same color share a primitive, and any
+    /// rounds past the end of `colors` are light gray.
+    pub fn to_gltf_with_colors(&self, colors: &[[f32; 3]]) -> String {
+        // the triangles of each color, in the order the colors are first used
+        let mut by_round: Vec<_> = self.triangles.iter().zip(&self.triangle_rounds).collect();
+        by_round.sort_by_key(|&(_, round)| round);
+
+        let mut groups: Vec<([f32; 3], Vec<[usize; 3]>)> = Vec::new();
+        for (tri, &round) in by_round {
+            let color = colors.get(round).copied().unwrap_or(DEFAULT_COLOR);
+            match groups.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, tris)) => tris.push(*tri),
+                None => groups.push((color, vec![*tri])),
+            }
+        }
+
+        // glTF's up is Y, and the rounds stack up along Z
+        let positions: Vec<[f32; 3]> = self
+            .vertices
+            .iter()
+            .map(|&[x, y, z]| [x as f32, z as f32, -y as f32])
+            .collect();
+        let normals: Vec<[f32; 3]> = vertex_normals(self)
+            .into_iter()
+            .map(|[x, y, z]| [x as f32, z as f32, -y as f32])
+            .collect();
+
+        let mut buffer = Vec::new();
+        for v in positions.iter().chain(&normals) {
+            for c in v {
+                buffer.extend(c.to_le_bytes());
+            }
+        }
+        let vertex_bytes = 12 * positions.len();
+
+        let mut views = vec![
+            format!(r#"{{"buffer":0,"byteOffset":0,"byteLength":{vertex_bytes},"target":34962}}"#),
+            format!(
+                r#"{{"buffer":0,"byteOffset":{vertex_bytes},"byteLength":{vertex_bytes},"target":34962}}"#
+            ),
+        ];
+
+        let (min, max) = bounds(&positions);
+        let mut accessors = vec![
+            format!(
+                r#"{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","min":{},"max":{}}}"#,
+                positions.len(),
+                vec3(min),
+                vec3(max)
+            ),
+            format!(
+                r#"{{"bufferView":1,"componentType":5126,"count":{},"type":"VEC3"}}"#,
+                normals.len()
+            ),
+        ];
+
+        let mut primitives = Vec::new();
+        let mut materials = Vec::new();
+        for (i, (color, tris)) in groups.iter().enumerate() {
+            let offset = buffer.len();
+            for &index in tris.iter().flatten() {
+                buffer.extend((index as u32).to_le_bytes());
+            }
+
+            views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{},"target":34963}}"#,
+                buffer.len() - offset
+            ));
+            accessors.push(format!(
+                r#"{{"bufferView":{},"componentType":5125,"count":{},"type":"SCALAR"}}"#,
+                views.len() - 1,
+                3 * tris.len()
+            ));
+            primitives.push(format!(
+                r#"{{"attributes":{{"POSITION":0,"NORMAL":1}},"indices":{},"material":{i}}}"#,
+                accessors.len() - 1
+            ));
+            let [r, g, b] = color;
+            materials.push(format!(
+                r#"{{"pbrMetallicRoughness":{{"baseColorFactor":[{r},{g},{b},1],"metallicFactor":0,"roughnessFactor":1}},"doubleSided":true}}"#
+            ));
+        }
+
+        let mut ret = String::from(r#"{"asset":{"version":"2.0","generator":"crochet"}"#);
+        write!(
+            ret,
+            r#","scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"meshes":[{{"primitives":[{}]}}],"materials":[{}],"buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}],"bufferViews":[{}],"accessors":[{}]}}"#,
+            primitives.join(","),
+            materials.join(","),
+            buffer.len(),
+            base64(&buffer),
+            views.join(","),
+            accessors.join(",")
+        )
+        .expect("writing to a string shouldn't fail");
+
+        ret
+    }
+}
+
+/// The yarn color of each round of a part of a pattern, for
+/// [`Mesh::to_gltf_with_colors`], with one color for each round worked like the mesh from
+/// [`crate::simulate_mesh`].
+///
+/// A round is the color that most of its stitches are made in (see [`Pattern::round_colors`]), and
+/// a color's name in the `colors:` header is looked up by its last word that's a common color
+/// name, like `blue` in `DK blue`, or as a hex code like `#3366cc`. Rounds whose color isn't
+/// recognized, or patterns without a palette, are light gray.
+///
+/// ```
+/// # use crochet::{parse_pattern, simulate_mesh, yarn_colors};
+/// let pattern = parse_pattern("colors: A = cream, B = #ff0000\nsc 6 in mr\nx2: @B, sc 6").unwrap();
+/// let colors = yarn_colors(&pattern, 0);
+///
+/// assert_eq!(colors, [[0.96, 0.92, 0.8], [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]]);
+///
+/// let mesh = simulate_mesh(&pattern.parts[0].rounds);
+/// assert_eq!(mesh.to_gltf_with_colors(&colors).matches(r#""material":"#).count(), 2);
+/// ```
+pub fn yarn_colors(pattern: &Pattern, part: usize) -> Vec<[f32; 3]> {
+    let palette = pattern.meta.palette();
+    let (Some(rounds), Some(colors)) = (
+        pattern.parts.get(part),
+        pattern.round_colors().into_iter().nth(part),
+    ) else {
+        return Vec::new();
+    };
+
+    rounds
+        .rounds
+        .iter()
+        .zip(colors)
+        .flat_map(|(round, counts)| {
+            // the first of the most used colors
+            let key = counts
+                .iter()
+                .rev()
+                .max_by_key(|(_, &n)| n)
+                .map(|(&key, _)| key);
+            let color = key
+                .and_then(|key| palette.iter().find(|(k, _)| *k == key))
+                .and_then(|(_, name)| parse_color(name))
+                .unwrap_or(DEFAULT_COLOR);
+
+            std::iter::repeat_n(color, round.times as usize)
+        })
+        .collect()
+}
+
+/// Looks up a color from a palette, as a hex code or by its last word that's a color name
+fn parse_color(name: &str) -> Option<[f32; 3]> {
+    if let Some(hex) = name.strip_prefix('#').filter(|h| h.len() == 6) {
+        let channel = |i: usize| {
+            let c = u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()?;
+            Some(f32::from(c) / 255.0)
+        };
+        return Some([channel(0)?, channel(2)?, channel(4)?]);
+    }
+
+    let name = name.to_lowercase();
+    name.split(|c: char| !c.is_alphabetic())
+        .rev()
+        .find_map(|word| {
+            COLOR_NAMES
+                .iter()
+                .find(|(n, _)| *n == word)
+                .map(|&(_, c)| c)
+        })
+}
+
+/// A color for round `r` that stands out from the rounds next to it, stepping around the color
+/// wheel by the golden angle
+fn round_color(r: usize) -> [f32; 3] {
+    let hue = (r as f32 * 0.618_034).fract() * 6.0;
+    let (s, v) = (0.5, 0.9);
+
+    let f = hue.fract();
+    let (p, q, t) = (v * (1.0 - s), v * (1.0 - s * f), v * (1.0 - s * (1.0 - f)));
+    match hue as u32 {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    }
+}
+
+/// The normal of each vertex, averaged from the triangles around it and weighted by their areas
+fn vertex_normals(mesh: &Mesh) -> Vec<[f64; 3]> {
+    let mut normals = vec![[0.0; 3]; mesh.vertices.len()];
+
+    for tri in &mesh.triangles {
+        let [a, b, c] = tri.map(|i| mesh.vertices[i]);
+        let n = cross(
+            [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+            [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+        );
+        for &i in tri {
+            for k in 0..3 {
+                normals[i][k] += n[k];
+            }
+        }
+    }
+
+    normals
+        .into_iter()
+        .map(|n| {
+            let len = dot(n, n).sqrt();
+            if len > 1e-12 {
+                n.map(|x| x / len)
+            } else {
+                [0.0, 0.0, 1.0]
+            }
+        })
+        .collect()
+}
+
+fn bounds(points: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    if points.is_empty() {
+        return ([0.0; 3], [0.0; 3]);
+    }
+
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for p in points {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+    }
+
+    (min, max)
+}
+
+fn vec3([x, y, z]: [f32; 3]) -> String {
+    format!("[{x},{y},{z}]")
+}
+
+/// Encodes bytes as standard base64, with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut ret = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(char::from(ALPHABET[(n >> (18 - 6 * i)) as usize & 63]));
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_pattern, parse_rounds, simulate_mesh};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("DK Blue"), Some([0.2, 0.4, 0.8]));
+        assert_eq!(parse_color("navy-blue"), Some([0.2, 0.4, 0.8]));
+        assert_eq!(parse_color("#00ff00"), Some([0.0, 1.0, 0.0]));
+        assert_eq!(parse_color("#00ff0"), None);
+        assert_eq!(parse_color("sunset"), None);
+    }
+
+    #[test]
+    fn test_round_colors_differ() {
+        for r in 0..20 {
+            assert_ne!(round_color(r), round_color(r + 1));
+        }
+    }
+
+    #[test]
+    fn test_yarn_colors() {
+        let src = "colors: A = cream, B = dark brown, C = sparkles\n== Head ==\nsc 6 in mr\n\
+            @B, inc 6\n@C, sc 12\n== Arm ==\nsc 6 in mr\n@B, sc 2, @A, sc 4";
+        let pattern = parse_pattern(src).unwrap();
+
+        let cream = [0.96, 0.92, 0.8];
+        let brown = [0.5, 0.32, 0.2];
+        assert_eq!(yarn_colors(&pattern, 0), [cream, brown, DEFAULT_COLOR]);
+        // each part starts in the main color, and a round takes its most used color
+        assert_eq!(yarn_colors(&pattern, 1), [cream, cream]);
+        assert_eq!(yarn_colors(&pattern, 2), Vec::<[f32; 3]>::new());
+
+        let no_palette = parse_pattern("sc 6 in mr").unwrap();
+        assert_eq!(yarn_colors(&no_palette, 0), [DEFAULT_COLOR]);
+    }
+
+    #[test]
+    fn test_to_gltf() {
+        let mesh = simulate_mesh(&parse_rounds("sc 6 in mr\ninc 6\nsc 12").unwrap());
+        let gltf = mesh.to_gltf();
+
+        let json: serde_json::Value = serde_json::from_str(&gltf).unwrap();
+        assert_eq!(json["meshes"][0]["primitives"].as_array().unwrap().len(), 3);
+        assert_eq!(json["materials"].as_array().unwrap().len(), 3);
+        assert_eq!(json["accessors"][0]["count"], 6 + 12 + 12 + 1);
+
+        // each round's triangles, as indices
+        let index_counts: Vec<_> = (2..5)
+            .map(|i| json["accessors"][i]["count"].as_u64().unwrap())
+            .collect();
+        assert_eq!(index_counts, [3 * 6, 3 * (6 + 12), 3 * (12 + 12)]);
+
+        // the buffer holds the positions, normals and indices
+        let length = json["buffers"][0]["byteLength"].as_u64().unwrap();
+        assert_eq!(length, 2 * 12 * 31 + 4 * 3 * (6 + 18 + 24));
+        let uri = json["buffers"][0]["uri"].as_str().unwrap();
+        let data = uri
+            .strip_prefix("data:application/octet-stream;base64,")
+            .unwrap();
+        assert_eq!(data.len() as u64, length.div_ceil(3) * 4);
+
+        // the rounds stack up along Y
+        let max_y = json["accessors"][0]["max"][1].as_f64().unwrap();
+        assert!(max_y > 1.0);
+
+        // rounds of one color share a primitive
+        let gltf = mesh.to_gltf_with_colors(&[[1.0, 0.0, 0.0]; 3]);
+        assert_eq!(gltf.matches(r#""material":"#).count(), 1);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::round::expand_rounds;
+use crate::{Instruction, Round};
+use std::fmt::Write;
+
+/// Which stitches of the previous round each stitch is worked into.
+///
+/// Rounds are numbered in the order they're worked, from 0, with each block of identical rounds
+/// (`x3: sc 18`) counting as that many rounds. Stitches are numbered in the order they're made
+/// within their round, from 0.
+///
+/// Stitches made without working into the previous round, like chains and stitches worked into
+/// the magic ring, don't have any parents. The counts aren't checked, so a round that works into
+/// more stitches than the previous round made has parents that don't exist; see
+/// [`crate::lint_rounds`] for catching that.
+///
+/// ```
+/// # use crochet::{parse_rounds, StitchGraph};
+/// let rounds = parse_rounds("sc 4 in mr\ninc, sc, dec").unwrap();
+/// let graph = StitchGraph::new(&rounds);
+///
+/// assert_eq!(graph.parents(0, 3), Some(&[][..]));
+/// // both stitches of the inc are worked into the first stitch
+/// assert_eq!(graph.parents(1, 0), Some(&[0][..]));
+/// assert_eq!(graph.parents(1, 1), Some(&[0][..]));
+/// assert_eq!(graph.parents(1, 2), Some(&[1][..]));
+/// // the dec works the last two stitches together
+/// assert_eq!(graph.parents(1, 3), Some(&[2, 3][..]));
+/// assert_eq!(graph.children(0, 3), [3]);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct StitchGraph {
+    /// For each round, for each stitch it makes, the stitches of the previous round it's worked
+    /// into
+    parents: Vec<Vec<Vec<usize>>>,
+}
+
+impl StitchGraph {
+    pub fn new(rounds: &[Round]) -> Self {
+        let parents = expand_rounds(rounds)
+            .map(|round| {
+                let mut builder = Builder::default();
+                for inst in &round.instructions {
+                    builder.add(inst, Into::Previous);
+                }
+                builder.stitches
+            })
+            .collect();
+
+        Self { parents }
+    }
+
+    /// How many rounds are worked
+    pub fn rounds(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// How many stitches `round` makes, or `None` if there's no such round
+    pub fn stitch_count(&self, round: usize) -> Option<usize> {
+        Some(self.parents.get(round)?.len())
+    }
+
+    /// The stitches of round `round - 1` that `stitch` of `round` is worked into, or `None` if
+    /// there's no such stitch.
+    pub fn parents(&self, round: usize, stitch: usize) -> Option<&[usize]> {
+        Some(self.parents.get(round)?.get(stitch)?)
+    }
+
+    /// The stitches of round `round + 1` that are worked into `stitch` of `round`.
+    pub fn children(&self, round: usize, stitch: usize) -> Vec<usize> {
+        let Some(next) = self.parents.get(round + 1) else {
+            return Vec::new();
+        };
+
+        next.iter()
+            .enumerate()
+            .filter(|(_, parents)| parents.contains(&stitch))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Writes the graph in Graphviz's DOT language, for looking at in other graph tools. Each
+    /// stitch is a node labelled with its round and stitch number counting from 1, with the
+    /// rounds stacked from the bottom up, and each stitch has an edge to each stitch it's worked
+    /// into. Parents that don't exist, from a round that works into too many stitches, are left
+    /// out.
+    ///
+    /// ```
+    /// # use crochet::{parse_rounds, StitchGraph};
+    /// let graph = StitchGraph::new(&parse_rounds("sc 2 in mr\ninc, sc").unwrap());
+    ///
+    /// assert_eq!(
+    ///     graph.to_dot(),
+    ///     r#"digraph stitches {
+    ///   rankdir=BT;
+    ///   node [shape=circle];
+    ///   { rank=same; r0s0 [label="1.1"]; r0s1 [label="1.2"]; }
+    ///   { rank=same; r1s0 [label="2.1"]; r1s1 [label="2.2"]; r1s2 [label="2.3"]; }
+    ///   r1s0 -> r0s0;
+    ///   r1s1 -> r0s0;
+    ///   r1s2 -> r0s1;
+    /// }
+    /// "#
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut ret = String::from("digraph stitches {\n  rankdir=BT;\n  node [shape=circle];\n");
+
+        for (r, round) in self.parents.iter().enumerate() {
+            ret.push_str("  { rank=same;");
+            for s in 0..round.len() {
+                write!(ret, " r{r}s{s} [label=\"{}.{}\"];", r + 1, s + 1)
+                    .expect("writing to a string shouldn't fail");
+            }
+            ret.push_str(" }\n");
+        }
+
+        for (r, round) in self.parents.iter().enumerate().skip(1) {
+            let previous = self.parents[r - 1].len();
+            for (s, parents) in round.iter().enumerate() {
+                for p in parents.iter().filter(|&&p| p < previous) {
+                    writeln!(ret, "  r{r}s{s} -> r{}s{p};", r - 1)
+                        .expect("writing to a string shouldn't fail");
+                }
+            }
+        }
+
+        ret.push_str("}\n");
+        ret
+    }
+}
+
+/// What the stitches of an instruction are worked into
+#[derive(Clone, Copy)]
+enum Into {
+    /// The next stitches of the previous round
+    Previous,
+    /// The given stitch of the previous round, e.g. in `(sc, ch 2, sc) in next st`
+    One(usize),
+    MagicRing,
+}
+
+/// Works out the parents of one round's stitches
+#[derive(Default)]
+struct Builder {
+    /// The next stitch of the previous round to be worked into
+    cursor: usize,
+    stitches: Vec<Vec<usize>>,
+}
+
+impl Builder {
+    fn add(&mut self, inst: &Instruction, into: Into) {
+        use Instruction::*;
+
+        match inst {
+            Group(insts) => {
+                for i in insts {
+                    self.add(i, into);
+                }
+            }
+            Repeat(i, times) => {
+                for _ in 0..*times {
+                    self.add(i, into);
+                }
+            }
+            IntoMagicRing(i) => self.add(i, Into::MagicRing),
+            IntoOne(i) => {
+                let into = match self.take(1, into)[..] {
+                    [p] => Into::One(p),
+                    _ => into,
+                };
+                self.add(i, into);
+            }
+            // the first stitch is worked into the second of the two stitches, and vice versa
+            Crossed(a, b) => {
+                let parents = self.take(2, into);
+                let (first, second) = match parents[..] {
+                    [p, q] => (Into::One(q), Into::One(p)),
+                    _ => (into, into),
+                };
+                self.add(a, first);
+                self.add(b, second);
+            }
+            ChSpace { chains, skipped } => {
+                self.take(*skipped, into);
+                self.push(*chains, Vec::new());
+            }
+            _ => {
+                let parents = self.take(inst.input_count(), into);
+                self.push(inst.output_count(), parents);
+            }
+        }
+    }
+
+    /// Takes `n` stitches of the previous round to work into
+    fn take(&mut self, n: u32, into: Into) -> Vec<usize> {
+        match into {
+            Into::Previous => {
+                let start = self.cursor;
+                self.cursor += n as usize;
+                (start..self.cursor).collect()
+            }
+            Into::One(p) if n > 0 => vec![p],
+            Into::One(_) | Into::MagicRing => Vec::new(),
+        }
+    }
+
+    /// Makes `n` stitches worked into `parents`
+    fn push(&mut self, n: u32, parents: Vec<usize>) {
+        for _ in 0..n {
+            self.stitches.push(parents.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn graph(src: &str) -> StitchGraph {
+        StitchGraph::new(&parse_rounds(src).unwrap())
+    }
+
+    #[test]
+    fn test_skips_and_chain_spaces() {
+        let g = graph("sc 6 in mr\nsc, skip 1, sc, ch 2, skip 2, sc");
+        assert_eq!(g.stitch_count(1), Some(5));
+        assert_eq!(g.parents(1, 0), Some(&[0][..]));
+        assert_eq!(g.parents(1, 1), Some(&[2][..]));
+        assert_eq!(g.parents(1, 2), Some(&[][..]));
+        assert_eq!(g.parents(1, 3), Some(&[][..]));
+        assert_eq!(g.parents(1, 4), Some(&[5][..]));
+        assert_eq!(g.children(0, 1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_into_one_and_crossed() {
+        let g = graph("sc 4 in mr\n(sc, ch 2, sc) in next st, cross(fpsc, sc), sc");
+        let parents: Vec<_> = (0..7).map(|i| g.parents(1, i).unwrap().to_vec()).collect();
+        assert_eq!(
+            parents,
+            [vec![0], vec![], vec![], vec![0], vec![2], vec![1], vec![3]]
+        );
+        assert_eq!(g.children(0, 0), [0, 3]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = graph("sc 2 in mr\nsc, ch 1, dec").to_dot();
+        assert!(dot.contains("  r1s0 -> r0s0;\n"));
+        // the chain isn't worked into anything, and the dec's second stitch doesn't exist
+        assert!(!dot.contains("r1s1 ->"));
+        assert!(dot.contains("  r1s2 -> r0s1;\n"));
+        assert!(!dot.contains("r0s2"));
+        assert_eq!(dot.matches(" -> ").count(), 2);
+
+        assert_eq!(
+            StitchGraph::default().to_dot(),
+            "digraph stitches {\n  rankdir=BT;\n  node [shape=circle];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_repeated_rounds() {
+        let g = graph("sc 6 in mr\nx2: sc 6\ninc 6");
+        assert_eq!(g.rounds(), 4);
+        assert_eq!(g.stitch_count(3), Some(12));
+        assert_eq!(g.children(2, 5), [10, 11]);
+        assert_eq!(g.parents(4, 0), None);
+    }
+}
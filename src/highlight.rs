@@ -0,0 +1,207 @@
+use crate::lex::{tokenize, TokenKind};
+use crate::Span;
+use std::ops::Range;
+
+/// What kind of text a highlighted piece of source is, see [`highlight`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum TokenClass {
+    /// Stitches and other words with a fixed meaning, like `sc`, `in mr` or `include`
+    Keyword,
+    Number,
+    /// `[`, `]`, `(` and `)`
+    Bracket,
+    /// Commas and arithmetic operators
+    Punctuation,
+    Comment,
+    /// Double-quoted strings, like the path in `include "arm.crochet"`
+    String,
+    /// Variables and color changes, like the `n` in `sc n` or `@A`
+    Variable,
+    /// The start of a round that names or repeats it, like `ears:`, `x3:` or `rounds 4-8:`, and
+    /// the `name =` of a variable binding
+    Label,
+    /// `key: value` header lines and `== Name ==` section headers
+    Header,
+    /// Fenced free-text blocks
+    Text,
+    /// Spaces, tabs and newlines
+    Whitespace,
+    /// Anything that isn't valid pattern source
+    Unknown,
+}
+
+impl TokenClass {
+    fn of(kind: TokenKind) -> Self {
+        use TokenKind::*;
+
+        match kind {
+            Ch | Tch | Sc | Fpsc | Bpsc | Blsc | Inc | Flinc | Blinc | Dec | InMr | InNextSt
+            | Mr | AltMr | Skip | SurfaceSlSt | Cross | Include | Hook(_) | Yarn => Self::Keyword,
+            Number(_) => Self::Number,
+            LBracket | RBracket | LParen | RParen => Self::Bracket,
+            Plus | Minus | Star | Slash | Comma => Self::Punctuation,
+            Comment(_) => Self::Comment,
+            Str(_) => Self::String,
+            Ident(_) | Color(_) => Self::Variable,
+            Label(_) | RoundRepeat(_) | RoundRange(..) | Binding(_) => Self::Label,
+            Meta(..) | Section(_) => Self::Header,
+            TextBlock(..) => Self::Text,
+            Newline => Self::Whitespace,
+        }
+    }
+}
+
+/// Splits `source` into classified pieces for syntax highlighting.
+///
+/// The pieces are in order and cover the whole source, including whitespace and anything that
+/// isn't valid, so every byte of the source is in exactly one of them. This only looks at the
+/// tokens, so it works on source that doesn't parse.
+///
+/// ```
+/// # use crochet::{highlight, Span, TokenClass::*};
+/// let pieces = highlight("sc 6 ?!\n");
+/// assert_eq!(
+///     pieces,
+///     [
+///         (Span::new((1, 1), (1, 3)), Keyword),
+///         (Span::new((1, 3), (1, 4)), Whitespace),
+///         (Span::new((1, 4), (1, 5)), Number),
+///         (Span::new((1, 5), (1, 6)), Whitespace),
+///         (Span::new((1, 6), (1, 8)), Unknown),
+///         (Span::new((1, 8), (2, 1)), Whitespace),
+///     ]
+/// );
+/// ```
+pub fn highlight(source: &str) -> Vec<(Span, TokenClass)> {
+    let locs = Locations::new(source);
+    highlight_ranges(source)
+        .into_iter()
+        .map(|(range, class)| (Span::new(locs.get(range.start), locs.get(range.end)), class))
+        .collect()
+}
+
+/// Like [`highlight`], but with the byte range of each piece instead of its span
+pub(crate) fn highlight_ranges(source: &str) -> Vec<(Range<usize>, TokenClass)> {
+    let mut pieces: Vec<(Range<usize>, TokenClass)> = Vec::new();
+    let mut push = |range: Range<usize>, class| {
+        // join up neighbouring whitespace, e.g. spaces before a newline
+        match pieces.last_mut() {
+            Some((last, TokenClass::Whitespace)) if class == TokenClass::Whitespace => {
+                last.end = range.end
+            }
+            _ if range.is_empty() => {}
+            _ => pieces.push((range, class)),
+        }
+    };
+
+    let mut ts = tokenize(source);
+    let mut pos = 0;
+    loop {
+        for token in &mut ts {
+            let range = token.byte_range();
+            push(pos..range.start, TokenClass::Whitespace);
+            pos = range.end;
+            push(range, TokenClass::of(token.kind()));
+        }
+
+        if ts.is_empty() {
+            break;
+        }
+
+        let unknown = ts.skip_unknown();
+        push(pos..unknown.start, TokenClass::Whitespace);
+        pos = unknown.end;
+        push(unknown, TokenClass::Unknown);
+    }
+    push(pos..source.len(), TokenClass::Whitespace);
+
+    pieces
+}
+
+/// Converts byte offsets to one-based `(line, col)` locations, counting columns in bytes like the
+/// lexer does
+struct Locations {
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+impl Locations {
+    fn new(source: &str) -> Self {
+        let newlines = source.match_indices('\n').map(|(i, _)| i + 1);
+        Self {
+            line_starts: std::iter::once(0).chain(newlines).collect(),
+        }
+    }
+
+    fn get(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Highlights `source`, pairing each piece's text with its class
+    fn pieces(source: &str) -> Vec<(&str, TokenClass)> {
+        let lines: Vec<_> = source.lines().collect();
+        highlight(source)
+            .into_iter()
+            .map(|(span, class)| {
+                // only single-line pieces are used in these tests
+                assert_eq!(span.start.0, span.end.0, "{span:?}");
+                let line = lines[span.start.0 - 1];
+                (&line[span.start.1 - 1..span.end.1 - 1], class)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classes() {
+        use TokenClass::*;
+
+        assert_eq!(
+            pieces("ears: [inc, sc n] 3 in mr, % hi %, @A"),
+            [
+                ("ears:", Label),
+                (" ", Whitespace),
+                ("[", Bracket),
+                ("inc", Keyword),
+                (",", Punctuation),
+                (" ", Whitespace),
+                ("sc", Keyword),
+                (" ", Whitespace),
+                ("n", Variable),
+                ("]", Bracket),
+                (" ", Whitespace),
+                ("3", Number),
+                (" ", Whitespace),
+                ("in mr", Keyword),
+                (",", Punctuation),
+                (" ", Whitespace),
+                ("% hi %", Comment),
+                (",", Punctuation),
+                (" ", Whitespace),
+                ("@A", Variable),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_covers_everything() {
+        let src = "  name: Ball \n\n== Head ==\nsc 6 ~~ in mr, ??\t\ninclude \"x\" $  \n```\nnotes\n```\n";
+        let pieces = highlight(src);
+
+        let locs = Locations::new(src);
+        let mut expected_start = (1, 1);
+        for (span, _) in &pieces {
+            assert_eq!(span.start, expected_start);
+            expected_start = span.end;
+        }
+        assert_eq!(expected_start, locs.get(src.len()));
+
+        let unknown = pieces.iter().filter(|(_, c)| *c == TokenClass::Unknown);
+        assert_eq!(unknown.count(), 3);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::format::FormatOptions;
+use crate::glossary::ENTRIES;
+use crate::highlight::highlight_ranges;
+use crate::pretty_print::{pretty_lines, PrettyLine};
+use crate::text::escape;
+use crate::{Pattern, TokenClass};
+use std::fmt::Write;
+
+/// Renders a pattern as an HTML fragment for publishing on the web, laid out like
+/// [`crate::pretty_format_pattern`].
+///
+/// Every stitch abbreviation is wrapped in a `<span class="crochet-stitch">` whose `title` is the
+/// stitch's full name, so browsers show it as a tooltip, and color changes get the palette's name
+/// for the color. Each round is its own `<p class="crochet-round">` with an id like
+/// `part-2-round-5`, which stays the same as long as the parts and rounds before it do, so pages
+/// can link to or highlight a round. A block of repeated rounds gets the id of its first round.
+///
+/// ```
+/// # use crochet::{parse_pattern, to_html};
+/// let src = "colors: A = cream, B = brown\n== Head ==\nsc 6 in mr\n@B, inc 6";
+/// let html = to_html(&parse_pattern(src).unwrap());
+///
+/// assert!(html.contains(r#"<p class="crochet-round" id="part-1-round-2">Round 2: "#));
+/// assert!(html.contains(r#"<span class="crochet-stitch" title="into the magic ring">in mr</span>"#));
+/// assert!(html.contains(r#"<span class="crochet-color" title="brown">@B</span>"#));
+/// ```
+pub fn to_html(pattern: &Pattern) -> String {
+    let mut ret = String::from("<article class=\"crochet-pattern\">\n");
+    let palette = pattern.meta.palette();
+
+    for block in &pattern.notes {
+        ret.push_str("<section class=\"crochet-notes\">\n");
+        if !block.name.is_empty() {
+            writeln!(ret, "<h2>{}</h2>", escape(block.name))
+                .expect("writing to a string shouldn't fail");
+        }
+        writeln!(ret, "<pre>{}</pre>", escape(block.text))
+            .expect("writing to a string shouldn't fail");
+        ret.push_str("</section>\n");
+    }
+
+    let opts = FormatOptions {
+        mode: pattern.mode(),
+        ..Default::default()
+    };
+
+    for (i, part) in pattern.parts.iter().enumerate() {
+        let id = format!("part-{}", i + 1);
+        writeln!(ret, "<section class=\"crochet-part\" id=\"{id}\">")
+            .expect("writing to a string shouldn't fail");
+        if let Some(name) = part.name {
+            writeln!(ret, "<h2>{}</h2>", escape(name)).expect("writing to a string shouldn't fail");
+        }
+
+        for line in pretty_lines(&part.rounds, &opts) {
+            match line {
+                PrettyLine::Directive(d) => {
+                    writeln!(ret, "<p class=\"crochet-directive\">{}</p>", escape(&d))
+                }
+                PrettyLine::Round {
+                    number,
+                    heading,
+                    instructions,
+                    count,
+                } => writeln!(
+                    ret,
+                    "<p class=\"crochet-round\" id=\"{id}-round-{number}\">{}{}{}</p>",
+                    escape(&heading),
+                    markup(&instructions, &palette),
+                    escape(&count)
+                ),
+            }
+            .expect("writing to a string shouldn't fail");
+        }
+
+        ret.push_str("</section>\n");
+    }
+
+    if !pattern.assembly.is_empty() {
+        ret.push_str("<section class=\"crochet-assembly\">\n<h2>Assembly</h2>\n<ol>\n");
+        for step in &pattern.assembly {
+            writeln!(ret, "<li>{}</li>", escape(step.text))
+                .expect("writing to a string shouldn't fail");
+        }
+        ret.push_str("</ol>\n</section>\n");
+    }
+
+    if let Some(footer) = pattern.meta.provenance_footer() {
+        writeln!(
+            ret,
+            "<footer>{}</footer>",
+            escape(&footer).replace('\n', "<br>")
+        )
+        .expect("writing to a string shouldn't fail");
+    }
+
+    ret.push_str("</article>");
+    ret
+}
+
+/// Escapes the pretty printed instructions of a round, wrapping stitch abbreviations and color
+/// changes in spans with tooltips
+fn markup(instructions: &str, palette: &[(&str, &str)]) -> String {
+    let mut ret = String::new();
+
+    for (range, class) in highlight_ranges(instructions) {
+        let text = &instructions[range];
+
+        let full_name = ENTRIES.iter().find(|(abbr, ..)| *abbr == text);
+        let color = text
+            .strip_prefix('@')
+            .and_then(|key| palette.iter().find(|(k, _)| *k == key));
+
+        match (class, full_name, color) {
+            (TokenClass::Keyword, Some((_, name, _)), _) => {
+                write!(
+                    ret,
+                    "<span class=\"crochet-stitch\" title=\"{name}\">{text}</span>"
+                )
+            }
+            (TokenClass::Variable, _, Some((_, color))) => write!(
+                ret,
+                "<span class=\"crochet-color\" title=\"{}\">{text}</span>",
+                escape(color)
+            ),
+            _ => write!(ret, "{}", escape(text)),
+        }
+        .expect("writing to a string shouldn't fail");
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pattern;
+
+    #[test]
+    fn test_to_html() {
+        let src = "license: CC0\n```Materials\n- <4mm> hook\n```\n== Arm ==\nsc 6 in mr\n\
+            hook 4mm\nx2: [inc, sc] 3, % don't <stuff> %\n== Body ==\nsc 8 in mr\n\
+            == Assembly ==\nsew Arm to Body";
+        let expected = r#"<article class="crochet-pattern">
+<section class="crochet-notes">
+<h2>Materials</h2>
+<pre>- &lt;4mm&gt; hook</pre>
+</section>
+<section class="crochet-part" id="part-1">
+<h2>Arm</h2>
+<p class="crochet-round" id="part-1-round-1">Round 1: <span class="crochet-stitch" title="single crochet">sc</span> 6 <span class="crochet-stitch" title="into the magic ring">in mr</span> (6)</p>
+<p class="crochet-directive">hook 4mm</p>
+<p class="crochet-round" id="part-1-round-2">Rounds 2-3: [<span class="crochet-stitch" title="increase">inc</span>, <span class="crochet-stitch" title="single crochet">sc</span>] 3, % don&#39;t &lt;stuff&gt; % (9)</p>
+</section>
+<section class="crochet-part" id="part-2">
+<h2>Body</h2>
+<p class="crochet-round" id="part-2-round-1">Round 1: <span class="crochet-stitch" title="single crochet">sc</span> 8 <span class="crochet-stitch" title="into the magic ring">in mr</span> (8)</p>
+</section>
+<section class="crochet-assembly">
+<h2>Assembly</h2>
+<ol>
+<li>sew Arm to Body</li>
+</ol>
+</section>
+<footer>License: CC0</footer>
+</article>"#;
+
+        assert_eq!(to_html(&parse_pattern(src).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_markup_multiple_lines() {
+        assert_eq!(
+            markup("sc 2,\nsc é", &[]),
+            "<span class=\"crochet-stitch\" title=\"single crochet\">sc</span> 2,\n\
+                <span class=\"crochet-stitch\" title=\"single crochet\">sc</span> é"
+        );
+    }
+
+    #[test]
+    fn test_every_keyword_has_a_full_name() {
+        for (keyword, _) in crate::lex::KEYWORDS {
+            if !["include", "yarn"].contains(&keyword) {
+                assert!(
+                    ENTRIES.iter().any(|(abbr, ..)| *abbr == keyword),
+                    "{keyword}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+/// Provides the source of the files that patterns pull in with `include "path"` lines.
+///
+/// This lets the crate resolve includes without doing any IO itself; the caller decides where
+/// files come from. Any `FnMut(&str) -> Option<&str>` closure is a loader:
+///
+/// ```
+/// # use crochet::parse_pattern_with_loader;
+/// let files = [("arm.crochet", "sc 6 in mr\nsc 6")];
+/// let src = "
+///     == Left Arm ==
+///     include \"arm.crochet\"
+///     == Right Arm ==
+///     include \"arm.crochet\"
+///     sc 6
+/// ";
+///
+/// let loader = |path: &str| files.iter().find(|(p, _)| *p == path).map(|(_, s)| *s);
+/// let pattern = parse_pattern_with_loader(src, loader).unwrap();
+///
+/// assert_eq!(pattern.parts[0].rounds.len(), 2);
+/// assert_eq!(pattern.parts[1].rounds.len(), 3);
+/// ```
+pub trait SourceLoader<'a> {
+    /// Returns the source of the file at `path`, or `None` if it can't be loaded.
+    fn load(&mut self, path: &str) -> Option<&'a str>;
+}
+
+impl<'a, F: FnMut(&str) -> Option<&'a str>> SourceLoader<'a> for F {
+    fn load(&mut self, path: &str) -> Option<&'a str> {
+        self(path)
+    }
+}
+
+/// State for resolving `include` lines while parsing.
+#[derive(Default)]
+pub(crate) struct Includes<'a, 'l> {
+    /// `None` when includes aren't supported, e.g. when parsing with [`crate::parse_pattern`]
+    loader: Option<&'l mut dyn SourceLoader<'a>>,
+    /// Paths of the files currently being included, so that include cycles can be caught
+    stack: Vec<String>,
+    /// How many files have been loaded so far, which is the number of the last one (see
+    /// [`crate::Span::file`])
+    loaded: usize,
+}
+
+impl<'a, 'l> Includes<'a, 'l> {
+    pub fn new(loader: &'l mut dyn SourceLoader<'a>) -> Self {
+        Self {
+            loader: Some(loader),
+            stack: Vec::new(),
+            loaded: 0,
+        }
+    }
+
+    /// Loads the file at `path`, marking it as being included until [`Self::finish`] is called.
+    /// Returns the file's source and number, or `None` if it can't be loaded or is already being
+    /// included.
+    pub fn start(&mut self, path: &str) -> Option<(&'a str, usize)> {
+        if self.stack.iter().any(|p| p == path) {
+            return None;
+        }
+
+        let source = self.loader.as_mut()?.load(path)?;
+        self.stack.push(path.to_owned());
+        self.loaded += 1;
+
+        Some((source, self.loaded))
+    }
+
+    /// Marks the most recently started include as done.
+    pub fn finish(&mut self) {
+        self.stack.pop();
+    }
+}
@@ -0,0 +1,655 @@
+use crate::assembly::AssemblyStep;
+use crate::meta::MetaField;
+use crate::parse::{self, Context};
+use crate::pattern::Part;
+use crate::{
+    Directive, Instruction, ParseError, Pattern, PatternMeta, Round, Size, Span, SpanTree,
+    TextBlock,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A change to a pattern's source: the text in `range` (in bytes) is replaced with `text`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TextEdit<'e> {
+    pub range: Range<usize>,
+    pub text: &'e str,
+}
+
+impl TextEdit<'_> {
+    /// The result of making this edit to `source`, or `None` if the edit's range isn't in
+    /// `source` or doesn't start and end on character boundaries
+    pub fn apply(&self, source: &str) -> Option<String> {
+        source.get(self.range.clone())?;
+
+        let mut ret = source.to_owned();
+        ret.replace_range(self.range.clone(), self.text);
+        Some(ret)
+    }
+
+    /// How much longer the source is after the edit
+    fn len_change(&self) -> isize {
+        self.text.len() as isize - self.range.len() as isize
+    }
+}
+
+/// A parsed pattern (like [`crate::parse_pattern`] returns), along with what's needed to update it
+/// with [`reparse`] when its source is edited.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParsedPattern<'a> {
+    source: &'a str,
+    pattern: Pattern<'a>,
+    /// The size the pattern was parsed for, if it was parsed for one
+    size: Option<Size>,
+    /// Every variable binding in the source, in order, as `(line, name, value)`
+    bindings: Vec<(usize, &'a str, u32)>,
+}
+
+impl<'a> ParsedPattern<'a> {
+    /// Parses `source` like [`crate::parse_pattern`]
+    pub fn parse(source: &'a str) -> Result<Self, ParseError> {
+        Self::parse_with(source, Context::default(), None)
+    }
+
+    /// Parses `source` for one of the pattern's sizes, like [`crate::parse_rounds_for_size`]
+    pub fn parse_for_size(source: &'a str, size: Size) -> Result<Self, ParseError> {
+        Self::parse_with(source, Context::with_size(size), Some(size))
+    }
+
+    fn parse_with(
+        source: &'a str,
+        mut cx: Context<'a, '_>,
+        size: Option<Size>,
+    ) -> Result<Self, ParseError> {
+        let mut ts = cx.tokenize(source);
+        let pattern = parse::parse_pattern_with(&mut ts, &mut cx)?;
+        if !ts.is_empty() {
+            return Err(ts.current_loc().into());
+        }
+
+        Ok(Self {
+            source,
+            pattern,
+            size,
+            bindings: cx.bindings().to_vec(),
+        })
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn pattern(&self) -> &Pattern<'a> {
+        &self.pattern
+    }
+
+    pub fn into_pattern(self) -> Pattern<'a> {
+        self.pattern
+    }
+}
+
+/// Why [`reparse`] couldn't update a pattern
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ReparseError {
+    /// The edit's range isn't in the old source or doesn't start and end on character boundaries,
+    /// or the new source isn't the old one with the edit made
+    InvalidEdit,
+    /// The edited source doesn't parse
+    Parse(ParseError),
+}
+
+impl std::fmt::Display for ReparseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidEdit => write!(f, "the edit doesn't match the source"),
+            Self::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReparseError {}
+
+impl From<ParseError> for ReparseError {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Updates `old` after `edit` has been made to its source, giving `new_source`. The parsed
+/// pattern borrows from its source, so the edited source has to be passed in for the result to
+/// borrow from.
+///
+/// When the edit only touches lines that each hold a single round, only those lines are lexed
+/// and parsed again, with the variables, colors and size that were in effect there, and
+/// everything else is moved over to `new_source`. Otherwise (e.g. when a variable's value or the
+/// header changes, or a round is added to the middle of a part), the whole of `new_source` is
+/// parsed. Either way, the result is the same as parsing `new_source` from scratch.
+///
+/// ```
+/// # use crochet::{parse_pattern, reparse, ParsedPattern, TextEdit};
+/// let old_source = "n = 6\nsc n in mr\ninc n\n== Arm ==\nsc 6 in mr";
+/// let old = ParsedPattern::parse(old_source).unwrap();
+///
+/// // change `inc n` into `inc (n - 1), sc`
+/// let edit = TextEdit { range: 21..22, text: "(n - 1), sc" };
+/// let new_source = edit.apply(old_source).unwrap();
+/// let new = reparse(&old, &edit, &new_source).unwrap();
+///
+/// assert_eq!(new.pattern().parts[0].rounds[1].to_string(), "inc 5, sc");
+/// assert_eq!(new.pattern(), &parse_pattern(&new_source).unwrap());
+/// ```
+pub fn reparse<'b>(
+    old: &ParsedPattern<'_>,
+    edit: &TextEdit,
+    new_source: &'b str,
+) -> Result<ParsedPattern<'b>, ReparseError> {
+    let range = edit.range.clone();
+    let (Some(_), Some(before), Some(after)) = (
+        old.source.get(range.clone()),
+        old.source.get(..range.start),
+        old.source.get(range.end..),
+    ) else {
+        return Err(ReparseError::InvalidEdit);
+    };
+    let edited = new_source.len() == before.len() + edit.text.len() + after.len()
+        && new_source.starts_with(before)
+        && new_source[before.len()..].starts_with(edit.text)
+        && new_source.ends_with(after);
+    if !edited {
+        return Err(ReparseError::InvalidEdit);
+    }
+
+    match reparse_lines(old, edit, new_source) {
+        Some(new) => Ok(new),
+        None => {
+            let cx = match old.size {
+                Some(size) => Context::with_size(size),
+                None => Context::default(),
+            };
+            Ok(ParsedPattern::parse_with(new_source, cx, old.size)?)
+        }
+    }
+}
+
+/// Reparses just the lines touched by `edit`, or returns `None` if that might not give the same
+/// result as parsing the whole source.
+fn reparse_lines<'b>(
+    old: &ParsedPattern<'_>,
+    edit: &TextEdit,
+    new_source: &'b str,
+) -> Option<ParsedPattern<'b>> {
+    // the byte range of the lines touched by the edit, and their line numbers
+    let start = old.source[..edit.range.start]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    let old_end = old.source[edit.range.end..]
+        .find('\n')
+        .map_or(old.source.len(), |i| edit.range.end + i);
+    let new_end = old_end.checked_add_signed(edit.len_change())?;
+    let first_line = old.source[..start].matches('\n').count() + 1;
+    let last_line = first_line + old.source[start..old_end].matches('\n').count();
+
+    // the first line might be a `%crochet N%` pragma after the edit
+    if first_line == 1 {
+        return None;
+    }
+
+    // every touched line has to be a whole round of the same part on its own, since anything
+    // else (e.g. a blank line inside a text block) might mean something different depending on
+    // what's around it
+    let lines = first_line..=last_line;
+    let (part, first, replaced) = old.pattern.parts.iter().enumerate().find_map(|(p, part)| {
+        let first = part.rounds.partition_point(|r| r.span.start.0 < first_line);
+        let replaced = &part.rounds[first..];
+        let replaced = &replaced[..replaced.partition_point(|r| r.span.start.0 <= last_line)];
+        (!replaced.is_empty()).then_some((p, first, replaced))
+    })?;
+    if replaced.len() != lines.clone().count()
+        || !replaced
+            .iter()
+            .zip(lines)
+            .all(|(r, line)| r.span.start.0 == line && r.span.end.0 == line)
+    {
+        return None;
+    }
+    let after = &old.pattern.parts[part].rounds[first + replaced.len()..];
+
+    let moved = Move {
+        old: old.source,
+        new: new_source,
+        edit: edit.range.clone(),
+        offset: edit.len_change(),
+    };
+
+    // the variables bound before the touched lines, and the colors from the header
+    let mut vars = HashMap::new();
+    for &(line, name, value) in &old.bindings {
+        if line < first_line {
+            vars.insert(moved.str(name)?, value);
+        }
+    }
+    let palette = old.pattern.meta.palette().into_iter();
+    let palette = palette
+        .map(|(key, _)| moved.str(key))
+        .collect::<Option<_>>()?;
+
+    let mut cx = Context::resuming(replaced[0].index, vars, palette, old.size);
+    let mut ts = cx.tokenize(&new_source[start..new_end]);
+    let mut rounds = parse::parse_with(&mut ts, &mut cx).ok()?;
+    if !ts.is_empty() || cx.found_non_rounds() || rounds.is_empty() {
+        return None;
+    }
+
+    // the rounds after these have to keep their numbers, so that `rounds A-B:` ranges still work
+    let times = |rounds: &[Round]| rounds.iter().map(|r| r.times).sum::<u32>();
+    if !after.is_empty() && times(&rounds) != times(replaced) {
+        return None;
+    }
+
+    let line_change = edit.text.matches('\n').count() as isize
+        - old.source[edit.range.clone()].matches('\n').count() as isize;
+    // how far to move something on `line` of the old source
+    let shift = |line: usize| if line > last_line { line_change } else { 0 };
+
+    // hook and yarn changes from before the touched lines
+    let mut directives = moved.directives(&replaced[0].directives)?;
+    directives.append(&mut rounds[0].directives);
+    rounds[0].directives = directives;
+    for round in &mut rounds {
+        shift_round(round, first_line as isize - 1);
+    }
+
+    let mut parts = Vec::with_capacity(old.pattern.parts.len());
+    for (p, old_part) in old.pattern.parts.iter().enumerate() {
+        let name = match old_part.name {
+            Some(name) => Some(moved.str(name)?),
+            None => None,
+        };
+
+        let mut new_rounds = Vec::with_capacity(old_part.rounds.len());
+        for (i, r) in old_part.rounds.iter().enumerate() {
+            if p == part && i == first {
+                new_rounds.append(&mut rounds);
+            }
+            if p != part || !(first..first + replaced.len()).contains(&i) {
+                new_rounds.push(moved.round(r, shift(r.span.start.0))?);
+            }
+        }
+
+        parts.push(Part {
+            name,
+            rounds: new_rounds,
+        });
+    }
+
+    let mut assembly = Vec::with_capacity(old.pattern.assembly.len());
+    for step in &old.pattern.assembly {
+        let mut span = step.span;
+        let lines = shift(span.start.0);
+        span.start.0 = span.start.0.saturating_add_signed(lines);
+        span.end.0 = span.end.0.saturating_add_signed(lines);
+
+        assembly.push(AssemblyStep {
+            text: moved.str(step.text)?,
+            parts: step
+                .parts
+                .iter()
+                .map(|p| moved.str(p))
+                .collect::<Option<_>>()?,
+            rounds: step.rounds,
+            span,
+        });
+    }
+
+    let mut meta = PatternMeta::default();
+    for field in MetaField::ALL {
+        if let Some(value) = old.pattern.meta.get(field) {
+            *meta.field_mut(field) = Some(moved.str(value)?);
+        }
+    }
+
+    let notes = old
+        .pattern
+        .notes
+        .iter()
+        .map(|n| {
+            Some(TextBlock {
+                name: moved.str(n.name)?,
+                text: moved.str(n.text)?,
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    let bindings = old
+        .bindings
+        .iter()
+        .map(|&(line, name, value)| {
+            let line = line.saturating_add_signed(shift(line));
+            Some((line, moved.str(name)?, value))
+        })
+        .collect::<Option<_>>()?;
+
+    Some(ParsedPattern {
+        source: new_source,
+        pattern: Pattern {
+            meta,
+            parts,
+            assembly,
+            version: old.pattern.version,
+            notes,
+        },
+        size: old.size,
+        bindings,
+    })
+}
+
+/// Moves the lines of the round's spans by `lines`
+fn shift_round(round: &mut Round, lines: isize) {
+    fn shift_span(span: &mut Span, lines: isize) {
+        span.start.0 = span.start.0.saturating_add_signed(lines);
+        span.end.0 = span.end.0.saturating_add_signed(lines);
+    }
+
+    fn shift_tree(tree: &mut SpanTree, lines: isize) {
+        shift_span(&mut tree.span, lines);
+        tree.children.iter_mut().for_each(|t| shift_tree(t, lines));
+    }
+
+    shift_span(&mut round.span, lines);
+    round
+        .instruction_spans
+        .iter_mut()
+        .for_each(|t| shift_tree(t, lines));
+}
+
+/// Moves things parsed from `old` over to `new`, which is `old` with the text in `edit` replaced
+/// by `offset` more bytes of text
+struct Move<'o, 'b> {
+    old: &'o str,
+    new: &'b str,
+    edit: Range<usize>,
+    offset: isize,
+}
+
+impl<'b> Move<'_, 'b> {
+    /// The same text in `new`, or `None` if `s` isn't from `old` or overlaps the edit
+    fn str(&self, s: &str) -> Option<&'b str> {
+        let start = (s.as_ptr() as usize).checked_sub(self.old.as_ptr() as usize)?;
+        let end = start + s.len();
+        if end > self.old.len() {
+            return None;
+        }
+
+        let start = if end <= self.edit.start {
+            start
+        } else if start >= self.edit.end {
+            start.checked_add_signed(self.offset)?
+        } else {
+            return None;
+        };
+        self.new.get(start..start + s.len())
+    }
+
+    fn directive(&self, directive: &Directive) -> Option<Directive<'b>> {
+        match directive {
+            Directive::Hook(size) => self.str(size).map(Directive::Hook),
+            Directive::Yarn(yarn) => self.str(yarn).map(Directive::Yarn),
+        }
+    }
+
+    fn directives(&self, directives: &[Directive]) -> Option<Vec<Directive<'b>>> {
+        directives.iter().map(|d| self.directive(d)).collect()
+    }
+
+    fn instruction(&self, inst: &Instruction) -> Option<Instruction<'b>> {
+        use Instruction::*;
+
+        Some(match inst {
+            Ch => Ch,
+            Tch => Tch,
+            Sc => Sc,
+            Fpsc => Fpsc,
+            Bpsc => Bpsc,
+            Blsc => Blsc,
+            Inc => Inc,
+            Flinc => Flinc,
+            Blinc => Blinc,
+            Dec => Dec,
+            IntoMagicRing(i) => IntoMagicRing(self.instruction(i)?.into()),
+            Group(insts) => Group(self.instructions(insts)?),
+            Repeat(i, times) => Repeat(self.instruction(i)?.into(), *times),
+            Comment(s) => Comment(self.str(s)?),
+            Skip(n) => Skip(*n),
+            SurfaceSlSt(n) => SurfaceSlSt(*n),
+            Crossed(a, b) => Crossed(self.instruction(a)?.into(), self.instruction(b)?.into()),
+            ChSpace { chains, skipped } => ChSpace {
+                chains: *chains,
+                skipped: *skipped,
+            },
+            IntoOne(i) => IntoOne(self.instruction(i)?.into()),
+            Color(c) => Color(self.str(c)?),
+            Custom(stitch) => Custom(stitch.clone()),
+        })
+    }
+
+    fn instructions(&self, insts: &[Instruction]) -> Option<Vec<Instruction<'b>>> {
+        insts.iter().map(|i| self.instruction(i)).collect()
+    }
+
+    /// Copies the round, moving its spans `lines` lines later
+    fn round(&self, round: &Round, lines: isize) -> Option<Round<'b>> {
+        let label = match round.label {
+            Some(label) => Some(self.str(label)?),
+            None => None,
+        };
+
+        let mut round = Round {
+            index: round.index,
+            label,
+            instructions: self.instructions(&round.instructions)?,
+            times: round.times,
+            directives: self.directives(&round.directives)?,
+            span: round.span,
+            instruction_spans: round.instruction_spans.clone(),
+        };
+        shift_round(&mut round, lines);
+
+        Some(round)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes each edit in turn, checking that reparsing gives the same result as parsing from
+    /// scratch. Returns whether each reparse only parsed the touched lines.
+    fn assert_reparses(source: &str, edits: &[(Range<usize>, &str)]) -> Vec<bool> {
+        assert_reparses_with(source, None, edits)
+    }
+
+    fn assert_reparses_with(
+        source: &str,
+        size: Option<Size>,
+        edits: &[(Range<usize>, &str)],
+    ) -> Vec<bool> {
+        let parse = |source| match size {
+            Some(size) => ParsedPattern::parse_for_size(source, size),
+            None => ParsedPattern::parse(source),
+        };
+
+        let mut sources = vec![source.to_owned()];
+        for (range, text) in edits {
+            let edit = TextEdit {
+                range: range.clone(),
+                text,
+            };
+            sources.push(edit.apply(sources.last().unwrap()).unwrap());
+        }
+
+        let mut incremental = Vec::new();
+        let mut parsed = parse(&sources[0]).unwrap();
+        for ((range, text), source) in edits.iter().zip(&sources[1..]) {
+            let edit = TextEdit {
+                range: range.clone(),
+                text,
+            };
+            incremental.push(reparse_lines(&parsed, &edit, source).is_some());
+
+            let new = reparse(&parsed, &edit, source);
+            let expected = parse(source).map_err(ReparseError::Parse);
+            assert_eq!(new, expected, "after edit to {source:?}");
+            parsed = match new {
+                Ok(new) => new,
+                Err(_) => return incremental,
+            };
+        }
+
+        incremental
+    }
+
+    #[test]
+    fn test_edits_within_rounds() {
+        let src = "hook 4mm\nsc 6 in mr\nyarn \"blue\"\nears: inc 6, % hi %\nx2: sc 12\nsc 12";
+        assert_eq!(
+            assert_reparses(
+                src,
+                &[
+                    // `inc 6` -> `inc 5, sc`
+                    (42..43, "5, sc"),
+                    // `sc 6 in mr` -> `sc 16 in mr`, moving the label and comment later on
+                    (12..13, "16"),
+                    // two rounds at once
+                    (43..65, "6\nx2: sc 1"),
+                    // the last round
+                    (60..60, ", sc 2"),
+                ]
+            ),
+            [true, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_parts_and_header() {
+        let src = "name: Bear\ncolors: A = brown, B = cream\n\
+            == Head ==\nsc 6 in mr\n@B, inc 6\n```notes\nstuff it\n```\n\
+            == Arm ==\nsc 6 in mr\nsc 6\n== Assembly ==\nsew the Arm to round 2 of the Head";
+        assert_eq!(
+            assert_reparses(
+                src,
+                &[
+                    // a round of the first part, moving the second part and the assembly
+                    (70..71, "5, sc"),
+                    // a round of the second part
+                    (122..123, "5, sc"),
+                    // the header
+                    (6..10, "Teddy"),
+                ]
+            ),
+            [true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_variables_and_sizes() {
+        assert_eq!(
+            assert_reparses(
+                "n = 6\nsc n in mr\ninc n\nn = 12\nsc n",
+                &[
+                    // rounds using the variable
+                    (21..22, "(n - 1), sc"),
+                    (44..44, ", sc"),
+                    // the variable's value
+                    (4..5, "5"),
+                    // a round turned into a binding
+                    (17..32, "n = 7"),
+                ]
+            ),
+            [true, true, false, false]
+        );
+
+        // the variable is bound after the round, so isn't in effect there
+        assert_eq!(
+            assert_reparses("sc 6 in mr\nsc 6\nm = 6", &[(14..15, "m")]),
+            [false]
+        );
+
+        assert_eq!(
+            assert_reparses_with(
+                "sc 6(7,8) in mr\ninc 6(7,8)",
+                Some(Size::M),
+                &[(20..21, "5"), (16..16, "\nsc 12(14,16)")]
+            ),
+            [true, false]
+        );
+    }
+
+    #[test]
+    fn test_full_reparse() {
+        let src = "sc 6 in mr\ninc 6\nsc 12\n\nsc 12";
+        assert_eq!(
+            assert_reparses(
+                src,
+                &[
+                    // the first line
+                    (3..4, "5"),
+                    // a blank line
+                    (23..23, "sc 12"),
+                    // adding a round in the middle
+                    (16..16, "\nsc 12"),
+                    // the count of a round with rounds after it
+                    (11..11, "x2: "),
+                ]
+            ),
+            [false, false, false, false]
+        );
+
+        // edits that depend on the rest of the source
+        let edits = [
+            ("sc 6 in mr\ninc 6, % a\nb %\nsc 12", 12..13, "6"),
+            ("sc 6 in mr\nrounds 2-3: sc 6", 11..11, "inc 6\n"),
+            ("sc 6 in mr\n```\nnotes\n```\nsc 6", 11..11, "sc 6\n"),
+            ("sc 6 in mr\nsc 6\n```\nnotes\n```", 11..15, "```"),
+            ("sc 6 in mr\nsc 6", 11..15, "sc 6\n== Arm =="),
+        ];
+        for (src, range, text) in edits {
+            assert_eq!(assert_reparses(src, &[(range, text)]), [false]);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            assert_reparses("sc 6 in mr\ninc 6\nsc 12", &[(11..14, "inc ]")]),
+            [false]
+        );
+        assert_eq!(
+            assert_reparses("sc 6 in mr\ninc 6\nsc 12", &[(16..16, ", % hi")]),
+            [false]
+        );
+    }
+
+    #[test]
+    fn test_invalid_edits() {
+        let old = ParsedPattern::parse("sc 6 in mr, % é %").unwrap();
+        let edit = |range, text| TextEdit { range, text };
+
+        // out of range, backwards, and in the middle of the `é`
+        for range in [17..21, Range { start: 3, end: 2 }, 15..16] {
+            let edit = edit(range, "");
+            assert_eq!(edit.apply(old.source()), None);
+            assert_eq!(
+                reparse(&old, &edit, "sc 6 in mr"),
+                Err(ReparseError::InvalidEdit)
+            );
+        }
+
+        // a new source that doesn't match the edit
+        let edit = edit(3..4, "5");
+        assert_eq!(
+            reparse(&old, &edit, "sc 7 in mr, % é %"),
+            Err(ReparseError::InvalidEdit)
+        );
+        assert!(reparse(&old, &edit, "sc 5 in mr, % é %").is_ok());
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{Lint, Round, Severity, Span};
+use serde::Deserialize;
+
+/// The [JSON Schema](https://json-schema.org) describing the output of [`to_json`].
+///
+/// The layout only changes along with this crate's major version, so it's safe for tools in other
+/// languages to depend on.
+pub const JSON_SCHEMA: &str = include_str!("../schema/rounds.schema.json");
+
+/// Exports rounds as JSON in the layout described by [`JSON_SCHEMA`]. Unlike formatting the
+/// rounds as text, this keeps their spans, indices and directives.
+///
+/// ```
+/// # use crochet::{from_json, parse_rounds, to_json};
+/// let rounds = parse_rounds("sc 6 in mr\n[inc, sc] 3").unwrap();
+/// let json = to_json(&rounds);
+///
+/// assert!(json.contains(r#"{"Repeat":[{"Group":["Inc","Sc"]},3]}"#));
+/// assert_eq!(from_json(&json).unwrap().rounds(), rounds);
+/// ```
+pub fn to_json(rounds: &[Round]) -> String {
+    serde_json::to_string(rounds).expect("rounds can always be serialized")
+}
+
+/// Imports rounds exported by [`to_json`].
+///
+/// Strings in JSON can contain escape sequences, so the rounds can't borrow them from `json`
+/// directly. Instead, the imported [`JsonRounds`] own the unescaped strings, and the rounds borrow
+/// them from it.
+pub fn from_json(json: &str) -> Result<JsonRounds, serde_json::Error> {
+    let value = serde_json::from_str(json)?;
+    Vec::<Round>::deserialize(&value)?;
+
+    Ok(JsonRounds(value))
+}
+
+/// Rounds imported with [`from_json`]
+#[derive(Debug, Clone)]
+pub struct JsonRounds(serde_json::Value);
+
+impl JsonRounds {
+    /// The imported rounds, borrowing their strings from `self`
+    pub fn rounds(&self) -> Vec<Round<'_>> {
+        Vec::deserialize(&self.0).expect("the rounds were checked when importing")
+    }
+}
+
+/// Exports lints, e.g. from [`crate::lint_rounds_with_config`], as a JSON array for tools that
+/// report them. Each lint is an object with its `code`, `name`, `severity` (`"error"`,
+/// `"warning"` or `"info"`), human-readable `message`, `span` in the same layout as the spans of
+/// [`to_json`], and `notes`, each with a `message` and `span` (see [`Lint::notes`]).
+///
+/// ```
+/// # use crochet::{lint_rounds_with_config, lints_to_json, parse_rounds, LintConfig};
+/// let rounds = parse_rounds("sc 6 in mr\nsc 7").unwrap();
+/// let json = lints_to_json(lint_rounds_with_config(&rounds, &LintConfig::new()).lints());
+///
+/// assert!(json.contains(r#""code":"C001""#));
+/// assert!(json.contains(r#""severity":"error""#));
+/// assert!(json.contains(r#""span":{"end":[2,5],"start":[2,1]}"#));
+/// ```
+pub fn lints_to_json(lints: &[(Lint, Severity)]) -> String {
+    let lints: Vec<_> = lints
+        .iter()
+        .map(|(lint, severity)| {
+            serde_json::json!({
+                "code": lint.code(),
+                "name": lint.name(),
+                "severity": severity.to_string(),
+                "message": lint.to_string(),
+                "span": lint.span(),
+                "notes": lint.notes(),
+            })
+        })
+        .collect();
+
+    serde_json::to_string(&lints).expect("lints can always be serialized")
+}
+
+/// Exports lints as a [SARIF](https://sarifweb.azurewebsites.net) 2.1.0 log, which code scanning
+/// services and editors can show alongside the pattern. `uri` is the path of the pattern file the
+/// lints are about, e.g. relative to the root of its repository.
+///
+/// Lints about rounds that weren't parsed from a source, or about text in an included file rather
+/// than the file at `uri`, are reported without a location. A lint's
+/// [notes](Lint::notes) are reported as its related locations.
+///
+/// ```
+/// # use crochet::{lint_rounds_with_config, lints_to_sarif, parse_rounds, LintConfig};
+/// let rounds = parse_rounds("sc 6 in mr\nsc 7").unwrap();
+/// let sarif = lints_to_sarif(lint_rounds_with_config(&rounds, &LintConfig::new()).lints(), "ball.crochet");
+///
+/// assert!(sarif.contains(r#""ruleId":"C001""#));
+/// assert!(sarif.contains(r#""uri":"ball.crochet""#));
+/// ```
+pub fn lints_to_sarif(lints: &[(Lint, Severity)], uri: &str) -> String {
+    use serde_json::json;
+
+    // every kind of lint that's reported, which results refer to by index
+    let mut rules: Vec<&Lint> = Vec::new();
+    let results: Vec<_> = lints
+        .iter()
+        .map(|(lint, severity)| {
+            let rule_index = match rules.iter().position(|r| r.code() == lint.code()) {
+                Some(i) => i,
+                None => {
+                    rules.push(lint);
+                    rules.len() - 1
+                }
+            };
+
+            let locations: Vec<_> = sarif_location(lint.span(), uri)
+                .map(|location| json!({ "physicalLocation": location }))
+                .into_iter()
+                .collect();
+
+            let mut result = json!({
+                "ruleId": lint.code(),
+                "ruleIndex": rule_index,
+                "level": sarif_level(*severity),
+                "message": { "text": lint.to_string() },
+                "locations": locations,
+            });
+
+            let related: Vec<_> = lint
+                .notes()
+                .iter()
+                .filter_map(|note| {
+                    Some(json!({
+                        "message": { "text": note.message },
+                        "physicalLocation": sarif_location(note.span, uri)?,
+                    }))
+                })
+                .collect();
+            if !related.is_empty() {
+                result["relatedLocations"] = json!(related);
+            }
+
+            result
+        })
+        .collect();
+
+    let rules: Vec<_> = rules
+        .iter()
+        .map(|lint| {
+            json!({
+                "id": lint.code(),
+                "name": lint.name(),
+                "defaultConfiguration": {
+                    "level": sarif_level(lint.default_severity()),
+                },
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "crochet",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    serde_json::to_string(&log).expect("lints can always be serialized")
+}
+
+/// Where `span` is in the file at `uri`, or `None` if it isn't from that file
+fn sarif_location(span: Span, uri: &str) -> Option<serde_json::Value> {
+    let Span { start, end, .. } = span;
+    if start.0 == 0 || !span.in_main_file() {
+        return None;
+    }
+
+    Some(serde_json::json!({
+        "artifactLocation": { "uri": uri },
+        "region": {
+            "startLine": start.0,
+            "startColumn": start.1,
+            "endLine": end.0,
+            "endColumn": end.1,
+        },
+    }))
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_layout() {
+        let src = "colors: A = red\nhook 4mm\nx2: cross(fpsc, sc), ch 2, skip 1, % hi %, @A";
+        let rounds = crate::parse_rounds(src).unwrap();
+        let value: Value = serde_json::from_str(&to_json(&rounds)).unwrap();
+
+        assert_eq!(value[0]["index"], 1);
+        assert_eq!(value[0]["label"], Value::Null);
+        assert_eq!(value[0]["times"], 2);
+        assert_eq!(value[0]["directives"], json!([{ "Hook": "4mm" }]));
+        assert_eq!(value[0]["span"], json!({ "start": [3, 1], "end": [3, 46] }));
+        assert_eq!(
+            value[0]["instructions"],
+            json!([
+                { "Crossed": ["Fpsc", "Sc"] },
+                { "ChSpace": { "chains": 2, "skipped": 1 } },
+                { "Comment": "hi" },
+                { "Color": "A" },
+            ])
+        );
+        assert_eq!(
+            value[0]["instruction_spans"][0]["children"][1],
+            json!({ "span": { "start": [3, 17], "end": [3, 19] }, "children": [] })
+        );
+    }
+
+    #[test]
+    fn test_schema_is_json() {
+        let schema: Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        assert_eq!(schema["type"], "array");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_json("[{}]").is_err());
+
+        let json = to_json(&crate::parse_rounds("sc 6 in mr, % hi %").unwrap());
+        assert!(from_json(&json).is_ok());
+        assert!(from_json(&json.replace("\"Comment\"", "\"Note\"")).is_err());
+    }
+
+    #[test]
+    fn test_escapes() {
+        let rounds = crate::parse_rounds("sc 6 in mr, % say \"hi\" \\ \t twice %").unwrap();
+        let json = to_json(&rounds);
+        assert!(json.contains(r#"say \"hi\" \\ \t twice"#));
+        assert_eq!(from_json(&json).unwrap().rounds(), rounds);
+    }
+
+    #[test]
+    fn test_lints() {
+        let rounds = crate::parse_rounds("ch, sc 3\nsc 5").unwrap();
+        let config = crate::LintConfig::new()
+            .severity("nonzero_first_round_input", Severity::Info)
+            .unwrap();
+        let lints = crate::lint_rounds_with_config(&rounds, &config).into_lints();
+        let value: Value = serde_json::from_str(&lints_to_json(&lints)).unwrap();
+
+        assert_eq!(
+            value,
+            json!([
+                {
+                    "code": "C001",
+                    "name": "mismatched_stitch_count",
+                    "severity": "error",
+                    "message": "round 1 produces 4 stitches but round 2 consumes 5 stitches",
+                    "span": { "start": [2, 1], "end": [2, 5] },
+                    "notes": [],
+                },
+                {
+                    "code": "C002",
+                    "name": "nonzero_first_round_input",
+                    "severity": "info",
+                    "message": "round 1 consumes 3 stitches but the first round shouldn't consume any stitches",
+                    "span": { "start": [1, 5], "end": [1, 9] },
+                    "notes": [],
+                },
+            ])
+        );
+        assert_eq!(lints_to_json(&[]), "[]");
+    }
+
+    #[test]
+    fn test_sarif() {
+        let rounds = crate::parse_rounds("ch, sc 3\nsc 5\nsc 6").unwrap();
+        let config = crate::LintConfig::new()
+            .severity("nonzero_first_round_input", Severity::Info)
+            .unwrap();
+        let mut lints = crate::lint_rounds_with_config(&rounds, &config).into_lints();
+        lints.push((
+            crate::lint_rounds(&[crate::Round::new(vec![crate::Instruction::Sc])]).remove(0),
+            Severity::Warning,
+        ));
+        let value: Value = serde_json::from_str(&lints_to_sarif(&lints, "a/b.crochet")).unwrap();
+
+        assert_eq!(value["version"], "2.1.0");
+        let run = &value["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([
+                {
+                    "id": "C001",
+                    "name": "mismatched_stitch_count",
+                    "defaultConfiguration": { "level": "error" },
+                },
+                {
+                    "id": "C002",
+                    "name": "nonzero_first_round_input",
+                    "defaultConfiguration": { "level": "error" },
+                },
+            ])
+        );
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        // the mismatch of rounds 2 and 3 follows from the one of rounds 1 and 2
+        assert_eq!(
+            results[0]["relatedLocations"],
+            json!([{
+                "message": { "text": "round 2 produces 5 stitches but round 3 consumes 6 stitches" },
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "a/b.crochet" },
+                    "region": { "startLine": 3, "startColumn": 1, "endLine": 3, "endColumn": 5 },
+                },
+            }])
+        );
+        assert_eq!(
+            results[1],
+            json!({
+                "ruleId": "C002",
+                "ruleIndex": 1,
+                "level": "note",
+                "message": {
+                    "text": "round 1 consumes 3 stitches but the first round shouldn't consume any stitches",
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "a/b.crochet" },
+                        "region": { "startLine": 1, "startColumn": 5, "endLine": 1, "endColumn": 9 },
+                    },
+                }],
+            })
+        );
+        assert_eq!(results[1].get("relatedLocations"), None);
+        assert_eq!(results[2]["level"], "warning");
+        assert_eq!(results[2]["locations"], json!([]));
+    }
+
+    #[test]
+    fn test_sarif_included() {
+        let loader = |_: &str| Some("sc 1, [inc, sc] 6");
+        let pattern =
+            crate::parse_pattern_with_loader("sc 6 in mr\ninclude \"a\"", loader).unwrap();
+        let lints =
+            crate::lint_rounds_with_config(&pattern.parts[0].rounds, &crate::LintConfig::new())
+                .into_lints();
+        let value: Value = serde_json::from_str(&lints_to_sarif(&lints, "main.crochet")).unwrap();
+
+        let results = value["runs"][0]["results"].as_array().unwrap();
+        // the mismatch is reported at the include line, but the redundant repeat is in the
+        // included file
+        let locations: Vec<_> = results
+            .iter()
+            .map(|r| {
+                (
+                    r["ruleId"].as_str().unwrap(),
+                    r["locations"].as_array().unwrap().len(),
+                )
+            })
+            .collect();
+        assert!(
+            locations.starts_with(&[("C001", 1), ("C004", 0)]),
+            "{locations:?}"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::format::FormatOptions;
+use crate::pretty_print::{pretty_lines, PrettyLine};
+use crate::Pattern;
+use std::fmt::Write;
+
+/// Renders a pattern as a LaTeX fragment for typesetting, laid out like
+/// [`crate::pretty_format_pattern`].
+///
+/// The pattern's name is an unnumbered section, each part's rounds are an `enumerate` list
+/// labelled with the rounds' numbers, and the pattern's free-text blocks, part names and assembly
+/// steps are unnumbered subsections. Everything taken
+/// from the pattern, including comments, is escaped, so the fragment can be `\input` into a
+/// document without any packages.
+///
+/// ```
+/// # use crochet::{parse_pattern, to_latex};
+/// let src = "== Head ==\nsc 6 in mr\ninc 6, % stuff & close %";
+///
+/// let expected = r"\subsection*{Head}
+/// \begin{enumerate}
+/// \item[{Round 1:}] sc 6 in mr (6)
+/// \item[{Round 2:}] inc 6, \% stuff \& close \% (12)
+/// \end{enumerate}";
+///
+/// assert_eq!(to_latex(&parse_pattern(src).unwrap()), expected);
+/// ```
+pub fn to_latex(pattern: &Pattern) -> String {
+    let mut sections = Vec::new();
+
+    if let Some(name) = pattern.meta.name {
+        sections.push(format!("\\section*{{{}}}", escape(name)));
+    }
+
+    for block in &pattern.notes {
+        let text = lines(block.text);
+        sections.push(match block.name {
+            "" => text,
+            name => format!("\\subsection*{{{}}}\n{text}", escape(name)),
+        });
+    }
+
+    let opts = FormatOptions {
+        mode: pattern.mode(),
+        ..Default::default()
+    };
+
+    for part in &pattern.parts {
+        let mut section = String::new();
+        if let Some(name) = part.name {
+            writeln!(section, "\\subsection*{{{}}}", escape(name))
+                .expect("writing to a string shouldn't fail");
+        }
+
+        // an empty list isn't allowed, so a part without any rounds is just its name, if it has
+        // one
+        let lines = pretty_lines(&part.rounds, &opts);
+        if lines.is_empty() {
+            if !section.is_empty() {
+                sections.push(section.trim_end().to_string());
+            }
+            continue;
+        }
+
+        section.push_str("\\begin{enumerate}\n");
+        for line in lines {
+            match line {
+                PrettyLine::Directive(d) => writeln!(section, "\\item[] \\emph{{{}}}", escape(&d)),
+                PrettyLine::Round {
+                    heading,
+                    instructions,
+                    count,
+                    ..
+                } => writeln!(
+                    section,
+                    // the braces keep any `]` in the heading from ending the label
+                    "\\item[{{{}}}] {}{}",
+                    escape(heading.trim_end()),
+                    escape(&instructions),
+                    escape(&count)
+                ),
+            }
+            .expect("writing to a string shouldn't fail");
+        }
+        section.push_str("\\end{enumerate}");
+
+        sections.push(section);
+    }
+
+    if !pattern.assembly.is_empty() {
+        let steps: Vec<_> = pattern
+            .assembly
+            .iter()
+            .map(|step| format!("\\item {}", escape(step.text)))
+            .collect();
+        sections.push(format!(
+            "\\subsection*{{Assembly}}\n\\begin{{enumerate}}\n{}\n\\end{{enumerate}}",
+            steps.join("\n")
+        ));
+    }
+
+    if let Some(footer) = pattern.meta.provenance_footer() {
+        sections.push(lines(&footer));
+    }
+
+    sections.join("\n\n")
+}
+
+/// Escapes text with several lines, keeping its line breaks
+fn lines(text: &str) -> String {
+    let lines: Vec<_> = text
+        .lines()
+        // there has to be something on a line to break it
+        .map(|l| {
+            if l.is_empty() {
+                "~".to_string()
+            } else {
+                escape(l)
+            }
+        })
+        .collect();
+    lines.join("\\\\\n")
+}
+
+/// Escapes the characters that have a special meaning in LaTeX
+fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '~' => ret.push_str("\\textasciitilde{}"),
+            '^' => ret.push_str("\\textasciicircum{}"),
+            '\\' => ret.push_str("\\textbackslash{}"),
+            '<' => ret.push_str("\\textless{}"),
+            '>' => ret.push_str("\\textgreater{}"),
+            _ => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pattern;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r"50% of $5 & #1_{a}~^\"),
+            r"50\% of \$5 \& \#1\_\{a\}\textasciitilde{}\textasciicircum{}\textbackslash{}"
+        );
+        assert_eq!(escape("Part <2>"), r"Part \textless{}2\textgreater{}");
+    }
+
+    #[test]
+    fn test_to_latex() {
+        let src =
+            "name: Scarf\nlicense: CC0\nattribution: Tom & Jerry\nmode: rows\n```Materials\n- 4mm hook\n\n\
+            - stuffing\n```\n== Scarf ==\nch 11\nhook 4mm\nfringe: sc 10\nx2: sc 10\n\
+            == Assembly ==\nsew ends_together";
+        let expected = r"\section*{Scarf}
+
+\subsection*{Materials}
+- 4mm hook\\
+~\\
+- stuffing
+
+\subsection*{Scarf}
+\begin{enumerate}
+\item[{Row 1:}] ch 11, turn (11)
+\item[] \emph{hook 4mm}
+\item[{Row 2 (fringe):}] sc 10, ch 1, turn (10)
+\item[{Rows 3-4:}] sc 10, ch 1, turn (10)
+\end{enumerate}
+
+\subsection*{Assembly}
+\begin{enumerate}
+\item sew ends\_together
+\end{enumerate}
+
+License: CC0\\
+Attribution: Tom \& Jerry";
+
+        assert_eq!(to_latex(&parse_pattern(src).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_empty_part() {
+        let src = "== Head ==\n== Arm ==\nsc 6 in mr";
+        let expected = r"\subsection*{Head}
+
+\subsection*{Arm}
+\begin{enumerate}
+\item[{Round 1:}] sc 6 in mr (6)
+\end{enumerate}";
+
+        assert_eq!(to_latex(&parse_pattern(src).unwrap()), expected);
+        assert_eq!(
+            to_latex(&parse_pattern("name: Ball").unwrap()),
+            r"\section*{Ball}"
+        );
+    }
+}
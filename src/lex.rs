@@ -1,4 +1,9 @@
+use crate::meta::MetaField;
+use crate::{Dialect, Span};
+
+/// What a [`Token`] is, along with any text or numbers it carries, which borrow from the source.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
 pub enum TokenKind<'a> {
     Ch,
     Tch,
@@ -11,53 +16,178 @@ pub enum TokenKind<'a> {
     Blinc,
     Dec,
     InMr,
+    InNextSt,
+    Mr,
+    AltMr,
     Number(u32),
     Newline,
     LBracket,
     RBracket,
+    LParen,
+    RParen,
+    Plus,
+    Minus,
+    Star,
+    Slash,
     Comma,
     Comment(&'a str),
     Skip,
+    SurfaceSlSt,
+    Cross,
+    /// A `key: value` header line
+    Meta(MetaField, &'a str),
+    /// A `== Name ==` line starting a new part of the pattern
+    Section(&'a str),
+    /// The `name:` at the start of a labeled round
+    Label(&'a str),
+    /// The `xN:` at the start of a block of `N` identical rounds
+    RoundRepeat(u32),
+    /// The `rounds A-B:` at the start of a block of identical rounds
+    RoundRange(u32, u32),
+    Include,
+    /// A fenced block of free text, with the name after the opening fence and the text between
+    /// the fences
+    TextBlock(&'a str, &'a str),
+    /// A `hook 3.5mm` line switching hook sizes
+    Hook(&'a str),
+    Yarn,
+    /// A double-quoted string, e.g. the path in `include "arm.crochet"`
+    Str(&'a str),
+    /// A variable name
+    Ident(&'a str),
+    /// An `@A` color change
+    Color(&'a str),
+    /// The `name =` at the start of a variable binding line
+    Binding(&'a str),
+}
+
+/// The spelling of each keyword, see [`Dialect`] for adding others
+pub(crate) const KEYWORDS: [(&str, TokenKind<'static>); 19] = [
+    ("in mr", TokenKind::InMr),
+    ("in next st", TokenKind::InNextSt),
+    ("altmr", TokenKind::AltMr),
+    ("mr", TokenKind::Mr),
+    ("blinc", TokenKind::Blinc),
+    ("flinc", TokenKind::Flinc),
+    ("fpsc", TokenKind::Fpsc),
+    ("bpsc", TokenKind::Bpsc),
+    ("blsc", TokenKind::Blsc),
+    ("inc", TokenKind::Inc),
+    ("dec", TokenKind::Dec),
+    ("sc", TokenKind::Sc),
+    ("ch", TokenKind::Ch),
+    ("tch", TokenKind::Tch),
+    ("skip", TokenKind::Skip),
+    ("surface sl st", TokenKind::SurfaceSlSt),
+    ("cross", TokenKind::Cross),
+    ("include", TokenKind::Include),
+    ("yarn", TokenKind::Yarn),
+];
+
+impl TokenKind<'_> {
+    /// Describes the token for an error message, e.g. `']'` or `a new line`
+    pub(crate) fn describe(&self) -> &'static str {
+        use TokenKind::*;
+
+        match self {
+            InMr => "'in mr'",
+            InNextSt => "'in next st'",
+            Number(_) => "a number",
+            Newline => "a new line",
+            LBracket => "'['",
+            RBracket => "']'",
+            LParen => "'('",
+            RParen => "')'",
+            Plus => "'+'",
+            Minus => "'-'",
+            Star => "'*'",
+            Slash => "'/'",
+            Comma => "','",
+            Include => "'include'",
+            Yarn => "'yarn'",
+            Str(_) => "a string",
+            Ident(_) => "a variable",
+            _ => "an instruction",
+        }
+    }
 }
 
+/// A token lexed from a pattern's source, see [`tokenize`].
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct Token<'a> {
     kind: TokenKind<'a>,
     line: usize,
     col: usize,
+    /// Byte offsets of the start and end of the token in the source
+    start: usize,
+    end: usize,
 }
 
 impl<'a> Token<'a> {
+    /// A token starting at `line` and `col`. Its byte offsets are filled in by `lex_next`.
+    fn new(kind: TokenKind<'a>, line: usize, col: usize) -> Self {
+        Self {
+            kind,
+            line,
+            col,
+            start: 0,
+            end: 0,
+        }
+    }
+
     pub fn kind(&self) -> TokenKind<'a> {
         self.kind
     }
 
+    /// The one-based `(line, col)` of the start of the token
     pub fn source_loc(&self) -> (usize, usize) {
         (self.line, self.col)
     }
+
+    /// Where the token is in the source, in bytes
+    pub fn byte_range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
 }
 
+/// An iterator over the tokens of a pattern's source, see [`tokenize`].
 #[derive(Debug)]
 pub struct TokenStream<'a> {
+    /// The rest of the source that hasn't been lexed yet
     source: &'a [u8],
+    /// Length of the whole source, for working out byte offsets
+    source_len: usize,
     line: usize,
     col: usize,
     peeked_token: Option<Token<'a>>,
+    /// Where the peeked token ends
+    peeked_end: (usize, usize),
+    /// Where the last token returned by `next` ends
+    prev_end: (usize, usize),
+    /// Aliases for keywords
+    dialect: Dialect,
 }
 
 impl TokenStream<'_> {
+    /// The `(line, col)` of the next token, or of where lexing stopped if there isn't one
     pub fn current_loc(&self) -> (usize, usize) {
         match &self.peeked_token {
             Some(p) => p.source_loc(),
             None => (self.line, self.col),
         }
     }
+
+    /// The location just after the end of the last token that was consumed
+    pub(crate) fn prev_end(&self) -> (usize, usize) {
+        self.prev_end
+    }
 }
 
 impl<'a> TokenStream<'a> {
     pub fn peek(&mut self) -> Option<Token<'a>> {
         if self.peeked_token.is_none() {
-            self.peeked_token = self.next();
+            self.peeked_token = self.lex_next();
+            self.peeked_end = (self.line, self.col);
         }
         self.peeked_token
     }
@@ -66,21 +196,76 @@ impl<'a> TokenStream<'a> {
         self.peek().map(|x| x.kind())
     }
 
+    /// Whether the whole source has been lexed. When iteration ends without this being true, the
+    /// rest of the source couldn't be lexed.
     pub fn is_empty(&self) -> bool {
         self.source.is_empty() && self.peeked_token.is_none()
     }
 
-    fn new(source: &'a str) -> Self {
+    /// Consumes the rest of the current line as free-form text, without lexing it, along with
+    /// where the text is once it's trimmed. Returns `None` at the end of input or when the line is
+    /// a `== Name ==` section header.
+    ///
+    /// This must only be called at the start of a line, with nothing peeked.
+    pub(crate) fn text_line(&mut self) -> Option<(&'a str, Span)> {
+        debug_assert!(self.peeked_token.is_none());
+
+        let (before, line, col) = (self.source, self.line, self.col);
+        self.eat_whitespace();
+        let is_section = self.lex_section().is_some();
+        self.source = before;
+        self.line = line;
+        self.col = col;
+
+        if self.source.is_empty() || is_section {
+            return None;
+        }
+
+        let mut len = 0;
+        while !matches!(self.peek_char(), None | Some(b'\n')) {
+            self.next_char();
+            len += 1;
+        }
+        self.next_char();
+
+        let untrimmed = std::str::from_utf8(&before[..len]).unwrap();
+        let text = untrimmed.trim();
+        let start = col + (untrimmed.len() - untrimmed.trim_start().len());
+        Some((text, Span::new((line, start), (line, start + text.len()))))
+    }
+
+    /// Skips over input that couldn't be lexed, up to the next whitespace or newline, so that
+    /// lexing can carry on after it. Returns the byte range that was skipped.
+    ///
+    /// This must only be called after iteration has ended without reaching the end of the input.
+    pub(crate) fn skip_unknown(&mut self) -> std::ops::Range<usize> {
+        debug_assert!(self.peeked_token.is_none());
+
+        self.eat_whitespace();
+        let start = self.offset();
+        while !matches!(self.peek_char(), None | Some(b' ' | b'\t' | b'\n')) {
+            self.next_char();
+        }
+
+        start..self.offset()
+    }
+
+    fn new(source: &'a str, dialect: Dialect) -> Self {
+        let source = source.trim_end().as_bytes();
         Self {
-            source: source.trim_end().as_bytes(),
+            source,
+            source_len: source.len(),
             line: 1,
             col: 1,
             peeked_token: None,
+            peeked_end: (1, 1),
+            prev_end: (1, 1),
+            dialect,
         }
     }
 
     fn peek_char(&self) -> Option<u8> {
-        self.source.get(0).cloned()
+        self.source.first().cloned()
     }
 
     fn next_char(&mut self) -> Option<u8> {
@@ -101,11 +286,7 @@ impl<'a> TokenStream<'a> {
     }
 
     fn make_token<'x>(&self, kind: TokenKind<'x>) -> Token<'x> {
-        Token {
-            kind,
-            line: self.line,
-            col: self.col,
-        }
+        Token::new(kind, self.line, self.col)
     }
 
     fn eat_string(&mut self, string: &[u8]) -> bool {
@@ -125,6 +306,12 @@ impl<'a> TokenStream<'a> {
             (b'\n', TokenKind::Newline),
             (b'[', TokenKind::LBracket),
             (b']', TokenKind::RBracket),
+            (b'(', TokenKind::LParen),
+            (b')', TokenKind::RParen),
+            (b'+', TokenKind::Plus),
+            (b'-', TokenKind::Minus),
+            (b'*', TokenKind::Star),
+            (b'/', TokenKind::Slash),
             (b',', TokenKind::Comma),
         ];
 
@@ -142,42 +329,338 @@ impl<'a> TokenStream<'a> {
     }
 
     fn lex_keyword(&mut self) -> Option<Token<'a>> {
-        let mut keywords = [
-            (b"in mr".as_ref(), TokenKind::InMr),
-            (b"blinc".as_ref(), TokenKind::Blinc),
-            (b"flinc".as_ref(), TokenKind::Flinc),
-            (b"fpsc".as_ref(), TokenKind::Fpsc),
-            (b"bpsc".as_ref(), TokenKind::Bpsc),
-            (b"blsc".as_ref(), TokenKind::Blsc),
-            (b"inc".as_ref(), TokenKind::Inc),
-            (b"dec".as_ref(), TokenKind::Dec),
-            (b"sc".as_ref(), TokenKind::Sc),
-            (b"ch".as_ref(), TokenKind::Ch),
-            (b"tch".as_ref(), TokenKind::Tch),
-            (b"skip".as_ref(), TokenKind::Skip),
-        ];
+        // custom stitch names may contain digits, e.g. `sc3tog`, which would
+        // otherwise be lexed as `sc` followed by `3` and `tog`
+        let word_len = self
+            .source
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+            .count();
+        let word = std::str::from_utf8(&self.source[..word_len]).unwrap();
+        if self.dialect.custom_stitch(word).is_some() {
+            return None;
+        }
+
+        let mut keywords = KEYWORDS.map(|(s, tok)| (s.as_bytes(), tok));
         keywords.sort_by_key(|(x, _)| std::cmp::Reverse(x.len()));
 
-        for (s, tok) in keywords {
-            let t = self.make_token(tok);
-            if self.eat_string(s) {
-                return Some(t);
+        let aliases = std::mem::take(&mut self.dialect);
+        let ret = aliases
+            .aliases()
+            .chain(keywords)
+            .find_map(|(s, tok)| self.eat_keyword(s, tok));
+        self.dialect = aliases;
+
+        ret
+    }
+
+    /// Consumes the keyword `s` if it's next, returning it as a `tok` token
+    fn eat_keyword(&mut self, s: &[u8], tok: TokenKind<'static>) -> Option<Token<'a>> {
+        // keywords can't be directly followed by letters, otherwise e.g. the
+        // variable `scale` would be lexed as `sc` followed by `ale`
+        let followed_by_letter = matches!(
+            self.source.get(s.len()),
+            Some(b'a'..=b'z' | b'A'..=b'Z' | b'_')
+        );
+
+        let t = self.make_token(tok);
+        if !followed_by_letter && self.eat_string(s) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Consumes an identifier like `n` or `rows_2`, returning it
+    fn eat_ident(&mut self) -> Option<&'a str> {
+        let start = self.source;
+
+        if !matches!(self.peek_char(), Some(b'a'..=b'z' | b'A'..=b'Z' | b'_')) {
+            return None;
+        }
+
+        let mut len = 0;
+        while let Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_') = self.peek_char() {
+            self.next_char();
+            len += 1;
+        }
+
+        Some(std::str::from_utf8(&start[..len]).unwrap())
+    }
+
+    fn lex_ident(&mut self) -> Option<Token<'a>> {
+        let line = self.line;
+        let col = self.col;
+
+        self.eat_ident()
+            .map(|name| Token::new(TokenKind::Ident(name), line, col))
+    }
+
+    fn lex_color(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        if !self.eat_string(b"@") {
+            return None;
+        }
+
+        match self.eat_ident() {
+            Some(name) => Some(Token::new(TokenKind::Color(name), line, col)),
+            None => {
+                self.source = before;
+                self.line = line;
+                self.col = col;
+                None
+            }
+        }
+    }
+
+    /// Lexes the `name =` of a variable binding. Only valid at the start of a line.
+    fn lex_binding(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        let name = self.eat_ident();
+        self.eat_whitespace();
+
+        match name {
+            // make sure it's not the start of a `==` section header
+            Some(name) if self.eat_string(b"=") && self.peek_char() != Some(b'=') => {
+                Some(Token::new(TokenKind::Binding(name), line, col))
+            }
+            _ => {
+                self.source = before;
+                self.line = line;
+                self.col = col;
+                None
+            }
+        }
+    }
+
+    /// Lexes a header line like `license: CC-BY-4.0`. Only valid at the start of a line.
+    fn lex_meta(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        for field in MetaField::ALL {
+            if !self.eat_string(field.key().as_bytes()) {
+                continue;
+            }
+
+            self.eat_whitespace();
+            if !self.eat_string(b":") {
+                self.source = before;
+                self.line = line;
+                self.col = col;
+                continue;
+            }
+
+            let value_start = self.source;
+            let mut len = 0;
+            while !matches!(self.peek_char(), None | Some(b'\n')) {
+                self.next_char();
+                len += 1;
             }
+
+            let value = std::str::from_utf8(&value_start[..len]).unwrap().trim();
+
+            return Some(Token::new(TokenKind::Meta(field, value), line, col));
         }
 
         None
     }
 
+    /// Lexes a block of free text fenced by lines of three backticks, with an optional name after
+    /// the opening fence. Only valid at the start of a line.
+    fn lex_text_block(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        if !self.eat_string(b"```") {
+            return None;
+        }
+
+        let name_start = self.source;
+        let mut name_len = 0;
+        while !matches!(self.peek_char(), None | Some(b'\n')) {
+            self.next_char();
+            name_len += 1;
+        }
+        let name = std::str::from_utf8(&name_start[..name_len]).unwrap().trim();
+
+        // the text is every line up to the closing fence
+        self.next_char();
+        let text_start = self.source;
+        let mut text_len: usize = 0;
+        loop {
+            let mut rest = self.source;
+            while let Some(b' ' | b'\t') = rest.first() {
+                rest = &rest[1..];
+            }
+            if rest.starts_with(b"```") {
+                break;
+            }
+
+            while !matches!(self.peek_char(), None | Some(b'\n')) {
+                self.next_char();
+                text_len += 1;
+            }
+
+            // unterminated block
+            if self.next_char().is_none() {
+                self.source = before;
+                self.line = line;
+                self.col = col;
+                return None;
+            }
+            text_len += 1;
+        }
+        self.eat_whitespace();
+        self.eat_string(b"```");
+
+        // don't include the newline before the closing fence
+        let text = &text_start[..text_len.saturating_sub(1)];
+
+        Some(Token::new(
+            TokenKind::TextBlock(name, std::str::from_utf8(text).unwrap()),
+            line,
+            col,
+        ))
+    }
+
+    /// Lexes a `hook 3.5mm` line. Only valid at the start of a line.
+    fn lex_hook(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        if !self.eat_string(b"hook") || !matches!(self.peek_char(), Some(b' ' | b'\t')) {
+            self.source = before;
+            self.line = line;
+            self.col = col;
+            return None;
+        }
+        self.eat_whitespace();
+
+        // the size is everything up to the next whitespace, e.g. `3.5mm` or `H/8`
+        let size_start = self.source;
+        let mut len = 0;
+        while !matches!(self.peek_char(), None | Some(b' ' | b'\t' | b'\n' | b'%')) {
+            self.next_char();
+            len += 1;
+        }
+
+        if len == 0 {
+            self.source = before;
+            self.line = line;
+            self.col = col;
+            return None;
+        }
+
+        Some(Token::new(
+            TokenKind::Hook(std::str::from_utf8(&size_start[..len]).unwrap()),
+            line,
+            col,
+        ))
+    }
+
+    /// Lexes a `== Name ==` section header. Only valid at the start of a line.
+    fn lex_section(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        if !self.eat_string(b"==") {
+            return None;
+        }
+
+        let name_start = self.source;
+        let mut len = 0;
+        while !self.source.starts_with(b"==") {
+            match self.peek_char() {
+                None | Some(b'\n') => {
+                    self.source = before;
+                    self.line = line;
+                    self.col = col;
+                    return None;
+                }
+                Some(_) => {
+                    self.next_char();
+                    len += 1;
+                }
+            }
+        }
+        self.eat_string(b"==");
+
+        let name = std::str::from_utf8(&name_start[..len]).unwrap().trim();
+
+        Some(Token::new(TokenKind::Section(name), line, col))
+    }
+
+    /// Lexes the `xN:` or `rounds A-B:` before a block of identical rounds.
+    /// Only valid at the start of a line.
+    fn lex_round_repeat(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        let kind = if self.eat_string(b"x") {
+            self.eat_number().map(TokenKind::RoundRepeat)
+        } else if self.eat_string(b"rounds") {
+            self.eat_whitespace();
+            let start = self.eat_number();
+            self.eat_whitespace();
+            let dash = self.eat_string(b"-");
+            self.eat_whitespace();
+            let end = self.eat_number();
+
+            match (start, dash, end) {
+                (Some(a), true, Some(b)) => Some(TokenKind::RoundRange(a, b)),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        self.eat_whitespace();
+        match kind {
+            Some(kind) if self.eat_string(b":") => Some(Token::new(kind, line, col)),
+            _ => {
+                self.source = before;
+                self.line = line;
+                self.col = col;
+                None
+            }
+        }
+    }
+
+    /// Lexes a round label like `ears:`. Only valid at the start of a line.
+    fn lex_label(&mut self) -> Option<Token<'a>> {
+        let (before, line, col) = (self.source, self.line, self.col);
+
+        if !matches!(self.peek_char(), Some(b'a'..=b'z' | b'A'..=b'Z')) {
+            return None;
+        }
+
+        let mut len = 0;
+        while let Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'-') = self.peek_char() {
+            self.next_char();
+            len += 1;
+        }
+
+        self.eat_whitespace();
+        if !self.eat_string(b":") {
+            self.source = before;
+            self.line = line;
+            self.col = col;
+            return None;
+        }
+
+        Some(Token::new(
+            TokenKind::Label(std::str::from_utf8(&before[..len]).unwrap()),
+            line,
+            col,
+        ))
+    }
+
     fn eat_whitespace(&mut self) {
         while matches!(self.peek_char(), Some(b' ' | b'\t')) {
             self.next_char();
         }
     }
 
-    fn lex_number(&mut self) -> Option<Token<'a>> {
-        let line = self.line;
-        let col = self.col;
-
+    /// Consumes a run of digits, returning their value
+    fn eat_number(&mut self) -> Option<u32> {
         let start = self.source;
         let mut num_digits = 0;
         while let Some(b'0'..=b'9') = self.peek_char() {
@@ -188,19 +671,23 @@ impl<'a> TokenStream<'a> {
         if num_digits == 0 {
             None
         } else {
-            Some(Token {
-                kind: TokenKind::Number(
-                    std::str::from_utf8(&start[..num_digits])
-                        .unwrap()
-                        .parse()
-                        .unwrap(),
-                ),
-                line,
-                col,
-            })
+            Some(
+                std::str::from_utf8(&start[..num_digits])
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+            )
         }
     }
 
+    fn lex_number(&mut self) -> Option<Token<'a>> {
+        let line = self.line;
+        let col = self.col;
+
+        self.eat_number()
+            .map(|n| Token::new(TokenKind::Number(n), line, col))
+    }
+
     fn lex_comment(&mut self) -> Option<Token<'a>> {
         if let Some(b'%') = self.peek_char() {
             let line = self.line;
@@ -226,16 +713,42 @@ impl<'a> TokenStream<'a> {
                 return None;
             }
 
-            Some(Token {
-                kind: TokenKind::Comment(
-                    std::str::from_utf8(&beginning[..idx])
-                        .unwrap()
-                        .trim()
-                        .into(),
-                ),
+            Some(Token::new(
+                TokenKind::Comment(std::str::from_utf8(&beginning[..idx]).unwrap().trim()),
+                line,
+                col,
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn lex_string(&mut self) -> Option<Token<'a>> {
+        if let Some(b'"') = self.peek_char() {
+            let (before, line, col) = (self.source, self.line, self.col);
+
+            self.next_char();
+            let beginning = self.source;
+            let mut len = 0;
+            loop {
+                match self.next_char() {
+                    Some(b'"') => break,
+                    // strings can't span lines
+                    None | Some(b'\n') => {
+                        self.source = before;
+                        self.line = line;
+                        self.col = col;
+                        return None;
+                    }
+                    Some(_) => len += 1,
+                }
+            }
+
+            Some(Token::new(
+                TokenKind::Str(std::str::from_utf8(&beginning[..len]).unwrap()),
                 line,
                 col,
-            })
+            ))
         } else {
             None
         }
@@ -247,17 +760,62 @@ impl<'a> Iterator for TokenStream<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.peeked_token.is_some() {
+            self.prev_end = self.peeked_end;
             return self.peeked_token.take();
         }
 
+        let t = self.lex_next();
+        self.prev_end = (self.line, self.col);
+        t
+    }
+}
+
+impl<'a> TokenStream<'a> {
+    fn lex_next(&mut self) -> Option<Token<'a>> {
+        let line_start = self.col == 1;
+        self.eat_whitespace();
+
+        let start = self.offset();
+        let mut token = self.lex_token(line_start)?;
+        token.start = start;
+        token.end = self.offset();
+
+        Some(token)
+    }
+
+    /// How many bytes of the source have been lexed
+    fn offset(&self) -> usize {
+        self.source_len - self.source.len()
+    }
+
+    fn lex_token(&mut self, line_start: bool) -> Option<Token<'a>> {
         let lexers = [
             Self::lex_symbol,
             Self::lex_keyword,
             Self::lex_number,
             Self::lex_comment,
+            Self::lex_string,
+            Self::lex_ident,
+            Self::lex_color,
         ];
 
-        self.eat_whitespace();
+        if line_start {
+            let line_start_lexers = [
+                Self::lex_meta,
+                Self::lex_section,
+                Self::lex_text_block,
+                Self::lex_binding,
+                Self::lex_hook,
+                Self::lex_round_repeat,
+                Self::lex_label,
+            ];
+
+            for l in line_start_lexers {
+                if let ret @ Some(_) = l(self) {
+                    return ret;
+                }
+            }
+        }
 
         for l in lexers {
             if let ret @ Some(_) = l(self) {
@@ -269,14 +827,69 @@ impl<'a> Iterator for TokenStream<'a> {
     }
 }
 
+/// Lexes a pattern's source into tokens, for editor plugins and other tools that want to work
+/// with the source more directly than the parsed patterns allow.
+///
+/// Whitespace (other than newlines) isn't part of any token. Iteration stops at the first input
+/// that can't be lexed, see [`TokenStream::is_empty`].
+///
+/// ```
+/// # use crochet::{tokenize, TokenKind};
+/// let mut tokens = tokenize("sc 6 in mr\ninc 6");
+///
+/// let t = tokens.next().unwrap();
+/// assert_eq!(t.kind(), TokenKind::Sc);
+/// assert_eq!(t.byte_range(), 0..2);
+///
+/// let t = tokens.nth(1).unwrap();
+/// assert_eq!(t.kind(), TokenKind::InMr);
+/// assert_eq!(t.byte_range(), 5..10);
+///
+/// let t = tokens.last().unwrap();
+/// assert_eq!(t.kind(), TokenKind::Number(6));
+/// assert_eq!(t.source_loc(), (2, 5));
+/// assert_eq!(t.byte_range(), 15..16);
+/// ```
 pub fn tokenize<'a>(source: &'a str) -> TokenStream<'a> {
-    TokenStream::new(source)
+    TokenStream::new(source, Dialect::default())
+}
+
+/// Like [`tokenize`], but also lexes the aliases from `dialect` as the keywords they stand for.
+pub fn tokenize_with_dialect<'a>(source: &'a str, dialect: &Dialect) -> TokenStream<'a> {
+    TokenStream::new(source, dialect.clone())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Lexes `src` into each token's kind, line and column
+    fn lex(src: &str) -> Vec<(TokenKind<'_>, usize, usize)> {
+        tokenize(src).map(|t| (t.kind(), t.line, t.col)).collect()
+    }
+
+    #[test]
+    fn test_byte_ranges() {
+        let src = "name: Ball\n  ears: sc 2, % hi %\n== Arm ==\nsc";
+        let ranges: Vec<_> = tokenize(src).map(|t| &src[t.byte_range()]).collect();
+        assert_eq!(
+            ranges,
+            [
+                "name: Ball",
+                "\n",
+                "ears:",
+                "sc",
+                "2",
+                ",",
+                "% hi %",
+                "\n",
+                "== Arm ==",
+                "\n",
+                "sc"
+            ]
+        );
+    }
+
     #[test]
     fn test_tokenization() {
         use TokenKind::*;
@@ -288,88 +901,274 @@ mod tests {
                 kind: Sc,
                 line: 1,
                 col: 1,
+                start: 0,
+                end: 2,
             },
             Token {
                 kind: Number(6),
                 line: 1,
                 col: 4,
+                start: 3,
+                end: 4,
             },
             Token {
                 kind: Newline,
                 line: 1,
                 col: 5,
+                start: 4,
+                end: 5,
             },
             Token {
                 kind: Inc,
                 line: 2,
                 col: 1,
+                start: 5,
+                end: 8,
             },
             Token {
                 kind: Number(6),
                 line: 2,
                 col: 5,
+                start: 9,
+                end: 10,
             },
             Token {
                 kind: Newline,
                 line: 2,
                 col: 6,
+                start: 10,
+                end: 11,
             },
             Token {
                 kind: Sc,
                 line: 3,
                 col: 1,
+                start: 11,
+                end: 13,
             },
             Token {
                 kind: Number(2),
                 line: 3,
                 col: 4,
+                start: 14,
+                end: 15,
             },
             Token {
                 kind: Comma,
                 line: 3,
                 col: 5,
+                start: 15,
+                end: 16,
             },
             Token {
                 kind: LBracket,
                 line: 3,
                 col: 7,
+                start: 17,
+                end: 18,
             },
             Token {
                 kind: Sc,
                 line: 3,
                 col: 8,
+                start: 18,
+                end: 20,
             },
             Token {
                 kind: Comma,
                 line: 3,
                 col: 10,
+                start: 20,
+                end: 21,
             },
             Token {
                 kind: Inc,
                 line: 3,
                 col: 12,
+                start: 22,
+                end: 25,
             },
             Token {
                 kind: RBracket,
                 line: 3,
                 col: 15,
+                start: 25,
+                end: 26,
             },
             Token {
                 kind: Number(5),
                 line: 3,
                 col: 17,
+                start: 27,
+                end: 28,
             },
         ];
 
-        assert_eq!(tokenize(&src).collect::<Vec<_>>(), expected);
+        assert_eq!(tokenize(src).collect::<Vec<_>>(), expected);
 
         assert_eq!(
             tokenize("% hello there %").collect::<Vec<_>>(),
             vec![Token {
-                kind: Comment("hello there".into()),
+                kind: Comment("hello there"),
                 line: 1,
-                col: 1
+                col: 1,
+                start: 0,
+                end: 15,
             }]
         );
+
+        assert_eq!(
+            lex("  license : CC-BY-4.0 \nsc"),
+            vec![
+                (Meta(MetaField::License, "CC-BY-4.0"), 1, 3),
+                (Newline, 1, 23),
+                (Sc, 2, 1),
+            ]
+        );
+
+        assert_eq!(
+            lex("== Left Arm ==\nsc == x =="),
+            vec![(Section("Left Arm"), 1, 1), (Newline, 1, 15), (Sc, 2, 1),]
+        );
+
+        assert_eq!(
+            lex("left-ear2 : sc\nsc: inc"),
+            vec![
+                (Label("left-ear2"), 1, 1),
+                (Sc, 1, 13),
+                (Newline, 1, 15),
+                (Label("sc"), 2, 1),
+                (Inc, 2, 5),
+            ]
+        );
+
+        assert_eq!(
+            lex("x5: sc\nrounds 4 - 8:\nx: sc"),
+            vec![
+                (RoundRepeat(5), 1, 1),
+                (Sc, 1, 5),
+                (Newline, 1, 7),
+                (RoundRange(4, 8), 2, 1),
+                (Newline, 2, 14),
+                (Label("x"), 3, 1),
+                (Sc, 3, 4),
+            ]
+        );
+
+        assert_eq!(
+            lex("include \"arm.crochet\" \"oops"),
+            vec![(Include, 1, 1), (Str("arm.crochet"), 1, 9),]
+        );
+
+        assert_eq!(
+            tokenize("```materials\n  4mm hook\n\nstuffing\n  ```\nsc\n```\n```")
+                .map(|t| t.kind())
+                .collect::<Vec<_>>(),
+            vec![
+                TextBlock("materials", "  4mm hook\n\nstuffing"),
+                Newline,
+                Sc,
+                Newline,
+                TextBlock("", ""),
+            ]
+        );
+
+        // unterminated
+        assert_eq!(tokenize("```notes\nsc").next(), None);
+
+        let mut ts = tokenize("== Notes ==\n  sc (free text) \n\n== Head ==\nsc");
+        assert_eq!(ts.next().map(|t| t.kind()), Some(Section("Notes")));
+        assert_eq!(ts.next().map(|t| t.kind()), Some(Newline));
+        assert_eq!(
+            ts.text_line(),
+            Some(("sc (free text)", Span::new((2, 3), (2, 17))))
+        );
+        assert_eq!(ts.text_line().map(|(text, _)| text), Some(""));
+        assert_eq!(ts.text_line(), None);
+        assert_eq!(ts.next().map(|t| t.kind()), Some(Section("Head")));
+
+        assert_eq!(
+            lex("hook 3.5mm\nyarn \"DK blue\"\nsc hook"),
+            vec![
+                (Hook("3.5mm"), 1, 1),
+                (Newline, 1, 11),
+                (Yarn, 2, 1),
+                (Str("DK blue"), 2, 6),
+                (Newline, 2, 15),
+                (Sc, 3, 1),
+                (Ident("hook"), 3, 4),
+            ]
+        );
+
+        assert_eq!(
+            lex("hook = 3\nsc hook"),
+            vec![
+                (Binding("hook"), 1, 1),
+                (Number(3), 1, 8),
+                (Newline, 1, 9),
+                (Sc, 2, 1),
+                (Ident("hook"), 2, 4),
+            ]
+        );
+
+        assert_eq!(
+            tokenize("@B, sc, @ C")
+                .map(|t| t.kind())
+                .collect::<Vec<_>>(),
+            vec![Color("B"), Comma, Sc, Comma]
+        );
+
+        assert_eq!(
+            lex("n2 = 3\nsc n2, scale, sc6"),
+            vec![
+                (Binding("n2"), 1, 1),
+                (Number(3), 1, 6),
+                (Newline, 1, 7),
+                (Sc, 2, 1),
+                (Ident("n2"), 2, 4),
+                (Comma, 2, 6),
+                (Ident("scale"), 2, 8),
+                (Comma, 2, 13),
+                (Sc, 2, 15),
+                (Number(6), 2, 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dialect() {
+        use TokenKind::*;
+
+        let dialect = Dialect::new()
+            .alias("single crochet", "sc")
+            .and_then(|d| d.alias("single", "sc"))
+            .and_then(|d| d.alias("mc", "in mr"))
+            .and_then(|d| d.alias("dec", "inc"))
+            .unwrap();
+        let lex = |src| {
+            tokenize_with_dialect(src, &dialect)
+                .map(|t| t.kind())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lex("single crochet 6 mc\nsingle, singles, dec"),
+            [
+                Sc,
+                Number(6),
+                InMr,
+                Newline,
+                Sc,
+                Comma,
+                Ident("singles"),
+                Comma,
+                Inc
+            ]
+        );
+
+        let ranges: Vec<_> = tokenize_with_dialect("single 2", &dialect)
+            .map(|t| t.byte_range())
+            .collect();
+        assert_eq!(ranges, [0..6, 7..8]);
     }
 }
@@ -1,12 +1,140 @@
+mod accessible;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "arena")]
+mod arena;
+mod assembly;
+mod binary;
+mod braille;
+mod builder;
+mod canonical;
+mod chart;
+mod color;
+mod csv;
+mod custom;
+mod dialect;
+mod diff;
+mod directive;
+mod error;
+mod expand;
+mod format;
+mod glossary;
+mod gltf;
+mod graph;
+mod highlight;
+mod html;
+mod include;
+mod incremental;
+#[cfg(feature = "json")]
+mod json;
+mod latex;
 mod lex;
 mod lint;
+mod locale;
+mod mesh;
+mod meta;
+mod mode;
+mod outcome;
 mod parse;
+mod pattern;
+#[cfg(feature = "pdf")]
+mod pdf;
 mod pretty_print;
+mod prose;
+mod query;
+mod round;
+mod scale;
+mod schematic;
+mod shape;
+mod size;
+mod span;
+mod stats;
+mod steps;
+mod stitches;
+mod suggestion;
+mod terminology;
+mod text;
+mod text_block;
+mod visit;
 
-pub use lint::{lint_rounds, Lint};
-pub use pretty_print::pretty_format;
+pub use accessible::accessible_format;
+#[cfg(feature = "arena")]
+pub use arena::{ArenaRound, InstructionArena, Node, NodeId, NodeList};
+pub use assembly::AssemblyStep;
+pub use braille::{to_brf, BrailleOptions, BRF_PLACEHOLDER};
+pub use builder::RoundBuilder;
+pub use canonical::{canonicalize, simplify};
+pub use chart::{render_chart, render_row_chart};
+pub use color::ColorCounts;
+pub use csv::to_csv;
+pub use custom::CustomStitch;
+pub use dialect::{Dialect, UnknownKeyword};
+pub use diff::{diff, InstructionDiff, RoundDiff};
+pub use directive::Directive;
+pub use error::ParseError;
+pub use expand::{expand, expand_round};
+pub use format::{format_instruction, format_round, CountStyle, FormatOptions, RoundWord};
+pub use glossary::abbreviation_glossary;
+pub use gltf::yarn_colors;
+pub use graph::StitchGraph;
+pub use highlight::{highlight, TokenClass};
+pub use html::to_html;
+pub use include::SourceLoader;
+pub use incremental::{reparse, ParsedPattern, ReparseError, TextEdit};
+#[cfg(feature = "json")]
+pub use json::{from_json, lints_to_json, lints_to_sarif, to_json, JsonRounds, JSON_SCHEMA};
+pub use latex::to_latex;
+pub use lex::{tokenize, tokenize_with_dialect, Token, TokenKind, TokenStream};
+pub use lint::{
+    lint_parts, lint_pattern, lint_rounds, lint_rounds_in_mode, lint_rounds_with_config, Lint,
+    LintConfig, LintReport, Note, Severity, UnknownLint,
+};
+pub use locale::Locale;
+pub use mesh::{simulate_mesh, Mesh};
+pub use meta::{MetaField, PatternMeta};
+pub use mode::Mode;
+pub use outcome::ParseOutcome;
+pub use pattern::{Part, Pattern};
+#[cfg(feature = "pdf")]
+pub use pdf::render_pdf;
+pub use pretty_print::{
+    pretty_format, pretty_format_localized, pretty_format_pattern, pretty_format_with_options,
+};
+pub use prose::prose_format;
+pub use query::find_instructions;
+pub use round::Round;
+pub use scale::scale;
+pub use schematic::{schematic, Gauge, PartSchematic, RoundSize, Schematic, Unit};
+pub use shape::{classify_shape, ShapeGuess};
+pub use size::Size;
+pub use span::{Span, SpanTree};
+pub use stats::{pattern_stats, PatternStats};
+pub use steps::{step_by_step_format, stitch_steps};
+pub use stitches::Stitches;
+pub use suggestion::{apply_suggestions, Suggestion};
+pub use terminology::Terminology;
+pub use text_block::TextBlock;
+pub use visit::{
+    fold_nested_instructions, map_instructions, walk_instruction, InstructionFolder,
+    InstructionVisitor,
+};
 
-#[derive(Debug, PartialEq, Eq)]
+/// The newest dialect of the pattern language this crate understands.
+///
+/// A file can pin the dialect it's written in with a `%crochet N%` pragma on its first line, so
+/// that syntax added in later versions can't change its meaning. Files without one are parsed
+/// with the newest dialect.
+///
+/// ```
+/// # use crochet::{parse_pattern, LATEST_VERSION};
+/// assert_eq!(parse_pattern("%crochet 1%\nsc 6 in mr").unwrap().version, 1);
+/// assert_eq!(parse_pattern("sc 6 in mr").unwrap().version, LATEST_VERSION);
+/// assert!(parse_pattern("%crochet 99%\nsc 6 in mr").is_err());
+/// ```
+pub const LATEST_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Instruction<'a> {
     Ch,
     Tch,
@@ -24,16 +152,40 @@ pub enum Instruction<'a> {
     Repeat(Box<Instruction<'a>>, u32),
     Comment(&'a str),
     Skip(u32),
+    /// Surface slip stitches worked on top of the fabric as embellishment; these don't work into
+    /// (or create) any stitches of the round.
+    SurfaceSlSt(u32),
+    /// Skip a stitch and work the first instruction into the next one, then work the second
+    /// instruction back into the skipped stitch, crossing in front of the first.
+    Crossed(Box<Instruction<'a>>, Box<Instruction<'a>>),
+    /// Chain some stitches and skip over some stitches of the previous round, leaving a space
+    /// that later rounds can work into. Written `ch N, skip M`.
+    ChSpace {
+        chains: u32,
+        skipped: u32,
+    },
+    /// Work every instruction of the given group into the same stitch
+    IntoOne(Box<Instruction<'a>>),
+    /// Switch to the color with the given key from the pattern's `colors:` palette, e.g. `@B`
+    Color(&'a str),
+    /// A stitch registered by the application with [`Dialect::stitch`]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "custom::serialize", skip_deserializing)
+    )]
+    Custom(std::sync::Arc<dyn CustomStitch>),
 }
 
-impl Instruction<'_> {
-    /// How many stitches this instruction consumes.
+impl<'a> Instruction<'a> {
+    /// How many stitches this instruction consumes. Counts too big for a `u32`, like that of
+    /// `[sc 100000] 100000`, are capped at `u32::MAX`.
     ///
     /// Example:
     /// ```
     /// # use crochet::Instruction;
     /// assert_eq!(Instruction::Inc.input_count(), 1);
     /// assert_eq!(Instruction::Dec.input_count(), 2);
+    /// assert_eq!(crochet::parse_rounds("(sc, ch 2, sc) in next st").unwrap()[0].input_count(), 1);
     /// ```
     pub fn input_count(&self) -> u32 {
         use Instruction::*;
@@ -45,14 +197,21 @@ impl Instruction<'_> {
             Inc | Flinc | Blinc => 1,
             Dec => 2,
             IntoMagicRing(_) => 0,
-            Group(insts) => insts.iter().map(Self::input_count).sum(),
-            Repeat(inst, times) => inst.input_count() * times,
+            Group(insts) => saturating_sum(insts.iter().map(Self::input_count)),
+            Repeat(inst, times) => inst.input_count().saturating_mul(*times),
             Comment(_) => 0,
             Skip(n) => *n,
+            SurfaceSlSt(_) => 0,
+            Crossed(a, b) => a.input_count().saturating_add(b.input_count()),
+            ChSpace { skipped, .. } => *skipped,
+            IntoOne(_) => 1,
+            Color(_) => 0,
+            Custom(stitch) => stitch.input_count(),
         }
     }
 
-    /// How many stitches this instruction creates.
+    /// How many stitches this instruction creates. Like [`Self::input_count`], this is
+    /// `u32::MAX` if the count is too big for a `u32`.
     ///
     /// Example:
     /// ```
@@ -70,10 +229,183 @@ impl Instruction<'_> {
             Inc | Flinc | Blinc => 2,
             Dec => 1,
             IntoMagicRing(i) => i.output_count(),
-            Group(insts) => insts.iter().map(Self::output_count).sum(),
-            Repeat(inst, times) => inst.output_count() * times,
+            Group(insts) => saturating_sum(insts.iter().map(Self::output_count)),
+            Repeat(inst, times) => inst.output_count().saturating_mul(*times),
             Comment(_) => 0,
             Skip(_) => 0,
+            SurfaceSlSt(_) => 0,
+            Crossed(a, b) => a.output_count().saturating_add(b.output_count()),
+            ChSpace { chains, .. } => *chains,
+            IntoOne(g) => g.output_count(),
+            Color(_) => 0,
+            Custom(stitch) => stitch.output_count(),
+        }
+    }
+
+    /// How many chain spaces this instruction leaves for later rounds to work into. Like
+    /// [`Self::input_count`], this is `u32::MAX` if the count is too big for a `u32`.
+    ///
+    /// Example:
+    /// ```
+    /// # use crochet::parse_rounds;
+    /// let rounds = parse_rounds("[ch 2, skip 2, sc] 4\n[[ch 2, skip 2] 100000] 100000").unwrap();
+    /// assert_eq!(rounds[0].instructions[0].chain_spaces(), 4);
+    /// assert_eq!(rounds[1].instructions[0].chain_spaces(), u32::MAX);
+    /// ```
+    pub fn chain_spaces(&self) -> u32 {
+        use Instruction::*;
+
+        match self {
+            ChSpace { .. } => 1,
+            IntoMagicRing(i) | IntoOne(i) => i.chain_spaces(),
+            Group(insts) => saturating_sum(insts.iter().map(Self::chain_spaces)),
+            Repeat(inst, times) => inst.chain_spaces().saturating_mul(*times),
+            _ => 0,
+        }
+    }
+
+    /// Whether this instruction is a post stitch, which is worked around the post of a stitch of
+    /// the previous round instead of into its top.
+    ///
+    /// ```
+    /// # use crochet::Instruction::*;
+    /// assert!(Fpsc.is_post_stitch());
+    /// assert!(!Blsc.is_post_stitch());
+    /// assert!(!Repeat(Bpsc.into(), 6).is_post_stitch());
+    /// ```
+    pub fn is_post_stitch(&self) -> bool {
+        matches!(self, Self::Fpsc | Self::Bpsc)
+    }
+
+    /// Iterates over the elementary stitches this instruction is worked as, see [`Stitches`].
+    ///
+    /// ```
+    /// # use crochet::Instruction::*;
+    /// let inst = Repeat(Group(vec![Inc, Sc]).into(), 3);
+    /// assert_eq!(inst.iter_stitches().count(), 6);
+    /// assert_eq!(inst.iter_stitches().nth(3), Some((3, &Sc)));
+    /// ```
+    pub fn iter_stitches<'r>(&'r self) -> Stitches<'r, 'a> {
+        Stitches::new(std::slice::from_ref(self))
+    }
+
+    /// Whether this instruction is worked the same as `other`, regardless of how it's written.
+    /// Repeats are expanded and groups are flattened before comparing, and comments are ignored.
+    ///
+    /// ```
+    /// # use crochet::Instruction::*;
+    /// assert!(Repeat(Sc.into(), 2).semantically_eq(&Group(vec![Sc, Sc])));
+    /// assert!(Repeat(Group(vec![Inc, Sc]).into(), 2)
+    ///     .semantically_eq(&Group(vec![Inc, Sc, Repeat(Group(vec![Inc, Sc]).into(), 1)])));
+    /// assert!(!Repeat(Sc.into(), 2).semantically_eq(&Sc));
+    /// ```
+    pub fn semantically_eq(&self, other: &Instruction) -> bool {
+        use Instruction::*;
+
+        let same = |x: &Instruction, y: &Instruction| match (x, y) {
+            (IntoMagicRing(x), IntoMagicRing(y)) | (IntoOne(x), IntoOne(y)) => x.semantically_eq(y),
+            (Crossed(x1, x2), Crossed(y1, y2)) => x1.semantically_eq(y1) && x2.semantically_eq(y2),
+            _ => x == y,
+        };
+
+        // the runs of each side are used up a bit at a time, since they needn't line up
+        let (mut a, mut b) = (Runs::new(self), Runs::new(other));
+        let (mut x, mut y) = (a.next(), b.next());
+        loop {
+            match (x, y) {
+                (None, None) => return true,
+                (Some((xi, xn)), Some((yi, yn))) if same(xi, yi) => {
+                    let n = xn.min(yn);
+                    x = if xn == n {
+                        a.next()
+                    } else {
+                        Some((xi, xn - n))
+                    };
+                    y = if yn == n {
+                        b.next()
+                    } else {
+                        Some((yi, yn - n))
+                    };
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    /// Displays the instruction like its `Display` impl, but with the stitch names from `terms`
+    ///
+    /// ```
+    /// # use crochet::{Instruction, Terminology};
+    /// let inst = Instruction::Repeat(Instruction::Sc.into(), 6);
+    /// assert_eq!(inst.display_in(Terminology::Uk).to_string(), "dc 6");
+    /// ```
+    pub fn display_in(&self, terms: Terminology) -> impl std::fmt::Display + '_ {
+        terminology::InTerms { value: self, terms }
+    }
+
+    /// If this is a single instruction repeated, e.g. `[sc 2] 3`, that instruction and how many
+    /// times it's worked
+    fn single_run(&self) -> Option<(&Self, u64)> {
+        use Instruction::*;
+
+        match self {
+            Repeat(inst, times) => {
+                let (inst, n) = inst.single_run()?;
+                Some((inst, n.saturating_mul((*times).into())))
+            }
+            Group(insts) if insts.len() == 1 => insts[0].single_run(),
+            Group(_) | Comment(_) => None,
+            _ => Some((self, 1)),
+        }
+    }
+}
+
+/// Lazily walks the instructions worked in order, with groups flattened and comments removed.
+/// Each is yielded along with how many times in a row it's worked, so that e.g. `sc 1000000`
+/// doesn't have to be expanded.
+struct Runs<'r, 'a> {
+    /// The lists being walked, innermost last, each with the index of its next instruction and
+    /// how many more times it's worked, including the current time
+    stack: Vec<(&'r [Instruction<'a>], usize, u32)>,
+}
+
+impl<'r, 'a> Runs<'r, 'a> {
+    fn new(inst: &'r Instruction<'a>) -> Self {
+        Self {
+            stack: vec![(std::slice::from_ref(inst), 0, 1)],
+        }
+    }
+}
+
+impl<'r, 'a> Iterator for Runs<'r, 'a> {
+    type Item = (&'r Instruction<'a>, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use Instruction::*;
+
+        loop {
+            let (insts, idx, times) = self.stack.last_mut()?;
+            let Some(inst) = insts.get(*idx) else {
+                *times -= 1;
+                if *times == 0 {
+                    self.stack.pop();
+                } else {
+                    *idx = 0;
+                }
+                continue;
+            };
+            *idx += 1;
+
+            match inst {
+                Group(insts) => self.stack.push((insts, 0, 1)),
+                Repeat(_, 0) | Comment(_) => {}
+                Repeat(i, times) => match inst.single_run() {
+                    Some((_, 0)) => {}
+                    Some(run) => return Some(run),
+                    None => self.stack.push((std::slice::from_ref(i), 0, *times)),
+                },
+                _ => return Some((inst, 1)),
+            }
         }
     }
 }
@@ -114,19 +446,194 @@ impl std::fmt::Display for Instruction<'_> {
             }
             Comment(s) => write!(f, "% {s} %"),
             Skip(n) => write!(f, "skip {n}"),
+            SurfaceSlSt(n) => write!(f, "surface sl st {n}"),
+            Crossed(a, b) => write!(f, "cross({a}, {b})"),
+            ChSpace { chains, skipped } => write!(f, "ch {chains}, skip {skipped}"),
+            IntoOne(g) => write!(f, "({g}) in next st"),
+            Color(c) => write!(f, "@{c}"),
+            Custom(stitch) => write!(f, "{}", stitch.name()),
         }
     }
 }
 
-pub fn parse_rounds(source: &str) -> Result<Vec<Instruction>, (usize, usize)> {
+/// Parses a pattern's header and all of its parts.
+pub fn parse_pattern(source: &str) -> Result<Pattern<'_>, ParseError> {
+    let mut ts = lex::tokenize(source);
+
+    let pattern = parse::parse_pattern(&mut ts)?;
+
+    if ts.is_empty() {
+        Ok(pattern)
+    } else {
+        Err(ts.current_loc().into())
+    }
+}
+
+/// Like [`parse_pattern`], but also understands the aliases for keywords from `dialect`.
+///
+/// ```
+/// # use crochet::{parse_pattern_with_dialect, Dialect};
+/// let dialect = Dialect::new().alias("magic ring", "mr").unwrap();
+/// let pattern = parse_pattern_with_dialect("name: Ball\nmagic ring 6", &dialect).unwrap();
+/// assert_eq!(pattern.parts[0].rounds[0].to_string(), "sc 6 in mr");
+/// ```
+pub fn parse_pattern_with_dialect<'a>(
+    source: &'a str,
+    dialect: &Dialect,
+) -> Result<Pattern<'a>, ParseError> {
+    let mut cx = parse::Context::with_dialect(dialect);
+    let mut ts = cx.tokenize(source);
+
+    let pattern = parse::parse_pattern_with(&mut ts, &mut cx)?;
+
+    if ts.is_empty() {
+        Ok(pattern)
+    } else {
+        Err(ts.current_loc().into())
+    }
+}
+
+/// Like [`parse_pattern`], but also resolves `include "path"` lines, using `loader` to get the
+/// source of the included files. Included files must contain a single unnamed part; their rounds
+/// are spliced in where the `include` line is.
+pub fn parse_pattern_with_loader<'a>(
+    source: &'a str,
+    mut loader: impl SourceLoader<'a>,
+) -> Result<Pattern<'a>, ParseError> {
     let mut ts = lex::tokenize(source);
 
-    let res = parse::parse(&mut ts);
+    let pattern =
+        parse::parse_pattern_with(&mut ts, &mut parse::Context::with_loader(&mut loader))?;
 
     if ts.is_empty() {
-        res
+        Ok(pattern)
     } else {
-        Err(ts.current_loc())
+        Err(ts.current_loc().into())
+    }
+}
+
+/// Parses the rounds of a single-part pattern, ignoring its header.
+///
+/// For multi-size patterns, this uses the counts for the first size.
+pub fn parse_rounds(source: &str) -> Result<Vec<Round<'_>>, ParseError> {
+    parse_rounds_with(source, &mut parse::Context::default())
+}
+
+/// Like [`parse_rounds`], but parses the rounds one at a time as the iterator is advanced,
+/// instead of all up front. Iteration stops after the first error.
+///
+/// ```
+/// # use crochet::parse_rounds_iter;
+/// let mut rounds = parse_rounds_iter("sc 6 in mr\ninc 6\nsc 12,");
+///
+/// assert_eq!(rounds.next().unwrap().unwrap().output_count(), 6);
+/// assert_eq!(rounds.next().unwrap().unwrap().output_count(), 12);
+/// assert_eq!(rounds.next().unwrap().unwrap_err().loc(), (3, 7));
+/// assert!(rounds.next().is_none());
+/// ```
+pub fn parse_rounds_iter(source: &str) -> impl Iterator<Item = Result<Round<'_>, ParseError>> {
+    let mut ts = lex::tokenize(source);
+    let mut cx = parse::Context::default();
+    let mut parser =
+        parse::parse_header(&mut ts, &mut cx).map(|_| parse::RoundParser::new(&mut ts, &mut cx));
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        let next = match &mut parser {
+            Ok(parser) => match parser.next_round(&mut ts, &mut cx) {
+                Ok(Some(round)) => Some(Ok(round)),
+                Ok(None) if ts.is_empty() => None,
+                // the start of another part
+                Ok(None) => Some(Err(ts.current_loc().into())),
+                Err(e) => Some(Err(e)),
+            },
+            Err(e) => Some(Err(e.clone())),
+        };
+
+        done = !matches!(next, Some(Ok(_)));
+        next
+    })
+}
+
+/// Like [`parse_rounds`], but keeps the rounds that were parsed before an error, so that tools
+/// can still work with the valid start of a pattern that's being written.
+///
+/// ```
+/// # use crochet::{lint_rounds, parse_rounds_partial};
+/// let outcome = parse_rounds_partial("sc 6 in mr\nsc 6\n[inc, sc 6");
+///
+/// assert_eq!(outcome.rounds.len(), 2);
+/// assert_eq!(outcome.error.unwrap().loc(), (3, 11));
+/// assert!(lint_rounds(&outcome.rounds).is_empty());
+/// ```
+pub fn parse_rounds_partial(source: &str) -> ParseOutcome<'_> {
+    let mut rounds = Vec::new();
+
+    for round in parse_rounds_iter(source) {
+        match round {
+            Ok(round) => rounds.push(round),
+            Err(e) => {
+                return ParseOutcome {
+                    rounds,
+                    error: Some(e),
+                }
+            }
+        }
+    }
+
+    ParseOutcome {
+        rounds,
+        error: None,
+    }
+}
+
+/// Parses the rounds of a multi-size pattern for the given size.
+///
+/// ```
+/// # use crochet::{lint_rounds, parse_rounds_for_size, Size};
+/// let src = "sc 6(8,10) in mr\ninc 6(8,10)\nsc 12(16,18), % fasten off %";
+///
+/// let m = parse_rounds_for_size(src, Size::M).unwrap();
+/// assert_eq!(m[1].output_count(), 16);
+///
+/// // lints are run per size
+/// assert!(lint_rounds(&m).is_empty());
+/// let l = parse_rounds_for_size(src, Size::L).unwrap();
+/// assert_eq!(lint_rounds(&l).len(), 1);
+/// ```
+pub fn parse_rounds_for_size(source: &str, size: Size) -> Result<Vec<Round<'_>>, ParseError> {
+    parse_rounds_with(source, &mut parse::Context::with_size(size))
+}
+
+/// Like [`parse_rounds`], but also understands the aliases for keywords from `dialect`.
+pub fn parse_rounds_with_dialect<'a>(
+    source: &'a str,
+    dialect: &Dialect,
+) -> Result<Vec<Round<'a>>, ParseError> {
+    parse_rounds_with(source, &mut parse::Context::with_dialect(dialect))
+}
+
+/// Adds up stitch counts, capping the total at `u32::MAX` instead of overflowing
+pub(crate) fn saturating_sum(counts: impl IntoIterator<Item = u32>) -> u32 {
+    counts.into_iter().fold(0, u32::saturating_add)
+}
+
+fn parse_rounds_with<'a>(
+    source: &'a str,
+    cx: &mut parse::Context<'a, '_>,
+) -> Result<Vec<Round<'a>>, ParseError> {
+    let mut ts = cx.tokenize(source);
+
+    let rounds = parse::parse_header(&mut ts, cx).and_then(|_| parse::parse_with(&mut ts, cx))?;
+
+    if ts.is_empty() {
+        Ok(rounds)
+    } else {
+        Err(ts.current_loc().into())
     }
 }
 
@@ -153,6 +660,13 @@ mod tests {
             "sc 4 in mr, inc, [sc, % hi im a comment %, inc] 2",
             "% hi again %, sc, inc, sc 2\n[inc, sc] 3",
             "[sc, inc 2] in mr",
+            "sc 6, surface sl st 3",
+            "cross(fpsc, sc) 3, sc",
+            "sc, [ch 3, skip 3, sc] 4",
+            "[(sc, ch 2, sc) in next st, sc 3] 4",
+            "sc 6 in mr\nears: inc 6",
+            "sc 6 in mr\nx3: sc 6",
+            "sc 6 in mr\nhook 3.5mm\nyarn \"DK blue\"\ninc 6",
         ];
 
         for s in sources {
@@ -167,11 +681,104 @@ mod tests {
         assert_derser("[ch 1] 1", "[ch 1] 1");
         assert_derser("[sc 3 in mr]", "sc 3 in mr");
         assert_derser("[sc 6] in mr", "[sc 6] in mr");
+        assert_derser("rounds 1-4: sc 6", "x4: sc 6");
+        assert_derser(
+            "colors: A = red, B = blue\nsc 6, @B, sc 6\n[@A, sc, @B, inc] 3",
+            "sc 6, @B, sc 6[@A, sc, @B, inc] 3",
+        );
+        assert_derser("```notes\nhi\n```\nsc 6", "sc 6");
+    }
+
+    #[test]
+    fn test_crossed_counts() {
+        let rounds = crate::parse_rounds("ch 6\n[cross(inc, sc), sc] 2, sc 2").unwrap();
+        assert_eq!((rounds[1].input_count(), rounds[1].output_count()), (8, 10));
+        assert_eq!(StitchGraph::new(&rounds).stitch_count(1), Some(10));
+
+        let crossed = Instruction::Crossed(Instruction::Dec.into(), Instruction::Ch.into());
+        assert_eq!((crossed.input_count(), crossed.output_count()), (2, 2));
     }
 
     #[test]
     fn test_unexpected_at_end_of_input() {
-        assert_eq!(crate::parse_rounds("sc 3, % foobar"), Err((1, 7)));
-        assert_eq!(crate::parse_rounds("% foobar"), Err((1, 1)));
+        let loc = |src| crate::parse_rounds(src).map_err(|e| e.loc());
+        assert_eq!(loc("sc 3, % foobar"), Err((1, 7)));
+        assert_eq!(loc("% foobar"), Err((1, 1)));
+    }
+
+    #[test]
+    fn test_parse_rounds_iter() {
+        let sources = [
+            "colors: A = red\nn = 3\nsc n in mr, @A\n```notes\nhi\n```\nhook 4mm\nx2: inc n",
+            "name: Ball\n\nsc 6 in mr\n\n\ninc 6\n",
+            "",
+            "sc 6 in mr\n== Arm ==\nsc 6",
+            "sc 6 in mr\nhook 4mm",
+            "name: Ball\nname: Ball",
+            "sc 6 sc",
+        ];
+
+        for src in sources {
+            let streamed: Result<Vec<_>, _> = parse_rounds_iter(src).collect();
+            assert_eq!(streamed, parse_rounds(src), "{src:?}");
+            assert_eq!(parse_rounds_partial(src).into_result(), parse_rounds(src));
+        }
+
+        let outcome = parse_rounds_partial("sc 6 in mr\nsc 6\n== Arm ==\nsc 6");
+        assert_eq!(outcome.rounds.len(), 2);
+        assert_eq!(outcome.error.map(|e| e.loc()), Some((3, 1)));
+
+        // errors in the header
+        let outcome = parse_rounds_partial("colors: A\nsc 6 in mr");
+        assert!(outcome.rounds.is_empty() && !outcome.is_complete());
+    }
+
+    #[test]
+    fn test_semantically_eq() {
+        let inst = |src| Instruction::Group(parse_rounds(src).unwrap().remove(0).instructions);
+        let same = |a, b| inst(a).semantically_eq(&inst(b));
+
+        assert!(same("[sc, sc] 3 in mr", "sc 6 in mr"));
+        assert!(same("[inc, sc 2] 2", "inc, sc, sc, % hi %, inc, [sc] 2"));
+        assert!(same("cross([sc] 1, sc)", "cross(sc, sc)"));
+        assert!(same("(sc 2, ch) in next st", "(sc, sc, ch) in next st"));
+        assert!(!same("sc 6 in mr", "sc 6"));
+        assert!(!same("inc, sc", "sc, inc"));
+        assert!(!same("[inc, sc] 3", "[inc, sc] 4"));
+        assert!(same("sc, [inc, sc] 2", "[sc, inc] 2, sc"));
+        assert!(same("[sc 2000000000] 2, sc", "sc 4000000000, [sc] 1"));
+        assert!(!same("sc 4000000000", "sc 3999999999"));
+
+        let set: std::collections::HashSet<_> = [inst("sc 6"), inst("sc 6"), inst("sc 5")].into();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_expected_tokens() {
+        let expected = |src| crate::parse_rounds(src).unwrap_err().expected;
+        assert_eq!(expected("sc 6,"), ["an instruction"]);
+        assert_eq!(expected("sc 6 sc"), ["','", "a new line"]);
+        assert_eq!(expected("(sc, inc"), ["')'", "','"]);
+        assert_eq!(expected("sc (2 +"), ["a number", "a variable", "'('"]);
+        assert_eq!(expected("yarn sc"), ["a string"]);
+        assert_eq!(expected("(sc, inc) sc"), ["'in next st'"]);
+        assert_eq!(
+            crate::parse_rounds("sc 6 sc").unwrap_err().to_string(),
+            "1:6: expected ',' or a new line"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let src =
+            "name: Ball\ncolors: A = cream\n== Head ==\nsc 6 in mr, @A\nhook 4mm\nx2: [inc, sc] 3";
+        let pattern = parse_pattern(src).unwrap();
+        let json = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), pattern);
+
+        let lints = lint_rounds(&parse_rounds("sc 6 in mr\nsc 7").unwrap());
+        let json = serde_json::to_string(&lints).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Lint>>(&json).unwrap(), lints);
     }
 }
@@ -1,6 +1,9 @@
-use crate::Instruction;
+use crate::round::expand_rounds;
+use crate::{canonicalize, find_instructions, Instruction, Mode, Pattern, Round, Span, Suggestion};
+use std::collections::BTreeMap;
 
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Lint {
     MismatchedStitchCount {
         /// How many stitches the first round produces
@@ -11,11 +14,535 @@ pub enum Lint {
         b_in: u32,
         /// One-based round index
         b_idx: usize,
+        /// Where the second round is in the source
+        span: Span,
+        /// Changes the second round's repeat count to work into all of the first round's
+        /// stitches, if it's a single repeat that can
+        fix: Option<Suggestion>,
+        /// The mismatches right after this one, which it probably caused
+        notes: Vec<Note>,
     },
     NonzeroFirstRoundInput {
         /// How many stitches the first round actually consumed, when it was exspected to consume 0.
         actual_consumed: u32,
+        /// Where the first round is in the source
+        span: Span,
+        /// Where the round's first instruction that consumes stitches is, if the round was parsed
+        /// from a source
+        instruction_span: Option<Span>,
     },
+    RanOutOfStitches {
+        /// How many stitches the previous round produces
+        available: u32,
+        /// One-based round index of the previous round
+        prev_idx: usize,
+        /// One-based round index
+        idx: usize,
+        /// The one-based number of the first stitch the round tries to work into that isn't there
+        offset: u32,
+        /// Where the round is in the source
+        span: Span,
+        /// Where the instruction that runs out of stitches is, if the round was parsed from a
+        /// source
+        instruction_span: Option<Span>,
+    },
+    RedundantRepeat {
+        /// One-based round index
+        idx: usize,
+        /// The instruction that's repeated once, as written
+        written: String,
+        /// The same instruction without the repeat
+        simpler: String,
+        /// Where the round is in the source
+        span: Span,
+        /// Where the repeat is, if the round was parsed from a source
+        instruction_span: Option<Span>,
+        /// Replaces the repeat with the simpler instruction
+        fix: Option<Suggestion>,
+    },
+    EmptyRound {
+        /// One-based round index
+        idx: usize,
+        /// Where the round is in the source
+        span: Span,
+    },
+    MagicRingAfterFirstRound {
+        /// One-based round index
+        idx: usize,
+        /// Where the round is in the source
+        span: Span,
+        /// Where the instruction worked into the magic ring is, if the round was parsed from a
+        /// source
+        instruction_span: Option<Span>,
+    },
+    Ruffling {
+        /// One-based round index
+        idx: usize,
+        /// How many more stitches the round makes than it's worked into
+        increases: u32,
+        /// Where the round is in the source
+        span: Span,
+    },
+    Puckering {
+        /// One-based round index
+        idx: usize,
+        /// How many stitches the round is worked into
+        input: u32,
+        /// How many fewer stitches the round makes than it's worked into
+        decreases: u32,
+        /// Where the round is in the source
+        span: Span,
+    },
+    NeverCloses {
+        /// One-based round index of the last round
+        idx: usize,
+        /// How many stitches the last round leaves open
+        stitches: u32,
+        /// Where the last round is in the source
+        span: Span,
+    },
+    MixedFoundation {
+        /// One-based index of the first round, after any rounds of only comments
+        idx: usize,
+        /// How many chains the first round has
+        chains: u32,
+        /// Where the first round is in the source
+        span: Span,
+        /// Where the first round's chains are, if the round was parsed from a source
+        instruction_span: Option<Span>,
+    },
+    BunchedIncreases {
+        /// One-based round index
+        idx: usize,
+        /// Whether the round's changes are decreases rather than increases
+        decreasing: bool,
+        /// The round written with its increases or decreases spread out evenly
+        suggestion: String,
+        /// Where the round is in the source
+        span: Span,
+        /// Replaces the round's instructions with the suggestion, if it doesn't have any comments
+        /// that would be lost
+        fix: Option<Suggestion>,
+    },
+    RepeatedRounds {
+        /// One-based round index of the first of the rounds
+        first_idx: usize,
+        /// One-based round index of the last of the rounds
+        last_idx: usize,
+        /// The rounds written as a single range
+        suggestion: String,
+        /// Where the rounds are in the source, from the start of the first to the end of the last
+        span: Span,
+        /// Replaces the rounds with the suggestion
+        fix: Option<Suggestion>,
+    },
+    PostStitchInFirstRound {
+        /// One-based round index
+        idx: usize,
+        /// The post stitch, e.g. `fpsc`
+        stitch: String,
+        /// Where the round is in the source
+        span: Span,
+        /// Where the post stitch is, if the round was parsed from a source
+        instruction_span: Option<Span>,
+    },
+    MissingTurningChain {
+        /// One-based row index
+        idx: usize,
+        /// How many chains the row starts with, which is 0 if it has no turning chain
+        chains: u32,
+        /// Where the row is in the source
+        span: Span,
+    },
+    ExcessiveNesting {
+        /// One-based round index
+        idx: usize,
+        /// How many levels of brackets deep the round's instructions go
+        depth: u32,
+        /// Where the round is in the source
+        span: Span,
+        /// Where the most deeply nested of the round's instructions is, if the round was parsed
+        /// from a source
+        instruction_span: Option<Span>,
+    },
+    UnknownPart {
+        /// The name the assembly step refers to
+        name: String,
+        /// Where the name is in the source
+        span: Span,
+    },
+    DuplicatePart {
+        /// The name of the parts
+        name: String,
+        /// Where the first round of the second part with the name is in the source, or the
+        /// default span if it doesn't have any rounds
+        span: Span,
+    },
+    AbsurdStitchCount {
+        /// One-based round index
+        idx: usize,
+        /// How many stitches the round makes
+        stitches: u32,
+        /// Where the round is in the source
+        span: Span,
+    },
+    UnusedColor {
+        /// The color's key in the palette, e.g. `A`
+        key: String,
+        /// The default span, since the header lines that declare colors don't have spans
+        span: Span,
+    },
+    RedundantColorChange {
+        /// One-based round index
+        idx: usize,
+        /// The key of the color that's switched to, e.g. `A`
+        key: String,
+        /// Where the round is in the source
+        span: Span,
+        /// Where the color change is in the source
+        instruction_span: Option<Span>,
+    },
+    StitchesAfterFastenOff {
+        /// One-based round index of the round with the stitches
+        idx: usize,
+        /// One-based round index of the round that fastens off
+        fasten_off_idx: usize,
+        /// Where the round with the stitches is in the source
+        span: Span,
+        /// Where the first instruction after fastening off that works stitches is in the source
+        instruction_span: Option<Span>,
+    },
+    FoundationChainMismatch {
+        /// One-based round index of the row worked into the foundation chain
+        idx: usize,
+        /// How many chains the foundation chain has
+        chains: u32,
+        /// How many of them are skipped as the row's turning chain
+        turning: u32,
+        /// How many stitches the row consumes
+        consumed: u32,
+        /// Where the row is in the source
+        span: Span,
+        /// Changes the repeat count of the row so that it works into the rest of the chain, if it's
+        /// a single repeat that can
+        fix: Option<Suggestion>,
+        /// The mismatches right after this one, which it probably caused
+        notes: Vec<Note>,
+    },
+    StackedDecreases {
+        /// One-based round index of the round that starts with a decrease
+        idx: usize,
+        /// One-based round index of the round before it, which ends with a decrease. Rounds of
+        /// only comments in between are skipped over.
+        previous_idx: usize,
+        /// Where the round is in the source
+        span: Span,
+        /// Starts the round partway between its decreases, if it's only plain stitches, increases
+        /// and decreases and doesn't have any comments that would be lost
+        fix: Option<Suggestion>,
+    },
+}
+
+/// A secondary message attached to a [`Lint`], about something that follows from the problem,
+/// e.g. a later round that doesn't fit because of it
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Note {
+    pub message: String,
+    /// Where the note is about in the source
+    pub span: Span,
+}
+
+/// How serious a [`Lint`] is
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// The pattern can't be worked as written
+    Error,
+    /// The pattern is probably wrong, but might be intentional
+    Warning,
+    /// Something that's worth pointing out, but isn't a problem
+    Info,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl Lint {
+    /// The names of every kind of lint, which [`LintConfig`] refers to them by
+    pub const NAMES: &'static [&'static str] = &[
+        "mismatched_stitch_count",
+        "nonzero_first_round_input",
+        "ran_out_of_stitches",
+        "redundant_repeat",
+        "empty_round",
+        "magic_ring_after_first_round",
+        "ruffling",
+        "puckering",
+        "never_closes",
+        "mixed_foundation",
+        "bunched_increases",
+        "repeated_rounds",
+        "post_stitch_in_first_round",
+        "missing_turning_chain",
+        "excessive_nesting",
+        "unknown_part",
+        "duplicate_part",
+        "absurd_stitch_count",
+        "stacked_decreases",
+        "foundation_chain_mismatch",
+        "unused_color",
+        "redundant_color_change",
+        "stitches_after_fasten_off",
+    ];
+
+    /// The stable code of this kind of lint, e.g. `C001`, which is never reused for a different
+    /// kind of lint
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MismatchedStitchCount { .. } => "C001",
+            Self::NonzeroFirstRoundInput { .. } => "C002",
+            Self::RanOutOfStitches { .. } => "C003",
+            Self::RedundantRepeat { .. } => "C004",
+            Self::EmptyRound { .. } => "C005",
+            Self::MagicRingAfterFirstRound { .. } => "C006",
+            Self::Ruffling { .. } => "C007",
+            Self::Puckering { .. } => "C008",
+            Self::NeverCloses { .. } => "C009",
+            Self::MixedFoundation { .. } => "C010",
+            Self::BunchedIncreases { .. } => "C011",
+            Self::RepeatedRounds { .. } => "C012",
+            Self::PostStitchInFirstRound { .. } => "C013",
+            Self::MissingTurningChain { .. } => "C014",
+            Self::ExcessiveNesting { .. } => "C015",
+            Self::UnknownPart { .. } => "C016",
+            Self::DuplicatePart { .. } => "C017",
+            Self::AbsurdStitchCount { .. } => "C018",
+            Self::StackedDecreases { .. } => "C019",
+            Self::FoundationChainMismatch { .. } => "C020",
+            Self::UnusedColor { .. } => "C021",
+            Self::RedundantColorChange { .. } => "C022",
+            Self::StitchesAfterFastenOff { .. } => "C023",
+        }
+    }
+
+    /// The name of this kind of lint, one of [`Self::NAMES`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MismatchedStitchCount { .. } => "mismatched_stitch_count",
+            Self::NonzeroFirstRoundInput { .. } => "nonzero_first_round_input",
+            Self::RanOutOfStitches { .. } => "ran_out_of_stitches",
+            Self::RedundantRepeat { .. } => "redundant_repeat",
+            Self::EmptyRound { .. } => "empty_round",
+            Self::MagicRingAfterFirstRound { .. } => "magic_ring_after_first_round",
+            Self::Ruffling { .. } => "ruffling",
+            Self::Puckering { .. } => "puckering",
+            Self::NeverCloses { .. } => "never_closes",
+            Self::MixedFoundation { .. } => "mixed_foundation",
+            Self::BunchedIncreases { .. } => "bunched_increases",
+            Self::RepeatedRounds { .. } => "repeated_rounds",
+            Self::PostStitchInFirstRound { .. } => "post_stitch_in_first_round",
+            Self::MissingTurningChain { .. } => "missing_turning_chain",
+            Self::ExcessiveNesting { .. } => "excessive_nesting",
+            Self::UnknownPart { .. } => "unknown_part",
+            Self::DuplicatePart { .. } => "duplicate_part",
+            Self::AbsurdStitchCount { .. } => "absurd_stitch_count",
+            Self::StackedDecreases { .. } => "stacked_decreases",
+            Self::FoundationChainMismatch { .. } => "foundation_chain_mismatch",
+            Self::UnusedColor { .. } => "unused_color",
+            Self::RedundantColorChange { .. } => "redundant_color_change",
+            Self::StitchesAfterFastenOff { .. } => "stitches_after_fasten_off",
+        }
+    }
+
+    /// How serious this lint is, unless a [`LintConfig`] says otherwise
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Self::MismatchedStitchCount { .. } => Severity::Error,
+            Self::NonzeroFirstRoundInput { .. } => Severity::Error,
+            Self::RanOutOfStitches { .. } => Severity::Error,
+            Self::RedundantRepeat { .. } => Severity::Info,
+            Self::EmptyRound { .. } => Severity::Warning,
+            Self::MagicRingAfterFirstRound { .. } => Severity::Error,
+            Self::Ruffling { .. } => Severity::Warning,
+            Self::Puckering { .. } => Severity::Warning,
+            Self::NeverCloses { .. } => Severity::Warning,
+            Self::MixedFoundation { .. } => Severity::Warning,
+            Self::BunchedIncreases { .. } => Severity::Info,
+            Self::RepeatedRounds { .. } => Severity::Info,
+            Self::PostStitchInFirstRound { .. } => Severity::Warning,
+            Self::MissingTurningChain { .. } => Severity::Warning,
+            Self::ExcessiveNesting { .. } => Severity::Warning,
+            Self::UnknownPart { .. } => Severity::Error,
+            Self::DuplicatePart { .. } => Severity::Warning,
+            Self::AbsurdStitchCount { .. } => Severity::Warning,
+            Self::StackedDecreases { .. } => Severity::Warning,
+            Self::FoundationChainMismatch { .. } => Severity::Error,
+            Self::UnusedColor { .. } => Severity::Warning,
+            Self::RedundantColorChange { .. } => Severity::Warning,
+            Self::StitchesAfterFastenOff { .. } => Severity::Warning,
+        }
+    }
+
+    /// Where the problem is in the source: the span of the most specific instruction or round the
+    /// lint is about.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds, parse_rounds, Span};
+    /// let lints = lint_rounds(&parse_rounds("ch 6\ninc 3, sc 4").unwrap());
+    /// assert_eq!(lints[0].span(), Span::new((2, 1), (2, 12)));
+    ///
+    /// let lints = lint_rounds(&parse_rounds("ch, sc 3").unwrap());
+    /// assert_eq!(lints[0].span(), Span::new((1, 5), (1, 9)));
+    /// ```
+    pub fn span(&self) -> Span {
+        match self {
+            Self::MismatchedStitchCount { span, .. }
+            | Self::EmptyRound { span, .. }
+            | Self::Ruffling { span, .. }
+            | Self::Puckering { span, .. }
+            | Self::NeverCloses { span, .. }
+            | Self::BunchedIncreases { span, .. }
+            | Self::RepeatedRounds { span, .. }
+            | Self::MissingTurningChain { span, .. }
+            | Self::UnknownPart { span, .. }
+            | Self::DuplicatePart { span, .. }
+            | Self::AbsurdStitchCount { span, .. }
+            | Self::StackedDecreases { span, .. }
+            | Self::FoundationChainMismatch { span, .. }
+            | Self::UnusedColor { span, .. } => *span,
+            Self::NonzeroFirstRoundInput {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::RanOutOfStitches {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::RedundantRepeat {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::MagicRingAfterFirstRound {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::MixedFoundation {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::PostStitchInFirstRound {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::ExcessiveNesting {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::RedundantColorChange {
+                span,
+                instruction_span,
+                ..
+            }
+            | Self::StitchesAfterFastenOff {
+                span,
+                instruction_span,
+                ..
+            } => instruction_span.unwrap_or(*span),
+        }
+    }
+
+    /// Notes about the consequences of the problem, which are reported along with it instead of
+    /// as lints of their own.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds, parse_rounds};
+    /// // one round of the block makes more stitches than the next one works into
+    /// let lints = lint_rounds(&parse_rounds("sc 6 in mr\nx4: inc 6, % sew closed %").unwrap());
+    ///
+    /// assert_eq!(lints.len(), 1);
+    /// assert_eq!(lints[0].notes().len(), 2);
+    /// assert_eq!(
+    ///     lints[0].notes()[1].message,
+    ///     "round 4 produces 12 stitches but round 5 consumes 6 stitches"
+    /// );
+    /// ```
+    pub fn notes(&self) -> &[Note] {
+        match self {
+            Self::MismatchedStitchCount { notes, .. }
+            | Self::FoundationChainMismatch { notes, .. } => notes,
+            _ => &[],
+        }
+    }
+
+    /// The one-based index of the round this lint is about, or `None` if it isn't about a single
+    /// numbered round. For lints about how two rounds fit together, that's the second round, and
+    /// for lints about how a piece starts, it's the first round.
+    pub fn round(&self) -> Option<usize> {
+        match self {
+            Self::MismatchedStitchCount { b_idx: idx, .. }
+            | Self::RanOutOfStitches { idx, .. }
+            | Self::RedundantRepeat { idx, .. }
+            | Self::EmptyRound { idx, .. }
+            | Self::MagicRingAfterFirstRound { idx, .. }
+            | Self::Ruffling { idx, .. }
+            | Self::Puckering { idx, .. }
+            | Self::NeverCloses { idx, .. }
+            | Self::BunchedIncreases { idx, .. }
+            | Self::RepeatedRounds { first_idx: idx, .. }
+            | Self::PostStitchInFirstRound { idx, .. }
+            | Self::MissingTurningChain { idx, .. }
+            | Self::ExcessiveNesting { idx, .. }
+            | Self::AbsurdStitchCount { idx, .. }
+            | Self::StackedDecreases { idx, .. }
+            | Self::FoundationChainMismatch { idx, .. }
+            | Self::RedundantColorChange { idx, .. }
+            | Self::MixedFoundation { idx, .. }
+            | Self::StitchesAfterFastenOff { idx, .. } => Some(*idx),
+            Self::NonzeroFirstRoundInput { .. } => Some(1),
+            Self::UnknownPart { .. } | Self::DuplicatePart { .. } | Self::UnusedColor { .. } => {
+                None
+            }
+        }
+    }
+
+    /// A fix for the problem, which [`crate::apply_suggestions`] can make to the source. Only
+    /// some lints have one, and only if the rounds they're about were parsed from a source.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds, parse_rounds, Span};
+    /// let lints = lint_rounds(&parse_rounds("sc 6 in mr\ninc 7").unwrap());
+    /// let fix = lints[0].suggestion().unwrap();
+    ///
+    /// assert_eq!(fix.span, Span::new((2, 1), (2, 6)));
+    /// assert_eq!(fix.replacement, "inc 6");
+    /// ```
+    pub fn suggestion(&self) -> Option<&Suggestion> {
+        match self {
+            Self::MismatchedStitchCount { fix, .. }
+            | Self::RedundantRepeat { fix, .. }
+            | Self::BunchedIncreases { fix, .. }
+            | Self::RepeatedRounds { fix, .. }
+            | Self::StackedDecreases { fix, .. }
+            | Self::FoundationChainMismatch { fix, .. } => fix.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 fn pluralstitch(n: u32) -> &'static str {
@@ -34,6 +561,7 @@ impl std::fmt::Display for Lint {
                 a_idx,
                 b_in,
                 b_idx,
+                ..
             } => {
                 let aplural = pluralstitch(*a_out);
                 let bplural = pluralstitch(*b_in);
@@ -45,46 +573,294 @@ impl std::fmt::Display for Lint {
                         consumes {b_in} {bplural}",
                 )
             }
-            Self::NonzeroFirstRoundInput { actual_consumed } => {
+            Self::NonzeroFirstRoundInput {
+                actual_consumed, ..
+            } => {
                 let plural = pluralstitch(*actual_consumed);
                 write!(
                     f,
                     "round 1 consumes {actual_consumed} {plural} but the first round shouldn't consume any stitches"
                 )
             }
+            Self::RanOutOfStitches {
+                available,
+                prev_idx,
+                idx,
+                offset,
+                ..
+            } => {
+                let plural = pluralstitch(*available);
+                write!(
+                    f,
+                    "round {idx} runs out of stitches at stitch {offset}, since round {prev_idx} \
+                        only produces {available} {plural}"
+                )
+            }
+            Self::RedundantRepeat {
+                idx,
+                written,
+                simpler,
+                ..
+            } => {
+                write!(
+                    f,
+                    "round {idx} repeats `{written}` once, which can be written as `{simpler}`"
+                )
+            }
+            Self::EmptyRound { idx, .. } => {
+                write!(
+                    f,
+                    "round {idx} has no stitches, so the rounds after it are numbered one higher \
+                        than they're worked; put its comments on the round they're about instead"
+                )
+            }
+            Self::MagicRingAfterFirstRound { idx, .. } => {
+                write!(
+                    f,
+                    "round {idx} is worked into a magic ring, but only the first round can be"
+                )
+            }
+            Self::Ruffling { idx, increases, .. } => {
+                let plural = pluralstitch(*increases);
+                write!(
+                    f,
+                    "round {idx} increases by {increases} {plural}, much faster than a flat circle \
+                        (about 6 per round), so it will ruffle"
+                )
+            }
+            Self::Puckering {
+                idx,
+                input,
+                decreases,
+                ..
+            } => {
+                write!(
+                    f,
+                    "round {idx} takes away {decreases} of {input} stitches at once, so it will \
+                        pucker"
+                )
+            }
+            Self::NeverCloses { idx, stitches, .. } => {
+                write!(
+                    f,
+                    "the piece ends with {stitches} stitches open after round {idx}; decrease \
+                        until it's closed, or say how to finish it, e.g. `% fasten off %`"
+                )
+            }
+            Self::MixedFoundation { idx, chains, .. } => {
+                write!(
+                    f,
+                    "round {idx} starts with both a magic ring and a foundation chain of {chains}; \
+                        only one of them should be used"
+                )
+            }
+            Self::BunchedIncreases {
+                idx,
+                decreasing,
+                suggestion,
+                ..
+            } => {
+                let changes = if *decreasing {
+                    "decreases"
+                } else {
+                    "increases"
+                };
+                write!(
+                    f,
+                    "round {idx} has all of its {changes} next to each other, which leaves a \
+                        visible seam; spread them out, e.g. `{suggestion}`"
+                )
+            }
+            Self::RepeatedRounds {
+                first_idx,
+                last_idx,
+                suggestion,
+                ..
+            } => {
+                write!(
+                    f,
+                    "rounds {first_idx} to {last_idx} are all the same, and can be written as \
+                        `{suggestion}`"
+                )
+            }
+            Self::PostStitchInFirstRound { idx, stitch, .. } => {
+                write!(
+                    f,
+                    "round {idx} has a `{stitch}`, but it's worked into the start of the piece, \
+                        which has no posts to work around yet"
+                )
+            }
+            Self::MissingTurningChain { idx, chains: 0, .. } => {
+                write!(
+                    f,
+                    "row {idx} doesn't start with a turning chain, so its edge will lean and lose \
+                        a stitch; start it with `ch 1`"
+                )
+            }
+            Self::MissingTurningChain { idx, chains, .. } => {
+                write!(
+                    f,
+                    "row {idx} starts with a turning chain of {chains}, which is too tall for \
+                        single crochet and leaves a loose edge; start it with `ch 1`"
+                )
+            }
+            Self::ExcessiveNesting { idx, depth, .. } => {
+                write!(
+                    f,
+                    "round {idx} has brackets nested {depth} deep, which is hard to follow and \
+                        might be a misplaced bracket"
+                )
+            }
+            Self::UnknownPart { name, .. } => {
+                write!(
+                    f,
+                    "the assembly refers to a part called \"{name}\", but there isn't one"
+                )
+            }
+            Self::FoundationChainMismatch {
+                idx,
+                chains,
+                turning,
+                consumed,
+                ..
+            } => {
+                let left = chains - turning;
+                let (diff, which) = if consumed < &left {
+                    (left - consumed, "short")
+                } else {
+                    (consumed - left, "over")
+                };
+                write!(
+                    f,
+                    "row {idx} consumes {consumed} {} of a foundation chain of {chains}, which \
+                        has {left} left after skipping {turning} as the turning chain; it's {diff} \
+                        {} {which}",
+                    pluralstitch(*consumed),
+                    pluralstitch(diff)
+                )
+            }
+            Self::StackedDecreases {
+                idx, previous_idx, ..
+            } => {
+                write!(
+                    f,
+                    "round {previous_idx} ends with a decrease and round {idx} starts with one, \
+                        which stacks them into a visible seam; start round {idx} partway between \
+                        its decreases"
+                )
+            }
+            Self::AbsurdStitchCount { idx, stitches, .. } => {
+                write!(
+                    f,
+                    "round {idx} makes {stitches} stitches, which is more than any real piece; \
+                        check its counts for a typo"
+                )
+            }
+            Self::DuplicatePart { name, .. } => {
+                write!(
+                    f,
+                    "there's more than one part called \"{name}\", so it's unclear which one the \
+                        assembly means"
+                )
+            }
+            Self::StitchesAfterFastenOff {
+                idx,
+                fasten_off_idx,
+                ..
+            } => {
+                write!(
+                    f,
+                    "round {idx} works stitches after the yarn is fastened off in round \
+                        {fasten_off_idx}; is a part header like `== Arm ==` missing?"
+                )
+            }
+            Self::UnusedColor { key, .. } => {
+                write!(f, "color {key} is declared but no stitches are made in it")
+            }
+            Self::RedundantColorChange { idx, key, .. } => {
+                write!(
+                    f,
+                    "round {idx} switches to color {key}, but it's already the active color"
+                )
+            }
         }
     }
 }
 
-fn lint_nonzero_first_round_input(rounds: &[Instruction]) -> Option<Lint> {
-    let cnt = rounds.get(0)?.input_count();
+/// Whether `round` has an `% allow(...) %` comment that turns off `lint` for it. Lints can be
+/// allowed by their name or code, with several separated by commas, e.g.
+/// `% allow(C001, nonzero_first_round_input) %`.
+fn allows(round: &Round, lint: &Lint) -> bool {
+    round.instructions.iter().any(|inst| {
+        let Instruction::Comment(text) = inst else {
+            return false;
+        };
+
+        text.strip_prefix("allow(")
+            .and_then(|s| s.strip_suffix(')'))
+            .is_some_and(|list| {
+                list.split(',')
+                    .map(str::trim)
+                    .any(|l| l == lint.name() || l == lint.code())
+            })
+    })
+}
+
+fn lint_nonzero_first_round_input(rounds: &[&Round]) -> Option<Lint> {
+    let first = rounds.first()?;
+    let cnt = first.input_count();
 
     if cnt != 0 {
-        Some(Lint::NonzeroFirstRoundInput {
+        let consuming = first
+            .instructions
+            .iter()
+            .position(|i| i.input_count() != 0)?;
+
+        let lint = Lint::NonzeroFirstRoundInput {
             actual_consumed: cnt,
-        })
+            span: first.span,
+            instruction_span: first.instruction_span(&[consuming]),
+        };
+        (!allows(first, &lint)).then_some(lint)
     } else {
         None
     }
 }
 
-fn lint_mismatched_stitch_count(rounds: &[Instruction]) -> Vec<Lint> {
+/// Whether `round` is only a foundation chain, which the next round can be worked back along,
+/// skipping the chains closest to the hook as its turning chain
+fn is_foundation_chain(round: &Round) -> bool {
+    round.input_count() == 0
+        && round.output_count() > TURNING_CHAINS
+        && round.leading_chains() == round.output_count()
+}
+
+fn lint_mismatched_stitch_count(rounds: &[&Round], mode: Mode) -> Vec<Lint> {
     if rounds.len() < 2 {
         return Vec::new();
     }
 
     let mut ret = Vec::new();
+    // the index of the last round that didn't work into the one before it
+    let mut last_mismatched = None;
+    // the one-based index of the last round of the latest reported mismatch
+    let mut cascade_end = None;
+    // whether a round that makes or uses stitches has been seen, so that rounds of only comments
+    // before the first one don't count as the first round
+    let mut started = false;
 
     'outer: for i in 0..rounds.len() - 1 {
-        let a_out = rounds[i].output_count();
+        // joining and turning chains don't count as stitches
+        let a_out = mode.stitch_count(rounds[i], !started);
         if a_out == 0 && rounds[i].input_count() == 0 {
             // skip rounds that have 0 in and 0 out (e.g. a round of just comments)
             continue;
         }
+        let first = !std::mem::replace(&mut started, true);
 
         let mut b_offset = 1;
         // skip 0in0out rounds until we find a suitable b
-        let b_in = loop {
+        let b = loop {
             match rounds.get(i + b_offset) {
                 Some(possible_b) => {
                     let incount = possible_b.input_count();
@@ -92,7 +868,7 @@ fn lint_mismatched_stitch_count(rounds: &[Instruction]) -> Vec<Lint> {
                         b_offset += 1;
                         continue;
                     } else {
-                        break incount;
+                        break possible_b;
                     }
                 }
                 // we reached the end of the `rounds` array without finding a suitable 'b' round -
@@ -101,91 +877,1651 @@ fn lint_mismatched_stitch_count(rounds: &[Instruction]) -> Vec<Lint> {
             }
         };
 
-        if a_out != b_in {
-            ret.push(Lint::MismatchedStitchCount {
-                a_out,
-                b_in,
-                a_idx: i + 1,
-                b_idx: i + 2,
-            })
+        let b_in = b.input_count();
+        // a row worked back along a foundation chain skips its turning chain, in any mode
+        let turning = turning_chains_of(b);
+        let along_foundation = first
+            && is_foundation_chain(rounds[i])
+            && b_in == rounds[i].output_count().saturating_sub(turning);
+        if a_out != b_in && !along_foundation {
+            // what the rounds after b work into, which the fixed round still has to make. If b is
+            // repeated, the fixed round works into itself too.
+            let after_b = &rounds[i + b_offset + 1..];
+            let repeats = after_b.first().is_some_and(|r| std::ptr::eq(*r, *b));
+            let c_in = after_b
+                .iter()
+                .find(|r| !std::ptr::eq(**r, *b) && (r.input_count() != 0 || r.output_count() != 0))
+                .map(|r| r.input_count());
+
+            // a row worked into a foundation chain should leave out its turning chain
+            let foundation = (first && is_foundation_chain(rounds[i])).then(|| {
+                let chains = rounds[i].output_count();
+                (chains, chains.saturating_sub(turning))
+            });
+            let expected = foundation.map_or(a_out, |(_, left)| left);
+
+            // only fix a single wrong round: if a doesn't fit either, it's unclear which is wrong,
+            // and changing a block of repeated rounds would change the round before it too
+            let fix = if last_mismatched == Some(i) || std::ptr::eq(rounds[i], *b) {
+                None
+            } else {
+                let outputs: Vec<_> = repeats.then_some(a_out).into_iter().chain(c_in).collect();
+                repeat_count_fix(b, expected, &outputs, mode)
+            };
+            last_mismatched = Some(i + b_offset);
+
+            let lint = match foundation {
+                Some((chains, _)) => Lint::FoundationChainMismatch {
+                    idx: i + b_offset + 1,
+                    chains,
+                    turning,
+                    consumed: b_in,
+                    span: b.span,
+                    fix,
+                    notes: Vec::new(),
+                },
+                None => Lint::MismatchedStitchCount {
+                    a_out,
+                    b_in,
+                    a_idx: i + 1,
+                    b_idx: i + b_offset + 1,
+                    span: b.span,
+                    fix,
+                    notes: Vec::new(),
+                },
+            };
+            // the round that doesn't work into the previous one is where it'd be intentional
+            if allows(b, &lint) {
+                continue;
+            }
+
+            // one wrong round often leaves the rounds after it mismatched too, which are
+            // reported as notes on the first mismatch instead of as lints of their own
+            match ret.last_mut() {
+                Some(
+                    Lint::MismatchedStitchCount { notes, .. }
+                    | Lint::FoundationChainMismatch { notes, .. },
+                ) if cascade_end == Some(i + 1) => {
+                    notes.push(Note {
+                        message: lint.to_string(),
+                        span: b.span,
+                    });
+                }
+                _ => ret.push(lint),
+            }
+            cascade_end = Some(i + b_offset + 1);
         }
     }
 
     ret
 }
 
-pub fn lint_rounds(rounds: &[Instruction]) -> Vec<Lint> {
-    let mut lints = lint_mismatched_stitch_count(rounds);
+/// Changes the repeat count of a round whose only instruction that works into stitches is a
+/// repeat, like `[inc, sc] 7`, so that it works into `input` stitches, as long as it then makes
+/// as many stitches as each of `outputs`
+fn repeat_count_fix(round: &Round, input: u32, outputs: &[u32], mode: Mode) -> Option<Suggestion> {
+    let mut consuming = round
+        .instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, inst)| inst.input_count() != 0);
 
-    if let Some(l) = lint_nonzero_first_round_input(rounds) {
-        lints.push(l);
+    let (idx, Instruction::Repeat(inst, _)) = consuming.next()? else {
+        return None;
+    };
+    if consuming.next().is_some() {
+        return None;
     }
 
-    lints
-}
+    let per_repeat = inst.input_count();
+    if input == 0 || !input.is_multiple_of(per_repeat) {
+        return None;
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::parse_rounds;
+    let fixed = Instruction::Repeat(inst.clone(), input / per_repeat);
 
-    fn assert_produces_lint(src: &str, lint: &Lint) {
-        let rounds = parse_rounds(src).unwrap();
-        let lints = lint_rounds(&rounds);
+    let mut insts = round.instructions.clone();
+    insts[idx] = fixed.clone();
+    let fixed_out = mode.stitch_count(&Round::new(insts), false);
+    if outputs.iter().any(|&out| out != fixed_out) {
+        return None;
+    }
 
-        dbg!(&lints, &lint);
+    Some(Suggestion::new(
+        round.instruction_span(&[idx])?,
+        fixed.to_string(),
+    ))
+}
 
-        assert!(lints.contains(lint));
-    }
+/// Finds where rounds run out of stitches to work into before their last instruction, e.g. with a
+/// `skip` past the end of the previous round. Rounds that only run out at their end are already
+/// reported as [`Lint::MismatchedStitchCount`].
+fn lint_ran_out_of_stitches(rounds: &[&Round], mode: Mode) -> Vec<Lint> {
+    let mut ret = Vec::new();
+    // how many stitches the last round that made or used any produced, and its index
+    let mut prev: Option<(u32, usize)> = None;
 
-    #[test]
-    fn test_lint_nonzero_first_round_input() {
-        assert_produces_lint("sc 3", &Lint::NonzeroFirstRoundInput { actual_consumed: 3 });
-    }
+    for (i, round) in rounds.iter().enumerate() {
+        if round.input_count() == 0 && round.output_count() == 0 {
+            continue;
+        }
 
-    #[test]
-    fn test_lint_mismatched_stitch_counts() {
-        assert_produces_lint(
-            "sc 3\n[inc, sc] 2",
-            &Lint::MismatchedStitchCount {
-                a_out: 3,
-                b_in: 4,
-                a_idx: 1,
-                b_idx: 2,
-            },
-        );
-    }
+        if let Some((available, prev_idx)) = prev {
+            let insts = round.instructions.iter().zip(round.instruction_offsets());
+            let ran_out = insts
+                .enumerate()
+                .find(|(_, (inst, (input, _)))| input + inst.input_count() > available);
 
-    #[test]
-    fn test_lint_display() {
-        let s = format!(
-            "{}",
-            Lint::MismatchedStitchCount {
-                a_out: 1,
-                b_in: 3,
-                a_idx: 1,
-                b_idx: 2,
+            // the last instruction that works into stitches, which running out at isn't mid-round
+            let last = round
+                .instructions
+                .iter()
+                .rposition(|inst| inst.input_count() != 0);
+
+            if let Some((inst_idx, _)) = ran_out.filter(|(i, _)| Some(*i) != last) {
+                let lint = Lint::RanOutOfStitches {
+                    available,
+                    prev_idx,
+                    idx: i + 1,
+                    offset: available + 1,
+                    span: round.span,
+                    instruction_span: round.instruction_span(&[inst_idx]),
+                };
+                if !allows(round, &lint) {
+                    ret.push(lint);
+                }
             }
-        );
-        assert_eq!(
-            &s,
-            "round 1 produces 1 stitch but round 2 consumes 3 stitches"
-        );
+        }
 
-        let s = format!("{}", Lint::NonzeroFirstRoundInput { actual_consumed: 4 });
-        assert_eq!(
-            &s,
-            "round 1 consumes 4 stitches but the first round shouldn't consume any stitches"
-        );
+        prev = Some((mode.stitch_count(round, prev.is_none()), i + 1));
     }
 
-    fn no_lints(src: &str) {
-        let rounds = parse_rounds(src).unwrap();
-        let lints = lint_rounds(&rounds);
-        assert!(lints.is_empty());
-    }
+    ret
+}
 
-    #[test]
+/// Finds instructions that are repeated once, like `sc 1` or `[inc, sc] 1`, which are usually left
+/// over from editing a pattern
+fn lint_redundant_repeat(rounds: &[&Round]) -> Vec<Lint> {
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate() {
+        // a block of repeated rounds only needs to be reported once
+        if i > 0 && std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        // `ch 1` is the usual way to write a turning or joining chain, not an edit left behind
+        let once = |inst: &Instruction| match inst {
+            Instruction::Repeat(i, 1) => **i != Instruction::Ch,
+            _ => false,
+        };
+
+        for (_, path, inst) in find_instructions(std::slice::from_ref(*round), once) {
+            let Instruction::Repeat(repeated, _) = inst else {
+                unreachable!("only repeats are found");
+            };
+
+            let instruction_span = round.instruction_span(&path);
+            // a group that's nested in another instruction still needs its brackets
+            let replacement = match **repeated {
+                Instruction::Group(_) if path.len() > 1 => format!("[{repeated}]"),
+                _ => repeated.to_string(),
+            };
+
+            let lint = Lint::RedundantRepeat {
+                idx: i + 1,
+                written: inst.to_string(),
+                simpler: repeated.to_string(),
+                span: round.span,
+                instruction_span,
+                fix: instruction_span.map(|span| Suggestion::new(span, replacement)),
+            };
+            if !allows(round, &lint) {
+                ret.push(lint);
+            }
+        }
+    }
+
+    ret
+}
+
+/// Finds rounds that are only comments, in between rounds with stitches. These are usually notes
+/// written on their own line, which shift the numbers of the following rounds. This is only
+/// checked when [`LintConfig::report_empty_rounds`] is set.
+fn lint_empty_round(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    if !config.empty_rounds {
+        return Vec::new();
+    }
+
+    let has_stitches = |r: &&Round| r.input_count() != 0 || r.output_count() != 0;
+    let (Some(first), Some(last)) = (
+        rounds.iter().position(has_stitches),
+        rounds.iter().rposition(has_stitches),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate().take(last).skip(first + 1) {
+        // a block of repeated rounds only needs to be reported once
+        if std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        let empty = round
+            .instructions
+            .iter()
+            .all(|inst| matches!(inst, Instruction::Comment(_)));
+
+        let lint = Lint::EmptyRound {
+            idx: i + 1,
+            span: round.span,
+        };
+        if empty && !allows(round, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// Finds stitches worked into a magic ring after the first round, which are always left over from
+/// copying the first round
+fn lint_magic_ring_after_first_round(rounds: &[&Round]) -> Vec<Lint> {
+    // rounds of only comments before the first round with stitches don't count
+    let Some(first) = rounds
+        .iter()
+        .position(|r| r.input_count() != 0 || r.output_count() != 0)
+    else {
+        return Vec::new();
+    };
+
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate().skip(first + 1) {
+        // a block of repeated rounds that starts with a magic ring is only reported once
+        if i > first + 1 && std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        let mr = |inst: &Instruction| matches!(inst, Instruction::IntoMagicRing(_));
+        let Some((_, path, _)) = find_instructions(std::slice::from_ref(*round), mr)
+            .into_iter()
+            .next()
+        else {
+            continue;
+        };
+
+        let lint = Lint::MagicRingAfterFirstRound {
+            idx: i + 1,
+            span: round.span,
+            instruction_span: round.instruction_span(&path),
+        };
+        if !allows(round, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// How many stitches a flat circle of single crochet gains per round: its radius grows by one
+/// stitch height, which is about a stitch width, so its circumference grows by 2π stitches
+const FLAT_CIRCLE_INCREASES: f64 = std::f64::consts::TAU;
+
+/// Finds rounds worked in the round that increase so much faster than a flat circle that the
+/// fabric will ruffle
+fn lint_ruffling(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    // rows aren't circles
+    if config.mode == Mode::Rows {
+        return Vec::new();
+    }
+
+    // the first round, e.g. a foundation chain, isn't worked into anything to increase from
+    let Some(first) = rounds
+        .iter()
+        .position(|r| r.input_count() != 0 || r.output_count() != 0)
+    else {
+        return Vec::new();
+    };
+
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate().skip(first + 1) {
+        if std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        let increases = config
+            .mode
+            .stitch_count(round, false)
+            .saturating_sub(round.input_count());
+
+        if f64::from(increases) > config.ruffle_threshold * FLAT_CIRCLE_INCREASES {
+            let lint = Lint::Ruffling {
+                idx: i + 1,
+                increases,
+                span: round.span,
+            };
+            if !allows(round, &lint) {
+                ret.push(lint);
+            }
+        }
+    }
+
+    ret
+}
+
+/// How many stitches a round can be left with to count as closing the piece
+const CLOSING_STITCHES: u32 = 6;
+
+/// Finds rounds that decrease so much at once that the fabric will pucker, other than the rounds
+/// that close up the end of a piece
+fn lint_puckering(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate() {
+        if i > 0 && std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        let input = round.input_count();
+        let output = config.mode.stitch_count(round, i == 0);
+        let decreases = input.saturating_sub(output);
+
+        // every round from here on is small enough to be closing the piece
+        let closing = rounds[i..]
+            .iter()
+            .all(|r| config.mode.stitch_count(r, false) <= CLOSING_STITCHES);
+
+        if !closing && f64::from(decreases) > config.pucker_threshold * f64::from(input) {
+            let lint = Lint::Puckering {
+                idx: i + 1,
+                input,
+                decreases,
+                span: round.span,
+            };
+            if !allows(round, &lint) {
+                ret.push(lint);
+            }
+        }
+    }
+
+    ret
+}
+
+/// Words in a comment that say how the end of a piece is closed, other than by fastening off
+const CLOSING_WORDS: [&str; 2] = ["close", "sew"];
+
+/// Whether a comment says how the end of a piece is finished, e.g. `% fo %` or `% sew closed %`
+fn finishes(text: &str) -> bool {
+    fastens_off(text)
+        || CLOSING_WORDS
+            .iter()
+            .any(|w| text.to_lowercase().contains(w))
+}
+
+/// Finds pieces started from a magic ring that end with a hole bigger than
+/// [`LintConfig::closing_threshold`], without a comment saying how the end is finished
+fn lint_never_closes(rounds: &[&Round], config: &LintConfig) -> Option<Lint> {
+    let has_stitches = |r: &&&Round| r.input_count() != 0 || r.output_count() != 0;
+    let first = rounds.iter().find(has_stitches)?;
+    let idx = rounds.iter().rposition(|r| has_stitches(&r))?;
+    let last = rounds[idx];
+
+    let mr = |inst: &Instruction| matches!(inst, Instruction::IntoMagicRing(_));
+    if find_instructions(std::slice::from_ref(*first), mr).is_empty() {
+        return None;
+    }
+
+    let stitches = config.mode.stitch_count(last, idx == 0);
+    if stitches <= config.closing_threshold {
+        return None;
+    }
+
+    // comments on or after the last round, e.g. `% fasten off, leaving a tail for sewing %`
+    let finished = rounds[idx..]
+        .iter()
+        .flat_map(|r| &r.instructions)
+        .any(|inst| matches!(inst, Instruction::Comment(text) if finishes(text)));
+
+    let lint = Lint::NeverCloses {
+        idx: idx + 1,
+        stitches,
+        span: last.span,
+    };
+    (!finished && !allows(last, &lint)).then_some(lint)
+}
+
+/// Whether a comment says to fasten off, e.g. `% fasten off, leaving a long tail %` or `% fo %`
+fn fastens_off(text: &str) -> bool {
+    let text = text.to_lowercase();
+    text.contains("fasten off") || text.contains("finish off") || text.trim() == "fo"
+}
+
+/// Finds the first stitches worked after a comment that fastens off, which can't be worked
+/// without rejoining the yarn and usually belong to a part whose header is missing
+fn lint_stitches_after_fasten_off(rounds: &[&Round]) -> Option<Lint> {
+    let is_fasten_off =
+        |inst: &Instruction| matches!(inst, Instruction::Comment(t) if fastens_off(t));
+    let works = |inst: &Instruction| inst.input_count() != 0 || inst.output_count() != 0;
+
+    let (fo_idx, path) = rounds.iter().enumerate().find_map(|(i, r)| {
+        let found = find_instructions(std::slice::from_ref(*r), is_fasten_off);
+        found.into_iter().next().map(|(_, path, _)| (i, path))
+    })?;
+    let fo_round = rounds[fo_idx];
+
+    // the comment of a block of repeated rounds is about its last round
+    let last_repeat = fo_idx
+        + rounds[fo_idx + 1..]
+            .iter()
+            .take_while(|r| std::ptr::eq(**r, fo_round))
+            .count();
+
+    let (idx, inst_idx) = match fo_round.instructions[path[0] + 1..].iter().position(works) {
+        Some(i) => (last_repeat, path[0] + 1 + i),
+        None => rounds
+            .iter()
+            .enumerate()
+            .skip(last_repeat + 1)
+            .find_map(|(i, r)| r.instructions.iter().position(works).map(|p| (i, p)))?,
+    };
+
+    let round = rounds[idx];
+    let lint = Lint::StitchesAfterFastenOff {
+        idx: idx + 1,
+        fasten_off_idx: last_repeat + 1,
+        span: round.span,
+        instruction_span: round.instruction_span(&[inst_idx]),
+    };
+    (!allows(round, &lint)).then_some(lint)
+}
+
+/// Finds a first round that's worked into a magic ring, but also has a foundation chain, which
+/// happens when two ways of starting a piece are mixed up
+fn lint_mixed_foundation(rounds: &[&Round]) -> Option<Lint> {
+    let round = rounds
+        .iter()
+        .position(|r| r.input_count() != 0 || r.output_count() != 0)?;
+    let first = rounds[round];
+
+    let mr = |inst: &Instruction| matches!(inst, Instruction::IntoMagicRing(_));
+    if find_instructions(std::slice::from_ref(first), mr).is_empty() {
+        return None;
+    }
+
+    // a single chain is just the usual joining or turning chain
+    let (idx, chains) = first
+        .instructions
+        .iter()
+        .enumerate()
+        .find_map(|(i, inst)| match inst {
+            Instruction::Repeat(inst, n) if **inst == Instruction::Ch && *n > 1 => Some((i, *n)),
+            _ => None,
+        })?;
+
+    let lint = Lint::MixedFoundation {
+        idx: round + 1,
+        chains,
+        span: first.span,
+        instruction_span: first.instruction_span(&[idx]),
+    };
+    (!allows(first, &lint)).then_some(lint)
+}
+
+/// Finds post stitches in the round worked into the magic ring or foundation chain, since the
+/// stitches they'd be worked around haven't been made yet
+fn lint_post_stitch_in_first_round(rounds: &[&Round]) -> Option<Lint> {
+    let mut stitch_rounds = rounds
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.input_count() != 0 || r.output_count() != 0);
+
+    let mr = |inst: &Instruction| matches!(inst, Instruction::IntoMagicRing(_));
+    let (mut idx, mut round) = stitch_rounds.next()?;
+    // a foundation chain is worked into by the next round
+    if find_instructions(std::slice::from_ref(*round), mr).is_empty() && round.input_count() == 0 {
+        (idx, round) = stitch_rounds.next()?;
+    }
+
+    let (_, path, stitch) =
+        find_instructions(std::slice::from_ref(*round), Instruction::is_post_stitch)
+            .into_iter()
+            .next()?;
+
+    let lint = Lint::PostStitchInFirstRound {
+        idx: idx + 1,
+        stitch: stitch.to_string(),
+        span: round.span,
+        instruction_span: round.instruction_span(&path),
+    };
+    (!allows(round, &lint)).then_some(lint)
+}
+
+/// How many chains a turning chain has to be to reach the height of a row of single crochet
+const TURNING_CHAINS: u32 = 1;
+
+/// How many chains of a foundation chain `row` skips as its turning chain: as many as the chains
+/// it starts with stand in for, or the usual turning chain if it doesn't start with any
+fn turning_chains_of(row: &Round) -> u32 {
+    match (row.instructions.first(), row.leading_chains()) {
+        (Some(Instruction::Tch), _) | (_, 0) => TURNING_CHAINS,
+        (_, chains) => chains,
+    }
+}
+
+/// Finds rows that don't start with a turning chain as tall as their stitches, so their edges
+/// lean and lose stitches, or are left loose
+fn lint_missing_turning_chain(rounds: &[&Round], mode: Mode) -> Vec<Lint> {
+    if mode != Mode::Rows {
+        return Vec::new();
+    }
+
+    let mut ret = Vec::new();
+    let mut rows = rounds
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.input_count() != 0 || r.output_count() != 0);
+
+    let Some((_, first)) = rows.next() else {
+        return ret;
+    };
+    // the last chain of a foundation chain is the turning chain for the row worked into it
+    let mut after_foundation = first.trailing_chains() > 0;
+    // the last row that was checked, since a block of repeated rows is only checked once
+    let mut checked: Option<&Round> = None;
+
+    for (i, row) in rows {
+        if std::mem::take(&mut after_foundation) || checked.is_some_and(|c| std::ptr::eq(c, *row)) {
+            continue;
+        }
+        checked = Some(row);
+
+        let chains = match row.instructions.first() {
+            Some(Instruction::Tch) => TURNING_CHAINS,
+            _ => row.leading_chains(),
+        };
+        if chains == TURNING_CHAINS {
+            continue;
+        }
+
+        let lint = Lint::MissingTurningChain {
+            idx: i + 1,
+            chains,
+            span: row.span,
+        };
+        if !allows(row, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// Finds rounds that make more stitches than the config allows, which are almost certainly typos
+fn lint_absurd_stitch_count(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate() {
+        if i > 0 && std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        let stitches = round.output_count();
+        if stitches <= config.stitch_count_threshold {
+            continue;
+        }
+
+        let lint = Lint::AbsurdStitchCount {
+            idx: i + 1,
+            stitches,
+            span: round.span,
+        };
+        if !allows(round, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// Whether `round` makes or works into more stitches than the config allows, so is too big to be
+/// gone through stitch by stitch. [`lint_absurd_stitch_count`] reports these rounds instead.
+fn is_absurd(round: &Round, config: &LintConfig) -> bool {
+    round.input_count().max(round.output_count()) > config.stitch_count_threshold
+}
+
+/// Finds rounds that start with a decrease right after a round that ended with one, in spiral mode
+/// where there's no join to hide the seam they make
+fn lint_stacked_decreases(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    if config.mode != Mode::Spiral {
+        return Vec::new();
+    }
+
+    let mut ret = Vec::new();
+    let rounds: Vec<(usize, &Round)> = rounds
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.iter_stitches().next().is_some())
+        .map(|(i, r)| (i, *r))
+        .collect();
+    // the last pair of rounds that was checked, since a block of repeated rounds is only checked
+    // once
+    let mut checked: Option<(&Round, &Round)> = None;
+
+    for pair in rounds.windows(2) {
+        let [(h, a), (i, b)] = *pair else {
+            unreachable!()
+        };
+        if checked.is_some_and(|(ca, cb)| std::ptr::eq(ca, a) && std::ptr::eq(cb, b)) {
+            continue;
+        }
+        checked = Some((a, b));
+        if is_absurd(a, config) || is_absurd(b, config) {
+            continue;
+        }
+
+        let ends = a.iter_stitches().last().map(|(_, st)| st) == Some(&Instruction::Dec);
+        let starts = b.iter_stitches().next().map(|(_, st)| st) == Some(&Instruction::Dec);
+        // a round of only decreases, like the last round of a closed shape, can't be offset
+        let only_decreases = b.iter_stitches().all(|(_, st)| *st == Instruction::Dec);
+        if !ends || !starts || only_decreases {
+            continue;
+        }
+
+        let lint = Lint::StackedDecreases {
+            idx: i + 1,
+            previous_idx: h + 1,
+            span: b.span,
+            fix: offset_decreases_fix(b),
+        };
+        if !allows(b, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// Rewrites a round that starts with a decrease to start halfway along the stitches before its
+/// next decrease instead
+fn offset_decreases_fix(round: &Round) -> Option<Suggestion> {
+    use Instruction::*;
+
+    let mut stitches = Vec::new();
+    for (_, st) in round.iter_stitches() {
+        match st {
+            Sc | Fpsc | Bpsc | Blsc | Inc | Flinc | Blinc | Dec => stitches.push(st.clone()),
+            _ => return None,
+        }
+    }
+
+    // the stitches between the first decrease and the next one
+    let between = stitches[1..].iter().take_while(|st| **st != Dec).count();
+    if between < 2 {
+        return None;
+    }
+    stitches.rotate_left(1 + between / 2);
+
+    let is_comment = |inst: &Instruction| matches!(inst, Comment(_));
+    if !find_instructions(std::slice::from_ref(round), is_comment).is_empty() {
+        return None;
+    }
+    let first = round.instruction_span(&[0])?;
+    let last = round.instruction_span(&[round.instructions.len() - 1])?;
+
+    Some(Suggestion::new(
+        Span::new(first.start, last.end),
+        Instruction::Group(canonicalize(&stitches)).to_string(),
+    ))
+}
+
+/// How many levels of brackets `inst` is written with
+fn nesting_depth(inst: &Instruction) -> u32 {
+    use Instruction::*;
+
+    match inst {
+        Group(insts) => 1 + insts.iter().map(nesting_depth).max().unwrap_or(0),
+        Repeat(i, _) | IntoMagicRing(i) | IntoOne(i) => nesting_depth(i),
+        Crossed(a, b) => nesting_depth(a).max(nesting_depth(b)),
+        _ => 0,
+    }
+}
+
+/// Finds rounds with brackets nested more deeply than the config allows
+fn lint_excessive_nesting(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate() {
+        if i > 0 && std::ptr::eq(rounds[i - 1], *round) {
+            continue;
+        }
+
+        let Some((deepest, depth)) = round
+            .instructions
+            .iter()
+            .map(nesting_depth)
+            .enumerate()
+            .max_by_key(|(_, depth)| *depth)
+        else {
+            continue;
+        };
+        if depth <= config.nesting_threshold {
+            continue;
+        }
+
+        let lint = Lint::ExcessiveNesting {
+            idx: i + 1,
+            depth,
+            span: round.span,
+            instruction_span: round.instruction_span(&[deepest]),
+        };
+        if !allows(round, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// Finds rounds of plain stitches whose increases or decreases are all next to each other, like
+/// `inc 6, sc 18`, instead of being spread around the round
+fn lint_bunched_increases(rounds: &[&Round], config: &LintConfig) -> Vec<Lint> {
+    use Instruction::*;
+
+    let mut ret = Vec::new();
+
+    for (i, round) in rounds.iter().enumerate() {
+        if i > 0 && std::ptr::eq(rounds[i - 1], *round) || is_absurd(round, config) {
+            continue;
+        }
+
+        // whether each stitch is an increase or decrease, for rounds of only plain stitches
+        let changes: Option<Vec<bool>> = round
+            .iter_stitches()
+            .map(|(_, st)| match st {
+                Sc | Fpsc | Bpsc | Blsc => Some(false),
+                Inc | Flinc | Blinc | Dec => Some(true),
+                _ => None,
+            })
+            .collect();
+        let Some(changes) = changes else {
+            continue;
+        };
+
+        let count = changes.iter().filter(|&&c| c).count();
+        let runs = changes.windows(2).filter(|w| !w[0] && w[1]).count()
+            + usize::from(changes.first() == Some(&true));
+
+        // with fewer plain stitches than changes, they can't be spread out with a stitch between
+        if count < 2 || runs != 1 || changes.len() - count < count {
+            continue;
+        }
+
+        // spreading out a mix of shaping stitches, e.g. increases and decreases that cancel out,
+        // would turn them into a single kind
+        let mut shaping = round
+            .iter_stitches()
+            .map(|(_, st)| st)
+            .filter(|st| matches!(st, Inc | Flinc | Blinc | Dec));
+        let first = shaping.next();
+        if shaping.any(|st| Some(st) != first) {
+            continue;
+        }
+
+        let (input, output) = (round.input_count(), round.output_count());
+        let Some(spread) = crate::scale::respread(round, input, output) else {
+            continue;
+        };
+
+        let suggestion = Instruction::Group(spread).to_string();
+
+        let is_comment = |inst: &Instruction| matches!(inst, Comment(_));
+        let has_comments = !find_instructions(std::slice::from_ref(*round), is_comment).is_empty();
+        let insts = (
+            round.instruction_span(&[0]),
+            round.instruction_span(&[round.instructions.len() - 1]),
+        );
+        let fix = match insts {
+            (Some(first), Some(last)) if !has_comments => Some(Suggestion::new(
+                Span::new(first.start, last.end),
+                suggestion.clone(),
+            )),
+            _ => None,
+        };
+
+        let lint = Lint::BunchedIncreases {
+            idx: i + 1,
+            decreasing: output < input,
+            suggestion,
+            span: round.span,
+            fix,
+        };
+        if !allows(round, &lint) {
+            ret.push(lint);
+        }
+    }
+
+    ret
+}
+
+/// Finds runs of rounds that are worked the same, written as more than one round, which could be
+/// written as a single `rounds A-B:` range instead
+fn lint_repeated_rounds(rounds: &[&Round]) -> Vec<Lint> {
+    let mut ret = Vec::new();
+    let canonical: Vec<_> = rounds
+        .iter()
+        .map(|r| canonicalize(&r.instructions))
+        .collect();
+
+    let mut start = 0;
+    while start < rounds.len() {
+        // a round with a label or directives has to stay a round of its own, but can start a run
+        let mut end = start + 1;
+        while end < rounds.len()
+            && canonical[end] == canonical[start]
+            && (std::ptr::eq(rounds[end], rounds[end - 1])
+                || (rounds[end].label.is_none() && rounds[end].directives.is_empty()))
+        {
+            end += 1;
+        }
+
+        let run = &rounds[start..end];
+        let has_stitches = run[0].input_count() != 0 || run[0].output_count() != 0;
+        let written = 1 + run.windows(2).filter(|w| !std::ptr::eq(w[0], w[1])).count();
+
+        if has_stitches && written > 1 && run[0].label.is_none() {
+            let (first_idx, last_idx) = (start + 1, end);
+            let insts = Instruction::Group(canonical[start].clone());
+            let last = run[run.len() - 1];
+
+            let suggestion = format!("rounds {first_idx}-{last_idx}: {insts}");
+            let span = Span::new(run[0].span.start, last.span.end);
+
+            let lint = Lint::RepeatedRounds {
+                first_idx,
+                last_idx,
+                // rounds that weren't parsed from a source don't have spans to replace
+                fix: (span.start.0 != 0).then(|| Suggestion::new(span, suggestion.clone())),
+                suggestion,
+                span,
+            };
+            if !run.iter().any(|r| allows(r, &lint)) {
+                ret.push(lint);
+            }
+        }
+
+        start = end;
+    }
+
+    ret
+}
+
+/// Checks that the rounds' stitch counts fit together.
+///
+/// A lint can be turned off for a round that's meant to be that way with an `% allow(...) %`
+/// comment in the round, naming the lint by its [`Lint::name`] or [`Lint::code`]. Lints about two
+/// rounds are allowed on the second one.
+///
+/// ```
+/// # use crochet::{lint_rounds, parse_rounds};
+/// let gathered = parse_rounds("sc 6 in mr\ninc 6\n[sc 2] 3").unwrap();
+/// assert_eq!(lint_rounds(&gathered)[0].code(), "C001");
+///
+/// let gathered =
+///     parse_rounds("sc 6 in mr\ninc 6\n[sc 2] 3, % allow(mismatched_stitch_count) %").unwrap();
+/// assert!(lint_rounds(&gathered).is_empty());
+/// ```
+pub fn lint_rounds(rounds: &[Round]) -> Vec<Lint> {
+    lint_rounds_in_mode(rounds, Mode::Spiral)
+}
+
+/// Like [`lint_rounds`], but for rounds worked in the given [`Mode`].
+///
+/// ```
+/// # use crochet::{lint_rounds, lint_rounds_in_mode, parse_rounds, Mode};
+/// let rounds = parse_rounds("sc 6 in mr\nch 1, inc 6\nch 1, sc 12, % fasten off %").unwrap();
+///
+/// // the `ch 1` that starts each joined round isn't a stitch
+/// assert!(lint_rounds_in_mode(&rounds, Mode::Joined).is_empty());
+/// assert_eq!(lint_rounds(&rounds).len(), 1);
+/// ```
+pub fn lint_rounds_in_mode(rounds: &[Round], mode: Mode) -> Vec<Lint> {
+    run_lints(rounds, &LintConfig::new().mode(mode))
+}
+
+/// Every lint of `rounds`, including ones `config` disables
+fn run_lints(rounds: &[Round], config: &LintConfig) -> Vec<Lint> {
+    let mode = config.mode;
+    // lint blocks of repeated rounds as if each round was written out separately
+    let rounds: Vec<_> = expand_rounds(rounds).collect();
+
+    let mut lints = lint_mismatched_stitch_count(&rounds, mode);
+    lints.extend(lint_ran_out_of_stitches(&rounds, mode));
+
+    if let Some(l) = lint_nonzero_first_round_input(&rounds) {
+        lints.push(l);
+    }
+
+    lints.extend(lint_redundant_repeat(&rounds));
+    lints.extend(lint_empty_round(&rounds, config));
+    lints.extend(lint_magic_ring_after_first_round(&rounds));
+    lints.extend(lint_ruffling(&rounds, config));
+    lints.extend(lint_puckering(&rounds, config));
+    lints.extend(lint_never_closes(&rounds, config));
+    lints.extend(lint_mixed_foundation(&rounds));
+    lints.extend(lint_bunched_increases(&rounds, config));
+    lints.extend(lint_repeated_rounds(&rounds));
+    lints.extend(lint_post_stitch_in_first_round(&rounds));
+    lints.extend(lint_missing_turning_chain(&rounds, mode));
+    lints.extend(lint_excessive_nesting(&rounds, config));
+    lints.extend(lint_absurd_stitch_count(&rounds, config));
+    lints.extend(lint_stacked_decreases(&rounds, config));
+    lints.extend(lint_stitches_after_fasten_off(&rounds));
+
+    lints
+}
+
+/// Which lints to report and how seriously, for [`lint_rounds_with_config`].
+///
+/// ```
+/// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig, Severity, UnknownLint};
+/// let rounds = parse_rounds("sc 3\nch 6\nsc 6\nsc 4").unwrap();
+/// let config = LintConfig::new()
+///     .severity("mismatched_stitch_count", Severity::Warning)?
+///     .disable("nonzero_first_round_input")?;
+///
+/// let report = lint_rounds_with_config(&rounds, &config);
+/// assert_eq!(report.lints().len(), 2);
+/// assert_eq!(report.count(Severity::Warning), 2);
+/// # Ok::<(), UnknownLint>(())
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct LintConfig {
+    mode: Mode,
+    /// The lints whose severity was changed from their default, or `None` if they're disabled
+    overrides: Vec<(&'static str, Option<Severity>)>,
+    /// How many times the increases of a flat circle a round can have before it ruffles
+    ruffle_threshold: f64,
+    /// How much of the stitches a round can take away before it puckers
+    pucker_threshold: f64,
+    /// How many stitches a piece can end with and still count as closed
+    closing_threshold: u32,
+    /// Whether to report rounds that are only comments
+    empty_rounds: bool,
+    /// How many levels of brackets a round can be nested
+    nesting_threshold: u32,
+    /// How many stitches a round can make before it's too many to be real
+    stitch_count_threshold: u32,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            mode: Mode::default(),
+            overrides: Vec::new(),
+            ruffle_threshold: 1.6,
+            pucker_threshold: 0.5,
+            closing_threshold: 8,
+            empty_rounds: false,
+            nesting_threshold: 8,
+            stitch_count_threshold: 100_000,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Reports every lint with its default severity, for rounds worked in a spiral
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports rounds that increase by more than `ratio` times as many stitches as a flat circle
+    /// does (about 6 per round) as `ruffling`. This is 1.6 by default, which allows some room for
+    /// differences in tension and yarn.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig};
+    /// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[inc, sc] 6\n% sew closed %").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &LintConfig::new()).is_clean());
+    ///
+    /// let strict = LintConfig::new().ruffle_threshold(0.9);
+    /// assert_eq!(lint_rounds_with_config(&rounds, &strict).lints()[0].0.name(), "ruffling");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `ratio` isn't a positive number.
+    pub fn ruffle_threshold(mut self, ratio: f64) -> Self {
+        assert!(
+            ratio.is_finite() && ratio > 0.0,
+            "{ratio} isn't a valid threshold"
+        );
+        self.ruffle_threshold = ratio;
+        self
+    }
+
+    /// Reports rounds that take away more than `fraction` of the stitches they're worked into as
+    /// `puckering`, e.g. `0.5` (the default) for more than half of them. The rounds at the end of
+    /// a piece that close it up, leaving 6 stitches or fewer, are never reported.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig};
+    /// let rounds = parse_rounds("ch 24\nsc 24\n[dec, sc] 8\nsc 16").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &LintConfig::new()).is_clean());
+    ///
+    /// let strict = LintConfig::new().pucker_threshold(0.25);
+    /// assert_eq!(lint_rounds_with_config(&rounds, &strict).lints()[0].0.name(), "puckering");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `fraction` isn't more than 0 and at most 1.
+    pub fn pucker_threshold(mut self, fraction: f64) -> Self {
+        assert!(
+            fraction > 0.0 && fraction <= 1.0,
+            "{fraction} isn't a valid threshold"
+        );
+        self.pucker_threshold = fraction;
+        self
+    }
+
+    /// Reports pieces started from a magic ring that end with more than `stitches` stitches
+    /// (8 by default) as `never_closes`, unless a comment on or after their last round says how
+    /// the end is finished, e.g. `% fasten off, leaving a long tail %` or `% sew closed %`.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig};
+    /// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[dec, sc] 4").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &LintConfig::new()).is_clean());
+    ///
+    /// let strict = LintConfig::new().closing_threshold(6);
+    /// assert_eq!(lint_rounds_with_config(&rounds, &strict).lints()[0].0.name(), "never_closes");
+    ///
+    /// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[dec, sc] 4, % fo %").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &strict).is_clean());
+    /// ```
+    pub fn closing_threshold(mut self, stitches: u32) -> Self {
+        self.closing_threshold = stitches;
+        self
+    }
+
+    /// Reports rounds that are only comments, in between rounds with stitches, as `empty_round`.
+    /// Such a round still gets a number, so the rounds after it are numbered one higher than
+    /// they're worked. Plenty of patterns put notes on a line of their own on purpose, though, so
+    /// this isn't checked unless it's turned on.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig};
+    /// let rounds = parse_rounds("sc 6 in mr\n% now increase %\ninc 6, % fasten off %").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &LintConfig::new()).is_clean());
+    ///
+    /// let strict = LintConfig::new().report_empty_rounds();
+    /// assert_eq!(lint_rounds_with_config(&rounds, &strict).lints()[0].0.name(), "empty_round");
+    /// ```
+    pub fn report_empty_rounds(mut self) -> Self {
+        self.empty_rounds = true;
+        self
+    }
+
+    /// Reports rounds with brackets nested more than `depth` levels deep (8 by default) as
+    /// `excessive_nesting`.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig};
+    /// let rounds = parse_rounds("ch 22\n[[sc, [inc, sc] 2] 2, sc] 2").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &LintConfig::new()).is_clean());
+    ///
+    /// let strict = LintConfig::new().nesting_threshold(2);
+    /// assert_eq!(lint_rounds_with_config(&rounds, &strict).lints()[0].0.name(), "excessive_nesting");
+    /// ```
+    pub fn nesting_threshold(mut self, depth: u32) -> Self {
+        self.nesting_threshold = depth;
+        self
+    }
+
+    /// Reports rounds that make more than `stitches` stitches (100,000 by default) as
+    /// `absurd_stitch_count`, e.g. because of a typo like `sc 6000` for `sc 600`.
+    ///
+    /// ```
+    /// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig};
+    /// let rounds = parse_rounds("ch 600\nsc 600").unwrap();
+    /// assert!(lint_rounds_with_config(&rounds, &LintConfig::new()).is_clean());
+    ///
+    /// let strict = LintConfig::new().stitch_count_threshold(500);
+    /// let report = lint_rounds_with_config(&rounds, &strict);
+    /// assert_eq!(report.lints()[0].0.name(), "absurd_stitch_count");
+    /// ```
+    pub fn stitch_count_threshold(mut self, stitches: u32) -> Self {
+        self.stitch_count_threshold = stitches;
+        self
+    }
+
+    /// Lints rounds worked in the given [`Mode`], like [`lint_rounds_in_mode`]
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Reports the lint with the given name with `severity` instead of its default, or fails if
+    /// `lint` isn't one of [`Lint::NAMES`]
+    pub fn severity(self, lint: &str, severity: Severity) -> Result<Self, UnknownLint> {
+        self.set(lint, Some(severity))
+    }
+
+    /// Stops reporting the lint with the given name, or fails if `lint` isn't one of
+    /// [`Lint::NAMES`]
+    pub fn disable(self, lint: &str) -> Result<Self, UnknownLint> {
+        self.set(lint, None)
+    }
+
+    fn set(mut self, lint: &str, severity: Option<Severity>) -> Result<Self, UnknownLint> {
+        let name = Lint::NAMES
+            .iter()
+            .copied()
+            .find(|n| *n == lint)
+            .ok_or_else(|| UnknownLint(lint.to_string()))?;
+
+        self.overrides.retain(|(n, _)| *n != name);
+        self.overrides.push((name, severity));
+        Ok(self)
+    }
+
+    /// How seriously `lint` should be reported, or `None` if it's disabled
+    pub fn severity_of(&self, lint: &Lint) -> Option<Severity> {
+        match self.overrides.iter().find(|(n, _)| *n == lint.name()) {
+            Some((_, severity)) => *severity,
+            None => Some(lint.default_severity()),
+        }
+    }
+}
+
+/// A lint name given to [`LintConfig`] that isn't one of [`Lint::NAMES`].
+///
+/// ```
+/// # use crochet::{LintConfig, UnknownLint};
+/// let err = LintConfig::new().disable("mismatched_count").unwrap_err();
+/// assert_eq!(err, UnknownLint("mismatched_count".to_string()));
+/// assert_eq!(err.to_string(), "`mismatched_count` isn't a lint");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnknownLint(pub String);
+
+impl std::fmt::Display for UnknownLint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` isn't a lint", self.0)
+    }
+}
+
+impl std::error::Error for UnknownLint {}
+
+/// The lints found by [`lint_rounds_with_config`], each along with how serious it is.
+///
+/// ```
+/// # use crochet::{lint_rounds_with_config, parse_rounds, LintConfig, Severity};
+/// let rounds = parse_rounds("sc 6 in mr\ninc 5\n[inc, sc 1] 6\n% sew closed %").unwrap();
+/// let report = lint_rounds_with_config(&rounds, &LintConfig::new());
+///
+/// assert!(!report.is_clean());
+/// assert_eq!(report.count(Severity::Error), 1);
+/// assert_eq!(report.count(Severity::Info), 1);
+///
+/// let by_round = report.by_round();
+/// assert_eq!(by_round[&Some(2)][0].0.name(), "mismatched_stitch_count");
+/// assert_eq!(by_round[&Some(3)][0].0.name(), "redundant_repeat");
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LintReport {
+    /// Every lint, in the order they were found
+    lints: Vec<(Lint, Severity)>,
+}
+
+impl LintReport {
+    /// Every lint, in the order they were found
+    pub fn lints(&self) -> &[(Lint, Severity)] {
+        &self.lints
+    }
+
+    /// Every lint, in the order they were found, taken out of the report
+    pub fn into_lints(self) -> Vec<(Lint, Severity)> {
+        self.lints
+    }
+
+    /// The lints grouped by the one-based index of the round they're about (see
+    /// [`Lint::round`]), in order. Lints that aren't about a single numbered round are under
+    /// `None`.
+    pub fn by_round(&self) -> BTreeMap<Option<usize>, Vec<&(Lint, Severity)>> {
+        let mut ret: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for lint in &self.lints {
+            ret.entry(lint.0.round()).or_default().push(lint);
+        }
+        ret
+    }
+
+    /// How many lints were reported with `severity`
+    pub fn count(&self, severity: Severity) -> usize {
+        self.lints.iter().filter(|(_, s)| *s == severity).count()
+    }
+
+    /// Whether nothing was reported at all, not even an [`Severity::Info`] lint
+    pub fn is_clean(&self) -> bool {
+        self.lints.is_empty()
+    }
+}
+
+/// Like [`lint_rounds`], but with the lints chosen by `config`, each along with how serious it
+/// is.
+pub fn lint_rounds_with_config(rounds: &[Round], config: &LintConfig) -> LintReport {
+    let lints = run_lints(rounds, config)
+        .into_iter()
+        .filter_map(|lint| Some((config.severity_of(&lint)?, lint)))
+        .map(|(severity, lint)| (lint, severity))
+        .collect();
+
+    LintReport { lints }
+}
+
+/// Lints each part of a pattern separately. Returns one list of lints per part, in the same
+/// order as `pattern.parts`.
+pub fn lint_pattern(pattern: &Pattern) -> Vec<Vec<Lint>> {
+    pattern
+        .parts
+        .iter()
+        .map(|p| lint_rounds_in_mode(&p.rounds, pattern.mode()))
+        .collect()
+}
+
+/// Checks the parts of a pattern against each other and its header, for the problems that
+/// [`lint_pattern`] can't find by looking at each part on its own: parts with the same name,
+/// quoted part names in the assembly, like `attach "Arm" to the side`, that no part has, colors in
+/// the palette that no stitches are made in, and color changes like `@A` to the color that's
+/// already active.
+///
+/// ```
+/// # use crochet::{lint_parts, parse_pattern};
+/// let src = "== Body ==\nsc 6 in mr\n== Assembly ==\nattach \"arm\" to \"body\"";
+/// let lints = lint_parts(&parse_pattern(src).unwrap());
+///
+/// assert_eq!(lints.len(), 1);
+/// assert_eq!(
+///     lints[0].to_string(),
+///     "the assembly refers to a part called \"arm\", but there isn't one"
+/// );
+/// ```
+pub fn lint_parts(pattern: &Pattern) -> Vec<Lint> {
+    let mut ret = Vec::new();
+    let names: Vec<_> = pattern.parts.iter().filter_map(|p| p.name).collect();
+
+    for (i, part) in pattern.parts.iter().enumerate() {
+        let Some(name) = part.name else {
+            continue;
+        };
+        // only the first duplicate is reported, if there's more than one
+        let earlier = pattern.parts[..i].iter().filter(|p| p.name == Some(name));
+        if earlier.count() == 1 {
+            ret.push(Lint::DuplicatePart {
+                name: name.to_string(),
+                span: part.rounds.first().map_or(Span::default(), |r| r.span),
+            });
+        }
+    }
+
+    ret.extend(lint_colors(pattern));
+
+    for step in &pattern.assembly {
+        // part names can have quotes in them, so the names of the parts there are get blanked out
+        // first, to keep their quotes from being taken as the start or end of a quoted name
+        let text = step.text.to_ascii_lowercase();
+        let mut masked = text.clone().into_bytes();
+        for name in &names {
+            let name = name.to_ascii_lowercase();
+            for (i, _) in text.match_indices(&name) {
+                masked[i..i + name.len()].fill(b' ');
+            }
+        }
+
+        // the text between each pair of quotes, along with how far into the step it starts
+        let mut quoted = (0..masked.len()).filter(|&i| masked[i] == b'"');
+        while let (Some(open), Some(close)) = (quoted.next(), quoted.next()) {
+            let name = &step.text[open + 1..close];
+            if name.is_empty() || names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                continue;
+            }
+
+            let (line, col) = step.span.start;
+            ret.push(Lint::UnknownPart {
+                name: name.to_string(),
+                span: Span::new((line, col + open), (line, col + close + 1)),
+            });
+        }
+    }
+
+    ret
+}
+
+/// Finds colors in the pattern's palette that are never used, and color changes to the color
+/// that's already active. Each part starts out in the first color of the palette.
+fn lint_colors(pattern: &Pattern) -> Vec<Lint> {
+    let mut ret = Vec::new();
+    let palette = pattern.meta.palette();
+
+    for part in &pattern.parts {
+        let mut active = palette.first().map(|&(key, _)| key);
+        // the one-based index of the first round of each block of repeated rounds
+        let starts: Vec<_> = part
+            .rounds
+            .iter()
+            .scan(1, |idx, r| {
+                let start = *idx;
+                *idx += r.times as usize;
+                Some(start)
+            })
+            .collect();
+
+        // a change inside a repeat only counts the first time it's worked
+        let changes = find_instructions(&part.rounds, |i| matches!(i, Instruction::Color(_)));
+        for (r, path, inst) in changes {
+            let Instruction::Color(key) = *inst else {
+                unreachable!()
+            };
+            if active.replace(key) != Some(key) {
+                continue;
+            }
+
+            let round = &part.rounds[r];
+            let lint = Lint::RedundantColorChange {
+                idx: starts[r],
+                key: key.to_string(),
+                span: round.span,
+                instruction_span: round.instruction_span(&path),
+            };
+            if !allows(round, &lint) {
+                ret.push(lint);
+            }
+        }
+    }
+
+    let used: Vec<_> = pattern
+        .round_colors()
+        .into_iter()
+        .flatten()
+        .flat_map(|counts| counts.into_iter().filter(|&(_, n)| n != 0))
+        .map(|(key, _)| key)
+        .collect();
+    for (key, _) in palette {
+        if !used.contains(&key) {
+            ret.push(Lint::UnusedColor {
+                key: key.to_string(),
+                span: Span::default(),
+            });
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn assert_produces_lint(src: &str, lint: &Lint) {
+        let rounds = parse_rounds(src).unwrap();
+        let lints = lint_rounds(&rounds);
+
+        dbg!(&lints, &lint);
+
+        assert!(lints.contains(lint));
+    }
+
+    #[test]
+    fn test_lint_nonzero_first_round_input() {
+        assert_produces_lint(
+            "sc 3",
+            &Lint::NonzeroFirstRoundInput {
+                actual_consumed: 3,
+                span: Span::new((1, 1), (1, 5)),
+                instruction_span: Some(Span::new((1, 1), (1, 5))),
+            },
+        );
+
+        let lints = lint_rounds(&parse_rounds("sc 3").unwrap());
+        assert_eq!(lints[0].round(), Some(1));
+    }
+
+    #[test]
+    fn test_lint_mismatched_stitch_counts() {
+        assert_produces_lint(
+            "sc 3\n[inc, sc] 2",
+            &Lint::MismatchedStitchCount {
+                a_out: 3,
+                b_in: 4,
+                a_idx: 1,
+                b_idx: 2,
+                span: Span::new((2, 1), (2, 12)),
+                fix: None,
+                notes: Vec::new(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_lint_foundation_chain() {
+        // the chain closest to the hook is the turning chain
+        no_lints("ch 12\nsc 9, dec\nsc 10");
+        no_lints("% start %\nch 12\nsc 11");
+        no_lints("ch 12\nsc 12");
+        let pattern = crate::parse_pattern("mode: joined\nch 12\nsc 11\nch 1, sc 11").unwrap();
+        assert_eq!(lint_pattern(&pattern), [[]]);
+
+        let codes = |src| {
+            let lints = lint_rounds(&parse_rounds(src).unwrap());
+            lints.iter().map(Lint::code).collect::<Vec<_>>()
+        };
+        // rows that don't fit the chain are reported as foundation chain mismatches
+        assert_eq!(codes("ch 12\nsc 10"), ["C020"]);
+        assert_eq!(codes("ch 12\nsc 12\nsc 11"), ["C001"]);
+
+        // a row starting with its own chains skips that many
+        no_lints("ch 12\nch 2, sc 10");
+        no_lints("ch 12\ntch, sc 11\nsc 11");
+    }
+
+    #[test]
+    fn test_lint_foundation_chain_mismatch() {
+        let rounds = parse_rounds("ch 12\nsc 10").unwrap();
+        let lints = lint_rounds(&rounds);
+        assert_eq!(
+            lints,
+            [Lint::FoundationChainMismatch {
+                idx: 2,
+                chains: 12,
+                turning: 1,
+                consumed: 10,
+                span: Span::new((2, 1), (2, 6)),
+                fix: Some(Suggestion::new(Span::new((2, 1), (2, 6)), "sc 11".into())),
+                notes: Vec::new(),
+            }]
+        );
+        assert_eq!(
+            lints[0].to_string(),
+            "row 2 consumes 10 stitches of a foundation chain of 12, which has 11 left after \
+                skipping 1 as the turning chain; it's 1 stitch short"
+        );
+
+        let lints = lint_rounds(&parse_rounds("% start %\nch 9\n[sc, inc] 5").unwrap());
+        let [Lint::FoundationChainMismatch { idx: 3, fix, .. }] = &lints[..] else {
+            panic!("{lints:?}");
+        };
+        assert_eq!(
+            fix,
+            &Some(Suggestion::new(
+                Span::new((3, 1), (3, 12)),
+                "[sc, inc] 4".into()
+            ))
+        );
+        assert!(lints[0].to_string().ends_with("it's 2 stitches over"));
+
+        let lints = lint_rounds(&parse_rounds("ch 12\nch 3, sc 8\nsc 8").unwrap());
+        assert_eq!(
+            lints[0].to_string(),
+            "row 2 consumes 8 stitches of a foundation chain of 12, which has 9 left after \
+                skipping 3 as the turning chain; it's 1 stitch short"
+        );
+
+        // only the first row is worked into the foundation chain
+        let lints = lint_rounds(&parse_rounds("ch 12\nsc 11\nsc 10").unwrap());
+        assert_eq!(lints.iter().map(Lint::code).collect::<Vec<_>>(), ["C001"]);
+    }
+
+    #[test]
+    fn test_lint_repeated_rounds() {
+        assert_produces_lint(
+            "sc 6 in mr\nx3: inc 6\nsc 24",
+            &Lint::MismatchedStitchCount {
+                a_out: 12,
+                b_in: 6,
+                a_idx: 2,
+                b_idx: 3,
+                span: Span::new((2, 1), (2, 10)),
+                fix: None,
+                // every repeat of the block mismatches, and so does the round after it
+                notes: vec![
+                    Note {
+                        message: "round 3 produces 12 stitches but round 4 consumes 6 stitches"
+                            .into(),
+                        span: Span::new((2, 1), (2, 10)),
+                    },
+                    Note {
+                        message: "round 4 produces 12 stitches but round 5 consumes 24 stitches"
+                            .into(),
+                        span: Span::new((3, 1), (3, 6)),
+                    },
+                ],
+            },
+        );
+    }
+
+    #[test]
+    fn test_lint_cascading_mismatches() {
+        let lints =
+            lint_rounds(&parse_rounds("sc 6 in mr\ninc 7\n[inc, sc] 6\n% fasten off %").unwrap());
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].round(), Some(2));
+        assert_eq!(lints[0].notes().len(), 1);
+
+        // mismatches that aren't next to each other are separate problems
+        let lints = lint_rounds(&parse_rounds("ch 6\nsc 7\nsc 6\n[inc, sc 2] 2\nsc 7").unwrap());
+        let rounds: Vec<_> = lints.iter().map(|l| (l.round(), l.notes().len())).collect();
+        assert_eq!(rounds, [(Some(2), 1), (Some(5), 0)]);
+
+        // comment rounds in between don't break up a cascade
+        let lints = lint_rounds(&parse_rounds("ch 6\nsc 7\n% note %\nsc 6").unwrap());
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].notes()[0].message,
+            "round 2 produces 7 stitches but round 4 consumes 6 stitches"
+        );
+    }
+
+    #[test]
+    fn test_lint_joined_rounds() {
+        let pattern = crate::parse_pattern("mode: joined\nch 4\nch 1, sc 4\nch 2, sc 3").unwrap();
+        assert_eq!(
+            lint_pattern(&pattern),
+            [[Lint::MismatchedStitchCount {
+                a_out: 4,
+                b_in: 3,
+                a_idx: 2,
+                b_idx: 3,
+                span: Span::new((4, 1), (4, 11)),
+                fix: Some(Suggestion::new(Span::new((4, 7), (4, 11)), "sc 4".into())),
+                notes: Vec::new(),
+            }]]
+        );
+    }
+
+    #[test]
+    fn test_lint_rows() {
+        let pattern =
+            crate::parse_pattern("mode: rows\nch 11\nsc 10\nch 1, sc 10\nch 1, sc 9").unwrap();
+        assert_eq!(
+            lint_pattern(&pattern),
+            [[Lint::MismatchedStitchCount {
+                a_out: 10,
+                b_in: 9,
+                a_idx: 3,
+                b_idx: 4,
+                span: Span::new((5, 1), (5, 11)),
+                fix: Some(Suggestion::new(Span::new((5, 7), (5, 11)), "sc 10".into())),
+                notes: Vec::new(),
+            }]]
+        );
+    }
+
+    #[test]
+    fn test_lint_display() {
+        let s = format!(
+            "{}",
+            Lint::MismatchedStitchCount {
+                a_out: 1,
+                b_in: 3,
+                a_idx: 1,
+                b_idx: 2,
+                span: Span::default(),
+                fix: None,
+                notes: Vec::new(),
+            }
+        );
+        assert_eq!(
+            &s,
+            "round 1 produces 1 stitch but round 2 consumes 3 stitches"
+        );
+
+        let s = format!(
+            "{}",
+            Lint::NonzeroFirstRoundInput {
+                actual_consumed: 4,
+                span: Span::default(),
+                instruction_span: None,
+            }
+        );
+        assert_eq!(
+            &s,
+            "round 1 consumes 4 stitches but the first round shouldn't consume any stitches"
+        );
+    }
+
+    #[test]
+    fn test_lint_pattern_per_part() {
+        let pattern =
+            crate::parse_pattern("== A ==\nch 3\nsc 3\n== B ==\nsc 6 in mr\nsc 4").unwrap();
+        let lints = lint_pattern(&pattern);
+
+        assert!(lints[0].is_empty());
+        assert_eq!(
+            lints[1],
+            vec![Lint::MismatchedStitchCount {
+                a_out: 6,
+                a_idx: 1,
+                b_in: 4,
+                b_idx: 2,
+                span: Span::new((6, 1), (6, 5)),
+                fix: Some(Suggestion::new(Span::new((6, 1), (6, 5)), "sc 6".into())),
+                notes: Vec::new(),
+            }]
+        );
+    }
+
+    fn no_lints(src: &str) {
+        let rounds = parse_rounds(src).unwrap();
+        let lints = lint_rounds(&rounds);
+        assert!(lints.is_empty());
+    }
+
+    #[test]
     fn test_no_false_positives_in_lints() {
         no_lints(
             "
@@ -200,6 +2536,7 @@ mod tests {
             sc 6 in mr
             [sc, inc, sc]2
             [inc, sc] 4
+            % fasten off %
             ",
         );
 
@@ -211,6 +2548,14 @@ mod tests {
             ",
         );
 
+        no_lints(
+            "
+            sc 6 in mr
+            rounds 2-5: sc 6
+            x2: fpsc 6
+            ",
+        );
+
         no_lints(
             "
             sc 10 in mr
@@ -218,4 +2563,709 @@ mod tests {
             "
             );
     }
+
+    #[test]
+    fn test_lint_config() {
+        let rounds = parse_rounds("sc 3\nch 6\nch 1, sc 6").unwrap();
+        let severities = |config: &LintConfig| -> Vec<_> {
+            lint_rounds_with_config(&rounds, config)
+                .into_lints()
+                .into_iter()
+                .map(|(lint, severity)| (lint.name(), severity))
+                .collect()
+        };
+
+        assert_eq!(
+            severities(&LintConfig::new()),
+            [
+                ("mismatched_stitch_count", Severity::Error),
+                ("nonzero_first_round_input", Severity::Error),
+            ]
+        );
+
+        let config = LintConfig::new()
+            .mode(Mode::Joined)
+            .disable("mismatched_stitch_count")
+            .and_then(|c| c.severity("nonzero_first_round_input", Severity::Warning))
+            .unwrap();
+        assert_eq!(
+            severities(&config),
+            [("nonzero_first_round_input", Severity::Warning)]
+        );
+
+        let config = LintConfig::new()
+            .severity("mismatched_stitch_count", Severity::Info)
+            .unwrap();
+        assert_eq!(
+            severities(&config),
+            [
+                ("mismatched_stitch_count", Severity::Info),
+                ("nonzero_first_round_input", Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_config_mode() {
+        let rounds = parse_rounds("sc 6 in mr\nch 1, inc 6\nch 1, sc 12, % fasten off %").unwrap();
+        assert_eq!(
+            lint_rounds_with_config(&rounds, &LintConfig::new())
+                .lints()
+                .len(),
+            1
+        );
+
+        let joined = LintConfig::new().mode(Mode::Joined);
+        assert!(lint_rounds_with_config(&rounds, &joined).is_clean());
+    }
+
+    #[test]
+    fn test_unknown_lint() {
+        assert_eq!(
+            LintConfig::new().disable("mismatched_count"),
+            Err(UnknownLint("mismatched_count".to_string()))
+        );
+        assert!(LintConfig::new()
+            .severity("C001", Severity::Warning)
+            .is_err());
+    }
+
+    #[test]
+    fn test_allow() {
+        let allowed = |src| lint_rounds(&parse_rounds(src).unwrap()).is_empty();
+
+        assert!(allowed("sc 3, % allow(nonzero_first_round_input) %"));
+        assert!(allowed("sc 3, % allow(C002) %"));
+        assert!(allowed("sc 3, % allow( C001 , C002 ) %"));
+        assert!(!allowed("sc 3, % allow(C001) %"));
+        assert!(!allowed("sc 3, % allow C002 %"));
+        assert!(!allowed("sc 3, [% allow(C002) %] 1"));
+
+        // only on the round that doesn't fit
+        assert!(allowed("sc 6 in mr\nsc 5, % allow(C001) %"));
+        assert!(!allowed("sc 6 in mr, % allow(C001) %\nsc 5"));
+        assert!(!allowed("sc 6 in mr\nsc 5, % allow(C001) %\nsc 4"));
+        assert!(allowed("sc 6 in mr\nx2: sc 5, % allow(C001) %"));
+    }
+
+    #[test]
+    fn test_lint_ran_out_of_stitches() {
+        assert_produces_lint(
+            "sc 6 in mr\nsc 3, skip 5, sc",
+            &Lint::RanOutOfStitches {
+                available: 6,
+                prev_idx: 1,
+                idx: 2,
+                offset: 7,
+                span: Span::new((2, 1), (2, 17)),
+                instruction_span: Some(Span::new((2, 7), (2, 13))),
+            },
+        );
+
+        // rounds without stitches in between don't count
+        let rounds = parse_rounds("ch 4\ntch\nsc 2, dec 2, ch, sc").unwrap();
+        let lints = lint_rounds(&rounds);
+        assert_eq!(lints.len(), 2);
+        assert_eq!(lints[1].code(), "C003");
+        assert_eq!(lints[1].span(), Span::new((3, 7), (3, 12)));
+        assert_eq!(
+            lints[1].to_string(),
+            "round 3 runs out of stitches at stitch 5, since round 1 only produces 4 stitches"
+        );
+
+        no_lints("sc 6 in mr\nsc 3, skip 3");
+
+        // running out at the end of the round is only a mismatched stitch count
+        let codes = |src| {
+            let lints = lint_rounds(&parse_rounds(src).unwrap());
+            lints.iter().map(Lint::code).collect::<Vec<_>>()
+        };
+        assert_eq!(codes("sc 6 in mr\nsc 3, skip 4"), ["C001"]);
+        assert_eq!(codes("sc 4 in mr\nsc 5, % hi %, ch 2"), ["C001"]);
+        assert_eq!(codes("sc 6 in mr\nsc 7, sc"), ["C001", "C003"]);
+    }
+
+    #[test]
+    fn test_lint_redundant_repeat() {
+        assert_produces_lint(
+            "sc 6 in mr\n[inc, sc 1] 1, [inc, sc] 2",
+            &Lint::RedundantRepeat {
+                idx: 2,
+                written: "[inc, sc 1] 1".into(),
+                simpler: "inc, sc 1".into(),
+                span: Span::new((2, 1), (2, 27)),
+                instruction_span: Some(Span::new((2, 1), (2, 14))),
+                fix: Some(Suggestion::new(
+                    Span::new((2, 1), (2, 14)),
+                    "inc, sc 1".into(),
+                )),
+            },
+        );
+
+        let lints = lint_rounds(&parse_rounds("sc 6 in mr\nx2: [sc 1, sc] 3").unwrap());
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].default_severity(), Severity::Info);
+        assert_eq!(lints[0].span(), Span::new((2, 6), (2, 10)));
+        assert_eq!(
+            lints[0].to_string(),
+            "round 2 repeats `sc 1` once, which can be written as `sc`"
+        );
+
+        no_lints("ch 1\nsc, ch 1");
+    }
+
+    #[test]
+    fn test_lint_empty_round() {
+        let config = LintConfig::new().report_empty_rounds();
+        let lints = |rounds: &[Round]| -> Vec<Lint> {
+            let report = lint_rounds_with_config(rounds, &config);
+            report.into_lints().into_iter().map(|(l, _)| l).collect()
+        };
+
+        let rounds =
+            parse_rounds("% start %\nsc 6 in mr\n% now increase %\ninc 6\n% fasten off %").unwrap();
+        assert_eq!(
+            lints(&rounds),
+            [Lint::EmptyRound {
+                idx: 3,
+                span: Span::new((3, 1), (3, 17)),
+            }]
+        );
+        // only reported when asked for
+        assert!(lint_rounds(&rounds).is_empty());
+
+        let rounds = parse_rounds("sc 6 in mr\nx2: % hi %\nsc 6").unwrap();
+        let found = lints(&rounds);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].default_severity(), Severity::Warning);
+        assert_eq!(found[0].code(), "C005");
+
+        let mut rounds = parse_rounds("sc 6 in mr\nsc 6").unwrap();
+        rounds.insert(1, Round::new(Vec::new()));
+        assert_eq!(lints(&rounds).len(), 1);
+
+        let rounds = parse_rounds("sc 6 in mr\n% turn, then %, tch\nsc 6").unwrap();
+        assert!(lints(&rounds).is_empty());
+        let rounds = parse_rounds("ch 12\n% comment %, % allow(empty_round) %\nsc 12").unwrap();
+        assert!(lints(&rounds).is_empty());
+    }
+
+    #[test]
+    fn test_lint_magic_ring_after_first_round() {
+        assert_produces_lint(
+            "sc 6 in mr\ninc 6\nsc 6, [sc 6 in mr], mr 6",
+            &Lint::MagicRingAfterFirstRound {
+                idx: 3,
+                span: Span::new((3, 1), (3, 25)),
+                instruction_span: Some(Span::new((3, 8), (3, 18))),
+            },
+        );
+
+        let codes = |src| {
+            let lints = lint_rounds(&parse_rounds(src).unwrap());
+            lints.iter().map(Lint::code).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            codes("sc 6 in mr\nx2: sc 6 in mr"),
+            ["C001", "C006", "C012"]
+        );
+        // the first round repeated is still after the first round
+        assert_eq!(codes("x2: sc 6 in mr"), ["C001", "C006"]);
+
+        no_lints("% start %\nmr 6\ninc 6, % fasten off %");
+        no_lints("ch 4\n% comment %\n");
+    }
+
+    #[test]
+    fn test_lint_ruffling() {
+        assert_produces_lint(
+            "sc 6 in mr\ninc 6\ninc 12",
+            &Lint::Ruffling {
+                idx: 3,
+                increases: 12,
+                span: Span::new((3, 1), (3, 7)),
+            },
+        );
+
+        // the usual flat circle and sphere increases don't ruffle
+        no_lints("sc 6 in mr\ninc 6\n[inc, sc] 6\n[inc, sc 2] 6\n[inc, sc 3] 6\n% sew closed %");
+        no_lints("sc 8 in mr\ninc 8\n[inc, sc] 8\n% sew closed %");
+
+        let rounds = parse_rounds("ch 12\nch 1, inc 12").unwrap();
+        let lints =
+            |mode| lint_rounds_with_config(&rounds, &LintConfig::new().mode(mode)).into_lints();
+        assert_eq!(lints(Mode::Joined)[0].0.name(), "ruffling");
+        assert!(lints(Mode::Rows)
+            .iter()
+            .all(|(l, _)| l.name() != "ruffling"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_ruffle_threshold() {
+        let _ = LintConfig::new().ruffle_threshold(-1.0);
+    }
+
+    #[test]
+    fn test_lint_puckering() {
+        assert_produces_lint(
+            "ch 12\nsc 12\nsc 2, skip 8, sc 2\nsc 4, ch 4",
+            &Lint::Puckering {
+                idx: 3,
+                input: 12,
+                decreases: 8,
+                span: Span::new((3, 1), (3, 19)),
+            },
+        );
+
+        // closing up the end of a sphere is fine
+        no_lints("sc 6 in mr\ninc 6\n[inc, sc] 6\n[dec, sc] 6\ndec 6\ndec 3");
+        no_lints("sc 6 in mr\ninc 6\nsc 12\nsc, skip 10, sc");
+
+        let rounds = parse_rounds("ch 20\nsc 20\ndec 10\nsc 10").unwrap();
+        let lints = |fraction| {
+            let config = LintConfig::new().pucker_threshold(fraction);
+            lint_rounds_with_config(&rounds, &config).into_lints()
+        };
+        assert!(lints(0.5).is_empty());
+        assert_eq!(
+            lints(0.4)[0].0.to_string(),
+            "round 3 takes away 10 of 20 stitches at once, so it will pucker"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_pucker_threshold() {
+        let _ = LintConfig::new().pucker_threshold(1.5);
+    }
+
+    #[test]
+    fn test_lint_never_closes() {
+        let config = LintConfig::new().closing_threshold(8);
+        let lints = |src| {
+            let rounds = parse_rounds(src).unwrap();
+            let report = lint_rounds_with_config(&rounds, &config);
+            report
+                .into_lints()
+                .into_iter()
+                .map(|(l, _)| l)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lints("sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18\n% stuff %"),
+            [Lint::NeverCloses {
+                idx: 4,
+                stitches: 18,
+                span: Span::new((4, 1), (4, 6)),
+            }]
+        );
+        // 8 is the default threshold
+        let src = "sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18\n% stuff %";
+        assert_eq!(lint_rounds(&parse_rounds(src).unwrap()), lints(src));
+
+        for src in [
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18, % Fasten off, leaving a tail %",
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18\n% sew to the body %",
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18, % FO %",
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\n[dec, sc] 6\ndec 6",
+            // flat pieces started from a chain don't close
+            "ch 20\nsc 20",
+        ] {
+            assert_eq!(lints(src), [], "{src}");
+        }
+    }
+
+    #[test]
+    fn test_lint_mixed_foundation() {
+        assert_produces_lint(
+            "ch 6, sc 6 in mr",
+            &Lint::MixedFoundation {
+                idx: 1,
+                chains: 6,
+                span: Span::new((1, 1), (1, 17)),
+                instruction_span: Some(Span::new((1, 1), (1, 5))),
+            },
+        );
+
+        let codes = |src| {
+            let lints = lint_rounds(&parse_rounds(src).unwrap());
+            lints.iter().map(Lint::code).collect::<Vec<_>>()
+        };
+        assert_eq!(codes("% start %\nmr 6, ch 2\nsc 8"), ["C010"]);
+        let lints = lint_rounds(&parse_rounds("% start %\nmr 6, ch 2\nsc 8").unwrap());
+        assert_eq!(lints[0].round(), Some(2));
+        assert!(lints[0].to_string().starts_with("round 2 starts with"));
+        no_lints("ch 1, sc 6 in mr\nsc 7");
+        no_lints("ch 6\nsc 6");
+    }
+
+    #[test]
+    fn test_lint_bunched_increases() {
+        assert_produces_lint(
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\ninc 6, sc 18\n% fasten off %",
+            &Lint::BunchedIncreases {
+                idx: 4,
+                decreasing: false,
+                suggestion: "[inc, sc 3] 6".into(),
+                span: Span::new((4, 1), (4, 13)),
+                fix: Some(Suggestion::new(
+                    Span::new((4, 1), (4, 13)),
+                    "[inc, sc 3] 6".into(),
+                )),
+            },
+        );
+
+        let rounds = parse_rounds("ch 12\nsc 2, [dec] 2, dec 2, sc 2\nsc 8").unwrap();
+        let lints = lint_rounds(&rounds);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].to_string(),
+            "round 2 has all of its decreases next to each other, which leaves a visible seam; \
+                spread them out, e.g. `[dec, sc] 4`"
+        );
+
+        // a single change, or not enough stitches to put between them
+        no_lints("ch 12\ninc, sc 11\nsc 13");
+        no_lints("ch 8\ninc 5, sc 3\nsc 13");
+        // more than one kind of shaping stitch
+        no_lints("ch 16\ninc 2, dec 2, sc 10\nsc 16");
+        no_lints("ch 14\ninc, flinc, sc 12\nsc 16");
+    }
+
+    #[test]
+    fn test_lint_consecutive_identical_rounds() {
+        assert_produces_lint(
+            "ch 12\nsc 12\nx2: sc 12\nsc 6, sc 6\n[dec, sc 2] 3",
+            &Lint::RepeatedRounds {
+                first_idx: 2,
+                last_idx: 5,
+                suggestion: "rounds 2-5: sc 12".into(),
+                span: Span::new((2, 1), (4, 11)),
+                fix: Some(Suggestion::new(
+                    Span::new((2, 1), (4, 11)),
+                    "rounds 2-5: sc 12".into(),
+                )),
+            },
+        );
+
+        let rounds = parse_rounds("ch 12\nhook 4mm\nsc 12\nsc 12\nsc 12\nhook 3mm\nsc 12").unwrap();
+        let lints = lint_rounds(&rounds);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].to_string(),
+            "rounds 2 to 4 are all the same, and can be written as `rounds 2-4: sc 12`"
+        );
+
+        // already written as one round
+        no_lints("ch 12\nrounds 2-5: sc 12");
+        no_lints("ch 12\nsc 12\ntop: sc 12");
+    }
+
+    #[test]
+    fn test_lint_post_stitch_in_first_round() {
+        assert_produces_lint(
+            "[sc, fpsc] 3 in mr\nsc 6",
+            &Lint::PostStitchInFirstRound {
+                idx: 1,
+                stitch: "fpsc".into(),
+                span: Span::new((1, 1), (1, 19)),
+                instruction_span: Some(Span::new((1, 6), (1, 10))),
+            },
+        );
+
+        let codes = |src| {
+            let lints = lint_rounds(&parse_rounds(src).unwrap());
+            lints.iter().map(Lint::code).collect::<Vec<_>>()
+        };
+        assert_eq!(codes("ch 6\nbpsc 6"), ["C013"]);
+        assert_eq!(
+            codes("% start %\nbpsc 6 in mr\nsc 6, % sew closed %"),
+            ["C013"]
+        );
+        // the posts of round 1's stitches are there to work around
+        no_lints("ch 6\nsc 6\nfpsc 6");
+        no_lints("sc 6 in mr\nfpsc 6, % fasten off %");
+    }
+
+    #[test]
+    fn test_lint_missing_turning_chain() {
+        let lints = |src| {
+            let rounds = parse_rounds(src).unwrap();
+            let config = LintConfig::new().mode(Mode::Rows);
+            lint_rounds_with_config(&rounds, &config)
+                .into_lints()
+                .into_iter()
+                .map(|(l, _)| l)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            lints("ch 11\nsc 10\nsc 8, dec\nch 1, sc 9"),
+            [Lint::MissingTurningChain {
+                idx: 3,
+                chains: 0,
+                span: Span::new((3, 1), (3, 10)),
+            }]
+        );
+        assert_eq!(
+            lints("ch 11\nsc 10\nch 3, sc 10")[0].to_string(),
+            "row 3 starts with a turning chain of 3, which is too tall for single crochet and \
+                leaves a loose edge; start it with `ch 1`"
+        );
+        // the second of the repeated rows has nothing to turn with
+        assert_eq!(lints("ch 11\nx2: sc 10")[0].code(), "C014");
+
+        assert!(lints("ch 11\nsc 10\ntch, sc 10\nx3: ch 1, sc 10").is_empty());
+        assert!(lints("ch 11\nsc 10\nsc 10, % allow(missing_turning_chain) %").is_empty());
+        // rounds aren't turned
+        no_lints("ch 10\nsc 10\nsc 8, dec");
+    }
+
+    #[test]
+    fn test_lint_excessive_nesting() {
+        let src = format!("ch 2\nsc, {}sc{}", "[".repeat(9), "] 1".repeat(9));
+        let rounds = parse_rounds(&src).unwrap();
+        let lints: Vec<_> = lint_rounds(&rounds)
+            .into_iter()
+            .filter(|l| l.name() == "excessive_nesting")
+            .collect();
+        assert_eq!(
+            lints,
+            [Lint::ExcessiveNesting {
+                idx: 2,
+                depth: 9,
+                span: Span::new((2, 1), (2, 43)),
+                instruction_span: Some(Span::new((2, 5), (2, 43))),
+            }]
+        );
+
+        let rounds = parse_rounds("ch 22\n[[sc, [inc, sc] 2] 2, sc] 2").unwrap();
+        let config = LintConfig::new().nesting_threshold(2);
+        let lints = lint_rounds_with_config(&rounds, &config).into_lints();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].0.to_string(),
+            "round 2 has brackets nested 3 deep, which is hard to follow and might be a \
+                misplaced bracket"
+        );
+
+        assert_eq!(nesting_depth(&rounds[1].instructions[0]), 3);
+        assert_eq!(nesting_depth(&Instruction::Sc), 0);
+    }
+
+    #[test]
+    fn test_lint_absurd_stitch_count() {
+        assert_produces_lint(
+            "ch 6\nsc 6\n[inc, sc 2] 60000",
+            &Lint::AbsurdStitchCount {
+                idx: 3,
+                stitches: 240000,
+                span: Span::new((3, 1), (3, 18)),
+            },
+        );
+
+        // the rounds of a block are only reported once
+        let rounds = parse_rounds("ch 200000\nx3: sc 200000").unwrap();
+        let codes: Vec<_> = lint_rounds(&rounds).iter().map(Lint::code).collect();
+        assert_eq!(codes, ["C018", "C018"]);
+        assert_eq!(
+            lint_rounds(&rounds)[1].to_string(),
+            "round 2 makes 200000 stitches, which is more than any real piece; check its counts \
+                for a typo"
+        );
+
+        no_lints("ch 100000\nsc 100000");
+
+        // counts too big for a u32 are capped instead of overflowing
+        let rounds = parse_rounds("sc 6 in mr\n[sc 100000] 100000").unwrap();
+        assert!(lint_rounds(&rounds).contains(&Lint::AbsurdStitchCount {
+            idx: 2,
+            stitches: u32::MAX,
+            span: Span::new((2, 1), (2, 19)),
+        }));
+    }
+
+    #[test]
+    fn test_lint_stacked_decreases() {
+        assert_produces_lint(
+            "sc 6 in mr\ninc 6\n[sc 2, inc] 4\n[sc 2, dec] 4\n[dec, sc] 4",
+            &Lint::StackedDecreases {
+                idx: 5,
+                previous_idx: 4,
+                span: Span::new((5, 1), (5, 12)),
+                fix: None,
+            },
+        );
+
+        let src = "sc 6 in mr\ninc 6\n[sc 3, inc] 3\nsc 15\n[sc 3, dec] 3\n[dec, sc 2] 3";
+        let rounds = parse_rounds(src).unwrap();
+        let lints: Vec<_> = lint_rounds(&rounds)
+            .into_iter()
+            .filter(|l| l.name() == "stacked_decreases")
+            .collect();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            crate::apply_suggestions(src, &lints).lines().last(),
+            Some("sc, [dec, sc 2] 2, dec, sc")
+        );
+        assert_eq!(
+            lints[0].to_string(),
+            "round 5 ends with a decrease and round 6 starts with one, which stacks them into a \
+                visible seam; start round 6 partway between its decreases"
+        );
+
+        // rounds of only comments in between don't count
+        let lints = lint_rounds(&parse_rounds(&src.replace("\n[dec", "\n% now %\n[dec")).unwrap());
+        let stacked = lints.iter().find(|l| l.name() == "stacked_decreases");
+        assert!(stacked
+            .unwrap()
+            .to_string()
+            .starts_with("round 5 ends with a decrease and round 7 starts with one"));
+
+        // joined rounds hide the seam
+        assert!(lint_rounds_in_mode(&rounds, Mode::Joined)
+            .iter()
+            .all(|l| l.name() != "stacked_decreases"));
+
+        no_lints("sc 6 in mr\ninc 6\n[sc 3, inc] 3\nsc 15\n[sc 3, dec] 3\n[sc, dec, sc] 3, % fasten off %");
+        no_lints("sc 6 in mr\ninc 6\nsc 12\n[sc, dec] 4\ndec 4, % fasten off %");
+    }
+
+    #[test]
+    fn test_lint_stitches_after_fasten_off() {
+        assert_produces_lint(
+            "sc 6 in mr\ninc 6\nsc 12, % fasten off %\ndec 6\ndec 3",
+            &Lint::StitchesAfterFastenOff {
+                idx: 4,
+                fasten_off_idx: 3,
+                span: Span::new((4, 1), (4, 6)),
+                instruction_span: Some(Span::new((4, 1), (4, 6))),
+            },
+        );
+        assert_eq!(
+            lint_rounds(&parse_rounds("sc 6 in mr\n% fo %, sc 6").unwrap())[0].to_string(),
+            "round 2 works stitches after the yarn is fastened off in round 2; is a part header \
+                like `== Arm ==` missing?"
+        );
+
+        let src = "sc 6 in mr\ninc 6\nx2: sc 12, % fasten off %\nsc 12, % sew closed %";
+        let lints = lint_rounds(&parse_rounds(src).unwrap());
+        let [Lint::StitchesAfterFastenOff {
+            idx: 5,
+            fasten_off_idx: 4,
+            ..
+        }] = &lints[..]
+        else {
+            panic!("{lints:?}");
+        };
+
+        no_lints("sc 6 in mr\ninc 6\nx2: sc 12, % fasten off %\n% stuff firmly %");
+        no_lints("sc 6 in mr\ninc 6\nsc 12, % fasten off, leaving a long tail %\n");
+    }
+
+    #[test]
+    fn test_lint_report() {
+        let rounds = parse_rounds("ch 3, sc 2\nsc 4\nsc 6").unwrap();
+        let report = lint_rounds_with_config(&rounds, &LintConfig::new());
+        assert_eq!(report.count(Severity::Error), 2);
+        assert_eq!(report.count(Severity::Warning), 0);
+
+        let by_round = report.by_round();
+        let rounds: Vec<_> = by_round.keys().copied().collect();
+        assert_eq!(rounds, [Some(1), Some(2)]);
+        assert_eq!(by_round[&Some(1)][0].0.code(), "C002");
+
+        let report =
+            lint_rounds_with_config(&parse_rounds("ch 6\nsc 6").unwrap(), &LintConfig::new());
+        assert!(report.is_clean());
+        assert_eq!(report, LintReport::default());
+    }
+
+    #[test]
+    fn test_lint_parts() {
+        let src = "== Arm ==\nsc 6 in mr\n== Arm ==\nsc 8 in mr\n== Assembly ==\n\
+            attach \"Ear\" and \"arm\" to \"Head\"\n  sew \"Tail\" on";
+        let pattern = crate::parse_pattern(src).unwrap();
+        assert_eq!(
+            lint_parts(&pattern),
+            [
+                Lint::DuplicatePart {
+                    name: "Arm".into(),
+                    span: Span::new((4, 1), (4, 11)),
+                },
+                Lint::UnknownPart {
+                    name: "Ear".into(),
+                    span: Span::new((6, 8), (6, 13)),
+                },
+                Lint::UnknownPart {
+                    name: "Head".into(),
+                    span: Span::new((6, 27), (6, 33)),
+                },
+                Lint::UnknownPart {
+                    name: "Tail".into(),
+                    span: Span::new((7, 7), (7, 13)),
+                },
+            ]
+        );
+
+        let pattern =
+            crate::parse_pattern("sc 6 in mr\n== Head ==\n== Head ==\n== Head ==").unwrap();
+        let codes: Vec<_> = lint_parts(&pattern).iter().map(Lint::code).collect();
+        assert_eq!(codes, ["C017"]);
+
+        // quotes in a part's name aren't quoted names
+        let src = "== Part <2> & \"x\" ==\nsc 6 in mr\n== Assembly ==\n\
+            stuff Part <2> & \"x\"\nattach \"part <2> & \"x\"\" to \"y\"";
+        let pattern = crate::parse_pattern(src).unwrap();
+        assert_eq!(
+            lint_parts(&pattern),
+            [Lint::UnknownPart {
+                name: "y".into(),
+                span: Span::new((5, 28), (5, 31)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_colors() {
+        let src = "colors: A = cream, B = brown, C = pink\nsc 6 in mr, @A\n\
+            x2: @B, inc 6\n[@A, sc, @B, sc] 12\n== Tail ==\n@B, sc 6 in mr, @B";
+        let pattern = crate::parse_pattern(src).unwrap();
+        let lints = lint_parts(&pattern);
+        assert_eq!(
+            lints,
+            [
+                Lint::RedundantColorChange {
+                    idx: 1,
+                    key: "A".into(),
+                    span: Span::new((2, 1), (2, 15)),
+                    instruction_span: Some(Span::new((2, 13), (2, 15))),
+                },
+                Lint::RedundantColorChange {
+                    idx: 1,
+                    key: "B".into(),
+                    span: Span::new((6, 1), (6, 19)),
+                    instruction_span: Some(Span::new((6, 17), (6, 19))),
+                },
+                Lint::UnusedColor {
+                    key: "C".into(),
+                    span: Span::default(),
+                },
+            ]
+        );
+        assert_eq!(
+            lints[0].to_string(),
+            "round 1 switches to color A, but it's already the active color"
+        );
+        assert_eq!(
+            lints[2].to_string(),
+            "color C is declared but no stitches are made in it"
+        );
+
+        // the main color is used without switching to it
+        let pattern = crate::parse_pattern("colors: A = cream\nsc 6 in mr").unwrap();
+        assert!(lint_parts(&pattern).is_empty());
+    }
 }
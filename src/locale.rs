@@ -0,0 +1,271 @@
+use crate::Mode;
+
+/// The keywords that are translated, in the order of each locale's table. `join` and `turn` end
+/// joined rounds and rows in [`crate::pretty_format_with_options`].
+const KEYWORDS: [&str; 17] = [
+    "ch",
+    "tch",
+    "sc",
+    "fpsc",
+    "bpsc",
+    "blsc",
+    "inc",
+    "flinc",
+    "blinc",
+    "dec",
+    "in mr",
+    "in next st",
+    "skip",
+    "surface sl st",
+    "cross",
+    "join",
+    "turn",
+];
+
+const GERMAN: [&str; 17] = [
+    "Lm",
+    "WLm",
+    "fM",
+    "vRfM",
+    "hRfM",
+    "fM hMg",
+    "zun",
+    "zun vMg",
+    "zun hMg",
+    "abn",
+    "in FR",
+    "in nächste M",
+    "überspr",
+    "Oberfl-Km",
+    "kreuzen",
+    "mit Km schließen",
+    "wenden",
+];
+
+const SPANISH: [&str; 17] = [
+    "cad",
+    "cadv",
+    "pb",
+    "pbrd",
+    "pbrt",
+    "pb ht",
+    "aum",
+    "aum hd",
+    "aum ht",
+    "dism",
+    "en am",
+    "en sig pt",
+    "saltar",
+    "pd superficie",
+    "cruzar",
+    "unir",
+    "girar",
+];
+
+const FRENCH: [&str; 17] = [
+    "ml",
+    "ml tour",
+    "ms",
+    "msrav",
+    "msrar",
+    "ms brin arr",
+    "aug",
+    "aug brin av",
+    "aug brin arr",
+    "dim",
+    "dans cm",
+    "dans m suiv",
+    "sauter",
+    "mc surface",
+    "croiser",
+    "fermer",
+    "tourner",
+];
+
+const JAPANESE: [&str; 17] = [
+    "くさり",
+    "立ち上がりくさり",
+    "細編み",
+    "表引き上げ細編み",
+    "裏引き上げ細編み",
+    "細編みのすじ編み",
+    "増し目",
+    "手前半目の増し目",
+    "すじ編みの増し目",
+    "減らし目",
+    "輪の中に",
+    "同じ目に",
+    "飛ばす",
+    "表面引き抜き編み",
+    "交差",
+    "引き抜き",
+    "裏返す",
+];
+
+/// The language patterns are written out in by [`crate::pretty_format_localized`]: the stitch
+/// abbreviations and what the rounds are called. Comments, labels and color keys are written
+/// as-is.
+///
+/// ```
+/// # use crochet::{parse_rounds, pretty_format_localized, Locale};
+/// let rounds = parse_rounds("sc 6 in mr\n[inc, sc] 6").unwrap();
+///
+/// assert_eq!(
+///     pretty_format_localized(&rounds, Locale::German),
+///     "Runde 1: fM 6 in FR (6)\nRunde 2: [zun, fM] 6 (18)"
+/// );
+/// assert_eq!(
+///     pretty_format_localized(&rounds, Locale::French),
+///     "Tour 1: ms 6 dans cm (6)\nTour 2: [aug, ms] 6 (18)"
+/// );
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Locale {
+    /// The pattern language's own abbreviations, in US or UK terms (see
+    /// [`crate::FormatOptions::terminology`])
+    #[default]
+    English,
+    German,
+    Spanish,
+    French,
+    Japanese,
+}
+
+impl Locale {
+    pub const ALL: [Self; 5] = [
+        Self::English,
+        Self::German,
+        Self::Spanish,
+        Self::French,
+        Self::Japanese,
+    ];
+
+    /// The locale's language code, e.g. `de`
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::German => "de",
+            Self::Spanish => "es",
+            Self::French => "fr",
+            Self::Japanese => "ja",
+        }
+    }
+
+    /// Looks up a locale by its [`Self::code`]
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.code() == code)
+    }
+
+    fn table(self) -> Option<&'static [&'static str; 17]> {
+        match self {
+            Self::English => None,
+            Self::German => Some(&GERMAN),
+            Self::Spanish => Some(&SPANISH),
+            Self::French => Some(&FRENCH),
+            Self::Japanese => Some(&JAPANESE),
+        }
+    }
+
+    /// The translation of `keyword`, as the pattern language spells it, or `None` in English
+    pub(crate) fn keyword(self, keyword: &str) -> Option<&'static str> {
+        let i = KEYWORDS.iter().position(|k| *k == keyword)?;
+        self.table().map(|t| t[i])
+    }
+
+    /// What a round or block of rounds is called, given their `numbers` like `1` or `2-4`, or
+    /// `None` in English
+    pub(crate) fn numbering(self, mode: Mode, numbers: &str, plural: bool) -> Option<String> {
+        let rows = mode == Mode::Rows;
+        let word = match (self, rows, plural) {
+            (Self::English, ..) => return None,
+            // the same word is used for rounds and rows, after the number
+            (Self::Japanese, ..) => return Some(format!("{numbers}段目")),
+            (Self::German, false, false) => "Runde",
+            (Self::German, false, true) => "Runden",
+            (Self::German, true, false) => "Reihe",
+            (Self::German, true, true) => "Reihen",
+            (Self::Spanish, false, false) => "Vuelta",
+            (Self::Spanish, false, true) => "Vueltas",
+            (Self::Spanish, true, false) => "Hilera",
+            (Self::Spanish, true, true) => "Hileras",
+            (Self::French, false, false) => "Tour",
+            (Self::French, false, true) => "Tours",
+            (Self::French, true, false) => "Rang",
+            (Self::French, true, true) => "Rangs",
+        };
+
+        Some(format!("{word} {numbers}"))
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_rounds, pretty_format_localized, pretty_format_with_options};
+    use crate::{FormatOptions, Terminology};
+
+    #[test]
+    fn test_codes() {
+        for l in Locale::ALL {
+            assert_eq!(Locale::from_code(l.code()), Some(l));
+        }
+        assert_eq!(Locale::from_code("xx"), None);
+    }
+
+    #[test]
+    fn test_every_instruction() {
+        let rounds = parse_rounds(
+            "tch, ch 2, sc, fpsc, bpsc, blsc, inc, flinc, blinc, dec, [sc 2] in mr, skip 2, \
+                surface sl st 3, cross(fpsc, sc), ch 3, skip 2, (sc, ch 2) in next st, % Hi %",
+        )
+        .unwrap();
+
+        assert_eq!(
+            pretty_format_localized(&rounds, Locale::Spanish),
+            "Vuelta 1: cadv, cad 2, pb, pbrd, pbrt, pb ht, aum, aum hd, aum ht, dism, [pb 2] en am, \
+                saltar 2, pd superficie 3, cruzar(pbrd, pb), cad 3, saltar 2, (pb, cad 2) en sig pt, \
+                % Hi % (23)"
+        );
+        assert_eq!(
+            pretty_format_localized(&rounds, Locale::Japanese),
+            "1段目: 立ち上がりくさり, くさり 2, 細編み, 表引き上げ細編み, 裏引き上げ細編み, \
+                細編みのすじ編み, 増し目, 手前半目の増し目, すじ編みの増し目, 減らし目, \
+                [細編み 2] 輪の中に, 飛ばす 2, 表面引き抜き編み 3, 交差(表引き上げ細編み, 細編み), \
+                くさり 3, 飛ばす 2, (細編み, くさり 2) 同じ目に, % Hi % (23)"
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        let rounds = parse_rounds("ch 6\nx2: sc 5").unwrap();
+        let opts = FormatOptions {
+            mode: Mode::Rows,
+            locale: Locale::German,
+            ..Default::default()
+        };
+        assert_eq!(
+            pretty_format_with_options(&rounds, &opts),
+            "Reihe 1: Lm 6, wenden (6)\nReihen 2-3: fM 5, Lm 1, wenden (5)"
+        );
+    }
+
+    #[test]
+    fn test_terminology_is_only_english() {
+        let rounds = parse_rounds("sc 6 in mr").unwrap();
+        let opts = FormatOptions {
+            terminology: Terminology::Uk,
+            locale: Locale::French,
+            ..Default::default()
+        };
+        assert_eq!(
+            pretty_format_with_options(&rounds, &opts),
+            "Tour 1: ms 6 dans cm (6)"
+        );
+    }
+}
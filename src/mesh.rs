@@ -0,0 +1,359 @@
+use crate::{Instruction, Round, StitchGraph};
+use std::f64::consts::TAU;
+use std::fmt::Write;
+
+/// How many times the springs are relaxed
+const ITERATIONS: usize = 200;
+
+/// A triangle mesh of the shape a pattern worked in the round makes, from [`simulate_mesh`]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Mesh {
+    /// The position of every stitch, round by round in the order they're made, in stitch widths.
+    /// If the first round is worked into a magic ring, its center comes after them, to close it up.
+    pub vertices: Vec<[f64; 3]>,
+    /// Triangles as indices into `vertices`, wound counterclockwise when seen from outside
+    pub triangles: Vec<[usize; 3]>,
+    /// The round each triangle belongs to, from 0 in the order the rounds are worked: the strip
+    /// joining a round to the one before it, or the middle of the first round, if it's closed
+    pub triangle_rounds: Vec<usize>,
+}
+
+/// Simulates the shape that rounds make when they're worked, for previewing a piece before
+/// crocheting it.
+///
+/// Every stitch is taken to be the same size, so it's like a spring of a fixed length to the
+/// stitches next to it in its round and to the stitches it's worked into. The stitches start out
+/// as rings stacked up so that each ring is a stitch away from the one before it, and then the
+/// springs are relaxed, which evens out increases and decreases that aren't spread out evenly
+/// around their round.
+///
+/// The rings are joined up into a mesh. If the first round is worked into a magic ring, it's
+/// closed off in the middle, like the ring pulled tight; otherwise, e.g. for a tube started from a
+/// chain, it's left open. The last round is always left open.
+///
+/// ```
+/// # use crochet::{parse_rounds, simulate_mesh};
+/// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[inc, sc] 6\nx4: sc 18").unwrap();
+/// let mesh = simulate_mesh(&rounds);
+///
+/// // a stitch for every stitch made, and the middle of the magic ring
+/// assert_eq!(mesh.vertices.len(), 6 + 12 + 18 * 5 + 1);
+/// assert!(mesh.to_obj().starts_with("v "));
+/// assert!(mesh.to_stl().starts_with("solid crochet\n"));
+/// ```
+pub fn simulate_mesh(rounds: &[Round]) -> Mesh {
+    let graph = StitchGraph::new(rounds);
+    let counts: Vec<usize> = (0..graph.rounds())
+        .map(|r| graph.stitch_count(r).unwrap_or(0))
+        .collect();
+
+    // the index in `vertices` of the first stitch of each round
+    let mut starts = Vec::with_capacity(counts.len());
+    let mut vertices = Vec::new();
+    let (mut height, mut last_radius) = (0.0, None::<f64>);
+    for &count in &counts {
+        let radius = count as f64 / TAU;
+        if let Some(last) = last_radius {
+            // as high above the last ring as a stitch is long, unless the ring is too far out
+            height += (1.0 - (radius - last).powi(2)).max(0.0).sqrt();
+        }
+        last_radius = Some(radius);
+
+        starts.push(vertices.len());
+        for s in 0..count {
+            let angle = TAU * s as f64 / count as f64;
+            vertices.push([radius * angle.cos(), radius * angle.sin(), height]);
+        }
+    }
+
+    let springs = springs(&graph, &counts, &starts);
+    relax(&mut vertices, &springs);
+
+    let magic_ring = rounds.first().is_some_and(|r| {
+        r.instructions
+            .iter()
+            .any(|i| matches!(i, Instruction::IntoMagicRing(_)))
+    });
+    let (triangles, triangle_rounds) = triangulate(&mut vertices, &counts, &starts, magic_ring)
+        .into_iter()
+        .unzip();
+
+    Mesh {
+        vertices,
+        triangles,
+        triangle_rounds,
+    }
+}
+
+/// The pairs of stitches that are a stitch apart: neighbours in the same round, and stitches and
+/// the ones they're worked into
+fn springs(graph: &StitchGraph, counts: &[usize], starts: &[usize]) -> Vec<(usize, usize)> {
+    let mut ret = Vec::new();
+
+    for (r, &count) in counts.iter().enumerate() {
+        let start = starts[r];
+        // two stitches are already next to each other both ways around
+        let ring = if count > 2 {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        for s in 0..ring {
+            ret.push((start + s, start + (s + 1) % count));
+        }
+
+        if r == 0 {
+            continue;
+        }
+        for s in 0..count {
+            let parents = graph.parents(r, s).unwrap_or(&[]);
+            for &p in parents.iter().filter(|&&p| p < counts[r - 1]) {
+                ret.push((start + s, starts[r - 1] + p));
+            }
+        }
+    }
+
+    ret
+}
+
+/// Moves the stitches towards being a stitch apart from each other stitch they're sprung to,
+/// averaging out each stitch's pulls so that the result doesn't depend on the order of the springs
+fn relax(vertices: &mut [[f64; 3]], springs: &[(usize, usize)]) {
+    let mut moves = vec![[0.0; 3]; vertices.len()];
+    let mut pulls = vec![0_u32; vertices.len()];
+
+    for _ in 0..ITERATIONS {
+        moves.iter_mut().for_each(|m| *m = [0.0; 3]);
+        pulls.iter_mut().for_each(|n| *n = 0);
+
+        for &(a, b) in springs {
+            let d = sub(vertices[b], vertices[a]);
+            let len = dot(d, d).sqrt();
+            if len < 1e-9 {
+                continue;
+            }
+            // each end moves half of the way to the spring's length
+            let k = (len - 1.0) / len / 2.0;
+            for i in 0..3 {
+                moves[a][i] += d[i] * k;
+                moves[b][i] -= d[i] * k;
+            }
+            pulls[a] += 1;
+            pulls[b] += 1;
+        }
+
+        for ((v, m), &n) in vertices.iter_mut().zip(&moves).zip(&pulls) {
+            if n > 0 {
+                for i in 0..3 {
+                    v[i] += m[i] / f64::from(n);
+                }
+            }
+        }
+    }
+}
+
+/// Joins each round to the next with a strip of triangles, and if `closed`, closes off the first
+/// round with a fan around its center, which is added to `vertices`. Each triangle comes with its
+/// round.
+fn triangulate(
+    vertices: &mut Vec<[f64; 3]>,
+    counts: &[usize],
+    starts: &[usize],
+    closed: bool,
+) -> Vec<([usize; 3], usize)> {
+    let mut ret = Vec::new();
+
+    for r in 1..counts.len() {
+        let (n, m) = (counts[r - 1], counts[r]);
+        if n == 0 || m == 0 {
+            continue;
+        }
+        let (a, b) = (starts[r - 1], starts[r]);
+
+        // walk around both rings at once, always advancing on the one that's further behind
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            let next_i = (i + 1) as f64 / n as f64;
+            let next_j = (j + 1) as f64 / m as f64;
+            if j == m || (i < n && next_i <= next_j) {
+                ret.push(([a + i, a + (i + 1) % n, b + j % m], r));
+                i += 1;
+            } else {
+                ret.push(([a + i % n, b + (j + 1) % m, b + j], r));
+                j += 1;
+            }
+        }
+    }
+
+    if let Some(&count) = counts.first().filter(|&&c| closed && c > 2) {
+        let center = vertices.len();
+        let mut sum = [0.0; 3];
+        for v in &vertices[..count] {
+            for i in 0..3 {
+                sum[i] += v[i] / count as f64;
+            }
+        }
+        vertices.push(sum);
+
+        for s in 0..count {
+            ret.push(([center, (s + 1) % count, s], 0));
+        }
+    }
+
+    ret
+}
+
+impl Mesh {
+    /// Writes the mesh as a Wavefront OBJ file
+    pub fn to_obj(&self) -> String {
+        let mut ret = String::new();
+
+        for [x, y, z] in &self.vertices {
+            writeln!(ret, "v {x:.4} {y:.4} {z:.4}").expect("writing to a string shouldn't fail");
+        }
+        // OBJ counts vertices from 1
+        for [a, b, c] in &self.triangles {
+            writeln!(ret, "f {} {} {}", a + 1, b + 1, c + 1)
+                .expect("writing to a string shouldn't fail");
+        }
+
+        ret
+    }
+
+    /// Writes the mesh as an ASCII STL file, for 3D printing software
+    pub fn to_stl(&self) -> String {
+        let mut ret = String::from("solid crochet\n");
+
+        for tri in &self.triangles {
+            let [a, b, c] = tri.map(|i| self.vertices[i]);
+            let n = cross(sub(b, a), sub(c, a));
+            let len = dot(n, n).sqrt();
+            let [nx, ny, nz] = if len > 0.0 { n.map(|x| x / len) } else { n };
+
+            writeln!(ret, "facet normal {nx:.4} {ny:.4} {nz:.4}\nouter loop")
+                .expect("writing to a string shouldn't fail");
+            for [x, y, z] in [a, b, c] {
+                writeln!(ret, "vertex {x:.4} {y:.4} {z:.4}")
+                    .expect("writing to a string shouldn't fail");
+            }
+            ret.push_str("endloop\nendfacet\n");
+        }
+
+        ret.push_str("endsolid crochet\n");
+        ret
+    }
+}
+
+pub(crate) fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn mesh(src: &str) -> Mesh {
+        simulate_mesh(&parse_rounds(src).unwrap())
+    }
+
+    fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+        let d = sub(a, b);
+        dot(d, d).sqrt()
+    }
+
+    #[test]
+    fn test_springs_are_relaxed() {
+        let src = "sc 6 in mr\ninc 6\n[inc, sc] 6\n[inc, sc 2] 6\nx3: sc 24\n[dec, sc 2] 6\n\
+            [dec, sc] 6\ndec 6";
+        let rounds = parse_rounds(src).unwrap();
+        let m = simulate_mesh(&rounds);
+
+        let graph = StitchGraph::new(&rounds);
+        let counts: Vec<_> = (0..graph.rounds())
+            .map(|r| graph.stitch_count(r).unwrap())
+            .collect();
+        let starts: Vec<_> = counts
+            .iter()
+            .scan(0, |start, c| {
+                *start += c;
+                Some(*start - c)
+            })
+            .collect();
+        for (a, b) in springs(&graph, &counts, &starts) {
+            let d = distance(m.vertices[a], m.vertices[b]);
+            assert!((0.7..1.3).contains(&d), "{a} {b} {d}");
+        }
+    }
+
+    #[test]
+    fn test_tube() {
+        let m = mesh("sc 12 in mr\nx5: sc 12");
+        // the tube rounds all end up the same distance from the middle
+        let radius = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1]).sqrt();
+        let first = radius(m.vertices[12]);
+        for v in &m.vertices[12..72] {
+            assert!((radius(*v) - first).abs() < 0.05);
+        }
+        // and go up a stitch at a time
+        assert!((m.vertices[71][2] - m.vertices[11][2] - 5.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_triangulate() {
+        let m = mesh("sc 3 in mr\ninc 3");
+        // 3 + 6 triangles between the rounds, and 3 closing off the first
+        assert_eq!(m.triangles.len(), 12);
+        assert_eq!(m.triangle_rounds.iter().filter(|&&r| r == 1).count(), 9);
+        assert_eq!(m.vertices.len(), 10);
+        // every edge between the rounds is used by two triangles, except the ones on the open
+        // last round
+        let mut edges = std::collections::HashMap::new();
+        for t in &m.triangles {
+            for k in 0..3 {
+                let (a, b) = (t[k], t[(k + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        let open = edges.values().filter(|&&n| n == 1).count();
+        assert_eq!(open, 6);
+        assert!(edges.values().all(|&n| n <= 2));
+    }
+
+    #[test]
+    fn test_open_start() {
+        // a tube started from a chain isn't closed off at the bottom
+        let m = mesh("ch 12\nx5: sc 12");
+        assert_eq!(m.vertices.len(), 12 * 6);
+        assert_eq!(m.triangles.len(), 24 * 5);
+        assert!(m.triangle_rounds.iter().all(|&r| r > 0));
+    }
+
+    #[test]
+    fn test_exports() {
+        let m = mesh("sc 4 in mr\nsc 4");
+        let obj = m.to_obj();
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 9);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 12);
+        assert!(obj.lines().any(|l| l == "f 9 2 1"));
+
+        let stl = m.to_stl();
+        assert_eq!(stl.matches("facet normal").count(), 12);
+        assert_eq!(stl.matches("vertex ").count(), 36);
+        assert!(stl.ends_with("endsolid crochet\n"));
+
+        assert_eq!(mesh("").to_obj(), "");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::color::parse_palette;
+
+/// The fields that can appear in a pattern's header.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MetaField {
+    Name,
+    Designer,
+    HookSize,
+    YarnWeight,
+    Gauge,
+    License,
+    Source,
+    Attribution,
+    Mode,
+    Colors,
+}
+
+impl MetaField {
+    pub const ALL: [Self; 10] = [
+        Self::Name,
+        Self::Designer,
+        Self::HookSize,
+        Self::YarnWeight,
+        Self::Gauge,
+        Self::License,
+        Self::Source,
+        Self::Attribution,
+        Self::Mode,
+        Self::Colors,
+    ];
+
+    /// The key used for this field in a pattern header, e.g. `license` in `license: CC-BY-4.0`
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Designer => "designer",
+            Self::HookSize => "hook size",
+            Self::YarnWeight => "yarn weight",
+            Self::Gauge => "gauge",
+            Self::License => "license",
+            Self::Source => "source",
+            Self::Attribution => "attribution",
+            Self::Mode => "mode",
+            Self::Colors => "colors",
+        }
+    }
+}
+
+/// Information about a pattern, given as `key: value` lines at the top of the source.
+///
+/// ```
+/// # use crochet::parse_pattern;
+/// let src = "
+///     name: Tiny Ball
+///     hook size: 3.5mm
+///     yarn weight: DK
+///     gauge: 22 sts x 24 rnds = 10cm
+///     license: CC-BY-4.0
+///
+///     sc 6 in mr
+/// ";
+///
+/// let meta = parse_pattern(src).unwrap().meta;
+/// assert_eq!(meta.name, Some("Tiny Ball"));
+/// assert_eq!(meta.hook_size, Some("3.5mm"));
+/// assert_eq!(meta.license, Some("CC-BY-4.0"));
+/// assert_eq!(meta.source, None);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternMeta<'a> {
+    /// Name of the pattern
+    pub name: Option<&'a str>,
+    /// Who designed the pattern
+    pub designer: Option<&'a str>,
+    /// Recommended hook size, e.g. `3.5mm`
+    pub hook_size: Option<&'a str>,
+    /// Recommended yarn weight, e.g. `DK` or `worsted`
+    pub yarn_weight: Option<&'a str>,
+    /// Gauge the pattern was designed for, e.g. `22 sts x 24 rnds = 10cm`
+    pub gauge: Option<&'a str>,
+    /// License the pattern is published under
+    pub license: Option<&'a str>,
+    /// Where the pattern (or the pattern it was remixed/translated from) was published
+    pub source: Option<&'a str>,
+    /// Who to credit for the pattern
+    pub attribution: Option<&'a str>,
+    /// Whether the rounds are worked in a spiral or joined, see [`crate::Mode`]
+    pub mode: Option<&'a str>,
+    /// The colors used in the pattern, e.g. `A = cream, B = brown`. See [`Self::palette`].
+    pub colors: Option<&'a str>,
+}
+
+impl<'a> PatternMeta<'a> {
+    pub fn get(&self, field: MetaField) -> Option<&'a str> {
+        match field {
+            MetaField::Name => self.name,
+            MetaField::Designer => self.designer,
+            MetaField::HookSize => self.hook_size,
+            MetaField::YarnWeight => self.yarn_weight,
+            MetaField::Gauge => self.gauge,
+            MetaField::License => self.license,
+            MetaField::Source => self.source,
+            MetaField::Attribution => self.attribution,
+            MetaField::Mode => self.mode,
+            MetaField::Colors => self.colors,
+        }
+    }
+
+    pub(crate) fn field_mut(&mut self, field: MetaField) -> &mut Option<&'a str> {
+        match field {
+            MetaField::Name => &mut self.name,
+            MetaField::Designer => &mut self.designer,
+            MetaField::HookSize => &mut self.hook_size,
+            MetaField::YarnWeight => &mut self.yarn_weight,
+            MetaField::Gauge => &mut self.gauge,
+            MetaField::License => &mut self.license,
+            MetaField::Source => &mut self.source,
+            MetaField::Attribution => &mut self.attribution,
+            MetaField::Mode => &mut self.mode,
+            MetaField::Colors => &mut self.colors,
+        }
+    }
+
+    /// The `(key, color)` pairs declared by the `colors:` line, e.g. `[("A", "cream")]` for
+    /// `colors: A = cream`. Instructions switch to a color with e.g. `@A`.
+    pub fn palette(&self) -> Vec<(&'a str, &'a str)> {
+        self.colors.and_then(parse_palette).unwrap_or_default()
+    }
+
+    /// The license and attribution lines that exporters append to the end of a pattern,
+    /// or `None` if the header doesn't say anything about the pattern's provenance.
+    ///
+    /// ```
+    /// # use crochet::PatternMeta;
+    /// let meta = PatternMeta {
+    ///     license: Some("CC-BY-4.0"),
+    ///     source: Some("https://example.com/bear"),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     meta.provenance_footer().unwrap(),
+    ///     "License: CC-BY-4.0\nSource: https://example.com/bear"
+    /// );
+    /// ```
+    pub fn provenance_footer(&self) -> Option<String> {
+        let lines = [
+            ("License", self.license),
+            ("Source", self.source),
+            ("Attribution", self.attribution),
+        ];
+
+        let footer = lines
+            .iter()
+            .filter_map(|(name, val)| Some(format!("{name}: {}", (*val)?)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if footer.is_empty() {
+            None
+        } else {
+            Some(footer)
+        }
+    }
+}
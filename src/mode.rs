@@ -0,0 +1,54 @@
+use crate::Round;
+
+/// How the rounds of a pattern are connected, from a `mode: spiral`, `mode: joined`, or
+/// `mode: rows` header line.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Mode {
+    /// Each round is worked straight on from the previous one, without joining
+    #[default]
+    Spiral,
+    /// Each round is closed with a slip stitch join, and the next round starts with a chain
+    /// that doesn't count as a stitch
+    Joined,
+    /// Flat work: each line is a row, and the work is turned at the end of each row. Each row
+    /// starts with a turning chain that doesn't count as a stitch, and the last chain of a
+    /// foundation chain is the turning chain for the first row worked into it.
+    Rows,
+}
+
+impl Mode {
+    pub const ALL: [Self; 3] = [Self::Spiral, Self::Joined, Self::Rows];
+
+    /// The name used for this mode in a pattern header, e.g. `joined` in `mode: joined`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spiral => "spiral",
+            Self::Joined => "joined",
+            Self::Rows => "rows",
+        }
+    }
+
+    /// Looks up a mode by its [`Self::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    /// How many stitches `round` leaves for the next round to be worked into, not counting
+    /// joining and turning chains. `first` is whether it's the first round of its part.
+    pub(crate) fn stitch_count(self, round: &Round, first: bool) -> u32 {
+        let out = round.output_count();
+
+        match self {
+            Self::Spiral => out,
+            Self::Joined | Self::Rows if !first => out - round.leading_chains(),
+            Self::Joined => out,
+            Self::Rows => out - u32::from(round.trailing_chains() > 0),
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{ParseError, Round};
+
+/// The result of parsing as much of a pattern as possible, see [`crate::parse_rounds_partial`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseOutcome<'a> {
+    /// The rounds before the error, or every round if there wasn't one
+    pub rounds: Vec<Round<'a>>,
+    pub error: Option<ParseError>,
+}
+
+impl<'a> ParseOutcome<'a> {
+    /// Whether the whole source was parsed
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Discards the rounds if there was an error, like [`crate::parse_rounds`]
+    pub fn into_result(self) -> Result<Vec<Round<'a>>, ParseError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.rounds),
+        }
+    }
+}
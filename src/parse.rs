@@ -1,117 +1,899 @@
+use crate::assembly::AssemblyStep;
+use crate::color::parse_palette;
+use crate::include::{Includes, SourceLoader};
 use crate::lex::{TokenKind, TokenStream};
-use crate::Instruction;
+use crate::meta::{MetaField, PatternMeta};
+use crate::pattern::{Part, Pattern};
+use crate::{
+    Dialect, Directive, Instruction, Mode, ParseError, Round, Size, Span, SpanTree, TextBlock,
+};
+use std::collections::HashMap;
 
-/// Possibly modifies the given instruction, by parsing e.g. a repetition number or "in mr" after it
-fn maybe_parse_suffix<'a>(ts: &mut TokenStream<'a>, inst: Instruction<'a>) -> Instruction<'a> {
-    let inst = match ts.peek_kind() {
-        Some(TokenKind::Number(n)) => {
-            ts.next();
-            Instruction::Repeat(inst.into(), n)
+/// State that's carried along while parsing.
+#[derive(Default)]
+pub(crate) struct Context<'a, 'l> {
+    includes: Includes<'a, 'l>,
+    /// Values of the variables bound so far with e.g. `n = 6`
+    vars: HashMap<&'a str, u32>,
+    /// Every variable binding so far, in order, as `(line, name, value)`
+    bindings: Vec<(usize, &'a str, u32)>,
+    /// Which of the counts in e.g. `sc 24(28,32)` to use, or `None` to always use the first one
+    size: Option<Size>,
+    /// Fenced free-text blocks found so far
+    notes: Vec<TextBlock<'a>>,
+    /// Keys of the colors declared in the header, which `@A` color changes have to use
+    palette: Vec<&'a str>,
+    /// The dialect version from the file's `%crochet N%` pragma, if it has one
+    version: Option<u32>,
+    /// The number of the first round of the next list of rounds, if it isn't 1, for parsing some
+    /// rounds from the middle of a pattern on their own
+    first_round: Option<u32>,
+    /// Aliases for keywords, used when lexing included files, and the custom stitches
+    dialect: Dialect,
+}
+
+impl<'a, 'l> Context<'a, 'l> {
+    pub fn with_loader(loader: &'l mut dyn SourceLoader<'a>) -> Self {
+        Self {
+            includes: Includes::new(loader),
+            ..Default::default()
         }
-        _ => inst,
+    }
+
+    pub fn with_size(size: Size) -> Self {
+        Self {
+            size: Some(size),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_dialect(dialect: &Dialect) -> Self {
+        Self {
+            dialect: dialect.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Lexes `source` with this context's dialect
+    pub fn tokenize(&self, source: &'a str) -> TokenStream<'a> {
+        crate::lex::tokenize_with_dialect(source, &self.dialect)
+    }
+
+    /// A context for parsing some rounds from the middle of a pattern on their own, the first of
+    /// which is round `first_round`, with the variables, colors and size that were in effect there
+    pub fn resuming(
+        first_round: u32,
+        vars: HashMap<&'a str, u32>,
+        palette: Vec<&'a str>,
+        size: Option<Size>,
+    ) -> Self {
+        Self {
+            vars,
+            palette,
+            size,
+            first_round: Some(first_round),
+            ..Default::default()
+        }
+    }
+
+    /// Every variable binding so far, in order, as `(line, name, value)`
+    pub fn bindings(&self) -> &[(usize, &'a str, u32)] {
+        &self.bindings
+    }
+
+    /// Whether anything other than rounds was parsed, i.e. a variable binding or text block
+    pub fn found_non_rounds(&self) -> bool {
+        !self.bindings.is_empty() || !self.notes.is_empty()
+    }
+}
+
+/// Parses a number, variable, or parenthesized expression.
+fn parse_factor(ts: &mut TokenStream, cx: &Context) -> Result<i64, ParseError> {
+    const EXPECTED: &[&str] = &["a number", "a variable", "'('"];
+
+    let t = match ts.next() {
+        Some(t) => t,
+        None => return Err(ParseError::expected(ts.current_loc(), EXPECTED)),
+    };
+
+    match t.kind() {
+        TokenKind::Number(n) => Ok(n.into()),
+        TokenKind::Ident(name) => match cx.vars.get(name) {
+            Some(&n) => Ok(n.into()),
+            None => Err(t.source_loc().into()),
+        },
+        TokenKind::LParen => {
+            let value = parse_expr(ts, cx)?;
+            expect(ts, TokenKind::RParen)?;
+            Ok(value)
+        }
+        _ => Err(ParseError::expected(t.source_loc(), EXPECTED)),
+    }
+}
+
+/// Parses a product/quotient of factors.
+fn parse_term(ts: &mut TokenStream, cx: &Context) -> Result<i64, ParseError> {
+    let mut value = parse_factor(ts, cx)?;
+
+    while let Some(op @ (TokenKind::Star | TokenKind::Slash)) = ts.peek_kind() {
+        let op_loc = ts.next().unwrap().source_loc();
+        let rhs = parse_factor(ts, cx)?;
+
+        value = if op == TokenKind::Star {
+            value.checked_mul(rhs).ok_or(op_loc)?
+        } else if rhs != 0 && value % rhs == 0 {
+            value / rhs
+        } else {
+            // stitch counts have to be whole numbers
+            return Err(op_loc.into());
+        };
+    }
+
+    Ok(value)
+}
+
+/// Parses an arithmetic expression like `n*2 - 1`.
+fn parse_expr(ts: &mut TokenStream, cx: &Context) -> Result<i64, ParseError> {
+    let mut value = parse_term(ts, cx)?;
+
+    while let Some(op @ (TokenKind::Plus | TokenKind::Minus)) = ts.peek_kind() {
+        let op_loc = ts.next().unwrap().source_loc();
+        let rhs = parse_term(ts, cx)?;
+
+        value = if op == TokenKind::Plus {
+            value.checked_add(rhs)
+        } else {
+            value.checked_sub(rhs)
+        }
+        .ok_or(op_loc)?;
+    }
+
+    Ok(value)
+}
+
+/// Parses an expression that has to evaluate to a valid count.
+fn parse_count_expr(ts: &mut TokenStream, cx: &Context) -> Result<u32, ParseError> {
+    let loc = ts.current_loc();
+    parse_expr(ts, cx)?.try_into().map_err(|_| loc.into())
+}
+
+/// Parses a count (a number, a variable bound to one, or a parenthesized expression)
+/// if there is one next. The count can be followed by the counts for other sizes, e.g.
+/// `24(28,32)`, in which case the one for the selected size is returned.
+fn maybe_parse_count(ts: &mut TokenStream, cx: &Context) -> Result<Option<u32>, ParseError> {
+    if !matches!(
+        ts.peek_kind(),
+        Some(TokenKind::Number(_) | TokenKind::Ident(_) | TokenKind::LParen)
+    ) {
+        return Ok(None);
+    }
+
+    let loc = ts.current_loc();
+
+    // only single factors, so that e.g. `sc 2` doesn't try to continue into an expression
+    let mut counts = vec![parse_factor(ts, cx)?];
+
+    if let Some(TokenKind::LParen) = ts.peek_kind() {
+        ts.next();
+        loop {
+            counts.push(parse_expr(ts, cx)?);
+
+            match ts.next() {
+                Some(t) if t.kind() == TokenKind::Comma => continue,
+                Some(t) if t.kind() == TokenKind::RParen => break,
+                Some(t) => return Err(t.source_loc().into()),
+                None => return Err(ts.current_loc().into()),
+            }
+        }
+    }
+
+    // counts that don't give alternatives are the same for every size
+    let idx = match cx.size {
+        Some(size) if counts.len() > 1 => size.index(),
+        _ => 0,
+    };
+
+    let count = counts.get(idx).ok_or(loc)?;
+    Ok(Some((*count).try_into().map_err(|_| loc)?))
+}
+
+/// An instruction along with its spans
+type Spanned<'a> = (Instruction<'a>, SpanTree);
+
+/// How the parser puts together the instructions it parses, so that they can be built as boxed
+/// [`Instruction`]s with their spans, or as nodes of an arena without any boxes. Each method is
+/// given the span of the instruction it builds.
+pub(crate) trait Build<'a> {
+    type Inst;
+
+    /// An instruction with no other instructions nested in it
+    fn leaf(&mut self, inst: Instruction<'a>, span: Span) -> Self::Inst;
+    fn repeat(&mut self, inst: Self::Inst, times: u32, span: Span) -> Self::Inst;
+    fn magic_ring(&mut self, inst: Self::Inst, span: Span) -> Self::Inst;
+    fn one_stitch(&mut self, group: Self::Inst, span: Span) -> Self::Inst;
+    fn crossed(&mut self, first: Self::Inst, second: Self::Inst, span: Span) -> Self::Inst;
+    fn group(&mut self, insts: Vec<Self::Inst>, span: Span) -> Self::Inst;
+
+    /// How many chains `inst` is, if it's a `ch` or `ch N`
+    fn chains(&self, inst: &Self::Inst) -> Option<u32>;
+    /// How many stitches `inst` skips, if it's a `skip N`
+    fn skipped(&self, inst: &Self::Inst) -> Option<u32>;
+    /// Merges a `ch N` and the `skip M` right after it into a single chain space
+    fn ch_space(&mut self, ch: Self::Inst, skip: Self::Inst) -> Self::Inst;
+}
+
+/// Builds ordinary boxed instructions, along with their spans
+pub(crate) struct Boxed;
+
+impl<'a> Build<'a> for Boxed {
+    type Inst = Spanned<'a>;
+
+    fn leaf(&mut self, inst: Instruction<'a>, span: Span) -> Spanned<'a> {
+        (inst, SpanTree::leaf(span))
+    }
+
+    fn repeat(&mut self, inst: Spanned<'a>, times: u32, span: Span) -> Spanned<'a> {
+        let children = vec![inst.1];
+        (
+            Instruction::Repeat(inst.0.into(), times),
+            SpanTree { span, children },
+        )
+    }
+
+    fn magic_ring(&mut self, inst: Spanned<'a>, span: Span) -> Spanned<'a> {
+        let children = vec![inst.1];
+        (
+            Instruction::IntoMagicRing(inst.0.into()),
+            SpanTree { span, children },
+        )
+    }
+
+    fn one_stitch(&mut self, group: Spanned<'a>, span: Span) -> Spanned<'a> {
+        let children = vec![group.1];
+        (
+            Instruction::IntoOne(group.0.into()),
+            SpanTree { span, children },
+        )
+    }
+
+    fn crossed(&mut self, first: Spanned<'a>, second: Spanned<'a>, span: Span) -> Spanned<'a> {
+        let children = vec![first.1, second.1];
+        let crossed = Instruction::Crossed(first.0.into(), second.0.into());
+        (crossed, SpanTree { span, children })
+    }
+
+    fn group(&mut self, insts: Vec<Spanned<'a>>, span: Span) -> Spanned<'a> {
+        let (insts, children) = insts.into_iter().unzip();
+        (Instruction::Group(insts), SpanTree { span, children })
+    }
+
+    fn chains(&self, inst: &Spanned<'a>) -> Option<u32> {
+        match &inst.0 {
+            Instruction::Ch => Some(1),
+            Instruction::Repeat(i, n) if **i == Instruction::Ch => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn skipped(&self, inst: &Spanned<'a>) -> Option<u32> {
+        match inst.0 {
+            Instruction::Skip(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn ch_space(&mut self, ch: Spanned<'a>, skip: Spanned<'a>) -> Spanned<'a> {
+        let inst = Instruction::ChSpace {
+            chains: self.chains(&ch).unwrap(),
+            skipped: self.skipped(&skip).unwrap(),
+        };
+        self.leaf(inst, Span::new(ch.1.span.start, skip.1.span.end))
+    }
+}
+
+/// Possibly modifies the given instruction, which starts at `start`, by parsing e.g. a repetition
+/// number or "in mr" after it
+fn maybe_parse_suffix<'a, B: Build<'a>>(
+    ts: &mut TokenStream<'a>,
+    cx: &Context<'a, '_>,
+    b: &mut B,
+    start: (usize, usize),
+    inst: B::Inst,
+) -> Result<B::Inst, ParseError> {
+    let inst = match maybe_parse_count(ts, cx)? {
+        Some(n) => b.repeat(inst, n, Span::new(start, ts.prev_end())),
+        None => inst,
     };
 
     let inst = match ts.peek_kind() {
         Some(TokenKind::InMr) => {
             ts.next();
-            Instruction::IntoMagicRing(inst.into())
+            b.magic_ring(inst, Span::new(start, ts.prev_end()))
         }
         _ => inst,
     };
 
-    inst
+    Ok(inst)
+}
+
+/// Parses the mandatory count after e.g. `skip`
+fn parse_count(ts: &mut TokenStream, cx: &Context) -> Result<u32, ParseError> {
+    maybe_parse_count(ts, cx)?.ok_or(ParseError::expected(ts.current_loc(), &["a number"]))
+}
+
+/// Errors if the next token isn't of the given kind
+fn expect(ts: &mut TokenStream, kind: TokenKind) -> Result<(), ParseError> {
+    match ts.next() {
+        Some(t) if t.kind() == kind => Ok(()),
+        Some(unexpected) => Err(ParseError::expected(
+            unexpected.source_loc(),
+            &[kind.describe()],
+        )),
+        None => Err(ParseError::expected(ts.current_loc(), &[kind.describe()])),
+    }
+}
+
+/// Like [`expect`], for the token closing a list, where another `,` would also have been valid
+fn expect_after_list(ts: &mut TokenStream, kind: TokenKind) -> Result<(), ParseError> {
+    expect(ts, kind).map_err(|mut e| {
+        e.expected.push(TokenKind::Comma.describe());
+        e
+    })
+}
+
+/// Parses a string like the `"arm.crochet"` in `include "arm.crochet"`
+fn expect_str<'a>(ts: &mut TokenStream<'a>) -> Result<&'a str, ParseError> {
+    match ts.next() {
+        Some(t) => match t.kind() {
+            TokenKind::Str(s) => Ok(s),
+            _ => Err(ParseError::expected(t.source_loc(), &["a string"])),
+        },
+        None => Err(ParseError::expected(ts.current_loc(), &["a string"])),
+    }
 }
 
-/// Parses as many comma-separated instructions into a group as possible.
-/// Returns the group when it can't parse another instruction into the group.
+/// Turns each `ch N, skip M` pair into a single chain space
+fn merge_chain_spaces<'a, B: Build<'a>>(b: &mut B, insts: Vec<B::Inst>) -> Vec<B::Inst> {
+    let mut ret: Vec<B::Inst> = Vec::with_capacity(insts.len());
+
+    for inst in insts {
+        let chains = ret.last().and_then(|ch| b.chains(ch));
+
+        match (chains, b.skipped(&inst)) {
+            (Some(_), Some(_)) => {
+                let ch = ret.pop().unwrap();
+                let ch_space = b.ch_space(ch, inst);
+                ret.push(ch_space);
+            }
+            _ => ret.push(inst),
+        }
+    }
+
+    ret
+}
+
+/// Parses as many comma-separated instructions as possible.
+/// Returns the instructions when it can't parse another one.
 /// Errors if it cannot parse at least one instruction.
-fn parse_group<'a>(ts: &mut TokenStream<'a>) -> Result<Instruction<'a>, (usize, usize)> {
+pub(crate) fn parse_list<'a, B: Build<'a>>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+    b: &mut B,
+) -> Result<Vec<B::Inst>, ParseError> {
     let mut insts = Vec::new();
 
     loop {
-        insts.push(parse_inst(ts)?);
+        insts.push(parse_inst(ts, cx, b)?);
 
         match ts.peek_kind() {
             Some(TokenKind::Comma) => ts.next(),
             _ => {
-                return Ok(Instruction::Group(insts));
+                return Ok(merge_chain_spaces(b, insts));
             }
         };
     }
 }
 
+/// Parses a list of instructions into a group, up to and including the `close` token after it.
+/// The group's span is from `start` to the end of `close`.
+fn parse_group<'a, B: Build<'a>>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+    b: &mut B,
+    start: (usize, usize),
+    close: TokenKind,
+) -> Result<B::Inst, ParseError> {
+    let insts = parse_list(ts, cx, b)?;
+    expect_after_list(ts, close)?;
+
+    Ok(b.group(insts, Span::new(start, ts.prev_end())))
+}
+
+/// Parses a single (possibly labeled or repeated) round.
+/// `number` is the one-based number that the round will have, used to check `rounds A-B:` ranges.
+fn parse_round<'a>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+    number: u32,
+) -> Result<Round<'a>, ParseError> {
+    let start = ts.current_loc();
+    let (label, times) = parse_round_start(ts, number)?;
+    let (instructions, instruction_spans) = parse_list(ts, cx, &mut Boxed)?.into_iter().unzip();
+
+    Ok(Round {
+        index: number,
+        label,
+        instructions,
+        times,
+        directives: Vec::new(),
+        span: Span::new(start, ts.prev_end()),
+        instruction_spans,
+    })
+}
+
+/// Parses the label, or how many times the round is worked, at the start of a round, returning
+/// the label (if any) and how many times
+pub(crate) fn parse_round_start<'a>(
+    ts: &mut TokenStream<'a>,
+    number: u32,
+) -> Result<(Option<&'a str>, u32), ParseError> {
+    let mut label = None;
+    let mut times = 1;
+
+    match ts.peek_kind() {
+        Some(TokenKind::Label(l)) => {
+            ts.next();
+            label = Some(l);
+        }
+        Some(TokenKind::RoundRepeat(n)) => {
+            let t = ts.next().unwrap();
+            if n == 0 {
+                return Err(t.source_loc().into());
+            }
+            times = n;
+        }
+        Some(TokenKind::RoundRange(start, end)) => {
+            let t = ts.next().unwrap();
+            // range has to start at the actual round number
+            if start != number || end < start {
+                return Err(t.source_loc().into());
+            }
+            times = end - start + 1;
+        }
+        _ => {}
+    }
+
+    Ok((label, times))
+}
+
 /// Errors if `ts` is empty
-fn parse_inst<'a>(ts: &mut TokenStream<'a>) -> Result<Instruction<'a>, (usize, usize)> {
+fn parse_inst<'a, B: Build<'a>>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+    b: &mut B,
+) -> Result<B::Inst, ParseError> {
     use TokenKind::*;
 
     let next = match ts.next() {
         Some(x) => x,
-        None => return Err(ts.current_loc()),
+        None => return Err(ParseError::expected(ts.current_loc(), &["an instruction"])),
     };
+    let start = next.source_loc();
+    // the span of everything from the start of the instruction to the last token parsed
+    let span = |ts: &TokenStream| Span::new(start, ts.prev_end());
+    // a stitch, which can be followed by a suffix
+    let stitch = match next.kind() {
+        Ch => Some(Instruction::Ch),
+        Sc => Some(Instruction::Sc),
+        Fpsc => Some(Instruction::Fpsc),
+        Bpsc => Some(Instruction::Bpsc),
+        Blsc => Some(Instruction::Blsc),
+        Inc => Some(Instruction::Inc),
+        Flinc => Some(Instruction::Flinc),
+        Blinc => Some(Instruction::Blinc),
+        Dec => Some(Instruction::Dec),
+        Ident(name) => cx
+            .dialect
+            .custom_stitch(name)
+            .cloned()
+            .map(Instruction::Custom),
+        _ => None,
+    };
+    if let Some(stitch) = stitch {
+        let inst = b.leaf(stitch, span(ts));
+        return maybe_parse_suffix(ts, cx, b, start, inst);
+    }
 
     match next.kind() {
-        Ch => Ok(maybe_parse_suffix(ts, Instruction::Ch)),
-        Tch => Ok(Instruction::Tch),
-        Sc => Ok(maybe_parse_suffix(ts, Instruction::Sc)),
-        Fpsc => Ok(maybe_parse_suffix(ts, Instruction::Fpsc)),
-        Bpsc => Ok(maybe_parse_suffix(ts, Instruction::Bpsc)),
-        Blsc => Ok(maybe_parse_suffix(ts, Instruction::Blsc)),
-        Inc => Ok(maybe_parse_suffix(ts, Instruction::Inc)),
-        Flinc => Ok(maybe_parse_suffix(ts, Instruction::Flinc)),
-        Blinc => Ok(maybe_parse_suffix(ts, Instruction::Blinc)),
-        Dec => Ok(maybe_parse_suffix(ts, Instruction::Dec)),
+        Tch => Ok(b.leaf(Instruction::Tch, span(ts))),
         LBracket => {
-            let group = parse_group(ts)?;
+            let group = parse_group(ts, cx, b, start, RBracket)?;
+            maybe_parse_suffix(ts, cx, b, start, group)
+        }
+        Comment(s) => Ok(b.leaf(Instruction::Comment(s), span(ts))),
+        Color(c) if cx.palette.contains(&c) => Ok(b.leaf(Instruction::Color(c), span(ts))),
+        Skip => {
+            let n = parse_count(ts, cx)?;
+            Ok(b.leaf(Instruction::Skip(n), span(ts)))
+        }
+        SurfaceSlSt => {
+            let n = parse_count(ts, cx)?;
+            Ok(b.leaf(Instruction::SurfaceSlSt(n), span(ts)))
+        }
+        // `mr N` and `altmr N` are both shorthand for `sc N in mr`
+        Mr | AltMr => {
+            let n = parse_count(ts, cx)?;
+            let sc = b.leaf(Instruction::Sc, span(ts));
+            let repeat = b.repeat(sc, n, span(ts));
+            Ok(b.magic_ring(repeat, span(ts)))
+        }
+        LParen => {
+            let group = parse_group(ts, cx, b, start, RParen)?;
+            expect(ts, InNextSt)?;
 
-            match ts.next() {
-                Some(t) if t.kind() == RBracket => Ok(maybe_parse_suffix(ts, group)),
-                Some(unexpected) => Err(unexpected.source_loc()),
-                None => Err(ts.current_loc()),
-            }
+            Ok(b.one_stitch(group, span(ts)))
         }
-        Comment(s) => Ok(Instruction::Comment(s)),
-        Skip => match ts.next() {
-            Some(t) => match t.kind() {
-                Number(n) => Ok(Instruction::Skip(n)),
-                _ => Err(t.source_loc()),
-            },
-            None => Err(ts.current_loc()),
+        Cross => {
+            expect(ts, LParen)?;
+            let first = parse_inst(ts, cx, b)?;
+            expect(ts, Comma)?;
+            let second = parse_inst(ts, cx, b)?;
+            expect(ts, RParen)?;
+
+            let crossed = b.crossed(first, second, span(ts));
+            maybe_parse_suffix(ts, cx, b, start, crossed)
+        }
+        Ch | Sc | Fpsc | Bpsc | Blsc | Inc | Flinc | Blinc | Dec | RBracket | RParen | Plus
+        | Minus | Star | Slash | Comma | Newline | Number(_) | InMr | InNextSt | Meta(..)
+        | Section(_) | Label(_) | RoundRepeat(_) | RoundRange(..) | Include | TextBlock(..)
+        | Hook(_) | Yarn | Str(_) | Ident(_) | Binding(_) | Color(_) => {
+            Err(ParseError::expected(next.source_loc(), &["an instruction"]))
+        }
+    }
+}
+
+/// Parses the header like [`parse_meta`], and makes the colors it declares available to the rest
+/// of the pattern.
+pub(crate) fn parse_header<'a>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+) -> Result<PatternMeta<'a>, ParseError> {
+    cx.version = parse_pragma(ts)?;
+    let meta = parse_meta(ts)?;
+
+    // an included file without a palette uses the including file's
+    if meta.colors.is_some() {
+        cx.palette = meta.palette().into_iter().map(|(key, _)| key).collect();
+    }
+
+    Ok(meta)
+}
+
+/// Parses the `%crochet N%` pragma that can be on the first line of a file, returning the
+/// dialect version it selects.
+fn parse_pragma(ts: &mut TokenStream) -> Result<Option<u32>, ParseError> {
+    let t = match ts.peek() {
+        Some(t) if t.source_loc() == (1, 1) => t,
+        _ => return Ok(None),
+    };
+
+    let version = match t.kind() {
+        TokenKind::Comment(s) => match s.strip_prefix("crochet ") {
+            Some(version) => version.trim(),
+            // just a comment at the start of the first round
+            None => return Ok(None),
         },
-        RBracket | Comma | Newline | Number(_) | InMr => Err(next.source_loc()),
+        _ => return Ok(None),
+    };
+
+    let version = match version.parse() {
+        Ok(v @ 1..=crate::LATEST_VERSION) => v,
+        _ => return Err(t.source_loc().into()),
+    };
+    ts.next();
+
+    // the pragma has to be on its own line
+    match ts.peek_kind() {
+        Some(TokenKind::Newline) | None => Ok(Some(version)),
+        _ => Err(ts.current_loc().into()),
     }
 }
 
-/// Parses a list of rounds.
-pub fn parse<'a>(ts: &mut TokenStream<'a>) -> Result<Vec<Instruction<'a>>, (usize, usize)> {
-    while let Some(TokenKind::Newline) = ts.peek_kind() {
-        ts.next();
+/// Parses the (possibly empty) header at the start of a pattern.
+pub fn parse_meta<'a>(ts: &mut TokenStream<'a>) -> Result<PatternMeta<'a>, ParseError> {
+    let mut meta = PatternMeta::default();
+
+    loop {
+        match ts.peek_kind() {
+            Some(TokenKind::Newline) => {
+                ts.next();
+            }
+            Some(TokenKind::Meta(field, value)) => {
+                let t = ts.next().unwrap();
+                let slot = meta.field_mut(field);
+
+                // each field can only be given once
+                if slot.is_some() {
+                    return Err(t.source_loc().into());
+                }
+                if field == MetaField::Mode && Mode::from_name(value).is_none() {
+                    return Err(t.source_loc().into());
+                }
+                if field == MetaField::Colors && parse_palette(value).is_none() {
+                    return Err(t.source_loc().into());
+                }
+                *slot = Some(value);
+            }
+            _ => return Ok(meta),
+        }
     }
+}
 
-    let mut rounds = Vec::new();
+/// Parses an `include "path"` line, returning the rounds of the included file.
+fn parse_include<'a>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+) -> Result<Vec<Round<'a>>, ParseError> {
+    let loc = ts.current_loc();
+    expect(ts, TokenKind::Include)?;
 
-    while ts.peek().is_some() {
-        rounds.push(parse_group(ts)?);
+    let path = expect_str(ts)?;
 
-        if !matches!(ts.peek_kind(), Some(TokenKind::Newline)) && !ts.is_empty() {
-            return Err(ts.current_loc());
+    // errors in the included file are reported at the include line, since locations in
+    // a different file wouldn't make sense to the caller
+    let (source, file) = cx.includes.start(path).ok_or(loc)?;
+    let mut included_ts = cx.tokenize(source);
+    let version = cx.version;
+    let res = parse_pattern_with(&mut included_ts, cx);
+    cx.version = version;
+    cx.includes.finish();
+
+    let mut pattern = res.map_err(|_| loc)?;
+    if !included_ts.is_empty() {
+        return Err(loc.into());
+    }
+    cx.notes.append(&mut pattern.notes);
+
+    // only the rounds of a single-part file can be spliced into the current part
+    match &mut pattern.parts[..] {
+        [part] if part.name.is_none() && pattern.assembly.is_empty() => {
+            let mut rounds = std::mem::take(&mut part.rounds);
+            // rounds from files this one included are already marked with their own file
+            for tree in rounds.iter_mut().flat_map(|r| &mut r.instruction_spans) {
+                tree.move_to_file(file);
+            }
+            Ok(rounds)
         }
+        _ => Err(loc.into()),
+    }
+}
+
+/// Parses a list of rounds one at a time, up until the end of input or the start of the next
+/// part.
+pub(crate) struct RoundParser<'a> {
+    /// The number the next round will have
+    number: u32,
+    /// Hook and yarn changes waiting for the round they come before
+    directives: Vec<Directive<'a>>,
+    directives_loc: Option<(usize, usize)>,
+    /// Rounds of an included file that haven't been returned yet
+    included: std::vec::IntoIter<Round<'a>>,
+}
+
+impl<'a> RoundParser<'a> {
+    pub fn new(ts: &mut TokenStream<'a>, cx: &mut Context<'a, '_>) -> Self {
         while let Some(TokenKind::Newline) = ts.peek_kind() {
             ts.next();
         }
+
+        Self {
+            number: cx.first_round.take().unwrap_or(1),
+            directives: Vec::new(),
+            directives_loc: None,
+            included: Vec::new().into_iter(),
+        }
+    }
+
+    /// Parses the next round, or returns `None` if there aren't any more in this list
+    pub fn next_round(
+        &mut self,
+        ts: &mut TokenStream<'a>,
+        cx: &mut Context<'a, '_>,
+    ) -> Result<Option<Round<'a>>, ParseError> {
+        loop {
+            if let Some(round) = self.included.next() {
+                return Ok(Some(round));
+            }
+
+            if matches!(ts.peek_kind(), None | Some(TokenKind::Section(_))) {
+                // a change has to be followed by a round to apply to
+                return match self.directives_loc.take() {
+                    Some(loc) => Err(loc.into()),
+                    None => Ok(None),
+                };
+            }
+
+            let round = self.parse_line(ts, cx)?;
+
+            if !matches!(ts.peek_kind(), Some(TokenKind::Newline)) && !ts.is_empty() {
+                return Err(ParseError::expected(
+                    ts.current_loc(),
+                    &[TokenKind::Comma.describe(), TokenKind::Newline.describe()],
+                ));
+            }
+            while let Some(TokenKind::Newline) = ts.peek_kind() {
+                ts.next();
+            }
+
+            if round.is_some() {
+                return Ok(round);
+            }
+        }
+    }
+
+    /// Parses a single line, returning the round on it if it's a round
+    fn parse_line(
+        &mut self,
+        ts: &mut TokenStream<'a>,
+        cx: &mut Context<'a, '_>,
+    ) -> Result<Option<Round<'a>>, ParseError> {
+        match ts.peek_kind() {
+            Some(TokenKind::Include) => {
+                let start = ts.current_loc();
+                let mut included = parse_include(ts, cx)?;
+                let span = Span::new(start, ts.prev_end());
+
+                for round in &mut included {
+                    round.index = self.number;
+                    round.span = span;
+                    if !self.directives.is_empty() {
+                        self.directives.append(&mut round.directives);
+                        round.directives = std::mem::take(&mut self.directives);
+                        self.directives_loc = None;
+                    }
+                    self.number = self.number.checked_add(round.times).ok_or(start)?;
+                }
+                self.included = included.into_iter();
+            }
+            Some(TokenKind::TextBlock(name, text)) => {
+                ts.next();
+                cx.notes.push(TextBlock { name, text });
+            }
+            Some(TokenKind::Hook(size)) => {
+                self.directives_loc.get_or_insert(ts.current_loc());
+                ts.next();
+                self.directives.push(Directive::Hook(size));
+            }
+            Some(TokenKind::Yarn) => {
+                self.directives_loc.get_or_insert(ts.current_loc());
+                ts.next();
+                self.directives.push(Directive::Yarn(expect_str(ts)?));
+            }
+            Some(TokenKind::Binding(name)) => {
+                let line = ts.current_loc().0;
+                ts.next();
+                let value = parse_count_expr(ts, cx)?;
+                cx.vars.insert(name, value);
+                cx.bindings.push((line, name, value));
+            }
+            _ => {
+                let mut round = parse_round(ts, cx, self.number)?;
+                round.directives = std::mem::take(&mut self.directives);
+                self.directives_loc = None;
+                self.number = self
+                    .number
+                    .checked_add(round.times)
+                    .ok_or(round.span.start)?;
+                return Ok(Some(round));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Parses a list of rounds, up until the end of input or the start of the next part.
+pub(crate) fn parse_with<'a>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+) -> Result<Vec<Round<'a>>, ParseError> {
+    let mut parser = RoundParser::new(ts, cx);
+    let mut rounds = Vec::new();
+
+    while let Some(round) = parser.next_round(ts, cx)? {
+        rounds.push(round);
     }
 
     Ok(rounds)
 }
 
+/// Parses a whole pattern: the header, followed by one or more parts.
+pub fn parse_pattern<'a>(ts: &mut TokenStream<'a>) -> Result<Pattern<'a>, ParseError> {
+    parse_pattern_with(ts, &mut Context::default())
+}
+
+/// Like [`parse_pattern`], but with the given parsing context (e.g. to resolve `include` lines).
+pub(crate) fn parse_pattern_with<'a>(
+    ts: &mut TokenStream<'a>,
+    cx: &mut Context<'a, '_>,
+) -> Result<Pattern<'a>, ParseError> {
+    let meta = parse_header(ts, cx)?;
+    let mut parts = Vec::new();
+    let mut steps = Vec::new();
+
+    // rounds that come before the first `== Name ==` line go in an unnamed part
+    let rounds = parse_with(ts, cx)?;
+    if !rounds.is_empty() || !matches!(ts.peek_kind(), Some(TokenKind::Section(_))) {
+        parts.push(Part { name: None, rounds });
+    }
+
+    while let Some(TokenKind::Section(name)) = ts.peek_kind() {
+        ts.next();
+
+        // section header has to be on its own line
+        if !matches!(ts.peek_kind(), Some(TokenKind::Newline)) && !ts.is_empty() {
+            return Err(ParseError::expected(
+                ts.current_loc(),
+                &[TokenKind::Newline.describe()],
+            ));
+        }
+
+        if name.eq_ignore_ascii_case("assembly") {
+            // the steps are free-form text, so they're read a line at a time instead of lexed
+            ts.next();
+            while let Some((line, span)) = ts.text_line() {
+                if !line.is_empty() {
+                    steps.push((line, span));
+                }
+            }
+            continue;
+        }
+
+        parts.push(Part {
+            name: Some(name),
+            rounds: parse_with(ts, cx)?,
+        });
+    }
+
+    let part_names: Vec<_> = parts.iter().filter_map(|p| p.name).collect();
+    let assembly = steps
+        .into_iter()
+        .map(|(text, span)| AssemblyStep::new(text, span, &part_names))
+        .collect();
+
+    Ok(Pattern {
+        meta,
+        parts,
+        assembly,
+        version: cx.version.unwrap_or(crate::LATEST_VERSION),
+        // when this is an included file, these are put back by `parse_include`
+        notes: std::mem::take(&mut cx.notes),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse<'a>(ts: &mut TokenStream<'a>) -> Result<Vec<Round<'a>>, (usize, usize)> {
+        parse_with(ts, &mut Context::default()).map_err(|e| e.loc())
+    }
+
+    /// Parses rounds, keeping only their instructions
+    fn parse_insts<'a>(
+        ts: &mut TokenStream<'a>,
+    ) -> Result<Vec<Vec<Instruction<'a>>>, (usize, usize)> {
+        parse(ts).map(|rounds| rounds.into_iter().map(|r| r.instructions).collect())
+    }
+
     #[test]
     fn test_group() {
         let mut ts = crate::lex::tokenize("[sc, inc, dec]");
         let ast = Instruction::Group(vec![Instruction::Sc, Instruction::Inc, Instruction::Dec]);
-        assert_eq!(parse_inst(&mut ts), Ok(ast));
+        assert_eq!(
+            parse_inst(&mut ts, &mut Context::default(), &mut Boxed).map(|(i, _)| i),
+            Ok(ast)
+        );
     }
 
     #[test]
@@ -120,7 +902,10 @@ mod tests {
 
         let mut ts = crate::lex::tokenize("[inc 2, sc] 3");
         let ast = Repeat(Group(vec![Repeat(Inc.into(), 2), Sc]).into(), 3);
-        assert_eq!(parse_inst(&mut ts), Ok(ast));
+        assert_eq!(
+            parse_inst(&mut ts, &mut Context::default(), &mut Boxed).map(|(i, _)| i),
+            Ok(ast)
+        );
     }
 
     #[test]
@@ -128,8 +913,53 @@ mod tests {
         use Instruction::*;
 
         let mut ts = crate::lex::tokenize("sc\nsc 2, inc");
-        let rounds = vec![Group(vec![Sc]), Group(vec![Repeat(Sc.into(), 2), Inc])];
-        assert_eq!(parse(&mut ts), Ok(rounds));
+        let rounds = vec![vec![Sc], vec![Repeat(Sc.into(), 2), Inc]];
+        assert_eq!(parse_insts(&mut ts), Ok(rounds));
+    }
+
+    #[test]
+    fn test_round_index_and_span() {
+        let mut ts = crate::lex::tokenize("sc 6 in mr\n\n  x2: inc 6 \nn = 3\nears: [inc, sc] 6");
+        let rounds = parse(&mut ts).unwrap();
+
+        let indices: Vec<_> = rounds.iter().map(|r| r.index).collect();
+        assert_eq!(indices, [1, 2, 4]);
+
+        let spans: Vec<_> = rounds.iter().map(|r| r.span).collect();
+        assert_eq!(
+            spans,
+            [
+                Span::new((1, 1), (1, 11)),
+                Span::new((3, 3), (3, 12)),
+                Span::new((5, 1), (5, 18)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instruction_spans() {
+        let mut ts = crate::lex::tokenize(
+            "mr 6\n[inc, sc] 3, cross(fpsc, sc)\n(sc, ch 2, skip 1) in next st, ch 3, skip 3",
+        );
+        let rounds = parse(&mut ts).unwrap();
+
+        let span = |line, start, end| Some(Span::new((line, start), (line, end)));
+
+        // the shorthand `mr 6` is three nested instructions
+        assert_eq!(rounds[0].instruction_span(&[0]), span(1, 1, 5));
+        assert_eq!(rounds[0].instruction_span(&[0, 0, 0]), span(1, 1, 5));
+
+        assert_eq!(rounds[1].instruction_span(&[0]), span(2, 1, 12));
+        assert_eq!(rounds[1].instruction_span(&[0, 0]), span(2, 1, 10));
+        assert_eq!(rounds[1].instruction_span(&[0, 0, 1]), span(2, 7, 9));
+        assert_eq!(rounds[1].instruction_span(&[1]), span(2, 14, 29));
+        assert_eq!(rounds[1].instruction_span(&[1, 1]), span(2, 26, 28));
+        assert_eq!(rounds[1].instruction_span(&[1, 2]), None);
+
+        assert_eq!(rounds[2].instruction_span(&[0]), span(3, 1, 30));
+        assert_eq!(rounds[2].instruction_span(&[0, 0]), span(3, 1, 19));
+        assert_eq!(rounds[2].instruction_span(&[0, 0, 1]), span(3, 6, 18));
+        assert_eq!(rounds[2].instruction_span(&[1]), span(3, 32, 44));
     }
 
     #[test]
@@ -138,11 +968,189 @@ mod tests {
 
         let mut ts = crate::lex::tokenize("\n\n\nsc 2\ninc\n\nsc 123");
         let rounds = vec![
-            Group(vec![Repeat(Sc.into(), 2)]),
-            Group(vec![Inc]),
-            Group(vec![Repeat(Sc.into(), 123)]),
+            vec![Repeat(Sc.into(), 2)],
+            vec![Inc],
+            vec![Repeat(Sc.into(), 123)],
+        ];
+        assert_eq!(parse_insts(&mut ts), Ok(rounds));
+    }
+
+    #[test]
+    fn test_labeled_rounds() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nears: sc 4, inc 2");
+        let rounds = parse(&mut ts).unwrap();
+        assert_eq!(rounds[0].label, None);
+        assert_eq!(rounds[1].label, Some("ears"));
+        assert_eq!(
+            rounds[1].instructions,
+            [Repeat(Sc.into(), 4), Repeat(Inc.into(), 2)]
+        );
+        assert_eq!(rounds[1].span, Span::new((2, 1), (2, 18)));
+
+        // label has to be followed by instructions
+        let mut ts = crate::lex::tokenize("ears:\nsc");
+        assert_eq!(parse(&mut ts), Err((1, 6)));
+    }
+
+    #[test]
+    fn test_repeated_rounds() {
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nx2: sc 6\nrounds 4-8: sc 6\nsc 6");
+        let times: Vec<_> = parse(&mut ts).unwrap().iter().map(|r| r.times).collect();
+        assert_eq!(times, [1, 2, 5, 1]);
+
+        // range doesn't start at the right round
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nrounds 3-4: sc 6");
+        assert_eq!(parse(&mut ts), Err((2, 1)));
+
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nrounds 2-1: sc 6");
+        assert_eq!(parse(&mut ts), Err((2, 1)));
+
+        let mut ts = crate::lex::tokenize("x0: sc 6");
+        assert_eq!(parse(&mut ts), Err((1, 1)));
+
+        // more rounds than can be numbered
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nx4294967295: sc 6\nsc 6");
+        assert_eq!(parse(&mut ts), Err((2, 1)));
+    }
+
+    #[test]
+    fn test_include() {
+        let mut loader = |path: &str| match path {
+            "ring" => Some("sc 6 in mr"),
+            "body" => Some("include \"ring\"\ninc 6"),
+            "loop" => Some("include \"loop\""),
+            "parts" => Some("== A ==\nsc 6 in mr"),
+            "bad" => Some("sc 6 in mr, ]"),
+            _ => None,
+        };
+
+        let mut ts = crate::lex::tokenize("include \"body\"\nx2: sc 12\ninclude \"ring\"");
+        let rounds = parse_with(&mut ts, &mut Context::with_loader(&mut loader)).unwrap();
+        let counts: Vec<_> = rounds.iter().map(Round::output_count).collect();
+        assert_eq!(counts, [6, 12, 12, 6]);
+
+        let bad_includes = [
+            "sc 6 in mr\ninclude \"loop\"",
+            "sc 6 in mr\ninclude \"parts\"",
+            "sc 6 in mr\ninclude \"bad\"",
+            "sc 6 in mr\ninclude \"missing\"",
+        ];
+        for src in bad_includes {
+            let mut ts = crate::lex::tokenize(src);
+            let res = parse_with(&mut ts, &mut Context::with_loader(&mut loader));
+            assert_eq!(res.map_err(|e| e.loc()), Err((2, 1)));
+        }
+
+        // includes aren't supported without a loader
+        let mut ts = crate::lex::tokenize("include \"ring\"");
+        assert_eq!(parse(&mut ts), Err((1, 1)));
+
+        let mut ts = crate::lex::tokenize("include ring");
+        assert_eq!(
+            parse_with(&mut ts, &mut Context::with_loader(&mut loader)).map_err(|e| e.loc()),
+            Err((1, 9))
+        );
+    }
+
+    #[test]
+    fn test_directives() {
+        let mut ts = crate::lex::tokenize(
+            "yarn \"white\"\nsc 6 in mr\ninc 6\nhook 4mm\nyarn \"DK blue\"\nx2: sc 12",
+        );
+        let rounds = parse(&mut ts).unwrap();
+        assert_eq!(rounds[0].directives, vec![Directive::Yarn("white")]);
+        assert_eq!(rounds[1].directives, vec![]);
+        assert_eq!(
+            rounds[2].directives,
+            vec![Directive::Hook("4mm"), Directive::Yarn("DK blue")]
+        );
+
+        // a change needs a round after it
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nhook 4mm\n\n");
+        assert_eq!(parse(&mut ts), Err((2, 1)));
+
+        let mut ts = crate::lex::tokenize("yarn blue\nsc 6 in mr");
+        assert_eq!(parse(&mut ts), Err((1, 6)));
+    }
+
+    #[test]
+    fn test_variables() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("n = 6\nsc n in mr\nn = 3\n[inc, sc] n, skip n");
+        let rounds = vec![
+            vec![IntoMagicRing(Repeat(Sc.into(), 6).into())],
+            vec![Repeat(Group(vec![Inc, Sc]).into(), 3), Skip(3)],
         ];
-        assert_eq!(parse(&mut ts), Ok(rounds));
+        assert_eq!(parse_insts(&mut ts), Ok(rounds));
+
+        // undefined variable
+        let mut ts = crate::lex::tokenize("n = 6\nsc m");
+        assert_eq!(parse(&mut ts), Err((2, 4)));
+
+        // variables can be used in included files
+        let mut loader = |_: &str| Some("sc n in mr");
+        let mut ts = crate::lex::tokenize("n = 8\ninclude \"ring\"");
+        let rounds = parse_with(&mut ts, &mut Context::with_loader(&mut loader)).unwrap();
+        assert_eq!(rounds[0].output_count(), 8);
+
+        // names that start a directive are still bound as variables
+        let mut ts = crate::lex::tokenize("hook = 6\nsc hook in mr");
+        let rounds = vec![vec![IntoMagicRing(Repeat(Sc.into(), 6).into())]];
+        assert_eq!(parse_insts(&mut ts), Ok(rounds));
+    }
+
+    #[test]
+    fn test_expressions() {
+        let counts = |src| -> Vec<u32> {
+            let mut ts = crate::lex::tokenize(src);
+            parse(&mut ts)
+                .unwrap()
+                .iter()
+                .map(Round::output_count)
+                .collect()
+        };
+
+        assert_eq!(counts("n = 3\nsc (n*2 - 1) in mr"), [5]);
+        assert_eq!(counts("n = 4\nm = n * (n + 2) / 3 - 1\nsc m in mr"), [7]);
+        assert_eq!(counts("sc (12 / 4 / 3) in mr"), [1]);
+
+        let error = |src| {
+            let mut ts = crate::lex::tokenize(src);
+            parse(&mut ts).unwrap_err()
+        };
+
+        // negative
+        assert_eq!(error("sc (2 - 3) in mr"), (1, 4));
+        // division by 0 and non-whole division
+        assert_eq!(error("sc (2 / 0) in mr"), (1, 7));
+        assert_eq!(error("n = 5 / 2"), (1, 7));
+        // unclosed paren
+        assert_eq!(error("sc (2 + 3"), (1, 10));
+        // missing operand
+        assert_eq!(error("sc (2 + ) in mr"), (1, 9));
+    }
+
+    #[test]
+    fn test_sizes() {
+        let counts = |src, size: Option<Size>| -> Result<Vec<u32>, _> {
+            let mut ts = crate::lex::tokenize(src);
+            let mut cx = size.map(Context::with_size).unwrap_or_default();
+            let rounds = parse_with(&mut ts, &mut cx).map_err(|e| e.loc())?;
+            Ok(rounds.iter().map(Round::output_count).collect())
+        };
+
+        let src = "n = 2\nsc 6(8, n*5) in mr\n[inc, sc 2(3,4)] 6";
+        assert_eq!(counts(src, None), Ok(vec![6, 24]));
+        assert_eq!(counts(src, Some(Size::S)), Ok(vec![6, 24]));
+        assert_eq!(counts(src, Some(Size::M)), Ok(vec![8, 30]));
+        assert_eq!(counts(src, Some(Size::L)), Ok(vec![10, 36]));
+
+        // no count given for size L
+        assert_eq!(counts("sc 6(8) in mr", Some(Size::L)), Err((1, 4)));
+        assert_eq!(counts("sc 6(8 in mr", None), Err((1, 8)));
     }
 
     #[test]
@@ -151,6 +1159,240 @@ mod tests {
         assert_eq!(parse(&mut ts), Err((2, 7)));
     }
 
+    #[test]
+    fn test_mr_shorthand() {
+        use Instruction::*;
+
+        for src in ["mr 6", "altmr 6", "sc 6 in mr"] {
+            let mut ts = crate::lex::tokenize(src);
+            let ring = IntoMagicRing(Repeat(Sc.into(), 6).into());
+            assert_eq!(parse_insts(&mut ts), Ok(vec![vec![ring]]));
+        }
+
+        let mut ts = crate::lex::tokenize("mr, sc");
+        assert_eq!(parse(&mut ts), Err((1, 3)));
+    }
+
+    #[test]
+    fn test_surface_sl_st() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("sc 6, surface sl st 4");
+        let rounds = vec![vec![Repeat(Sc.into(), 6), SurfaceSlSt(4)]];
+        assert_eq!(parse_insts(&mut ts), Ok(rounds));
+
+        let mut ts = crate::lex::tokenize("surface sl st");
+        assert_eq!(parse(&mut ts), Err((1, 14)));
+    }
+
+    #[test]
+    fn test_crossed() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("cross(fpsc, sc) 3");
+        let ast = Repeat(Crossed(Fpsc.into(), Sc.into()).into(), 3);
+        assert_eq!(
+            parse_inst(&mut ts, &mut Context::default(), &mut Boxed).map(|(i, _)| i),
+            Ok(ast)
+        );
+
+        let mut ts = crate::lex::tokenize("cross(sc sc)");
+        assert_eq!(
+            parse_inst(&mut ts, &mut Context::default(), &mut Boxed)
+                .map(|(i, _)| i)
+                .map_err(|e| e.loc()),
+            Err((1, 10))
+        );
+    }
+
+    #[test]
+    fn test_meta() {
+        let mut ts = crate::lex::tokenize(
+            "\nname: Ball\nhook size : 4mm\nlicense: CC0\nsource: a book\n\nsc 6 in mr",
+        );
+        let meta = PatternMeta {
+            name: Some("Ball"),
+            hook_size: Some("4mm"),
+            license: Some("CC0"),
+            source: Some("a book"),
+            ..Default::default()
+        };
+        assert_eq!(parse_meta(&mut ts), Ok(meta));
+        assert_eq!(parse(&mut ts).map(|r| r.len()), Ok(1));
+
+        let mut ts = crate::lex::tokenize("license: CC0\nlicense: CC0");
+        assert_eq!(parse_meta(&mut ts).map_err(|e| e.loc()), Err((2, 1)));
+
+        let mut ts = crate::lex::tokenize("mode: joined");
+        assert_eq!(parse_pattern(&mut ts).unwrap().mode(), Mode::Joined);
+        let mut ts = crate::lex::tokenize("name: Ball\nmode: sideways");
+        assert_eq!(parse_meta(&mut ts).map_err(|e| e.loc()), Err((2, 1)));
+
+        // the header has to come before any rounds
+        let mut ts = crate::lex::tokenize("sc 6 in mr\nlicense: CC0");
+        assert_eq!(parse_meta(&mut ts), Ok(PatternMeta::default()));
+        assert_eq!(parse(&mut ts), Err((2, 1)));
+    }
+
+    #[test]
+    fn test_chain_space() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("sc, ch 2, skip 2, sc, ch, skip 1, skip 1");
+        let ast = Group(vec![
+            Sc,
+            ChSpace {
+                chains: 2,
+                skipped: 2,
+            },
+            Sc,
+            ChSpace {
+                chains: 1,
+                skipped: 1,
+            },
+            Skip(1),
+        ]);
+        assert_eq!(
+            parse_list(&mut ts, &mut Context::default(), &mut Boxed)
+                .map(|insts| Group(insts.into_iter().map(|(i, _)| i).collect())),
+            Ok(ast)
+        );
+    }
+
+    #[test]
+    fn test_into_one() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("(sc, ch 2, sc) in next st");
+        let ast = IntoOne(Group(vec![Sc, Repeat(Ch.into(), 2), Sc]).into());
+        assert_eq!(
+            parse_inst(&mut ts, &mut Context::default(), &mut Boxed).map(|(i, _)| i),
+            Ok(ast)
+        );
+
+        let mut ts = crate::lex::tokenize("(sc, ch 2, sc)");
+        assert_eq!(
+            parse_inst(&mut ts, &mut Context::default(), &mut Boxed)
+                .map(|(i, _)| i)
+                .map_err(|e| e.loc()),
+            Err((1, 15))
+        );
+    }
+
+    #[test]
+    fn test_parts() {
+        use Instruction::*;
+
+        let mut ts = crate::lex::tokenize("sc 6 in mr\n== Arm ==\n\nsc\n== Leg ==\n== Tail ==\nsc");
+        let pattern = parse_pattern(&mut ts).unwrap();
+        let names: Vec<_> = pattern.parts.iter().map(|p| p.name).collect();
+        assert_eq!(names, [None, Some("Arm"), Some("Leg"), Some("Tail")]);
+        assert_eq!(pattern.parts[1].rounds[0].instructions, vec![Sc]);
+        assert!(pattern.parts[2].rounds.is_empty());
+
+        // no unnamed part if the first part is named
+        let mut ts = crate::lex::tokenize("\n== Arm ==\nsc");
+        assert_eq!(parse_pattern(&mut ts).unwrap().parts.len(), 1);
+
+        let mut ts = crate::lex::tokenize("");
+        assert_eq!(parse_pattern(&mut ts).unwrap().parts.len(), 1);
+
+        let mut ts = crate::lex::tokenize("== Arm == sc");
+        assert_eq!(parse_pattern(&mut ts).map_err(|e| e.loc()), Err((1, 11)));
+    }
+
+    #[test]
+    fn test_text_blocks() {
+        let src = "name: Ball\n```materials\nyarn\n```\nsc 6 in mr\n```\n% not a comment %\n```\ninc 6\n== Part ==\n```notes\n```";
+        let mut ts = crate::lex::tokenize(src);
+        let pattern = parse_pattern(&mut ts).unwrap();
+
+        assert_eq!(pattern.parts[0].rounds.len(), 2);
+        assert_eq!(
+            pattern.notes,
+            [
+                TextBlock {
+                    name: "materials",
+                    text: "yarn"
+                },
+                TextBlock {
+                    name: "",
+                    text: "% not a comment %"
+                },
+                TextBlock {
+                    name: "notes",
+                    text: ""
+                },
+            ]
+        );
+
+        // a block has to be on its own lines
+        let mut ts = crate::lex::tokenize("sc 6 in mr\n```notes\n``` sc");
+        assert_eq!(parse(&mut ts), Err((3, 5)));
+    }
+
+    #[test]
+    fn test_pragma() {
+        let mut ts = crate::lex::tokenize("%crochet 1%\nname: Ball\nsc 6 in mr");
+        let pattern = parse_pattern(&mut ts).unwrap();
+        assert_eq!(pattern.version, 1);
+        assert_eq!(pattern.meta.name, Some("Ball"));
+
+        // anywhere but the first line, it's just a comment
+        let mut ts = crate::lex::tokenize("sc 6 in mr\n%crochet 1%");
+        let rounds = &parse_pattern(&mut ts).unwrap().parts[0].rounds;
+        assert_eq!(rounds[1].instructions, [Instruction::Comment("crochet 1")]);
+        let mut ts = crate::lex::tokenize("%crochet 1%, sc 6 in mr");
+        assert_eq!(parse_pattern(&mut ts).map_err(|e| e.loc()), Err((1, 12)));
+
+        // unknown version
+        let mut ts = crate::lex::tokenize("%crochet 999%\nsc 6 in mr");
+        assert_eq!(parse_pattern(&mut ts).map_err(|e| e.loc()), Err((1, 1)));
+
+        // ordinary comment
+        let mut ts = crate::lex::tokenize("%crocheted by me%, sc 6 in mr");
+        assert_eq!(parse_pattern(&mut ts).unwrap().parts[0].rounds.len(), 1);
+    }
+
+    #[test]
+    fn test_colors() {
+        let mut ts = crate::lex::tokenize("colors: A = cream, B = brown\nsc 6 in mr\n@B, inc 6");
+        let pattern = parse_pattern(&mut ts).unwrap();
+        assert_eq!(
+            pattern.parts[0].rounds[1].instructions,
+            [
+                Instruction::Color("B"),
+                Instruction::Repeat(Instruction::Inc.into(), 6)
+            ]
+        );
+
+        // colors have to be in the palette
+        let mut ts = crate::lex::tokenize("colors: A = cream\nsc 6 in mr, @B");
+        assert_eq!(parse_pattern(&mut ts).map_err(|e| e.loc()), Err((2, 13)));
+        let mut ts = crate::lex::tokenize("sc 6 in mr, @A");
+        assert_eq!(parse_pattern(&mut ts).map_err(|e| e.loc()), Err((1, 13)));
+
+        let mut ts = crate::lex::tokenize("colors: A cream");
+        assert_eq!(parse_pattern(&mut ts).map_err(|e| e.loc()), Err((1, 1)));
+    }
+
+    #[test]
+    fn test_assembly() {
+        let src = "== Arm ==\nsc 6 in mr\n== Assembly ==\nsew Arm to Body [round 4]\n\n  stuff (lightly)\n== Body ==\nsc 6 in mr";
+        let mut ts = crate::lex::tokenize(src);
+        let pattern = parse_pattern(&mut ts).unwrap();
+
+        let names: Vec<_> = pattern.parts.iter().map(|p| p.name).collect();
+        assert_eq!(names, [Some("Arm"), Some("Body")]);
+
+        let steps: Vec<_> = pattern.assembly.iter().map(|s| s.text).collect();
+        assert_eq!(steps, ["sew Arm to Body [round 4]", "stuff (lightly)"]);
+        // parts defined after the assembly section can be referenced too
+        assert_eq!(pattern.assembly[0].parts, ["Arm", "Body"]);
+        assert_eq!(pattern.assembly[0].rounds, Some((4, 4)));
+        assert_eq!(pattern.assembly[1].span, Span::new((6, 3), (6, 18)));
+    }
+
     #[test]
     fn test_skip_must_have_count() {
         let mut ts = crate::lex::tokenize("sc, skip, sc");
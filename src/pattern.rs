@@ -0,0 +1,273 @@
+use crate::assembly::AssemblyStep;
+use crate::binary;
+use crate::color::count_colors;
+use crate::meta::PatternMeta;
+use crate::{ColorCounts, Instruction, Mode, ParseError, Round, SourceLoader, Span, TextBlock};
+
+/// One separately-worked piece of a pattern, e.g. the head of an amigurumi.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Part<'a> {
+    /// The name from the part's `== Name ==` line, or `None` for rounds that come before any
+    /// named part.
+    pub name: Option<&'a str>,
+    pub rounds: Vec<Round<'a>>,
+}
+
+impl Part<'_> {
+    /// Numbers the part's rounds in order starting from 1, the same way the parser does, e.g.
+    /// after rounds have been added or removed
+    pub fn renumber(&mut self) {
+        let mut number = 1;
+        for round in &mut self.rounds {
+            round.index = number;
+            number = number.saturating_add(round.times);
+        }
+    }
+}
+
+/// A whole parsed pattern file.
+///
+/// ```
+/// # use crochet::parse_pattern;
+/// let src = "
+///     name: Snowman
+///
+///     == Head ==
+///     sc 6 in mr
+///     inc 6
+///
+///     == Body ==
+///     sc 8 in mr
+/// ";
+///
+/// let pattern = parse_pattern(src).unwrap();
+/// assert_eq!(pattern.meta.name, Some("Snowman"));
+/// assert_eq!(pattern.parts.len(), 2);
+/// assert_eq!(pattern.parts[0].name, Some("Head"));
+/// assert_eq!(pattern.parts[1].rounds.len(), 1);
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub meta: PatternMeta<'a>,
+    pub parts: Vec<Part<'a>>,
+    /// The steps from the pattern's `== Assembly ==` section, if it has one
+    pub assembly: Vec<AssemblyStep<'a>>,
+    /// The dialect version selected by the `%crochet N%` pragma on the first line, or
+    /// [`crate::LATEST_VERSION`] if there isn't one
+    pub version: u32,
+    /// Fenced free-text blocks (materials, notes, ...) from anywhere in the pattern, in order
+    pub notes: Vec<TextBlock<'a>>,
+}
+
+impl<'a> Pattern<'a> {
+    /// Parses a pattern's header and all of its parts. Same as [`crate::parse_pattern`].
+    ///
+    /// ```
+    /// # use crochet::Pattern;
+    /// let pattern = Pattern::parse("name: Ball\n== Ball ==\nsc 6 in mr\ninc 6").unwrap();
+    /// assert_eq!(pattern.meta.name, Some("Ball"));
+    /// assert_eq!(pattern.part("Ball").unwrap().rounds[1].output_count(), 12);
+    /// ```
+    pub fn parse(source: &'a str) -> Result<Self, ParseError> {
+        crate::parse_pattern(source)
+    }
+
+    /// Like [`Self::parse`], but resolves `include` lines with `loader`. Same as
+    /// [`crate::parse_pattern_with_loader`].
+    pub fn parse_with_loader(
+        source: &'a str,
+        loader: impl SourceLoader<'a>,
+    ) -> Result<Self, ParseError> {
+        crate::parse_pattern_with_loader(source, loader)
+    }
+
+    /// Encodes the pattern in a compact binary format, which [`Self::from_bytes`] can load much
+    /// faster than parsing the source again.
+    ///
+    /// ```
+    /// # use crochet::Pattern;
+    /// let pattern = Pattern::parse("name: Ball\nsc 6 in mr\ninc 6").unwrap();
+    /// let bytes = pattern.to_bytes();
+    /// assert_eq!(Pattern::from_bytes(&bytes), Some(pattern));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(self)
+    }
+
+    /// Decodes a pattern encoded by [`Self::to_bytes`]. Its strings are borrowed from `bytes`.
+    ///
+    /// Returns `None` if `bytes` is malformed, was encoded by a version of this crate with an
+    /// incompatible format, or contains any [`crate::CustomStitch`]es.
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        binary::decode(bytes)
+    }
+
+    /// How the pattern's rounds are connected, as declared by its `mode:` header line
+    pub fn mode(&self) -> Mode {
+        self.meta.mode.and_then(Mode::from_name).unwrap_or_default()
+    }
+
+    /// How many stitches of each color every round makes, as one list per part with one entry per
+    /// round.
+    ///
+    /// Each part starts out in the first color of the palette, and each `@A` switches colors
+    /// until the next switch.
+    ///
+    /// ```
+    /// # use crochet::parse_pattern;
+    /// let src = "
+    ///     colors: A = cream, B = brown
+    ///     sc 6 in mr
+    ///     @B, [inc, sc] 3
+    ///     sc 3, @A, sc 6
+    /// ";
+    ///
+    /// let colors = &parse_pattern(src).unwrap().round_colors()[0];
+    /// assert_eq!(colors[0], [("A", 6)].into());
+    /// assert_eq!(colors[1], [("B", 9)].into());
+    /// assert_eq!(colors[2], [("A", 6), ("B", 3)].into());
+    /// ```
+    pub fn round_colors(&self) -> Vec<Vec<ColorCounts<'a>>> {
+        let main_color = self.meta.palette().first().map(|&(key, _)| key);
+
+        self.parts
+            .iter()
+            .map(|part| {
+                let mut active = main_color;
+
+                part.rounds
+                    .iter()
+                    .map(|round| {
+                        let mut counts = ColorCounts::new();
+                        for _ in 0..round.times {
+                            for inst in &round.instructions {
+                                count_colors(inst, &mut active, &mut counts);
+                            }
+                        }
+                        counts
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Finds the part with the given name
+    pub fn part(&self, name: &str) -> Option<&Part<'a>> {
+        self.parts.iter().find(|p| p.name == Some(name))
+    }
+
+    /// Adds the parts of `other` to the end of this pattern, for assembling a pattern from
+    /// reusable pieces.
+    ///
+    /// Rounds at the start of `other` that aren't in a named part continue this pattern's last
+    /// part, and are renumbered to follow on from its rounds. If `join` is given, a round with just
+    /// that comment is put between them to mark where they were joined. Since they end up in the
+    /// same part, [`crate::lint_pattern`] checks that the first of the new rounds works into the
+    /// last of the old ones. Named parts of `other` are added as they are.
+    ///
+    /// `other`'s assembly steps and notes are added after this pattern's, but its header is
+    /// ignored. The added rounds and assembly steps lose their spans, which would point into
+    /// `other`'s source, so they're treated like rounds that weren't parsed from a source: lints
+    /// about them have no location or suggested fix.
+    ///
+    /// ```
+    /// # use crochet::{lint_pattern, Pattern};
+    /// let mut pattern = Pattern::parse("name: Bear\n== Head ==\nsc 6 in mr\ninc 6").unwrap();
+    /// pattern.append(Pattern::parse("x3: sc 12\n% fasten off %\n== Ear ==\nsc 4 in mr").unwrap(), Some("join"));
+    ///
+    /// let head: Vec<_> = pattern.parts[0].rounds.iter().map(|r| r.index).collect();
+    /// assert_eq!(head, [1, 2, 3, 4, 7]);
+    /// assert_eq!(pattern.parts[0].rounds[2].to_string(), "% join %");
+    /// assert_eq!(pattern.parts[1].name, Some("Ear"));
+    /// assert!(lint_pattern(&pattern).iter().all(Vec::is_empty));
+    /// ```
+    pub fn append(&mut self, mut other: Pattern<'a>, join: Option<&'a str>) {
+        for round in other.parts.iter_mut().flat_map(|p| &mut p.rounds) {
+            round.span = Span::default();
+            round.instruction_spans.clear();
+        }
+        for step in &mut other.assembly {
+            step.span = Span::default();
+        }
+
+        let mut parts = other.parts.into_iter().peekable();
+
+        if let (Some(last), Some(Part { name: None, .. })) = (self.parts.last_mut(), parts.peek()) {
+            let continued = parts.next().unwrap();
+
+            let seam = !last.rounds.is_empty() && !continued.rounds.is_empty();
+            if let Some(text) = join.filter(|_| seam) {
+                last.rounds
+                    .push(Round::new(vec![Instruction::Comment(text)]));
+            }
+            last.rounds.extend(continued.rounds);
+            last.renumber();
+        }
+
+        self.parts.extend(parts);
+        self.assembly.extend(other.assembly);
+        self.notes.extend(other.notes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lint_pattern, Lint};
+
+    #[test]
+    fn test_append() {
+        let mut pattern = Pattern::parse("sc 6 in mr\ninc 6").unwrap();
+        pattern.append(
+            Pattern::parse("x2: sc 18\nsc 18, % fasten off %").unwrap(),
+            None,
+        );
+
+        let indices: Vec<_> = pattern.parts[0].rounds.iter().map(|r| r.index).collect();
+        assert_eq!(indices, [1, 2, 3, 5]);
+
+        // the seam is linted like any other pair of rounds
+        assert_eq!(
+            lint_pattern(&pattern),
+            [[Lint::MismatchedStitchCount {
+                a_out: 12,
+                a_idx: 2,
+                b_in: 18,
+                b_idx: 3,
+                // the added rounds aren't in this pattern's source
+                span: Span::default(),
+                fix: None,
+                notes: Vec::new(),
+            }]]
+        );
+
+        // nothing to continue
+        let mut empty = Pattern::parse("name: Empty").unwrap();
+        empty.append(
+            Pattern::parse("sc 6 in mr\n```notes\nhi\n```").unwrap(),
+            Some("join"),
+        );
+        assert_eq!(empty.parts.len(), 1);
+        assert_eq!(empty.parts[0].rounds.len(), 1);
+        assert_eq!(empty.notes.len(), 1);
+        assert_eq!(empty.parts[0].rounds[0].to_string(), "sc 6 in mr");
+
+        let mut pattern = Pattern::parse("sc 6 in mr").unwrap();
+        pattern.append(Pattern::parse("inc 2, sc 4").unwrap(), Some("join"));
+        let rounds: Vec<_> = pattern.parts[0]
+            .rounds
+            .iter()
+            .map(Round::to_string)
+            .collect();
+        assert_eq!(rounds, ["sc 6 in mr", "% join %", "inc 2, sc 4"]);
+        assert_eq!(pattern.parts[0].rounds[2].span, Span::default());
+        assert_eq!(pattern.parts[0].rounds[2].instruction_span(&[0]), None);
+        assert_eq!(
+            pattern.parts[0].rounds[0].instruction_span(&[0]),
+            Some(Span::new((1, 1), (1, 11)))
+        );
+    }
+}
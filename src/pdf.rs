@@ -0,0 +1,366 @@
+use crate::{
+    lint_parts, lint_rounds_with_config, pretty_format_pattern, LintConfig, MetaField, Pattern,
+};
+use std::io::Write;
+
+/// Letter-sized pages, in points
+const PAGE_WIDTH: u32 = 612;
+const PAGE_HEIGHT: u32 = 792;
+const MARGIN: u32 = 72;
+
+/// The size and line height of body text, in points
+const FONT_SIZE: u32 = 10;
+const LEADING: u32 = 14;
+/// How many characters of body text fit on a line. Helvetica's characters are about half as wide
+/// as its font size on average, so this leaves some room for wide ones.
+const LINE_CHARS: usize = 85;
+
+/// The header fields shown under the title. The name is the title itself, and the license, source
+/// and attribution are already at the end of the pretty printed pattern.
+const META_FIELDS: [MetaField; 5] = [
+    MetaField::Designer,
+    MetaField::HookSize,
+    MetaField::YarnWeight,
+    MetaField::Gauge,
+    MetaField::Colors,
+];
+
+/// A line of text on a page
+struct Line {
+    text: String,
+    bold: bool,
+    size: u32,
+}
+
+impl Line {
+    fn body(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            bold: false,
+            size: FONT_SIZE,
+        }
+    }
+
+    fn heading(text: impl Into<String>, size: u32) -> Self {
+        Self {
+            text: text.into(),
+            bold: true,
+            size,
+        }
+    }
+
+    fn height(&self) -> u32 {
+        LEADING.max(self.size + 4)
+    }
+}
+
+/// Renders a pattern as a printable PDF document: its name and header information, then the
+/// pattern laid out like [`pretty_format_pattern`], then any lints, so the problems aren't lost
+/// when the pattern is printed.
+///
+/// The PDF is written directly, using only the standard Helvetica fonts that every PDF reader
+/// has, so characters outside of Latin-1 are replaced with `?`. Long lines are wrapped and the
+/// document is split into as many pages as it needs.
+///
+/// ```
+/// # use crochet::{parse_pattern, render_pdf};
+/// let pattern = parse_pattern("name: Tiny Ball\nsc 6 in mr\ninc 6\nsc 12, % fasten off %").unwrap();
+/// let pdf = render_pdf(&pattern);
+///
+/// assert!(pdf.starts_with(b"%PDF-1.4"));
+/// assert!(pdf.ends_with(b"%%EOF\n"));
+/// ```
+pub fn render_pdf(pattern: &Pattern) -> Vec<u8> {
+    let mut lines = Vec::new();
+
+    if let Some(name) = pattern.meta.name {
+        lines.push(Line::heading(name, 18));
+    }
+    for field in META_FIELDS {
+        if let Some(value) = pattern.meta.get(field) {
+            let key = field.key();
+            let key = key[..1].to_uppercase() + &key[1..];
+            lines.extend(wrap(&format!("{key}: {value}")).map(Line::body));
+        }
+    }
+    if !lines.is_empty() {
+        lines.push(Line::body(""));
+    }
+
+    for line in pretty_format_pattern(pattern).lines() {
+        lines.extend(wrap(line).map(Line::body));
+    }
+
+    // the same lints, with the same severities, as crochetcli reports
+    let config = LintConfig::new().mode(pattern.mode());
+    let mut lints: Vec<_> = pattern
+        .parts
+        .iter()
+        .map(|p| (p, lint_rounds_with_config(&p.rounds, &config).into_lints()))
+        .collect();
+    let cross_part: Vec<_> = lint_parts(pattern)
+        .into_iter()
+        .filter_map(|l| Some((config.severity_of(&l)?, l)))
+        .collect();
+    if lints.iter().any(|(_, l)| !l.is_empty()) || !cross_part.is_empty() {
+        lines.push(Line::body(""));
+        lines.push(Line::heading("Lints", 14));
+
+        lints.retain(|(_, l)| !l.is_empty());
+        for (part, part_lints) in lints {
+            for (lint, severity) in part_lints {
+                let code = lint.code();
+                let text = match part.name {
+                    Some(name) => format!("{code} ({severity}): {name}: {lint}"),
+                    None => format!("{code} ({severity}): {lint}"),
+                };
+                lines.extend(wrap(&text).map(Line::body));
+            }
+        }
+        for (severity, lint) in cross_part {
+            let text = format!("{} ({severity}): {lint}", lint.code());
+            lines.extend(wrap(&text).map(Line::body));
+        }
+    }
+
+    write_pdf(&paginate(lines), pattern.meta.name)
+}
+
+/// Splits `text` into lines of at most [`LINE_CHARS`] characters, at spaces if it can
+fn wrap(text: &str) -> impl Iterator<Item = String> + '_ {
+    let mut rest = text;
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        if rest.chars().count() <= LINE_CHARS {
+            done = true;
+            return Some(rest.to_string());
+        }
+
+        let limit = rest
+            .char_indices()
+            .nth(LINE_CHARS)
+            .map_or(rest.len(), |(i, _)| i);
+        let (line, next) = match rest[..limit].rfind(' ') {
+            Some(space) if space > 0 => (&rest[..space], &rest[space + 1..]),
+            _ => rest.split_at(limit),
+        };
+        rest = next;
+
+        Some(line.to_string())
+    })
+}
+
+/// Splits lines into pages, as the content stream of each page
+fn paginate(lines: Vec<Line>) -> Vec<Vec<u8>> {
+    let mut pages = Vec::new();
+    let mut content = Vec::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    for line in lines {
+        if y < MARGIN + line.height() {
+            pages.push(std::mem::take(&mut content));
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        y -= line.height();
+
+        if line.text.is_empty() {
+            continue;
+        }
+
+        let font = if line.bold { "F2" } else { "F1" };
+        write!(content, "BT /{font} {} Tf {MARGIN} {y} Td (", line.size)
+            .expect("writing to a vec shouldn't fail");
+        content.extend(encode(&line.text));
+        content.extend(b") Tj ET\n");
+    }
+    pages.push(content);
+
+    pages
+}
+
+/// Encodes text for a string in a content stream, in the fonts' WinAnsiEncoding, which matches
+/// Latin-1 for the characters it has
+fn encode(text: &str) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => ret.extend([b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => ret.push(c as u32 as u8),
+            _ => ret.push(b'?'),
+        }
+    }
+
+    ret
+}
+
+/// Writes a PDF document with one page for each content stream in `pages`
+fn write_pdf(pages: &[Vec<u8>], title: Option<&str>) -> Vec<u8> {
+    // the catalog, page tree, fonts and info dictionary come first, then each page and its content
+    let first_page = 6;
+    let page_ids = (0..pages.len()).map(|i| first_page + 2 * i);
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+
+    let mut info = b"<< /Producer (crochet)".to_vec();
+    if let Some(title) = title {
+        info.extend(b" /Title (");
+        info.extend(encode(title));
+        info.push(b')');
+    }
+    info.extend(b" >>");
+    objects.push(info);
+
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                    /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                first_page + 2 * i + 1
+            )
+            .into_bytes(),
+        );
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut ret = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(ret.len());
+        writeln!(ret, "{} 0 obj", i + 1).expect("writing to a vec shouldn't fail");
+        ret.extend(object);
+        ret.extend(b"\nendobj\n");
+    }
+
+    let xref = ret.len();
+    write!(ret, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1)
+        .expect("writing to a vec shouldn't fail");
+    for offset in offsets {
+        writeln!(ret, "{offset:010} 00000 n ").expect("writing to a vec shouldn't fail");
+    }
+    write!(
+        ret,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )
+    .expect("writing to a vec shouldn't fail");
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pattern;
+
+    /// `pdf` as text, with any bytes that aren't ASCII replaced so that offsets stay the same
+    fn text(pdf: &[u8]) -> String {
+        pdf.iter()
+            .map(|&b| if b.is_ascii() { b as char } else { '?' })
+            .collect()
+    }
+
+    /// Checks that the cross-reference table of `pdf` points at the start of every object
+    fn assert_valid_xref(pdf: &[u8]) {
+        let text = text(pdf);
+        let startxref = text.rfind("startxref\n").unwrap();
+        let xref: usize = text[startxref + 10..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(text[xref..].starts_with("xref\n"));
+
+        let entries = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| !l.starts_with("trailer"));
+        for (i, entry) in entries.enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(
+                text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)),
+                "{entry}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let src = "name: Ball (small)\ndesigner: Zoë\nsc 6 in mr\ninc 6\nsc 12, % fasten off %";
+        let pdf = render_pdf(&parse_pattern(src).unwrap());
+        assert_valid_xref(&pdf);
+
+        let text = text(&pdf);
+        assert!(text.contains("/Title (Ball \\(small\\))"));
+        assert!(text.contains("/Count 1"));
+        assert!(text.contains("Td (Round 2: inc 6 \\(12\\)) Tj ET"));
+        // `ë` is in Latin-1
+        assert!(pdf.windows(11).any(|w| w == b"Designer: Z"));
+        assert!(pdf.windows(3).any(|w| w == b"Zo\xeb"));
+        assert!(!text.contains("Lints"));
+    }
+
+    #[test]
+    fn test_render_pdf_pages_and_lints() {
+        let mut src = String::from("sc 6 in mr\ninc 6\n");
+        for _ in 0..100 {
+            src.push_str(
+                "sc 12, % a long comment about nothing in particular, to wrap the line \
+                onto the next one of the page %\n",
+            );
+        }
+        src.push_str("sc 13");
+        let pdf = render_pdf(&parse_pattern(&src).unwrap());
+        assert_valid_xref(&pdf);
+
+        let text = text(&pdf);
+        assert!(text.contains("/Count 5"));
+        assert!(text.contains("(Lints)"));
+        assert!(text.contains("(C001 \\(error\\): round 102 produces 12 stitches but round 103"));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let long = "word ".repeat(40);
+        let lines: Vec<_> = wrap(long.trim_end()).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines
+            .iter()
+            .all(|l| l.len() <= LINE_CHARS && !l.starts_with(' ')));
+
+        let unbroken = "x".repeat(100);
+        let lines: Vec<_> = wrap(&unbroken).collect();
+        assert_eq!(lines, ["x".repeat(85), "x".repeat(15)]);
+
+        assert_eq!(wrap("").collect::<Vec<_>>(), [""]);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("a(b)\\ é 漢"), b"a\\(b\\)\\\\ \xe9 ?");
+    }
+}
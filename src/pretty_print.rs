@@ -1,4 +1,5 @@
-use crate::Instruction;
+use crate::format::{format_round_instructions, CountStyle, FormatOptions};
+use crate::{Locale, Mode, Pattern, Round};
 use std::fmt::Write;
 
 /// Formats rounds into a format suitible for publishing.
@@ -9,21 +10,66 @@ use std::fmt::Write;
 ///
 /// let expected = "Round 1: sc 6 in mr (6)
 /// Round 2: inc 6 (12)
-/// Round 3: [inc, sc] 6 (18)";
+/// Round 3 (widest): [inc, sc] 6 (18)
+/// hook 4mm
+/// Rounds 4-6: sc 18 (18)";
 ///
 /// let src = "
 ///     sc6in mr
 ///     inc 6
-///     [inc,sc] 6
+///     widest: [inc,sc] 6
+///     hook 4mm
+///     x3: sc 18
 /// ";
 ///
 /// assert_eq!(pretty_format(&parse_rounds(src).unwrap()), expected);
 /// ```
-pub fn pretty_format(rounds: &[Instruction]) -> String {
+pub fn pretty_format(rounds: &[Round]) -> String {
+    pretty_format_with_options(rounds, &FormatOptions::default())
+}
+
+/// Like [`pretty_format`], but formats each round according to `opts`, which can also match a
+/// publisher's house style for numbering rounds and writing stitch counts.
+///
+/// ```rust
+/// # use crochet::{parse_rounds, pretty_format_with_options, CountStyle, FormatOptions, RoundWord};
+/// let rounds = parse_rounds("[inc, sc] 6\nx2: sc 18").unwrap();
+/// let opts = FormatOptions {
+///     progressive_repeats: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     pretty_format_with_options(&rounds, &opts),
+///     "Round 1: *inc, sc; repeat from * 5 more times (18)\nRounds 2-3: sc 18 (18)"
+/// );
+///
+/// let opts = FormatOptions {
+///     round_word: RoundWord::Rnd,
+///     first_number: Some(4),
+///     count_style: CountStyle::Equals,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     pretty_format_with_options(&rounds, &opts),
+///     "Rnd 4: [inc, sc] 6 = 18 sts\nRnds 5-6: sc 18 = 18 sts"
+/// );
+/// ```
+pub fn pretty_format_with_options(rounds: &[Round], opts: &FormatOptions) -> String {
     let mut ret = String::new();
 
-    for (i, round) in rounds.iter().enumerate() {
-        write!(ret, "Round {}: {round} ({})\n", i + 1, round.output_count()).expect("writing to a string shouldn't fail... right?");
+    for line in pretty_lines(rounds, opts) {
+        match line {
+            PrettyLine::Directive(d) => writeln!(ret, "{d}"),
+            PrettyLine::Round {
+                heading,
+                instructions,
+                count,
+                ..
+            } => writeln!(ret, "{heading}{instructions}{count}"),
+        }
+        .expect("writing to a string shouldn't fail... right?");
     }
 
     // remove trailing newline
@@ -31,3 +77,288 @@ pub fn pretty_format(rounds: &[Instruction]) -> String {
 
     ret
 }
+
+/// Like [`pretty_format`], but writes the stitches and rounds in the language of `locale`, see
+/// [`Locale`].
+pub fn pretty_format_localized(rounds: &[Round], locale: Locale) -> String {
+    pretty_format_with_options(
+        rounds,
+        &FormatOptions {
+            locale,
+            ..Default::default()
+        },
+    )
+}
+
+/// A line of pretty printed rounds, split up so that other renderers can mark up its pieces
+pub(crate) enum PrettyLine {
+    /// A directive before a round, like `hook 4mm`
+    Directive(String),
+    Round {
+        /// The number of the round, or of the first round of a block of repeated rounds
+        number: u32,
+        /// The round's number and label, e.g. `Round 3 (widest): `
+        heading: String,
+        /// The round's instructions, followed by its join or turn if it has one
+        instructions: String,
+        /// The round's stitch count, e.g. ` (18)`, which is empty if counts are hidden
+        count: String,
+    },
+}
+
+/// The lines of [`pretty_format_with_options`]
+pub(crate) fn pretty_lines(rounds: &[Round], opts: &FormatOptions) -> Vec<PrettyLine> {
+    let mut ret = Vec::new();
+
+    let mut number = opts.first_number.unwrap_or(1);
+
+    for (i, round) in rounds.iter().enumerate() {
+        for d in &round.directives {
+            ret.push(PrettyLine::Directive(d.to_string()));
+        }
+
+        let mut formatted = format_round_instructions(round, opts);
+        let count = if i == 0 {
+            round.output_count()
+        } else {
+            opts.mode.stitch_count(round, false)
+        };
+
+        // the round worked after this one, which is the same round for a block of repeated rounds
+        let next = if round.times > 1 {
+            Some(round)
+        } else {
+            rounds.get(i + 1)
+        };
+        // a joining/turning chain is only added if the next round doesn't start with one itself
+        let needs_chain = next.is_some_and(|r| r.leading_chains() == 0);
+
+        let word = |w| opts.locale.keyword(w).unwrap_or(w);
+        let chain = format!("{} 1", word("ch"));
+        match opts.mode {
+            Mode::Spiral => {}
+            Mode::Joined => {
+                write!(formatted, ", {}", word("join"))
+                    .expect("writing to a string shouldn't fail");
+                if needs_chain {
+                    write!(formatted, ", {chain}").expect("writing to a string shouldn't fail");
+                }
+            }
+            Mode::Rows if next.is_some() => {
+                // a foundation chain already ends with the turning chain
+                if needs_chain && round.trailing_chains() == 0 {
+                    write!(formatted, ", {chain}").expect("writing to a string shouldn't fail");
+                }
+                write!(formatted, ", {}", word("turn"))
+                    .expect("writing to a string shouldn't fail");
+            }
+            Mode::Rows => {}
+        }
+
+        let label = match round.label {
+            Some(l) => format!(" ({l})"),
+            None => String::new(),
+        };
+
+        let last = round.last_number(number);
+        let (numbers, plural) = if round.times == 1 {
+            (number.to_string(), false)
+        } else {
+            (format!("{number}-{last}"), true)
+        };
+        let numbering = opts
+            .locale
+            .numbering(opts.mode, &numbers, plural)
+            .unwrap_or_else(|| format!("{}{numbers}", opts.round_word.word(opts.mode, plural)));
+
+        let count = match opts.count_style {
+            _ if opts.hide_counts => String::new(),
+            CountStyle::Parenthesized => format!(" ({count})"),
+            CountStyle::Equals if count == 1 => " = 1 st".to_string(),
+            CountStyle::Equals => format!(" = {count} sts"),
+        };
+
+        ret.push(PrettyLine::Round {
+            number,
+            heading: format!("{numbering}{label}: "),
+            instructions: formatted,
+            count,
+        });
+
+        number = last.saturating_add(1);
+    }
+
+    ret
+}
+
+/// Formats every part of a pattern, each under its name and with its own round numbering.
+///
+/// The parts come after the pattern's free-text blocks (see [`crate::TextBlock`]), and are
+/// followed by the assembly steps and the pattern's license and attribution (if any).
+///
+/// ```rust
+/// # use crochet::{parse_pattern, pretty_format_pattern};
+/// let src = "
+///     license: CC0
+///     == Head ==
+///     sc 6 in mr
+///     == Body ==
+///     sc 8 in mr
+///     inc 8
+///     == Assembly ==
+///     sew Head to Body
+/// ";
+///
+/// let expected = "Head
+/// Round 1: sc 6 in mr (6)
+///
+/// Body
+/// Round 1: sc 8 in mr (8)
+/// Round 2: inc 8 (16)
+///
+/// Assembly
+/// 1. sew Head to Body
+///
+/// License: CC0";
+///
+/// assert_eq!(pretty_format_pattern(&parse_pattern(src).unwrap()), expected);
+/// ```
+pub fn pretty_format_pattern(pattern: &Pattern) -> String {
+    let mut sections = Vec::new();
+
+    for block in pattern.notes.iter() {
+        sections.push(match block.name {
+            "" => block.text.to_owned(),
+            name => format!("{name}\n{}", block.text),
+        });
+    }
+
+    let opts = FormatOptions {
+        mode: pattern.mode(),
+        ..Default::default()
+    };
+
+    for part in pattern.parts.iter() {
+        let rounds = pretty_format_with_options(&part.rounds, &opts);
+
+        sections.push(match part.name {
+            Some(name) => format!("{name}\n{rounds}"),
+            None => rounds,
+        });
+    }
+
+    if !pattern.assembly.is_empty() {
+        let steps = pattern.assembly.iter().enumerate();
+        let steps: Vec<_> = steps
+            .map(|(i, step)| format!("{}. {step}", i + 1))
+            .collect();
+        sections.push(format!("Assembly\n{}", steps.join("\n")));
+    }
+
+    if let Some(footer) = pattern.meta.provenance_footer() {
+        sections.push(footer);
+    }
+
+    sections.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_pattern, RoundWord};
+
+    #[test]
+    fn test_joined_rounds() {
+        let src = "mode: joined\nsc 6 in mr\ninc 6\nch 2, [inc, sc] 6\nx2: sc 18";
+        let expected = "Round 1: sc 6 in mr, join, ch 1 (6)
+Round 2: inc 6, join (12)
+Round 3: ch 2, [inc, sc] 6, join, ch 1 (18)
+Rounds 4-5: sc 18, join, ch 1 (18)";
+
+        assert_eq!(
+            pretty_format_pattern(&parse_pattern(src).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        let src = "mode: rows\nch 11\nsc 10\nx2: sc 10\nch 2, sc 10\nsc 10";
+        let expected = "Row 1: ch 11, turn (11)
+Row 2: sc 10, ch 1, turn (10)
+Rows 3-4: sc 10, ch 1, turn (10)
+Row 5: ch 2, sc 10, ch 1, turn (10)
+Row 6: sc 10 (10)";
+
+        assert_eq!(
+            pretty_format_pattern(&parse_pattern(src).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_house_styles() {
+        let rounds = crate::parse_rounds("ch 5\nsc 4\nx2: sc 4").unwrap();
+        let format = |opts: FormatOptions| {
+            pretty_format_with_options(
+                &rounds,
+                &FormatOptions {
+                    mode: Mode::Rows,
+                    ..opts
+                },
+            )
+        };
+
+        assert_eq!(
+            format(FormatOptions {
+                round_word: RoundWord::R,
+                first_number: Some(0),
+                hide_counts: true,
+                ..Default::default()
+            }),
+            "R0: ch 5, turn\nR1: sc 4, ch 1, turn\nR2-3: sc 4, ch 1, turn"
+        );
+        // numbers that don't fit stop at the biggest one
+        assert_eq!(
+            format(FormatOptions {
+                round_word: RoundWord::R,
+                first_number: Some(u32::MAX - 1),
+                hide_counts: true,
+                ..Default::default()
+            }),
+            "R4294967294: ch 5, turn\nR4294967295: sc 4, ch 1, turn\n\
+                R4294967295-4294967295: sc 4, ch 1, turn"
+        );
+        assert_eq!(
+            format(FormatOptions {
+                round_word: RoundWord::Rnd,
+                count_style: CountStyle::Equals,
+                ..Default::default()
+            }),
+            "Row 1: ch 5, turn = 5 sts\nRow 2: sc 4, ch 1, turn = 4 sts\n\
+                Rows 3-4: sc 4, ch 1, turn = 4 sts"
+        );
+        assert_eq!(
+            pretty_format_with_options(
+                &crate::parse_rounds("sc in mr").unwrap(),
+                &FormatOptions {
+                    count_style: CountStyle::Equals,
+                    ..Default::default()
+                }
+            ),
+            "Round 1: sc in mr = 1 st"
+        );
+    }
+
+    #[test]
+    fn test_text_blocks() {
+        let src = "```Materials\n- 4mm hook\n  - stuffing\n```\nsc 6 in mr\n```\nGood luck!\n```";
+        let expected =
+            "Materials\n- 4mm hook\n  - stuffing\n\nGood luck!\n\nRound 1: sc 6 in mr (6)";
+
+        assert_eq!(
+            pretty_format_pattern(&parse_pattern(src).unwrap()),
+            expected
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::text::{capitalize, skipped, stitches, times};
+use crate::{Directive, Instruction, Round};
+use std::fmt::Write;
+
+/// Writes rounds out in plain English, for crocheters who can't read the abbreviated form yet.
+///
+/// Every stitch is written out in full along with where it's worked, repeats say how many times
+/// they're worked, and each round ends with how many stitches it makes.
+///
+/// ```
+/// # use crochet::{parse_rounds, prose_format};
+/// let rounds = parse_rounds("sc 6 in mr\nhook 4mm\n[inc, sc] 6\nx2: sc 18").unwrap();
+///
+/// assert_eq!(
+///     prose_format(&rounds),
+///     "Round 1: Work six single crochet into the magic ring — 6 stitches.
+/// Switch to a 4mm hook.
+/// Round 2: (Increase in the next stitch, then single crochet in the following stitch) six \
+/// times — 18 stitches.
+/// Rounds 3-4: Single crochet in each of the next 18 stitches — 18 stitches."
+/// );
+/// ```
+pub fn prose_format(rounds: &[Round]) -> String {
+    let mut ret = String::new();
+    let mut number = 1;
+
+    for round in rounds {
+        for d in &round.directives {
+            match d {
+                Directive::Hook(size) => writeln!(ret, "Switch to a {size} hook."),
+                Directive::Yarn(yarn) => writeln!(ret, "Switch to the yarn \"{yarn}\"."),
+            }
+            .expect("writing to a string shouldn't fail");
+        }
+
+        let last = round.last_number(number);
+        let label = match round.label {
+            Some(l) => format!(" ({l})"),
+            None => String::new(),
+        };
+        let numbering = if round.times == 1 {
+            format!("Round {number}{label}")
+        } else {
+            format!("Rounds {number}-{last}{label}")
+        };
+
+        let mut place = Place::Next;
+        let sentence = list(&round.instructions, &mut place);
+        writeln!(
+            ret,
+            "{numbering}: {} — {}.",
+            capitalize(&sentence),
+            stitches(round.output_count())
+        )
+        .expect("writing to a string shouldn't fail");
+
+        number = last.saturating_add(1);
+    }
+
+    // remove trailing newline
+    ret.pop();
+
+    ret
+}
+
+/// Which stitch of the previous round an instruction is worked into, relative to the instructions
+/// before it
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Place {
+    /// Nothing has been worked into the previous round yet
+    Next,
+    /// Something has, so the instruction is worked into the stitch after it
+    Following,
+}
+
+impl Place {
+    fn word(self) -> &'static str {
+        match self {
+            Self::Next => "next",
+            Self::Following => "following",
+        }
+    }
+
+    /// Moves on to the stitch after this one, returning this one's word
+    fn take(&mut self) -> &'static str {
+        std::mem::replace(self, Self::Following).word()
+    }
+}
+
+/// The full name of a single stitch, its plural, and how it's worked into the stitch below it
+pub(crate) fn stitch_names(
+    inst: &Instruction,
+) -> Option<(&'static str, &'static str, &'static str)> {
+    use Instruction::*;
+
+    Some(match inst {
+        Sc => ("single crochet", "single crochet", "in"),
+        Fpsc => (
+            "front post single crochet",
+            "front post single crochet",
+            "around",
+        ),
+        Bpsc => (
+            "back post single crochet",
+            "back post single crochet",
+            "around",
+        ),
+        Blsc => ("single crochet", "single crochet", "in the back loop of"),
+        Inc => ("increase", "increases", "in"),
+        Flinc => ("increase", "increases", "in the front loop of"),
+        Blinc => ("increase", "increases", "in the back loop of"),
+        Dec => ("decrease", "decreases", "over"),
+        _ => return None,
+    })
+}
+
+/// Describes a list of instructions worked one after another
+fn list(insts: &[Instruction], place: &mut Place) -> String {
+    let described: Vec<_> = insts.iter().map(|i| describe(i, place)).collect();
+    described.join(", then ")
+}
+
+/// Describes an instruction worked across the stitches of the previous round
+fn describe(inst: &Instruction, place: &mut Place) -> String {
+    use Instruction::*;
+
+    match inst {
+        Repeat(i, n) if stitch_names(i).is_some() => stitch_run(i, *n, place),
+        Repeat(i, n) if **i == Ch => format!("chain {}", number(*n)),
+        Repeat(i, n) => {
+            let inner = match &**i {
+                Group(insts) => list(insts, place),
+                i => describe(i, place),
+            };
+            format!("({}) {}", capitalize(&inner), times(*n, number))
+        }
+        Group(insts) => list(insts, place),
+        Ch => "chain one".to_string(),
+        Tch => "chain one to turn".to_string(),
+        IntoMagicRing(i) => format!("work {} into the magic ring", worked(i)),
+        IntoOne(g) => format!("work {} all in the {} stitch", worked(g), place.take()),
+        Crossed(a, b) => {
+            let skipped = place.take();
+            format!(
+                "skip the {skipped} stitch and {}, then work {} back into the skipped stitch, \
+                    crossing in front of it",
+                describe(a, place),
+                worked(b)
+            )
+        }
+        ChSpace { chains, skipped: n } => format!(
+            "chain {} and skip the {}",
+            number(*chains),
+            skipped(place.take(), *n, number)
+        ),
+        Skip(n) => format!("skip the {}", skipped(place.take(), *n, number)),
+        SurfaceSlSt(n) if *n == 1 => "work a surface slip stitch on top of the fabric".into(),
+        SurfaceSlSt(n) => format!(
+            "work {} surface slip stitches on top of the fabric",
+            number(*n)
+        ),
+        Comment(c) => format!("({c})"),
+        Color(c) => format!("change to color {c}"),
+        Custom(stitch) => format!("{} in the {} stitch", stitch.name(), place.take()),
+        _ => stitch_run(inst, 1, place),
+    }
+}
+
+/// Describes a stitch worked into each of the next `n` stitches, or a decrease worked `n` times
+fn stitch_run(inst: &Instruction, n: u32, place: &mut Place) -> String {
+    let Some((name, _, prep)) = stitch_names(inst) else {
+        unreachable!("{inst} isn't a single stitch")
+    };
+    let place = place.take();
+
+    match (inst, n) {
+        (Instruction::Dec, 1) => format!("{name} {prep} the {place} two stitches"),
+        (Instruction::Dec, n) => format!(
+            "{name} {} {prep} the {place} {} stitches",
+            times(n, number),
+            number(n * 2)
+        ),
+        (_, 1) => format!("{name} {prep} the {place} stitch"),
+        (_, n) => format!("{name} {prep} each of the {place} {} stitches", number(n)),
+    }
+}
+
+/// Describes the stitches of an instruction worked all into the same place, e.g. the magic ring
+fn worked(inst: &Instruction) -> String {
+    use Instruction::*;
+
+    match inst {
+        Repeat(i, n) => match stitch_names(i) {
+            Some((_, plural, _)) => format!("{} {plural}", number(*n)),
+            None if **i == Ch => format!("chain {}", number(*n)),
+            None => format!("({}) {}", worked(i), times(*n, number)),
+        },
+        Group(insts) => {
+            let worked: Vec<_> = insts.iter().map(worked).collect();
+            worked.join(", ")
+        }
+        Ch => "chain one".to_string(),
+        Custom(stitch) => stitch.name().to_string(),
+        _ => match stitch_names(inst) {
+            Some((name, ..)) => format!("one {name}"),
+            None => inst.to_string(),
+        },
+    }
+}
+
+/// Writes small numbers out as words, like people do in prose
+fn number(n: u32) -> String {
+    const WORDS: [&str; 13] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve",
+    ];
+
+    match WORDS.get(n as usize) {
+        Some(word) => word.to_string(),
+        None => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn prose(src: &str) -> String {
+        let src = format!("colors: A = red\n{src}");
+        let round = &parse_rounds(&src).unwrap()[0];
+        list(&round.instructions, &mut Place::Next)
+    }
+
+    #[test]
+    fn test_stitches() {
+        assert_eq!(
+            prose("sc, sc 2, dec, dec 3, blinc"),
+            "single crochet in the next stitch, then single crochet in each of the following two \
+                stitches, then decrease over the following two stitches, then decrease three times \
+                over the following six stitches, then increase in the back loop of the following \
+                stitch"
+        );
+        assert_eq!(
+            prose("tch, fpsc 20, skip 1, ch 3, skip 2, surface sl st 4, @A"),
+            "chain one to turn, then front post single crochet around each of the next 20 \
+                stitches, then skip the following stitch, then chain three and skip the following \
+                two stitches, then work four surface slip stitches on top of the fabric, then \
+                change to color A"
+        );
+    }
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(
+            prose("[sc, inc] 3 in mr"),
+            "work (one single crochet, one increase) three times into the magic ring"
+        );
+        assert_eq!(
+            prose("ch 2, (sc, ch 2, sc) in next st, cross(fpsc, sc), % stuff firmly %"),
+            "chain two, then work one single crochet, chain two, one single crochet all in the \
+                next stitch, then skip the following stitch and front post single crochet around \
+                the following stitch, then work one single crochet back into the skipped stitch, \
+                crossing in front of it, then (stuff firmly)"
+        );
+        assert_eq!(
+            prose("[[inc, sc] 2, dec] 2"),
+            "([Increase in the next stitch, then single crochet in the following stitch] twice, \
+                then decrease over the following two stitches) twice"
+                .replace('[', "(")
+                .replace(']', ")")
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(number(12), "twelve");
+        assert_eq!(number(13), "13");
+        assert_eq!(times(7, number), "seven times");
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{Instruction, Round};
+
+/// Finds every instruction in `rounds` that `pred` returns true for, including nested ones, in the
+/// order they're written.
+///
+/// Each match is returned with the index of its round in `rounds`, and its path within the round,
+/// which leads to it the same way as in [`Round::instruction_span`]: the index into the round's
+/// `instructions`, and then into the instructions nested in each one. Like with
+/// [`crate::InstructionVisitor`], the instruction inside a `Repeat` is only looked at once.
+///
+/// ```
+/// # use crochet::{find_instructions, parse_rounds, Instruction};
+/// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[dec, sc] 4, dec 2").unwrap();
+/// let decs = find_instructions(&rounds, |i| *i == Instruction::Dec);
+///
+/// let found: Vec<_> = decs.iter().map(|(round, path, _)| (*round, &path[..])).collect();
+/// assert_eq!(found, [(2, &[0, 0, 0][..]), (2, &[1, 0][..])]);
+///
+/// let (round, path, _) = &decs[1];
+/// assert_eq!(rounds[*round].instruction_span(path).unwrap().start, (3, 14));
+/// ```
+pub fn find_instructions<'r, 'a>(
+    rounds: &'r [Round<'a>],
+    mut pred: impl FnMut(&Instruction<'a>) -> bool,
+) -> Vec<(usize, Vec<usize>, &'r Instruction<'a>)> {
+    let mut found = Vec::new();
+
+    for (idx, round) in rounds.iter().enumerate() {
+        for (i, inst) in round.instructions.iter().enumerate() {
+            find_in(inst, &mut vec![i], &mut |path, inst| {
+                if pred(inst) {
+                    found.push((idx, path.to_vec(), inst));
+                }
+            });
+        }
+    }
+
+    found
+}
+
+/// Calls `f` with `inst` and then everything nested in it, along with their paths, where `path` is
+/// the path to `inst`
+fn find_in<'r, 'a>(
+    inst: &'r Instruction<'a>,
+    path: &mut Vec<usize>,
+    f: &mut impl FnMut(&[usize], &'r Instruction<'a>),
+) {
+    use Instruction::*;
+
+    f(path, inst);
+
+    let nested: &[Instruction] = match inst {
+        Group(insts) => insts,
+        Repeat(i, _) | IntoMagicRing(i) | IntoOne(i) => std::slice::from_ref(i),
+        Crossed(a, b) => {
+            path.push(0);
+            find_in(a, path, f);
+            path.pop();
+            path.push(1);
+            find_in(b, path, f);
+            path.pop();
+            return;
+        }
+        _ => &[],
+    };
+
+    for (i, nested) in nested.iter().enumerate() {
+        path.push(i);
+        find_in(nested, path, f);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_rounds, Span};
+
+    #[test]
+    fn test_paths_match_spans() {
+        let src = "sc 6 in mr\n(sc, ch 2, sc) in next st, cross(fpsc, [sc, inc] 1), mr 3\n% hi %";
+        let rounds = parse_rounds(src).unwrap();
+        let found = find_instructions(&rounds, |_| true);
+
+        let written: Vec<_> = found.iter().map(|(_, _, i)| i.to_string()).collect();
+        assert_eq!(
+            written[3..],
+            [
+                "(sc, ch 2, sc) in next st",
+                "sc, ch 2, sc",
+                "sc",
+                "ch 2",
+                "ch",
+                "sc",
+                "cross(fpsc, [sc, inc] 1)",
+                "fpsc",
+                "[sc, inc] 1",
+                "sc, inc",
+                "sc",
+                "inc",
+                "sc 3 in mr",
+                "sc 3",
+                "sc",
+                "% hi %",
+            ]
+        );
+
+        // every path leads to a span
+        for (round, path, _) in &found {
+            assert!(rounds[*round].instruction_span(path).is_some(), "{path:?}");
+        }
+        assert_eq!(found[14].1, [1, 1, 0, 1]);
+        assert_eq!(
+            rounds[1].instruction_span(&found[14].1),
+            Some(Span::new((2, 45), (2, 48)))
+        );
+    }
+
+    #[test]
+    fn test_no_matches() {
+        let rounds = parse_rounds("sc 6 in mr\nsc 6").unwrap();
+        assert!(find_instructions(&rounds, |i| matches!(i, Instruction::Dec)).is_empty());
+        assert_eq!(
+            find_instructions(&rounds, |i| *i == Instruction::Sc).len(),
+            2
+        );
+    }
+}
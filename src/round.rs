@@ -0,0 +1,170 @@
+use crate::terminology::InTerms;
+use crate::{saturating_sum, Directive, Instruction, Span, SpanTree, Stitches, Terminology};
+
+/// A single line of a pattern: one round, or a block of identical rounds.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Round<'a> {
+    /// One-based number of this round within its part (the first one, for a block of identical
+    /// rounds). This is 0 for rounds that weren't parsed from a source.
+    pub index: u32,
+    /// Name given to the round with e.g. `ears: sc 4, inc 2`, so it can be referred to later
+    pub label: Option<&'a str>,
+    pub instructions: Vec<Instruction<'a>>,
+    /// How many times in a row this round is worked, from e.g. `x5: sc 24` or `rounds 4-8: sc 24`.
+    /// This is 1 for an ordinary round.
+    pub times: u32,
+    /// Hook and yarn changes made just before working this round
+    pub directives: Vec<Directive<'a>>,
+    /// Where the round's line is in the source, not including any directives before it. Rounds
+    /// pulled in from another file get the span of their `include` line.
+    pub span: Span,
+    /// The spans of each of the round's `instructions`, or empty if the round wasn't parsed from
+    /// a source. See [`Self::instruction_span`].
+    pub instruction_spans: Vec<SpanTree>,
+}
+
+impl<'a> Round<'a> {
+    /// Creates an unlabeled round that isn't tied to a source location
+    pub fn new(instructions: Vec<Instruction<'a>>) -> Self {
+        Self {
+            index: 0,
+            label: None,
+            instructions,
+            times: 1,
+            directives: Vec::new(),
+            span: Span::default(),
+            instruction_spans: Vec::new(),
+        }
+    }
+
+    /// Where the instruction at `path` is in the source. The first element of `path` is the
+    /// index into `instructions`, and the rest lead into nested instructions like in
+    /// [`SpanTree::get`].
+    ///
+    /// ```
+    /// # use crochet::{parse_rounds, Span};
+    /// let rounds = parse_rounds("sc 6 in mr\nsc, [inc, sc] 6").unwrap();
+    ///
+    /// // the `inc` inside the repeated group
+    /// assert_eq!(rounds[1].instruction_span(&[1, 0, 0]), Some(Span::new((2, 6), (2, 9))));
+    /// ```
+    pub fn instruction_span(&self, path: &[usize]) -> Option<Span> {
+        let (&first, rest) = path.split_first()?;
+        self.instruction_spans.get(first)?.get(rest)
+    }
+
+    /// The number of the last of the rounds this stands for when the first is numbered `number`,
+    /// or `u32::MAX` if it'd be any bigger
+    pub(crate) fn last_number(&self, number: u32) -> u32 {
+        number.checked_add(self.times).map_or(u32::MAX, |n| n - 1)
+    }
+
+    /// Iterates over the elementary stitches of one repetition of this round, see [`Stitches`].
+    pub fn iter_stitches(&self) -> Stitches<'_, 'a> {
+        Stitches::new(&self.instructions)
+    }
+
+    /// Where each of the round's instructions starts, as `(input, output)`: how many stitches of
+    /// the previous round are worked, and how many stitches are made, before the instruction.
+    ///
+    /// ```
+    /// # use crochet::parse_rounds;
+    /// let round = &parse_rounds("sc 10, dec, [inc, sc] 2").unwrap()[0];
+    /// let offsets = round.instruction_offsets();
+    ///
+    /// // the dec is worked into stitches 11-12 (one-based), and makes stitch 11
+    /// assert_eq!(offsets[1], (10, 10));
+    /// assert_eq!(offsets[2], (12, 11));
+    /// ```
+    pub fn instruction_offsets(&self) -> Vec<(u32, u32)> {
+        self.instructions
+            .iter()
+            .scan((0, 0), |(input, output), inst| {
+                let start = (*input, *output);
+                *input = u32::saturating_add(*input, inst.input_count());
+                *output = u32::saturating_add(*output, inst.output_count());
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// How many stitches this round consumes from the previous round.
+    pub fn input_count(&self) -> u32 {
+        saturating_sum(self.instructions.iter().map(Instruction::input_count))
+    }
+
+    /// How many stitches this round creates.
+    ///
+    /// Example:
+    /// ```
+    /// # use crochet::parse_rounds;
+    /// let rounds = parse_rounds("sc 6 in mr\nears: [inc, sc] 3").unwrap();
+    /// assert_eq!(rounds[1].label, Some("ears"));
+    /// assert_eq!(rounds[1].index, 2);
+    /// assert_eq!(rounds[1].output_count(), 9);
+    /// ```
+    pub fn output_count(&self) -> u32 {
+        saturating_sum(self.instructions.iter().map(Instruction::output_count))
+    }
+
+    /// Displays the round like its `Display` impl, but with the stitch names from `terms`
+    pub fn display_in(&self, terms: Terminology) -> impl std::fmt::Display + '_ {
+        InTerms { value: self, terms }
+    }
+
+    /// How many chains this round starts with. In joined rounds these are the joining chain,
+    /// which doesn't count as a stitch.
+    pub(crate) fn leading_chains(&self) -> u32 {
+        match self.instructions.first() {
+            Some(Instruction::Ch) => 1,
+            Some(Instruction::Repeat(inst, n)) if **inst == Instruction::Ch => *n,
+            _ => 0,
+        }
+    }
+
+    /// How many chains this round ends with
+    pub(crate) fn trailing_chains(&self) -> u32 {
+        match self.instructions.last() {
+            Some(Instruction::Ch) => 1,
+            Some(Instruction::Repeat(inst, n)) if **inst == Instruction::Ch => *n,
+            _ => 0,
+        }
+    }
+}
+
+/// Iterates over the rounds in the order they're worked, repeating each block of identical rounds
+/// as many times as it's worked.
+pub(crate) fn expand_rounds<'r, 'a>(
+    rounds: &'r [Round<'a>],
+) -> impl Iterator<Item = &'r Round<'a>> {
+    rounds
+        .iter()
+        .flat_map(|r| std::iter::repeat_n(r, r.times as usize))
+}
+
+impl std::fmt::Display for Round<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for d in &self.directives {
+            writeln!(f, "{d}")?;
+        }
+
+        if self.times != 1 {
+            write!(f, "x{}: ", self.times)?;
+        }
+
+        if let Some(label) = self.label {
+            write!(f, "{label}: ")?;
+        }
+
+        if !self.instructions.is_empty() {
+            write!(f, "{}", self.instructions[0])?;
+        }
+
+        for i in self.instructions.iter().skip(1) {
+            write!(f, ", {i}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{canonicalize, Instruction, Round};
+
+/// Resizes a shape by `factor`, e.g. `1.5` for a sphere with one and a half times the
+/// circumference.
+///
+/// Every round's stitch count is multiplied by `factor` (rounded to a whole number of stitches),
+/// keeping the rounds' stitch counts consistent with each other, so the result's stitch counts
+/// fit together if `rounds`' do. A round that's a single repeat, like `[inc, sc 2] 6` or `sc 6 in mr`, is scaled
+/// by repeating it more or fewer times. Any other round made of only `sc`s, `inc`s and `dec`s (or
+/// their front/back loop and post variants) is rewritten with its increases or decreases spread
+/// evenly around it; comments inside these rounds are dropped. Rounds that don't make or use any
+/// stitches are kept as they are.
+///
+/// Returns `None` if a round can't be scaled either way, e.g. because it has chain spaces or
+/// color changes in it.
+///
+/// ```
+/// # use crochet::{lint_rounds, parse_rounds, scale};
+/// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[inc, sc] 6\nx2: sc 18\n[dec, sc] 6").unwrap();
+/// let scaled = scale(&rounds, 1.5).unwrap();
+///
+/// let scaled: Vec<_> = scaled.iter().map(ToString::to_string).collect();
+/// assert_eq!(scaled, ["sc 9 in mr", "inc 9", "[inc, sc] 9", "x2: sc 27", "[dec, sc] 9"]);
+/// ```
+///
+/// # Panics
+///
+/// If `factor` isn't a positive number.
+pub fn scale<'a>(rounds: &[Round<'a>], factor: f64) -> Option<Vec<Round<'a>>> {
+    assert!(
+        factor.is_finite() && factor > 0.0,
+        "can't scale a pattern by {factor}"
+    );
+
+    let mut ret = Vec::with_capacity(rounds.len());
+    // how many stitches the last scaled round made, which the next one has to work into
+    let mut prev_out = None;
+
+    for round in rounds {
+        let (input, output) = (round.input_count(), round.output_count());
+        if input == 0 && output == 0 {
+            ret.push(copy_round(round, round.instructions.clone()));
+            continue;
+        }
+
+        let target_in = prev_out.unwrap_or(0);
+        let instructions = match repeat_more(round, factor, target_in) {
+            Some(insts) => insts,
+            // rounds worked even stay even, instead of drifting from rounding
+            None if input == output => respread(round, target_in, target_in)?,
+            None => {
+                let target_out = (output as f64 * factor).round() as u32;
+                respread(round, target_in, target_out)?
+            }
+        };
+
+        let scaled = copy_round(round, instructions);
+        prev_out = Some(scaled.output_count());
+        ret.push(scaled);
+    }
+
+    Some(ret)
+}
+
+/// A round with the instructions `insts`, and the rest of `round`'s fields
+fn copy_round<'a>(round: &Round<'a>, insts: Vec<Instruction<'a>>) -> Round<'a> {
+    Round {
+        index: round.index,
+        label: round.label,
+        times: round.times,
+        directives: round.directives.clone(),
+        span: round.span,
+        ..Round::new(insts)
+    }
+}
+
+/// Scales a round that's a single repeat by changing how many times it's repeated, if the result
+/// works into `target_in` stitches
+fn repeat_more<'a>(round: &Round<'a>, factor: f64, target_in: u32) -> Option<Vec<Instruction<'a>>> {
+    use Instruction::*;
+
+    fn scaled<'a>(inst: &Instruction<'a>, factor: f64) -> Option<Instruction<'a>> {
+        match inst {
+            IntoMagicRing(i) => Some(IntoMagicRing(scaled(i, factor)?.into())),
+            Repeat(i, times) => {
+                let times = ((*times as f64 * factor).round() as u32).max(1);
+                Some(Repeat(i.clone(), times))
+            }
+            _ => None,
+        }
+    }
+
+    let [inst] = &round.instructions[..] else {
+        return None;
+    };
+    let inst = scaled(inst, factor)?;
+
+    (inst.input_count() == target_in).then(|| vec![inst])
+}
+
+/// Rewrites a round of plain stitches to work `target_in` stitches into `target_out`, with the
+/// increases or decreases spread out evenly
+pub(crate) fn respread<'a>(
+    round: &Round<'a>,
+    target_in: u32,
+    target_out: u32,
+) -> Option<Vec<Instruction<'a>>> {
+    use Instruction::*;
+
+    // the variants of sc and inc used in the round, which the new round should keep using
+    let mut plain = None;
+    let mut inc = None;
+    let mut stack: Vec<&Instruction> = round.instructions.iter().collect();
+    while let Some(inst) = stack.pop() {
+        let kind = match inst {
+            Group(insts) => {
+                stack.extend(insts);
+                continue;
+            }
+            Repeat(i, _) => {
+                stack.push(i);
+                continue;
+            }
+            Comment(_) | Dec => continue,
+            Sc | Fpsc | Bpsc | Blsc => &mut plain,
+            Inc | Flinc | Blinc => &mut inc,
+            _ => return None,
+        };
+
+        match kind {
+            Some(k) if *k != inst => return None,
+            _ => *kind = Some(inst),
+        }
+    }
+
+    let plain = plain.cloned().unwrap_or(Sc);
+    let inc = inc.cloned().unwrap_or(Inc);
+
+    // there's no way to more than double or halve the stitches with only incs and decs
+    let target_out = target_out.clamp(target_in.div_ceil(2), target_in * 2);
+
+    let (change, changes, plains) = if target_out >= target_in {
+        let incs = target_out - target_in;
+        (inc, incs, target_in - incs)
+    } else {
+        let decs = target_in - target_out;
+        (Dec, decs, target_in - 2 * decs)
+    };
+
+    let mut flat = Vec::new();
+    for i in 0..changes {
+        flat.push(change.clone());
+        let between = plains / changes + u32::from(i < plains % changes);
+        flat.extend(std::iter::repeat_n(plain.clone(), between as usize));
+    }
+    if changes == 0 {
+        flat.extend(std::iter::repeat_n(plain, plains as usize));
+    }
+
+    Some(canonicalize(&flat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lint_rounds, parse_rounds, Severity};
+
+    fn scaled(src: &str, factor: f64) -> Option<Vec<String>> {
+        let rounds = parse_rounds(src).unwrap();
+        let scaled = scale(&rounds, factor)?;
+        // scaling up can make rounds ruffle, but their stitch counts still have to fit
+        let errors = lint_rounds(&scaled)
+            .into_iter()
+            .filter(|l| l.default_severity() == Severity::Error);
+        assert_eq!(errors.count(), 0, "{scaled:?}");
+
+        Some(scaled.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn test_sphere() {
+        let src = "sc 6 in mr\ninc 6\n[inc, sc] 6\n[inc, sc 2] 6\nx4: sc 24\n\
+            [dec, sc 2] 6\n[dec, sc] 6\ndec 6";
+
+        assert_eq!(
+            scaled(src, 2.0).unwrap(),
+            [
+                "sc 12 in mr",
+                "inc 12",
+                "[inc, sc] 12",
+                "[inc, sc 2] 12",
+                "x4: sc 48",
+                "[dec, sc 2] 12",
+                "[dec, sc] 12",
+                "dec 12",
+            ]
+        );
+        assert_eq!(scaled(src, 0.5).unwrap()[3], "[inc, sc 2] 3");
+
+        // every round rounds 7.5 repeats up the same way
+        assert_eq!(
+            scaled(src, 1.25).unwrap(),
+            [
+                "sc 8 in mr",
+                "inc 8",
+                "[inc, sc] 8",
+                "[inc, sc 2] 8",
+                "x4: sc 32",
+                "[dec, sc 2] 8",
+                "[dec, sc] 8",
+                "dec 8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_respread() {
+        // 9 increases can't be spread evenly over 15 stitches
+        assert_eq!(
+            scaled("sc 10 in mr\n[inc, sc 2] 2, inc 4", 1.5).unwrap(),
+            ["sc 15 in mr", "[inc, sc] 6, inc 3"],
+        );
+        assert_eq!(
+            scaled("ch 12\nblsc 4, blinc 8", 0.5).unwrap(),
+            ["ch 6", "[blinc, blsc] 2, blinc 2"]
+        );
+        assert_eq!(
+            scaled("sc 8 in mr\n% round 2 %, sc 3, sc 5", 1.5).unwrap(),
+            ["sc 12 in mr", "sc 12"]
+        );
+    }
+
+    #[test]
+    fn test_unscalable() {
+        assert_eq!(scaled("sc 6 in mr\nsc 3, fpsc 3", 2.0), None);
+        assert_eq!(scaled("sc 6 in mr\n[ch 2, skip 1, sc] 2, sc 2", 2.0), None);
+        assert_eq!(
+            scaled("colors: A = red\nsc 6 in mr\nsc 5, @A, sc", 2.0),
+            None
+        );
+        assert_eq!(scaled("sc 5 in mr, inc in mr", 2.0), None);
+    }
+}
@@ -0,0 +1,446 @@
+use crate::round::expand_rounds;
+use crate::text::escape;
+use crate::{Instruction, Mode, Pattern, Round};
+use std::f64::consts::{PI, TAU};
+use std::fmt::Write;
+
+/// How many pixels of the SVG outline a centimeter is
+const SCALE: f64 = 10.0;
+/// The space around and between the outlines of the parts
+const MARGIN: f64 = 40.0;
+/// The height of a line of label text
+const LINE_HEIGHT: f64 = 16.0;
+
+/// A unit of length for the labels of a [`Schematic`]
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Unit {
+    #[default]
+    Centimeters,
+    Inches,
+}
+
+impl Unit {
+    pub const ALL: [Self; 2] = [Self::Centimeters, Self::Inches];
+
+    /// The abbreviation used after a length, e.g. `cm` in `10 cm`
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Centimeters => "cm",
+            Self::Inches => "in",
+        }
+    }
+
+    /// How many centimeters one of this unit is
+    pub fn centimeters(self) -> f64 {
+        match self {
+            Self::Centimeters => 1.0,
+            Self::Inches => 2.54,
+        }
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// How many stitches and rows (or rounds) fit in a square of fabric, as measured from a swatch.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Gauge {
+    /// Stitches across the square
+    pub stitches: f64,
+    /// Rows or rounds up the square
+    pub rows: f64,
+    /// The width and height of the square, in centimeters
+    pub length: f64,
+}
+
+impl Gauge {
+    /// `stitches` and `rows` over a square `length` of `unit`s across
+    pub fn new(stitches: f64, rows: f64, length: f64, unit: Unit) -> Self {
+        Self {
+            stitches,
+            rows,
+            length: length * unit.centimeters(),
+        }
+    }
+
+    /// Reads a gauge written the way a pattern's `gauge:` header usually gives it, e.g.
+    /// `22 sts x 24 rnds = 10cm` or `18 sc and 20 rows to 4"`: the number of stitches, then the
+    /// number of rows or rounds, then the length in centimeters, millimeters or inches.
+    ///
+    /// ```
+    /// # use crochet::{Gauge, Unit};
+    /// assert_eq!(
+    ///     Gauge::parse("22 sts x 24 rnds = 10cm"),
+    ///     Some(Gauge::new(22.0, 24.0, 10.0, Unit::Centimeters))
+    /// );
+    /// assert_eq!(
+    ///     Gauge::parse("18 sc and 20 rows to 4 inches"),
+    ///     Some(Gauge::new(18.0, 20.0, 4.0, Unit::Inches))
+    /// );
+    /// assert_eq!(Gauge::parse("tight"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+        let (counts, length) = text
+            .split_once('=')
+            .or_else(|| text.split_once(" to "))
+            .or_else(|| text.split_once(" over "))?;
+
+        let mut numbers = counts
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .filter_map(|n| n.parse::<f64>().ok());
+        let (stitches, rows) = (numbers.next()?, numbers.next()?);
+
+        let length = length.trim();
+        let end = length
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(length.len());
+        let (amount, unit) = length.split_at(end);
+        let amount: f64 = amount.parse().ok()?;
+        let centimeters = match unit.trim() {
+            "cm" | "centimeters" => amount,
+            "mm" | "millimeters" => amount / 10.0,
+            "in" | "inch" | "inches" | "\"" => amount * Unit::Inches.centimeters(),
+            _ => return None,
+        };
+
+        let gauge = Self::new(stitches, rows, centimeters, Unit::Centimeters);
+        (gauge.stitches > 0.0 && gauge.rows > 0.0 && gauge.length > 0.0).then_some(gauge)
+    }
+
+    /// How wide a stitch is, in centimeters
+    pub fn stitch_width(self) -> f64 {
+        self.length / self.stitches
+    }
+
+    /// How tall a row or round is, in centimeters
+    pub fn row_height(self) -> f64 {
+        self.length / self.rows
+    }
+}
+
+/// The estimated size of each round of a part, see [`schematic`]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RoundSize {
+    /// How many stitches the round leaves to work into, not counting joining or turning chains
+    pub stitches: u32,
+    /// The round's circumference, or the row's width for flat work, in centimeters
+    pub width: f64,
+    /// How far the top of the round is from the start of the part, in centimeters
+    pub height: f64,
+}
+
+/// The estimated size of one part of a pattern, see [`schematic`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct PartSchematic<'a> {
+    /// The name of the part, as in [`crate::Part::name`]
+    pub name: Option<&'a str>,
+    /// One entry for each round worked, in order, with repeated rounds repeated
+    pub rounds: Vec<RoundSize>,
+}
+
+impl PartSchematic<'_> {
+    /// How tall the finished part is, in centimeters
+    pub fn height(&self) -> f64 {
+        self.rounds.last().map_or(0.0, |r| r.height)
+    }
+
+    /// The widest round of the part, if it has any rounds
+    pub fn widest(&self) -> Option<&RoundSize> {
+        self.rounds
+            .iter()
+            .max_by(|a, b| a.width.total_cmp(&b.width))
+    }
+}
+
+/// The estimated sizes of a pattern's parts, see [`schematic`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Schematic<'a> {
+    /// Whether the widths are circumferences of rounds or widths of flat rows
+    pub mode: Mode,
+    pub parts: Vec<PartSchematic<'a>>,
+}
+
+/// Estimates how big each part of a pattern will come out when worked to `gauge`, for answering
+/// "how big will it be?" before anyone's made it.
+///
+/// Each round's width is its stitch count times the gauge's stitch width: the circumference of a
+/// round, or the width of a row in [`Mode::Rows`]. Each row adds one row height to the height of
+/// flat work. Rounds that grow or shrink lean outwards or inwards instead of going straight up, so
+/// they add less height, and a first round worked into a magic ring lies flat. A foundation chain
+/// adds no height, and neither do rounds that don't make or use any stitches.
+///
+/// Use [`Gauge::parse`] to read the pattern's own `gauge:` header.
+///
+/// ```
+/// # use crochet::{parse_pattern, schematic, Gauge, Unit};
+/// let pattern = parse_pattern("mode: rows\nch 21\nx19: sc 20").unwrap();
+/// let gauge = Gauge::new(20.0, 20.0, 10.0, Unit::Centimeters);
+/// let part = &schematic(&pattern, gauge).parts[0];
+///
+/// assert_eq!(part.rounds.len(), 20);
+/// assert_eq!(part.widest().unwrap().width, 10.0);
+/// assert_eq!(part.height(), 9.5);
+/// ```
+pub fn schematic<'a>(pattern: &Pattern<'a>, gauge: Gauge) -> Schematic<'a> {
+    let mode = pattern.mode();
+    let parts = pattern
+        .parts
+        .iter()
+        .map(|part| PartSchematic {
+            name: part.name,
+            rounds: round_sizes(&part.rounds, mode, gauge),
+        })
+        .collect();
+
+    Schematic { mode, parts }
+}
+
+fn round_sizes(rounds: &[Round], mode: Mode, gauge: Gauge) -> Vec<RoundSize> {
+    let row_height = gauge.row_height();
+    let mut ret: Vec<RoundSize> = Vec::new();
+
+    for (i, round) in expand_rounds(rounds).enumerate() {
+        let previous = ret.last().copied();
+        if round.input_count() == 0 && round.output_count() == 0 {
+            if let Some(previous) = previous {
+                ret.push(previous);
+            }
+            continue;
+        }
+
+        let stitches = mode.stitch_count(round, i == 0);
+        let width = f64::from(stitches) * gauge.stitch_width();
+        let below = previous.map_or(0.0, |r| r.height);
+
+        let chains_only =
+            round.input_count() == 0 && round.leading_chains() == round.output_count();
+        let rise = match previous {
+            _ if chains_only => 0.0,
+            None if into_magic_ring(round) => 0.0,
+            Some(previous) if mode != Mode::Rows => {
+                // the round leans out by how much wider its radius is than the one it's worked into
+                let lean = (width - previous.width) / TAU;
+                (row_height * row_height - lean * lean).max(0.0).sqrt()
+            }
+            _ => row_height,
+        };
+
+        ret.push(RoundSize {
+            stitches,
+            width,
+            height: below + rise,
+        });
+    }
+
+    ret
+}
+
+fn into_magic_ring(round: &Round) -> bool {
+    round
+        .instructions
+        .iter()
+        .any(|i| matches!(i, Instruction::IntoMagicRing(_)))
+}
+
+impl Schematic<'_> {
+    /// Draws each part's outline side by side as an SVG, labeled with its name, its height and the
+    /// width of its widest round in `unit`s. Parts worked in the round are drawn as they'd look
+    /// from the side when stuffed, as wide as their diameter, and flat work is drawn as it lies.
+    /// The first round is at the bottom.
+    ///
+    /// ```
+    /// # use crochet::{parse_pattern, schematic, Gauge, Unit};
+    /// let pattern = parse_pattern("== Ball ==\nsc 6 in mr\ninc 6\nx3: sc 12\ndec 6").unwrap();
+    /// let gauge = Gauge::parse("22 sts x 24 rnds = 10cm").unwrap();
+    /// let svg = schematic(&pattern, gauge).to_svg(Unit::Inches);
+    ///
+    /// assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    /// assert!(svg.contains(">Ball</text>"));
+    /// assert!(svg.contains(">circumference 2.1 in</text>"));
+    /// ```
+    pub fn to_svg(&self, unit: Unit) -> String {
+        let circular = self.mode != Mode::Rows;
+        // how wide each part's outline is drawn
+        let drawn_width = |width: f64| if circular { width / PI } else { width } * SCALE;
+
+        let widths: Vec<f64> = self
+            .parts
+            .iter()
+            .map(|p| p.widest().map_or(0.0, |r| drawn_width(r.width)))
+            .collect();
+        let tallest = self
+            .parts
+            .iter()
+            .map(|p| p.height() * SCALE)
+            .fold(0.0, f64::max);
+
+        // room for the name and width above each outline, and the height to its right
+        let label_width = 9.0 * LINE_HEIGHT;
+        let top = MARGIN + 2.0 * LINE_HEIGHT;
+        let width = MARGIN + widths.iter().map(|w| w + label_width).sum::<f64>();
+        let height = top + tallest + MARGIN;
+
+        let mut ret = String::new();
+        writeln!(
+            ret,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width:.1} {height:.1}\" \
+                width=\"{width:.1}\" height=\"{height:.1}\" font-family=\"sans-serif\" \
+                font-size=\"12\">"
+        )
+        .expect("writing to a string shouldn't fail");
+
+        let mut left = MARGIN;
+        for (part, &part_width) in self.parts.iter().zip(&widths) {
+            let center = left + part_width / 2.0;
+            let bottom = top + tallest;
+
+            // up the right side from the bottom of the first round, then back down the left
+            let right: Vec<(f64, f64)> = part
+                .rounds
+                .first()
+                .map(|r| (r.width, 0.0))
+                .into_iter()
+                .chain(part.rounds.iter().map(|r| (r.width, r.height)))
+                .map(|(w, h)| (drawn_width(w) / 2.0, bottom - h * SCALE))
+                .collect();
+            let points: Vec<String> = right
+                .iter()
+                .map(|&(half, y)| (center + half, y))
+                .chain(right.iter().rev().map(|&(half, y)| (center - half, y)))
+                .map(|(x, y)| format!("{x:.1},{y:.1}"))
+                .collect();
+            writeln!(
+                ret,
+                "<polygon points=\"{}\" fill=\"#eee\" stroke=\"black\"/>",
+                points.join(" ")
+            )
+            .expect("writing to a string shouldn't fail");
+
+            let part_top = bottom - part.height() * SCALE;
+            if let Some(name) = part.name {
+                writeln!(
+                    ret,
+                    "<text x=\"{center:.1}\" y=\"{:.1}\" text-anchor=\"middle\" \
+                        font-weight=\"bold\">{}</text>",
+                    MARGIN,
+                    escape(name)
+                )
+                .expect("writing to a string shouldn't fail");
+            }
+
+            if let Some(widest) = part.widest() {
+                let word = if circular { "circumference" } else { "width" };
+                writeln!(
+                    ret,
+                    "<text x=\"{center:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{word} {}</text>",
+                    MARGIN + LINE_HEIGHT,
+                    length(widest.width, unit)
+                )
+                .expect("writing to a string shouldn't fail");
+            }
+
+            // a dimension line for the height, to the right of the outline
+            let x = left + part_width + LINE_HEIGHT;
+            writeln!(
+                ret,
+                "<line x1=\"{x:.1}\" y1=\"{part_top:.1}\" x2=\"{x:.1}\" y2=\"{bottom:.1}\" \
+                    stroke=\"gray\"/>\n\
+                <text x=\"{:.1}\" y=\"{:.1}\">height {}</text>",
+                x + LINE_HEIGHT / 2.0,
+                (part_top + bottom) / 2.0,
+                length(part.height(), unit)
+            )
+            .expect("writing to a string shouldn't fail");
+
+            left += part_width + label_width;
+        }
+
+        ret.push_str("</svg>");
+        ret
+    }
+}
+
+/// A length in centimeters, written in `unit`s
+fn length(centimeters: f64, unit: Unit) -> String {
+    format!("{:.1} {unit}", centimeters / unit.centimeters())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pattern;
+
+    fn gauge() -> Gauge {
+        Gauge::new(10.0, 10.0, 10.0, Unit::Centimeters)
+    }
+
+    #[test]
+    fn test_parse_gauge() {
+        let cm = |s, r, l| Some(Gauge::new(s, r, l, Unit::Centimeters));
+        assert_eq!(
+            Gauge::parse("22 sts x 24 rnds = 10cm"),
+            cm(22.0, 24.0, 10.0)
+        );
+        assert_eq!(
+            Gauge::parse("14.5 SC x 16 ROWS = 100 mm"),
+            cm(14.5, 16.0, 10.0)
+        );
+        assert_eq!(
+            Gauge::parse("18 sts x 20 rows over 4\""),
+            Some(Gauge::new(18.0, 20.0, 4.0, Unit::Inches))
+        );
+        assert_eq!(Gauge::parse("22 sts = 10cm"), None);
+        assert_eq!(Gauge::parse("22 sts x 24 rnds = 10 furlongs"), None);
+        assert_eq!(Gauge::parse("0 sts x 24 rnds = 10cm"), None);
+    }
+
+    #[test]
+    fn test_round_sizes() {
+        let pattern = parse_pattern("sc 6 in mr\ninc 6\n% stuff it %\nx2: sc 12").unwrap();
+        let rounds = &schematic(&pattern, gauge()).parts[0].rounds;
+
+        let widths: Vec<_> = rounds.iter().map(|r| r.width).collect();
+        assert_eq!(widths, [6.0, 12.0, 12.0, 12.0, 12.0]);
+
+        // the magic ring round lies flat, and the increase round leans out
+        let heights: Vec<_> = rounds.iter().map(|r| r.height).collect();
+        assert_eq!(heights[0], 0.0);
+        assert!(heights[1] > 0.0 && heights[1] < 1.0);
+        assert_eq!(heights[2], heights[1]);
+        assert!((heights[4] - heights[1] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_joined_chain_ring() {
+        let pattern = parse_pattern("mode: joined\nch 20\nch 1, sc 20\nch 1, sc 20").unwrap();
+        let part = &schematic(&pattern, gauge()).parts[0];
+
+        let stitches: Vec<_> = part.rounds.iter().map(|r| r.stitches).collect();
+        assert_eq!(stitches, [20, 20, 20]);
+        assert_eq!(part.height(), 2.0);
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let src =
+            "mode: rows\n== Front & Back ==\nch 11\nx4: ch 1, sc 10\n== Strap ==\nch 3\nch 1, sc 2";
+        let schematic = schematic(&parse_pattern(src).unwrap(), gauge());
+        let svg = schematic.to_svg(Unit::Centimeters);
+
+        assert_eq!(svg.matches("<polygon").count(), 2);
+        assert!(svg.contains(">Front &amp; Back</text>"));
+        assert!(svg.contains(">width 10.0 cm</text>"));
+        assert!(svg.contains(">height 4.0 cm</text>"));
+        assert!(svg.contains(">width 2.0 cm</text>"));
+        assert!(svg.contains(">height 1.0 cm</text>"));
+        assert!(!svg.contains("circumference"));
+
+        let svg = schematic.to_svg(Unit::Inches);
+        assert!(svg.contains(">width 3.9 in</text>"));
+        assert!(svg.ends_with("</svg>"));
+    }
+}
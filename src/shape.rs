@@ -0,0 +1,134 @@
+use crate::round::expand_rounds;
+use crate::{Instruction, Round};
+
+/// A best guess at what a pattern produces, based on its stitch count profile.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ShapeGuess {
+    /// Grows from a magic ring, (optionally) plateaus, then shrinks again.
+    Sphere,
+    /// Mostly constant stitch counts worked in the round.
+    Tube,
+    /// Grows steadily, but slower than a flat circle would.
+    Cone,
+    /// Grows from a magic ring at the flat-circle rate.
+    FlatCircle,
+    /// Started from a chain, with constant stitch counts.
+    FlatRectangle,
+    /// Doesn't look like any of the above.
+    Irregular,
+}
+
+impl std::fmt::Display for ShapeGuess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Sphere => "sphere",
+            Self::Tube => "tube",
+            Self::Cone => "cone",
+            Self::FlatCircle => "flat circle",
+            Self::FlatRectangle => "flat rectangle",
+            Self::Irregular => "irregular",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+fn contains_magic_ring(inst: &Instruction) -> bool {
+    use Instruction::*;
+
+    match inst {
+        IntoMagicRing(_) => true,
+        Group(insts) => insts.iter().any(contains_magic_ring),
+        Repeat(inst, _) => contains_magic_ring(inst),
+        _ => false,
+    }
+}
+
+/// Guesses the shape of the piece that `rounds` produces.
+///
+/// ```
+/// # use crochet::{classify_shape, parse_rounds, ShapeGuess};
+/// let rounds = parse_rounds("sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18\n[dec, sc] 6\ndec 6").unwrap();
+/// assert_eq!(classify_shape(&rounds), ShapeGuess::Sphere);
+/// ```
+pub fn classify_shape(rounds: &[Round]) -> ShapeGuess {
+    // ignore rounds that have 0 in and 0 out (e.g. a round of just comments)
+    let rounds: Vec<_> = expand_rounds(rounds)
+        .filter(|r| r.input_count() != 0 || r.output_count() != 0)
+        .collect();
+
+    let Some(first) = rounds.first() else {
+        return ShapeGuess::Irregular;
+    };
+
+    let counts: Vec<i64> = rounds.iter().map(|r| r.output_count().into()).collect();
+    let deltas: Vec<i64> = counts.windows(2).map(|w| w[1] - w[0]).collect();
+
+    if !first.instructions.iter().any(contains_magic_ring) {
+        return if deltas.iter().all(|&d| d == 0) {
+            ShapeGuess::FlatRectangle
+        } else {
+            ShapeGuess::Irregular
+        };
+    }
+
+    // collapse the profile into its phases of growth and shrinkage, ignoring plateaus
+    let mut phases = Vec::new();
+    for d in deltas.iter().map(|d| d.signum()).filter(|&d| d != 0) {
+        if phases.last() != Some(&d) {
+            phases.push(d);
+        }
+    }
+
+    let growing_rounds = deltas.iter().filter(|&&d| d > 0).count();
+    let plateau_rounds = deltas.iter().filter(|&&d| d == 0).count();
+
+    match phases[..] {
+        [] => ShapeGuess::Tube,
+        [1, -1] => ShapeGuess::Sphere,
+        [1] if plateau_rounds >= growing_rounds => ShapeGuess::Tube,
+        [1] => {
+            // a flat circle gains the magic ring's stitch count every round
+            let total_growth: i64 = deltas.iter().sum();
+            let avg_growth = total_growth as f64 / deltas.len() as f64;
+
+            if avg_growth >= 0.8 * counts[0] as f64 {
+                ShapeGuess::FlatCircle
+            } else {
+                ShapeGuess::Cone
+            }
+        }
+        _ => ShapeGuess::Irregular,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn assert_shape(src: &str, shape: ShapeGuess) {
+        assert_eq!(classify_shape(&parse_rounds(src).unwrap()), shape);
+    }
+
+    #[test]
+    fn test_classify_shape() {
+        assert_shape(
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\n[inc, sc 2] 6",
+            ShapeGuess::FlatCircle,
+        );
+        assert_shape(
+            "sc 6 in mr\ninc 6\nsc 12\n[inc, sc] 6\nsc 18\n[inc, sc 2] 6",
+            ShapeGuess::Cone,
+        );
+        assert_shape("sc 6 in mr\ninc 6\nsc 12\nsc 12\nsc 12", ShapeGuess::Tube);
+        assert_shape(
+            "sc 6 in mr\ninc 6\nsc 12\n% stuff it %\ndec 6",
+            ShapeGuess::Sphere,
+        );
+        assert_shape("ch 10\nsc 10\nsc 10", ShapeGuess::FlatRectangle);
+        assert_shape("ch 10\nsc 5, inc 5", ShapeGuess::Irregular);
+        assert_shape("sc 6 in mr\ninc 6\ndec 6\ninc 6", ShapeGuess::Irregular);
+        assert_shape("", ShapeGuess::Irregular);
+    }
+}
@@ -0,0 +1,35 @@
+/// Which size to parse a multi-size pattern for.
+///
+/// Multi-size patterns give a count for each size, like `sc 24(28,32)`: the first number is for
+/// [`Size::S`], and the parenthesized ones are for [`Size::M`] and [`Size::L`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Size {
+    S,
+    M,
+    L,
+}
+
+impl Size {
+    pub const ALL: [Self; 3] = [Self::S, Self::M, Self::L];
+
+    /// Zero-based position of this size in a `24(28,32)` count
+    pub fn index(self) -> usize {
+        match self {
+            Self::S => 0,
+            Self::M => 1,
+            Self::L => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::S => "S",
+            Self::M => "M",
+            Self::L => "L",
+        };
+
+        write!(f, "{s}")
+    }
+}
@@ -0,0 +1,78 @@
+/// A range of source text, from the `(line, col)` of its first character to the `(line, col)`
+/// just after its last one. Lines and columns are one-based, like the locations in parse errors.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    /// Which file the span is in: 0 for the source being parsed, or for a file pulled in with
+    /// `include`, its number in the order the [`crate::SourceLoader`] loaded the included files,
+    /// starting at 1
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "is_main_file")
+    )]
+    pub file: usize,
+}
+
+impl Span {
+    /// A span in the source being parsed, not an included file
+    pub fn new(start: (usize, usize), end: (usize, usize)) -> Self {
+        Self {
+            start,
+            end,
+            file: 0,
+        }
+    }
+
+    /// Whether the span is in the source being parsed, rather than in an included file
+    pub fn in_main_file(&self) -> bool {
+        self.file == 0
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_main_file(file: &usize) -> bool {
+    *file == 0
+}
+
+/// The span of an instruction, along with the spans of the instructions nested inside it, in the
+/// same order as they are in the instruction (e.g. the elements of a `Group`, or the `a` then `b`
+/// of a `Crossed`).
+///
+/// Instructions that the parser creates as shorthand, like the `Repeat` and `IntoMagicRing` in
+/// `mr 6`, get the span of the text they're created from.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpanTree {
+    pub span: Span,
+    pub children: Vec<SpanTree>,
+}
+
+impl SpanTree {
+    pub fn leaf(span: Span) -> Self {
+        Self {
+            span,
+            children: Vec::new(),
+        }
+    }
+
+    /// Follows `path` down into the nested instructions, returning the span of the instruction
+    /// it leads to. An empty path is this instruction itself.
+    pub fn get(&self, path: &[usize]) -> Option<Span> {
+        match path.split_first() {
+            Some((&first, rest)) => self.children.get(first)?.get(rest),
+            None => Some(self.span),
+        }
+    }
+
+    /// Marks the spans that are in the main source as being in the included file `file` instead.
+    pub(crate) fn move_to_file(&mut self, file: usize) {
+        if self.span.in_main_file() {
+            self.span.file = file;
+        }
+        for child in &mut self.children {
+            child.move_to_file(file);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::round::expand_rounds;
+use crate::shape::{classify_shape, ShapeGuess};
+use crate::{saturating_sum, Round};
+
+/// Summary numbers describing a whole pattern.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PatternStats {
+    /// How many rounds the pattern has
+    pub round_count: usize,
+    /// How many stitches are made over the entire pattern, or `u32::MAX` if that's too many for a
+    /// `u32`
+    pub total_stitches: u32,
+    /// The largest number of stitches produced by a single round
+    pub max_round_stitches: u32,
+    /// What the finished piece probably looks like
+    pub shape: ShapeGuess,
+}
+
+/// Computes summary statistics for the given rounds.
+///
+/// ```
+/// # use crochet::{parse_rounds, pattern_stats, ShapeGuess};
+/// let stats = pattern_stats(&parse_rounds("sc 6 in mr\ninc 6\n[inc, sc] 6").unwrap());
+/// assert_eq!(stats.total_stitches, 36);
+/// assert_eq!(stats.max_round_stitches, 18);
+/// assert_eq!(stats.shape, ShapeGuess::FlatCircle);
+///
+/// let stats = pattern_stats(&parse_rounds("sc 4000000000 in mr\nsc 4000000000").unwrap());
+/// assert_eq!(stats.total_stitches, u32::MAX);
+/// ```
+pub fn pattern_stats(rounds: &[Round]) -> PatternStats {
+    PatternStats {
+        round_count: expand_rounds(rounds).count(),
+        total_stitches: saturating_sum(expand_rounds(rounds).map(Round::output_count)),
+        max_round_stitches: rounds.iter().map(Round::output_count).max().unwrap_or(0),
+        shape: classify_shape(rounds),
+    }
+}
@@ -0,0 +1,272 @@
+use crate::prose::stitch_names;
+use crate::round::expand_rounds;
+use crate::text::skipped;
+use crate::{Directive, Instruction, Round};
+use std::fmt::Write;
+
+/// Where the stitch an instruction is worked into is
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Place {
+    /// The next stitch of the previous round
+    Next,
+    MagicRing,
+    /// The same stitch as the stitches before it in a `(...) in next st`, or the next one if it's
+    /// the first of them
+    Same {
+        first: bool,
+    },
+    /// The stitch skipped by the first half of a `cross(a, b)`
+    Skipped,
+}
+
+/// Collects the steps of a round
+#[derive(Default)]
+struct Steps {
+    steps: Vec<String>,
+    /// Things to do before the next stitch, like color changes and skips
+    pending: Vec<String>,
+}
+
+impl Steps {
+    fn stitch(&mut self, text: String) {
+        if self.pending.is_empty() {
+            self.steps.push(text);
+        } else {
+            self.pending.push(text);
+            self.steps.push(self.pending.join(", then "));
+            self.pending.clear();
+        }
+    }
+
+    fn walk(&mut self, inst: &Instruction, place: &mut Place) {
+        use Instruction::*;
+
+        match inst {
+            Group(insts) => {
+                for i in insts {
+                    self.walk(i, place);
+                }
+            }
+            Repeat(i, n) => {
+                for _ in 0..*n {
+                    self.walk(i, place);
+                }
+            }
+            IntoMagicRing(i) => self.walk(i, &mut Place::MagicRing),
+            IntoOne(i) => self.walk(i, &mut Place::Same { first: true }),
+            Crossed(a, b) => {
+                self.pending.push(skip(1));
+                self.walk(a, &mut Place::Next);
+                self.walk(b, &mut Place::Skipped);
+            }
+            ChSpace { chains, skipped } => {
+                for _ in 0..*chains {
+                    self.stitch("chain 1".to_string());
+                }
+                self.pending.push(skip(*skipped));
+            }
+            Skip(n) => self.pending.push(skip(*n)),
+            Comment(c) => self.pending.push(format!("({})", c.trim())),
+            Color(c) => self.pending.push(format!("change to color {c}")),
+            SurfaceSlSt(n) => {
+                for _ in 0..*n {
+                    self.stitch("surface slip stitch on top of the fabric".to_string());
+                }
+            }
+            Ch => self.stitch("chain 1".to_string()),
+            Tch => self.stitch("chain 1 to turn".to_string()),
+            Custom(stitch) => {
+                let text = worked(stitch.name(), "in", stitch.input_count(), place);
+                self.stitch(text);
+            }
+            _ => {
+                let Some((name, _, prep)) = stitch_names(inst) else {
+                    unreachable!("{inst} isn't a single stitch")
+                };
+                let text = worked(name, prep, inst.input_count(), place);
+                self.stitch(text);
+            }
+        }
+    }
+}
+
+/// Describes a stitch called `name` worked `prep` (e.g. `around`) the `input` stitches at `place`
+fn worked(name: &str, prep: &str, input: u32, place: &mut Place) -> String {
+    match place {
+        _ if input == 0 => name.to_string(),
+        Place::MagicRing => format!("{name} into the magic ring"),
+        Place::Skipped => {
+            format!("{name} {prep} the skipped stitch, crossing in front of the last stitch")
+        }
+        Place::Same { first: false } => format!("{name} {prep} the same stitch"),
+        Place::Same { first } => {
+            *first = false;
+            format!("{name} {prep} the next stitch")
+        }
+        Place::Next if input == 1 => format!("{name} {prep} the next stitch"),
+        Place::Next => format!("{name} over the next {input} stitches"),
+    }
+}
+
+fn skip(n: u32) -> String {
+    format!("skip the {}", skipped("next", n, |n| n.to_string()))
+}
+
+/// Splits one repetition of a round into the individual stitches it's worked as, with a
+/// description of each, e.g. `decrease over the next 2 stitches`. Each stitch is one step, like
+/// one tap of a stitch counter.
+///
+/// Repeats are written out in full, and every stitch of a `(...) in next st` gets its own step.
+/// Color changes, skips and comments aren't stitches, so they're added to the step for the stitch
+/// after them, or the last step if they come at the end.
+///
+/// ```
+/// # use crochet::{parse_rounds, stitch_steps};
+/// let round = &parse_rounds("[sc, dec] 2, (sc, ch 2, sc) in next st").unwrap()[0];
+///
+/// assert_eq!(
+///     stitch_steps(round),
+///     [
+///         "single crochet in the next stitch",
+///         "decrease over the next 2 stitches",
+///         "single crochet in the next stitch",
+///         "decrease over the next 2 stitches",
+///         "single crochet in the next stitch",
+///         "chain 1",
+///         "chain 1",
+///         "single crochet in the same stitch",
+///     ]
+/// );
+/// ```
+pub fn stitch_steps(round: &Round) -> Vec<String> {
+    let mut steps = Steps::default();
+    for inst in &round.instructions {
+        steps.walk(inst, &mut Place::Next);
+    }
+
+    if !steps.pending.is_empty() {
+        let pending = steps.pending.join(", then ");
+        match steps.steps.last_mut() {
+            Some(last) => {
+                write!(last, ", then {pending}").expect("writing to a string shouldn't fail")
+            }
+            None => steps.steps.push(pending),
+        }
+    }
+
+    steps.steps
+}
+
+/// Writes rounds out one stitch at a time, for beginner handouts, with every stitch of every round
+/// numbered as in `Stitch 13 of 18: decrease over the next 2 stitches`. See [`stitch_steps`] for
+/// how rounds are split into stitches.
+///
+/// Each round worked gets its own heading, even the rounds of a block of repeated rounds.
+///
+/// ```
+/// # use crochet::{parse_rounds, step_by_step_format};
+/// let rounds = parse_rounds("sc 3 in mr\nhook 4mm\nx2: inc, sc 2").unwrap();
+///
+/// assert_eq!(
+///     step_by_step_format(&rounds),
+///     "Round 1:
+/// Stitch 1 of 3: single crochet into the magic ring
+/// Stitch 2 of 3: single crochet into the magic ring
+/// Stitch 3 of 3: single crochet into the magic ring
+///
+/// Switch to a 4mm hook.
+/// Round 2:
+/// Stitch 1 of 3: increase in the next stitch
+/// Stitch 2 of 3: single crochet in the next stitch
+/// Stitch 3 of 3: single crochet in the next stitch
+///
+/// Round 3:
+/// Stitch 1 of 3: increase in the next stitch
+/// Stitch 2 of 3: single crochet in the next stitch
+/// Stitch 3 of 3: single crochet in the next stitch"
+/// );
+/// ```
+pub fn step_by_step_format(rounds: &[Round]) -> String {
+    let mut blocks = Vec::new();
+    let mut previous: Option<&Round> = None;
+
+    for (number, round) in (1..).zip(expand_rounds(rounds)) {
+        let mut block = String::new();
+        // the directives come before the first of a block of repeated rounds
+        if !previous.is_some_and(|p| std::ptr::eq(p, round)) {
+            for d in &round.directives {
+                match d {
+                    Directive::Hook(size) => writeln!(block, "Switch to a {size} hook."),
+                    Directive::Yarn(yarn) => writeln!(block, "Switch to the yarn \"{yarn}\"."),
+                }
+                .expect("writing to a string shouldn't fail");
+            }
+        }
+        previous = Some(round);
+
+        match round.label {
+            Some(label) => write!(block, "Round {number} ({label}):"),
+            None => write!(block, "Round {number}:"),
+        }
+        .expect("writing to a string shouldn't fail");
+
+        let steps = stitch_steps(round);
+        for (i, step) in steps.iter().enumerate() {
+            write!(block, "\nStitch {} of {}: {step}", i + 1, steps.len())
+                .expect("writing to a string shouldn't fail");
+        }
+
+        blocks.push(block);
+    }
+
+    blocks.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    fn steps(src: &str) -> Vec<String> {
+        let src = format!("colors: A = red, B = blue\n{src}");
+        stitch_steps(&parse_rounds(&src).unwrap()[0])
+    }
+
+    #[test]
+    fn test_notes_go_with_the_next_stitch() {
+        assert_eq!(
+            steps("sc, @B, skip 2, fpsc, % tight %"),
+            [
+                "single crochet in the next stitch",
+                "change to color B, then skip the next 2 stitches, then front post single \
+                    crochet around the next stitch, then (tight)",
+            ]
+        );
+        assert_eq!(steps("% fasten off %"), ["(fasten off)"]);
+    }
+
+    #[test]
+    fn test_crossed_and_chain_spaces() {
+        assert_eq!(
+            steps("cross(blsc, sc), ch 2, skip 1, sc"),
+            [
+                "skip the next stitch, then single crochet in the back loop of the next stitch",
+                "single crochet in the skipped stitch, crossing in front of the last stitch",
+                "chain 1",
+                "chain 1",
+                "skip the next stitch, then single crochet in the next stitch",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_stitch_numbered() {
+        let rounds = parse_rounds("sc 6 in mr\nears: [inc, sc] 3\n[dec, sc] 3").unwrap();
+        let text = step_by_step_format(&rounds);
+
+        assert!(text.contains("\n\nRound 2 (ears):\nStitch 1 of 6: increase in the next stitch\n"));
+        assert!(text.ends_with("Stitch 6 of 6: single crochet in the next stitch"));
+        assert_eq!(text.matches("Stitch ").count(), 6 + 6 + 6);
+        assert_eq!(step_by_step_format(&[]), "");
+    }
+}
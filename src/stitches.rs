@@ -0,0 +1,113 @@
+use crate::Instruction;
+
+/// One level of nesting that [`Stitches`] is partway through
+struct Frame<'r, 'a> {
+    insts: &'r [Instruction<'a>],
+    /// Index of the next instruction in `insts`
+    pos: usize,
+    /// How many more times `insts` is worked, including the current time
+    remaining: u32,
+}
+
+/// Iterator over the elementary stitches of an instruction or round, along with each one's index
+/// among them. Created by [`Instruction::iter_stitches`] and [`crate::Round::iter_stitches`].
+///
+/// Repeats and groups are expanded as the iterator goes, rather than all up front. Comments, color
+/// changes and skips aren't worked as stitches, so they aren't yielded.
+///
+/// ```
+/// # use crochet::{parse_rounds, Instruction::*};
+/// let round = &parse_rounds("sc, [inc, sc] 2, % hi %, cross(fpsc, sc)").unwrap()[0];
+/// let stitches: Vec<_> = round.iter_stitches().collect();
+/// assert_eq!(
+///     stitches,
+///     [(0, &Sc), (1, &Inc), (2, &Sc), (3, &Inc), (4, &Sc), (5, &Fpsc), (6, &Sc)]
+/// );
+/// ```
+pub struct Stitches<'r, 'a> {
+    stack: Vec<Frame<'r, 'a>>,
+    index: usize,
+}
+
+impl<'r, 'a> Stitches<'r, 'a> {
+    pub(crate) fn new(insts: &'r [Instruction<'a>]) -> Self {
+        Self {
+            stack: vec![Frame {
+                insts,
+                pos: 0,
+                remaining: 1,
+            }],
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, insts: &'r [Instruction<'a>], times: u32) {
+        if times > 0 {
+            self.stack.push(Frame {
+                insts,
+                pos: 0,
+                remaining: times,
+            });
+        }
+    }
+}
+
+impl<'r, 'a> Iterator for Stitches<'r, 'a> {
+    type Item = (usize, &'r Instruction<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use Instruction::*;
+
+        loop {
+            let frame = self.stack.last_mut()?;
+
+            let Some(inst) = frame.insts.get(frame.pos) else {
+                frame.remaining -= 1;
+                frame.pos = 0;
+                if frame.remaining == 0 {
+                    self.stack.pop();
+                }
+                continue;
+            };
+            frame.pos += 1;
+
+            match inst {
+                Group(insts) => self.push(insts, 1),
+                Repeat(i, times) => self.push(std::slice::from_ref(i), *times),
+                IntoMagicRing(i) | IntoOne(i) => self.push(std::slice::from_ref(i), 1),
+                Crossed(a, b) => {
+                    // the top of the stack is worked first
+                    self.push(std::slice::from_ref(b), 1);
+                    self.push(std::slice::from_ref(a), 1);
+                }
+                Comment(_) | Color(_) | Skip(_) => {}
+                _ => {
+                    self.index += 1;
+                    return Some((self.index - 1, inst));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_rounds;
+    use crate::Instruction::*;
+
+    #[test]
+    fn test_nested_repeats() {
+        let round =
+            &parse_rounds("[sc, [inc] 2] 2 in mr, [dec] 0, (sc 2, ch) in next st").unwrap()[0];
+        let stitches: Vec<_> = round.iter_stitches().map(|(_, s)| s.clone()).collect();
+        assert_eq!(stitches, [Sc, Inc, Inc, Sc, Inc, Inc, Sc, Sc, Ch]);
+        assert_eq!(round.iter_stitches().last().map(|(i, _)| i), Some(8));
+    }
+
+    #[test]
+    fn test_lazy() {
+        // would take gigabytes if it were expanded up front
+        let round = &parse_rounds("[sc 1000000] 1000000").unwrap()[0];
+        assert_eq!(round.iter_stitches().nth(5_000_000), Some((5_000_000, &Sc)));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{Lint, Span};
+
+/// A fix for a [`Lint`]: the source text to replace and what to replace it with.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Suggestion {
+    /// The text to replace
+    pub span: Span,
+    /// What to replace it with
+    pub replacement: String,
+}
+
+impl Suggestion {
+    pub fn new(span: Span, replacement: String) -> Self {
+        Self { span, replacement }
+    }
+}
+
+/// The byte range of `span` in `source`, or `None` if it isn't in it
+fn byte_range(source: &str, span: Span) -> Option<std::ops::Range<usize>> {
+    let offset = |(line, col): (usize, usize)| {
+        let line_start = match line {
+            0 => return None,
+            1 => 0,
+            _ => source.match_indices('\n').nth(line - 2)?.0 + 1,
+        };
+        let offset = line_start + col.checked_sub(1)?;
+        source.is_char_boundary(offset).then_some(offset)
+    };
+
+    let (start, end) = (offset(span.start)?, offset(span.end)?);
+    (start <= end).then_some(start..end)
+}
+
+/// Applies the suggested fixes of `lints` to the `source` they were found in, e.g. for a `--fix`
+/// option.
+///
+/// Where suggestions overlap, only the one that starts first is applied, or the longest one if
+/// they start at the same place, so running the lints again on the result might suggest more
+/// fixes. Suggestions with spans that aren't in `source`, like ones for rounds from an included
+/// file, are skipped.
+///
+/// ```
+/// # use crochet::{apply_suggestions, lint_rounds, parse_rounds};
+/// let src = "sc 6 in mr\ninc 6\nsc 1, [inc, sc] 6";
+/// let lints = lint_rounds(&parse_rounds(src).unwrap());
+///
+/// assert_eq!(apply_suggestions(src, &lints), "sc 6 in mr\ninc 6\nsc, [inc, sc] 6");
+/// ```
+pub fn apply_suggestions(source: &str, lints: &[Lint]) -> String {
+    let mut edits: Vec<_> = lints
+        .iter()
+        .filter_map(Lint::suggestion)
+        .filter(|s| s.span.in_main_file())
+        .filter_map(|s| Some((byte_range(source, s.span)?, &s.replacement)))
+        .collect();
+    edits.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+
+    let mut ret = String::with_capacity(source.len());
+    // how much of `source` has been copied or replaced so far
+    let mut done = 0;
+    for (range, replacement) in edits {
+        if range.start < done {
+            continue;
+        }
+
+        ret.push_str(&source[done..range.start]);
+        ret.push_str(replacement);
+        done = range.end;
+    }
+    ret.push_str(&source[done..]);
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lint_rounds, parse_pattern_with_loader, parse_rounds};
+
+    fn fixed(src: &str) -> String {
+        apply_suggestions(src, &lint_rounds(&parse_rounds(src).unwrap()))
+    }
+
+    #[test]
+    fn test_byte_range() {
+        let src = "ab\ncdé\n\nf";
+        assert_eq!(byte_range(src, Span::new((1, 2), (2, 3))), Some(1..5));
+        assert_eq!(byte_range(src, Span::new((4, 1), (4, 2))), Some(9..10));
+        assert_eq!(byte_range(src, Span::new((3, 1), (3, 1))), Some(8..8));
+        // in the middle of the é
+        assert_eq!(byte_range(src, Span::new((2, 4), (2, 5))), None);
+        assert_eq!(byte_range(src, Span::new((5, 1), (5, 2))), None);
+        assert_eq!(byte_range(src, Span::default()), None);
+    }
+
+    #[test]
+    fn test_apply_suggestions() {
+        assert_eq!(
+            fixed("sc 6 in mr\ninc 6\n[inc, sc] 7\nsc 18, % fasten off %"),
+            "sc 6 in mr\ninc 6\n[inc, sc] 6\nsc 18, % fasten off %"
+        );
+        assert_eq!(
+            fixed("ch 12\nsc 12\nx2: sc 12\nsc 6, sc 6\n[dec, sc 2] 3"),
+            "ch 12\nrounds 2-5: sc 12\n[dec, sc 2] 3"
+        );
+        assert_eq!(
+            fixed("ch 24\nlast: dec 6, sc 12"),
+            "ch 24\nlast: [dec, sc 2] 6"
+        );
+        // the redundant repeat is inside the rounds that are collapsed
+        assert_eq!(fixed("ch 6\nsc 1, sc 5\nsc 6"), "ch 6\nrounds 2-3: sc 6");
+
+        let src = "sc 6 in mr\ninc 6";
+        assert_eq!(fixed(src), src);
+        // fixing round 2 would break round 3, so it's not clear which round is wrong
+        let src = "sc 6 in mr\ninc 7\n[inc, sc] 7\nsc 21, % fasten off %";
+        assert_eq!(fixed(src), src);
+    }
+
+    #[test]
+    fn test_included_rounds_not_fixed() {
+        let src = "sc 6 in mr\ninclude \"a\"\n[inc, sc] 6";
+        let loader = |_: &str| Some("sc 1, [inc, sc] 6");
+        let pattern = parse_pattern_with_loader(src, loader).unwrap();
+        let lints = lint_rounds(&pattern.parts[0].rounds);
+
+        assert!(lints.iter().any(|l| l.suggestion().is_some()));
+        assert_eq!(apply_suggestions(src, &lints), src);
+    }
+}
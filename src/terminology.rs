@@ -0,0 +1,165 @@
+use crate::format::{format_instruction, format_round, FormatOptions};
+use crate::{Dialect, Instruction, Round};
+
+/// The keywords whose names differ between US and UK terms, and their UK names. UK patterns call
+/// a single crochet a double crochet, and so on up; the other keywords are the same in both.
+const UK_NAMES: [(&str, &str); 4] = [
+    ("sc", "dc"),
+    ("fpsc", "fpdc"),
+    ("bpsc", "bpdc"),
+    ("blsc", "bldc"),
+];
+
+/// Which names the stitches are written with. The instructions themselves don't depend on it, so
+/// a pattern read in one can be written out in the other.
+///
+/// ```
+/// # use crochet::{parse_rounds_with_dialect, Terminology};
+/// let rounds =
+///     parse_rounds_with_dialect("dc 6 in mr\n[inc, dc] 6", &Terminology::Uk.dialect()).unwrap();
+///
+/// assert_eq!(rounds[1].to_string(), "[inc, sc] 6");
+/// assert_eq!(rounds[1].display_in(Terminology::Uk).to_string(), "[inc, dc] 6");
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Terminology {
+    /// US terms, which the pattern language itself uses: `sc`, `fpsc`, ...
+    #[default]
+    Us,
+    /// UK terms: `dc` for a single crochet, `fpdc`, ...
+    Uk,
+}
+
+impl Terminology {
+    pub const ALL: [Self; 2] = [Self::Us, Self::Uk];
+
+    /// The name of the terminology, `us` or `uk`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Us => "us",
+            Self::Uk => "uk",
+        }
+    }
+
+    /// Looks up a terminology by its [`Self::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// How `keyword`, as the pattern language spells it, is written in this terminology
+    pub(crate) fn keyword(self, keyword: &str) -> &str {
+        match self {
+            Self::Us => keyword,
+            Self::Uk => UK_NAMES
+                .iter()
+                .find(|(us, _)| *us == keyword)
+                .map_or(keyword, |(_, uk)| uk),
+        }
+    }
+
+    /// A dialect for reading patterns written in this terminology, which understands its names
+    /// for the stitches
+    pub fn dialect(self) -> Dialect {
+        match self {
+            Self::Us => Dialect::new(),
+            Self::Uk => UK_NAMES.iter().fold(Dialect::new(), |d, (us, uk)| {
+                d.alias(uk, us).expect("the UK names are for keywords")
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for Terminology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An instruction or round written with the names from a terminology, see
+/// [`Instruction::display_in`] and [`Round::display_in`]
+pub(crate) struct InTerms<'r, T> {
+    pub(crate) value: &'r T,
+    pub(crate) terms: Terminology,
+}
+
+impl<T> InTerms<'_, T> {
+    fn opts(&self) -> FormatOptions {
+        FormatOptions {
+            terminology: self.terms,
+            ..Default::default()
+        }
+    }
+}
+
+impl std::fmt::Display for InTerms<'_, Instruction<'_>> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_instruction(self.value, &self.opts()))
+    }
+}
+
+impl std::fmt::Display for InTerms<'_, Round<'_>> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for d in &self.value.directives {
+            writeln!(f, "{d}")?;
+        }
+
+        write!(f, "{}", format_round(self.value, &self.opts()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_rounds, parse_rounds_with_dialect, pretty_format_with_options};
+
+    #[test]
+    fn test_names() {
+        for t in Terminology::ALL {
+            assert_eq!(Terminology::from_name(t.name()), Some(t));
+        }
+        assert_eq!(Terminology::from_name("au"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let us = "sc 6 in mr\nfpsc, bpsc, blsc, inc, flinc, blinc, dec, ch 2, skip 1, [sc] 1";
+        let uk = "dc 6 in mr\nfpdc, bpdc, bldc, inc, flinc, blinc, dec, ch 2, skip 1, [dc] 1";
+
+        let rounds = parse_rounds(us).unwrap();
+        let written: Vec<_> = rounds
+            .iter()
+            .map(|r| r.display_in(Terminology::Uk).to_string())
+            .collect();
+        assert_eq!(written.join("\n"), uk);
+
+        let read = parse_rounds_with_dialect(uk, &Terminology::Uk.dialect()).unwrap();
+        assert_eq!(read, rounds);
+    }
+
+    #[test]
+    fn test_display_in() {
+        let inst = Instruction::Repeat(Instruction::Fpsc.into(), 3);
+        assert_eq!(inst.display_in(Terminology::Us).to_string(), "fpsc 3");
+        assert_eq!(inst.display_in(Terminology::Uk).to_string(), "fpdc 3");
+
+        let rounds = parse_rounds("yarn \"blue\"\nx2: sc 6").unwrap();
+        assert_eq!(
+            rounds[0].display_in(Terminology::Uk).to_string(),
+            "yarn \"blue\"\nx2: dc 6"
+        );
+    }
+
+    #[test]
+    fn test_pretty_format() {
+        let rounds = parse_rounds("sc 6 in mr\n[inc, sc] 6").unwrap();
+        let opts = FormatOptions {
+            terminology: Terminology::Uk,
+            uppercase_keywords: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            pretty_format_with_options(&rounds, &opts),
+            "Round 1: DC 6 IN MR (6)\nRound 2: [INC, DC] 6 (18)"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/// Escapes the characters that have a special meaning in HTML and SVG text and attribute values
+pub(crate) fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            _ => ret.push(c),
+        }
+    }
+
+    ret
+}
+
+pub(crate) fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// How many times something's repeated, e.g. `twice` or `3 times`, with the count written by
+/// `number`
+pub(crate) fn times(n: u32, number: fn(u32) -> String) -> String {
+    match n {
+        1 => "once".to_string(),
+        2 => "twice".to_string(),
+        n => format!("{} times", number(n)),
+    }
+}
+
+pub(crate) fn stitches(n: u32) -> String {
+    if n == 1 {
+        "1 stitch".to_string()
+    } else {
+        format!("{n} stitches")
+    }
+}
+
+/// The stitches skipped by `skip n`, e.g. `next stitch` or `next 3 stitches`, with the count
+/// written by `number`
+pub(crate) fn skipped(place: &str, n: u32, number: fn(u32) -> String) -> String {
+    if n == 1 {
+        format!("{place} stitch")
+    } else {
+        format!("{place} {} stitches", number(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<b>\"Tom & Jerry's\"</b>"),
+            "&lt;b&gt;&quot;Tom &amp; Jerry&#39;s&quot;&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn test_counts() {
+        assert_eq!(times(2, |n| n.to_string()), "twice");
+        assert_eq!(times(7, |n| n.to_string()), "7 times");
+        assert_eq!(stitches(1), "1 stitch");
+        assert_eq!(skipped("next", 1, |n| n.to_string()), "next stitch");
+        assert_eq!(skipped("next", 3, |n| n.to_string()), "next 3 stitches");
+    }
+}
@@ -0,0 +1,31 @@
+/// A fenced block of free text in a pattern, like a materials list or designer notes.
+///
+/// These are kept exactly as written:
+///
+/// ```
+/// # use crochet::parse_pattern;
+/// let src = "```materials\n- 4mm hook\n- worsted yarn\n```\nsc 6 in mr";
+///
+/// let pattern = parse_pattern(src).unwrap();
+/// assert_eq!(pattern.notes[0].name, "materials");
+/// assert_eq!(pattern.notes[0].text, "- 4mm hook\n- worsted yarn");
+/// assert_eq!(pattern.notes[0].to_string(), src.strip_suffix("\nsc 6 in mr").unwrap());
+/// ```
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextBlock<'a> {
+    /// The name after the opening fence, e.g. `materials`. This is empty if there isn't one.
+    pub name: &'a str,
+    /// Everything between the fences
+    pub text: &'a str,
+}
+
+impl std::fmt::Display for TextBlock<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "```{}", self.name)?;
+        if !self.text.is_empty() {
+            writeln!(f, "{}", self.text)?;
+        }
+        write!(f, "```")
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{Instruction, Round};
+
+/// Walks an instruction tree by reference.
+///
+/// By default, every nested instruction is visited once in the order it's written, so an
+/// implementation only has to override the methods it cares about. An overridden
+/// [`Self::visit_instruction`] can call [`walk_instruction`] to keep descending.
+///
+/// The instruction inside a `Repeat` is only visited once, no matter how many times it's repeated.
+///
+/// ```
+/// # use crochet::{parse_rounds, walk_instruction, Instruction, InstructionVisitor};
+/// /// Counts the `inc`s as written
+/// struct Incs(u32);
+///
+/// impl InstructionVisitor<'_> for Incs {
+///     fn visit_instruction(&mut self, inst: &Instruction) {
+///         if *inst == Instruction::Inc {
+///             self.0 += 1;
+///         }
+///         walk_instruction(self, inst);
+///     }
+/// }
+///
+/// let mut incs = Incs(0);
+/// incs.visit_round(&parse_rounds("inc, [inc, sc] 6, (sc, inc) in next st").unwrap()[0]);
+/// assert_eq!(incs.0, 3);
+/// ```
+pub trait InstructionVisitor<'a> {
+    fn visit_instruction(&mut self, inst: &Instruction<'a>) {
+        walk_instruction(self, inst);
+    }
+
+    fn visit_round(&mut self, round: &Round<'a>) {
+        for inst in &round.instructions {
+            self.visit_instruction(inst);
+        }
+    }
+}
+
+/// Visits each instruction directly nested in `inst` with `visitor`.
+pub fn walk_instruction<'a, V>(visitor: &mut V, inst: &Instruction<'a>)
+where
+    V: InstructionVisitor<'a> + ?Sized,
+{
+    use Instruction::*;
+
+    match inst {
+        Group(insts) => {
+            for i in insts {
+                visitor.visit_instruction(i);
+            }
+        }
+        Repeat(i, _) | IntoMagicRing(i) | IntoOne(i) => visitor.visit_instruction(i),
+        Crossed(a, b) => {
+            visitor.visit_instruction(a);
+            visitor.visit_instruction(b);
+        }
+        Ch
+        | Tch
+        | Sc
+        | Fpsc
+        | Bpsc
+        | Blsc
+        | Inc
+        | Flinc
+        | Blinc
+        | Dec
+        | Comment(_)
+        | Skip(_)
+        | SurfaceSlSt(_)
+        | ChSpace { .. }
+        | Color(_)
+        | Custom(_) => {}
+    }
+}
+
+/// Rebuilds an instruction tree, bottom up.
+///
+/// By default, every nested instruction is folded and the tree is otherwise left as it was. An
+/// overridden [`Self::fold_instruction`] can call [`fold_nested_instructions`] to fold the
+/// instructions inside the one it's given.
+///
+/// ```
+/// # use crochet::{fold_nested_instructions, parse_rounds, Instruction, InstructionFolder};
+/// /// Works every single crochet through the back loop only
+/// struct BackLoops;
+///
+/// impl<'a> InstructionFolder<'a> for BackLoops {
+///     fn fold_instruction(&mut self, inst: Instruction<'a>) -> Instruction<'a> {
+///         match inst {
+///             Instruction::Sc => Instruction::Blsc,
+///             _ => fold_nested_instructions(self, inst),
+///         }
+///     }
+/// }
+///
+/// let round = parse_rounds("ch, [inc, sc 2] 6").unwrap().remove(0);
+/// assert_eq!(BackLoops.fold_round(round).to_string(), "ch, [inc, blsc 2] 6");
+/// ```
+pub trait InstructionFolder<'a> {
+    fn fold_instruction(&mut self, inst: Instruction<'a>) -> Instruction<'a> {
+        fold_nested_instructions(self, inst)
+    }
+
+    /// Folds each of the round's instructions. The round's spans are left as they are, so they
+    /// may no longer line up with the new instructions.
+    fn fold_round(&mut self, mut round: Round<'a>) -> Round<'a> {
+        round.instructions = round
+            .instructions
+            .into_iter()
+            .map(|inst| self.fold_instruction(inst))
+            .collect();
+        round
+    }
+}
+
+/// Rebuilds every round with `f` applied to each instruction, bottom up: `f` is given each
+/// instruction after the ones nested in it have already been mapped. Like
+/// [`InstructionFolder::fold_round`], the rounds' spans are left as they are.
+///
+/// ```
+/// # use crochet::{map_instructions, parse_rounds, Instruction};
+/// let rounds = parse_rounds("sc 6 in mr\n[dec, sc] 2, (dec, sc) in next st").unwrap();
+///
+/// let rounds = map_instructions(rounds, |inst| match inst {
+///     Instruction::Dec => Instruction::Group(vec![Instruction::Sc, Instruction::Sc]),
+///     inst => inst,
+/// });
+/// assert_eq!(rounds[1].to_string(), "[sc, sc, sc] 2, (sc, sc, sc) in next st");
+/// ```
+pub fn map_instructions<'a>(
+    rounds: Vec<Round<'a>>,
+    f: impl FnMut(Instruction<'a>) -> Instruction<'a>,
+) -> Vec<Round<'a>> {
+    struct Map<F>(F);
+
+    impl<'a, F: FnMut(Instruction<'a>) -> Instruction<'a>> InstructionFolder<'a> for Map<F> {
+        fn fold_instruction(&mut self, inst: Instruction<'a>) -> Instruction<'a> {
+            let inst = fold_nested_instructions(self, inst);
+            (self.0)(inst)
+        }
+    }
+
+    let mut map = Map(f);
+    rounds.into_iter().map(|r| map.fold_round(r)).collect()
+}
+
+/// Folds each instruction directly nested in `inst` with `folder`, keeping `inst` itself.
+pub fn fold_nested_instructions<'a, F>(folder: &mut F, inst: Instruction<'a>) -> Instruction<'a>
+where
+    F: InstructionFolder<'a> + ?Sized,
+{
+    use Instruction::*;
+
+    let mut fold = |i: Instruction<'a>| folder.fold_instruction(i);
+
+    match inst {
+        Group(insts) => Group(insts.into_iter().map(fold).collect()),
+        Repeat(i, times) => Repeat(fold(*i).into(), times),
+        IntoMagicRing(i) => IntoMagicRing(fold(*i).into()),
+        IntoOne(i) => IntoOne(fold(*i).into()),
+        Crossed(a, b) => {
+            let a = fold(*a).into();
+            Crossed(a, fold(*b).into())
+        }
+        Ch
+        | Tch
+        | Sc
+        | Fpsc
+        | Bpsc
+        | Blsc
+        | Inc
+        | Flinc
+        | Blinc
+        | Dec
+        | Comment(_)
+        | Skip(_)
+        | SurfaceSlSt(_)
+        | ChSpace { .. }
+        | Color(_)
+        | Custom(_) => inst,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_rounds;
+
+    /// Records every instruction it visits, as written
+    struct Record(Vec<String>);
+
+    impl InstructionVisitor<'_> for Record {
+        fn visit_instruction(&mut self, inst: &Instruction) {
+            self.0.push(inst.to_string());
+            walk_instruction(self, inst);
+        }
+    }
+
+    #[test]
+    fn test_visit_order() {
+        let mut record = Record(Vec::new());
+        record.visit_round(&parse_rounds("sc 2 in mr, cross(fpsc, sc), % hi %").unwrap()[0]);
+        assert_eq!(
+            record.0,
+            [
+                "sc 2 in mr",
+                "sc 2",
+                "sc",
+                "cross(fpsc, sc)",
+                "fpsc",
+                "sc",
+                "% hi %"
+            ]
+        );
+    }
+
+    /// Doubles every repeat count
+    struct Double;
+
+    impl<'a> InstructionFolder<'a> for Double {
+        fn fold_instruction(&mut self, inst: Instruction<'a>) -> Instruction<'a> {
+            match fold_nested_instructions(self, inst) {
+                Instruction::Repeat(i, times) => Instruction::Repeat(i, times * 2),
+                inst => inst,
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_bottom_up() {
+        let round = parse_rounds("[inc 2, sc] 3 in mr, (sc 2, ch) in next st")
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            Double.fold_round(round).to_string(),
+            "[inc 4, sc] 6 in mr, (sc 4, ch) in next st"
+        );
+    }
+
+    #[test]
+    fn test_map_instructions() {
+        let rounds = parse_rounds("sc 6 in mr\n[inc, [sc] 2] 3, cross(sc, inc)").unwrap();
+        let mut seen = Vec::new();
+        let rounds = map_instructions(rounds, |inst| {
+            seen.push(inst.to_string());
+            match inst {
+                Instruction::Repeat(i, times) => Instruction::Repeat(i, times + 1),
+                Instruction::Sc => Instruction::Fpsc,
+                inst => inst,
+            }
+        });
+
+        assert_eq!(rounds[0].to_string(), "fpsc 7 in mr");
+        assert_eq!(rounds[1].to_string(), "[inc, [fpsc] 3] 4, cross(fpsc, inc)");
+        // nested instructions are mapped before the ones they're in
+        assert_eq!(
+            seen[3..8],
+            ["inc", "sc", "fpsc", "[fpsc] 2", "inc, [fpsc] 3"]
+        );
+    }
+}